@@ -0,0 +1,117 @@
+use crate::grid::Grid;
+
+/// Vecinos transitables de cada celda, precalculados al cargar el grid como
+/// una máscara de 4 bits (el bit `d` indica que el paso `DIRS[d]` cae dentro
+/// del grid y en una celda transitable). Así las expansiones recorren un
+/// array plano en vez de comprobar bordes y calcular coordenadas.
+#[derive(Clone, Debug)]
+pub(crate) struct Adjacency {
+    width: usize,
+    masks: Vec<u8>,
+    /// Revisión de las celdas del grid (ver `PathFinder::touched`) con la que
+    /// se calcularon las máscaras.
+    revision: u32,
+}
+
+impl Adjacency {
+    pub fn new(grid: &Grid, revision: u32) -> Self {
+        Self {
+            width: grid.width,
+            masks: (0..grid.cells.len()).map(|idx| mask(grid, idx)).collect(),
+            revision,
+        }
+    }
+
+    /// Si se calculó para un grid de las dimensiones de `grid`.
+    pub fn fits(&self, grid: &Grid) -> bool {
+        self.width == grid.width && self.masks.len() == grid.cells.len()
+    }
+
+    /// Se pone al día con `grid` en la revisión `revision`: si cambió por
+    /// otras vías que `update` se vuelve a calcular todo. Con la misma
+    /// revisión no mira las celdas.
+    pub fn sync(&mut self, grid: &Grid, revision: u32) {
+        if revision != self.revision || !self.fits(grid) {
+            *self = Self::new(grid, revision);
+        }
+    }
+
+    /// Ajusta las máscaras de los vecinos tras cambiar solo la celda `idx`,
+    /// cambio que llevó el grid a la revisión `revision`. Si ya iba atrasado
+    /// no hace nada y lo rehará `sync`.
+    pub fn update(&mut self, grid: &Grid, idx: usize, revision: u32) {
+        if self.revision.wrapping_add(1) != revision {
+            return;
+        }
+        self.revision = revision;
+        for next in grid.neighbors(grid.coords(idx)) {
+            let n = grid.idx(next);
+            self.masks[n] = mask(grid, n);
+        }
+    }
+
+    /// Complemento de la máscara: el bit `d` indica que el paso `DIRS[d]`
+    /// sale del grid o cae en una pared.
+    pub fn walls(&self, idx: usize) -> u8 {
+        !self.masks[idx] & 0b1111
+    }
+
+    /// Pasos `(dirección en DIRS, índice)` desde `idx` hacia vecinos
+    /// transitables, en el orden de `DIRS`.
+    pub fn steps(&self, idx: usize) -> impl Iterator<Item = (usize, usize)> + use<> {
+        let (mask, width) = (self.masks[idx], self.width);
+        (0..4)
+            .filter(move |d| mask & (1 << d) != 0)
+            .map(move |d| match d {
+                0 => (d, idx + width),
+                1 => (d, idx + 1),
+                2 => (d, idx - width),
+                _ => (d, idx - 1),
+            })
+    }
+}
+
+fn mask(grid: &Grid, idx: usize) -> u8 {
+    grid.steps(grid.coords(idx))
+        .filter(|&(_, next)| grid.walkable(next))
+        .fold(0, |mask, (d, _)| mask | 1 << d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expected(grid: &Grid, idx: usize) -> Vec<(usize, usize)> {
+        grid.steps(grid.coords(idx))
+            .filter(|&(_, next)| grid.walkable(next))
+            .map(|(d, next)| (d, grid.idx(next)))
+            .collect()
+    }
+
+    /// Test: coincide con recorrer los vecinos a mano, también tras cambios
+    #[test]
+    fn matches_grid_steps() {
+        let mut grid = Grid::new(vec![1, 1, 0, 1, 1, 1, 0, 1, 1, 1, 1, 0], 4, 3);
+        let mut adjacency = Adjacency::new(&grid, 0);
+        let matches = |adjacency: &Adjacency, grid: &Grid| {
+            (0..grid.cells.len())
+                .all(|idx| adjacency.steps(idx).collect::<Vec<_>>() == expected(grid, idx))
+        };
+        grid.cells[5] = 0;
+        adjacency.update(&grid, 5, 1);
+        assert!(matches(&adjacency, &grid));
+        grid.cells[2] = 1;
+        grid.cells[11] = 1;
+        adjacency.sync(&grid, 1);
+        assert!(!matches(&adjacency, &grid));
+        adjacency.sync(&grid, 2);
+        assert!(matches(&adjacency, &grid));
+        // Atrasado (el cambio de la revisión 3 no pasó por `update`), el de la
+        // 4 no se aplica suelto sino con `sync`.
+        grid.cells[0] = 0;
+        grid.cells[1] = 0;
+        adjacency.update(&grid, 1, 4);
+        adjacency.sync(&grid, 4);
+        assert!(matches(&adjacency, &grid));
+    }
+}
@@ -0,0 +1,295 @@
+use wasm_bindgen::prelude::*;
+
+/// Criterio de `PathFinder::allocate_tasks`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AllocationObjective {
+    /// Mínima suma de costes (algoritmo húngaro).
+    #[default]
+    TotalDistance,
+    /// Mínimo coste del agente que más tarda y, con ese máximo, mínima suma.
+    Makespan,
+    /// Voraz: el par agente-destino más barato primero. Rápido para muchos
+    /// agentes pero sin garantía de óptimo.
+    Greedy,
+}
+
+/// Reparto de destinos entre agentes (`PathFinder::allocate_tasks`).
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct Allocation {
+    goals: Vec<Option<usize>>,
+    costs: Vec<f64>,
+    paths: Vec<Vec<(usize, usize)>>,
+}
+
+#[wasm_bindgen]
+impl Allocation {
+    /// Destino asignado a cada agente (índice en la lista de destinos) o -1
+    /// si se queda sin ninguno.
+    pub fn assignments(&self) -> Vec<i32> {
+        self.goals
+            .iter()
+            .map(|g| g.map_or(-1, |g| g as i32))
+            .collect()
+    }
+
+    /// Coste de la ruta del agente `i` (NaN si no tiene destino).
+    pub fn cost(&self, i: usize) -> f64 {
+        self.costs.get(i).copied().unwrap_or(f64::NAN)
+    }
+
+    /// Ruta del agente `i` como `[x0, y0, x1, y1, ...]` (vacía si no tiene
+    /// destino).
+    pub fn path(&self, i: usize) -> Vec<usize> {
+        self.paths
+            .get(i)
+            .map_or_else(Vec::new, |p| p.iter().flat_map(|&(x, y)| [x, y]).collect())
+    }
+
+    /// Suma de los costes de los agentes con destino.
+    pub fn total(&self) -> f64 {
+        self.costs.iter().filter(|c| c.is_finite()).sum()
+    }
+
+    /// Mayor coste entre los agentes con destino (0 si no hay ninguno).
+    pub fn makespan(&self) -> f64 {
+        let finite = self.costs.iter().filter(|c| c.is_finite());
+        finite.fold(0.0, |a, &c| a.max(c))
+    }
+}
+
+impl Allocation {
+    pub(crate) fn new(
+        goals: Vec<Option<usize>>,
+        costs: Vec<f64>,
+        paths: Vec<Vec<(usize, usize)>>,
+    ) -> Self {
+        Self {
+            goals,
+            costs,
+            paths,
+        }
+    }
+}
+
+/// Destino de cada agente según `objective` sobre la matriz `cost[agente]
+/// [destino]` (infinito si no se llega). Se asignan tantos pares como se
+/// pueda; cada destino va a un agente como mucho.
+pub(crate) fn assign(cost: &[Vec<f64>], objective: AllocationObjective) -> Vec<Option<usize>> {
+    match objective {
+        AllocationObjective::TotalDistance => hungarian(cost),
+        AllocationObjective::Makespan => {
+            let mut thresholds: Vec<f64> = cost.iter().flatten().copied().collect();
+            thresholds.retain(|c| c.is_finite());
+            thresholds.sort_by(f64::total_cmp);
+            thresholds.dedup();
+            let most = matching(cost, f64::INFINITY);
+            let (mut lo, mut hi) = (0, thresholds.len());
+            while lo < hi {
+                let mid = (lo + hi) / 2;
+                if matching(cost, thresholds[mid]) == most {
+                    hi = mid;
+                } else {
+                    lo = mid + 1;
+                }
+            }
+            let Some(&limit) = thresholds.get(lo) else {
+                return vec![None; cost.len()];
+            };
+            let capped: Vec<Vec<f64>> = cost
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|&c| if c <= limit { c } else { f64::INFINITY })
+                        .collect()
+                })
+                .collect();
+            hungarian(&capped)
+        }
+        AllocationObjective::Greedy => {
+            let mut pairs: Vec<(f64, usize, usize)> = cost
+                .iter()
+                .enumerate()
+                .flat_map(|(a, row)| row.iter().enumerate().map(move |(g, &c)| (c, a, g)))
+                .filter(|p| p.0.is_finite())
+                .collect();
+            pairs.sort_by(|x, y| x.0.total_cmp(&y.0).then((x.1, x.2).cmp(&(y.1, y.2))));
+            let mut goals = vec![None; cost.len()];
+            let mut taken = vec![false; cost.first().map_or(0, Vec::len)];
+            for (_, a, g) in pairs {
+                if goals[a].is_none() && !taken[g] {
+                    goals[a] = Some(g);
+                    taken[g] = true;
+                }
+            }
+            goals
+        }
+    }
+}
+
+/// Tamaño del mayor emparejamiento usando solo pares de coste `limit` o
+/// menos (caminos aumentantes de Kuhn).
+fn matching(cost: &[Vec<f64>], limit: f64) -> usize {
+    let goals = cost.first().map_or(0, Vec::len);
+    let mut owner = vec![None; goals];
+    (0..cost.len())
+        .filter(|&a| augment(cost, limit, a, &mut owner))
+        .count()
+}
+
+/// Busca un camino aumentante desde el agente `root` y, si lo hay, lo
+/// aplica a `owner`. Búsqueda en profundidad con una pila explícita para no
+/// desbordar la del programa con muchos agentes: cada nivel guarda el
+/// agente y el siguiente destino que probar, y `via[i]` es el destino que
+/// lleva del nivel `i` al `i + 1`.
+fn augment(cost: &[Vec<f64>], limit: f64, root: usize, owner: &mut [Option<usize>]) -> bool {
+    let usable = |a: usize, g: usize| cost[a][g].is_finite() && cost[a][g] <= limit;
+    let mut seen = vec![false; owner.len()];
+    let mut stack = vec![(root, 0)];
+    let mut via = Vec::new();
+    while let Some(top) = stack.last_mut() {
+        let agent = top.0;
+        let Some(g) = (top.1..owner.len()).find(|&g| !seen[g] && usable(agent, g)) else {
+            stack.pop();
+            via.pop();
+            continue;
+        };
+        top.1 = g + 1;
+        seen[g] = true;
+        via.push(g);
+        match owner[g] {
+            Some(holder) => stack.push((holder, 0)),
+            None => {
+                for (&(agent, _), &goal) in stack.iter().zip(&via) {
+                    owner[goal] = Some(agent);
+                }
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Asignación de mínima suma (algoritmo húngaro con potenciales, O(n² m)).
+/// Los costes infinitos se cambian por uno mayor que cualquier suma finita,
+/// así se asignan primero tantos pares alcanzables como sea posible; los
+/// pares infinitos que queden se descartan.
+fn hungarian(cost: &[Vec<f64>]) -> Vec<Option<usize>> {
+    let (rows, cols) = (cost.len(), cost.first().map_or(0, Vec::len));
+    if rows == 0 || cols == 0 {
+        return vec![None; rows];
+    }
+    let finite: f64 = cost.iter().flatten().filter(|c| c.is_finite()).sum();
+    let big = finite + 1.0;
+    let transpose = rows > cols;
+    let (n, m) = if transpose {
+        (cols, rows)
+    } else {
+        (rows, cols)
+    };
+    let at = |i: usize, j: usize| {
+        let c = if transpose { cost[j][i] } else { cost[i][j] };
+        if c.is_finite() { c } else { big }
+    };
+
+    // Índices desde 1; la columna 0 es ficticia.
+    let mut u = vec![0.0; n + 1];
+    let mut v = vec![0.0; m + 1];
+    let mut row_of = vec![0; m + 1];
+    let mut way = vec![0; m + 1];
+    for i in 1..=n {
+        row_of[0] = i;
+        let mut j0 = 0;
+        let mut min = vec![f64::INFINITY; m + 1];
+        let mut used = vec![false; m + 1];
+        loop {
+            used[j0] = true;
+            let (i0, mut delta, mut j1) = (row_of[j0], f64::INFINITY, 0);
+            for j in 1..=m {
+                if !used[j] {
+                    let reduced = at(i0 - 1, j - 1) - u[i0] - v[j];
+                    if reduced < min[j] {
+                        min[j] = reduced;
+                        way[j] = j0;
+                    }
+                    if min[j] < delta {
+                        delta = min[j];
+                        j1 = j;
+                    }
+                }
+            }
+            for j in 0..=m {
+                if used[j] {
+                    u[row_of[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    min[j] -= delta;
+                }
+            }
+            j0 = j1;
+            if row_of[j0] == 0 {
+                break;
+            }
+        }
+        while j0 != 0 {
+            let j1 = way[j0];
+            row_of[j0] = row_of[j1];
+            j0 = j1;
+        }
+    }
+
+    let mut goals = vec![None; rows];
+    for (j, &i) in row_of.iter().enumerate().skip(1) {
+        if i == 0 {
+            continue;
+        }
+        let (i, j) = (i - 1, j - 1);
+        let (agent, goal) = if transpose { (j, i) } else { (i, j) };
+        if cost[agent][goal].is_finite() {
+            goals[agent] = Some(goal);
+        }
+    }
+    goals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: cada criterio elige su reparto y lo inalcanzable se queda fuera
+    #[test]
+    fn assignment_objectives() {
+        let inf = f64::INFINITY;
+        let cost = vec![vec![1.0, 2.0], vec![2.0, 10.0]];
+        assert_eq!(
+            assign(&cost, AllocationObjective::TotalDistance),
+            vec![Some(1), Some(0)]
+        );
+        assert_eq!(
+            assign(&cost, AllocationObjective::Greedy),
+            vec![Some(0), Some(1)]
+        );
+
+        // Suma mínima 1 + 9 = 10 con máximo 9; el máximo mínimo es 6.
+        let cost = vec![vec![1.0, 6.0], vec![5.0, 9.0]];
+        assert_eq!(
+            assign(&cost, AllocationObjective::TotalDistance),
+            vec![Some(0), Some(1)]
+        );
+        assert_eq!(
+            assign(&cost, AllocationObjective::Makespan),
+            vec![Some(1), Some(0)]
+        );
+
+        let cost = vec![vec![inf, 3.0], vec![inf, 1.0], vec![inf, 2.0]];
+        for objective in [
+            AllocationObjective::TotalDistance,
+            AllocationObjective::Makespan,
+            AllocationObjective::Greedy,
+        ] {
+            assert_eq!(assign(&cost, objective), vec![None, Some(1), None]);
+        }
+        assert!(assign(&[], AllocationObjective::Makespan).is_empty());
+    }
+}
@@ -0,0 +1,87 @@
+use crate::bytes::{Reader, Writer};
+
+const MAGIC: &[u8; 4] = b"PFPP";
+/// Versión 2: sección de landmarks. Se siguen leyendo los de la versión 1.
+const VERSION: u8 = 2;
+
+/// Secciones conocidas del contenedor de preprocesado.
+pub(crate) const SECTION_COMPONENTS: u8 = 1;
+pub(crate) const SECTION_LANDMARKS: u8 = 2;
+
+/// Contenedor de artefactos de preprocesado: la huella del grid para el que
+/// se calcularon y una lista de secciones `(etiqueta, u32s)`. Las etiquetas
+/// desconocidas se conservan al leer, así un build antiguo no rompe con
+/// artefactos nuevos.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Artifacts {
+    pub fingerprint: u64,
+    pub sections: Vec<(u8, Vec<u32>)>,
+}
+
+impl Artifacts {
+    pub fn section(&self, tag: u8) -> Option<&[u32]> {
+        self.sections
+            .iter()
+            .find(|(t, _)| *t == tag)
+            .map(|(_, data)| data.as_slice())
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut w = Writer::new();
+        w.bytes(MAGIC);
+        w.u8(VERSION);
+        w.u32(self.fingerprint as u32);
+        w.u32((self.fingerprint >> 32) as u32);
+        w.u32(self.sections.len() as u32);
+        for (tag, data) in &self.sections {
+            w.u8(*tag);
+            w.u32(data.len() as u32);
+            data.iter().for_each(|&v| w.u32(v));
+        }
+        w.finish()
+    }
+
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+        if r.bytes(4)? != MAGIC || !(1..=VERSION).contains(&r.u8()?) {
+            return None;
+        }
+        let fingerprint = u64::from(r.u32()?) | (u64::from(r.u32()?) << 32);
+        let count = r.u32()?;
+        let mut sections = Vec::new();
+        for _ in 0..count {
+            let tag = r.u8()?;
+            let len = r.u32()? as usize;
+            let data = (0..len).map(|_| r.u32()).collect::<Option<Vec<_>>>()?;
+            sections.push((tag, data));
+        }
+        r.is_empty().then_some(Self {
+            fingerprint,
+            sections,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: ida y vuelta del contenedor con varias secciones
+    #[test]
+    fn artifacts_roundtrip() {
+        let a = Artifacts {
+            fingerprint: 0x0123_4567_89AB_CDEF,
+            sections: vec![(SECTION_COMPONENTS, vec![0, 0, 1]), (9, vec![])],
+        };
+        let back = Artifacts::decode(&a.encode()).unwrap();
+        assert_eq!(back, a);
+        assert_eq!(back.section(SECTION_COMPONENTS), Some(&[0, 0, 1][..]));
+        assert_eq!(back.section(SECTION_LANDMARKS), None);
+
+        let mut old = a.encode();
+        old[4] = 1;
+        assert_eq!(Artifacts::decode(&old), Some(a));
+        old[4] = VERSION + 1;
+        assert_eq!(Artifacts::decode(&old), None);
+    }
+}
@@ -0,0 +1,167 @@
+use wasm_bindgen::prelude::*;
+
+use crate::distance::{self, UNREACHED};
+use crate::grid::Grid;
+use crate::rooms;
+
+/// Informe de equilibrio entre dos puntos de aparición
+/// (`PathFinder::validate_balance`) para aceptar o descartar un mapa
+/// generado. Las listas van en el orden de los objetivos.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BalanceReport {
+    distances_a: Vec<f64>,
+    distances_b: Vec<f64>,
+    chokepoints_a: Vec<u32>,
+    chokepoints_b: Vec<u32>,
+    safe_area_a: usize,
+    safe_area_b: usize,
+}
+
+#[wasm_bindgen]
+impl BalanceReport {
+    /// Pasos a pie de A a cada objetivo (infinito si no llega).
+    pub fn distances_a(&self) -> Vec<f64> {
+        self.distances_a.clone()
+    }
+
+    pub fn distances_b(&self) -> Vec<f64> {
+        self.distances_b.clone()
+    }
+
+    /// Estrechamientos (tramos de celdas con pared a ambos lados) en la ruta
+    /// más corta de A a cada objetivo.
+    pub fn chokepoints_a(&self) -> Vec<u32> {
+        self.chokepoints_a.clone()
+    }
+
+    pub fn chokepoints_b(&self) -> Vec<u32> {
+        self.chokepoints_b.clone()
+    }
+
+    /// Celdas a las que A llega antes (a pie) que B: su zona segura.
+    pub fn safe_area_a(&self) -> usize {
+        self.safe_area_a
+    }
+
+    pub fn safe_area_b(&self) -> usize {
+        self.safe_area_b
+    }
+
+    /// Mayor diferencia de distancia a un objetivo entre A y B (infinito si
+    /// alguno no llega a algún objetivo; 0 sin objetivos).
+    pub fn max_distance_gap(&self) -> f64 {
+        let gaps = self.distances_a.iter().zip(&self.distances_b);
+        gaps.fold(0.0, |worst, (a, b)| {
+            let gap = if a.is_finite() && b.is_finite() {
+                (a - b).abs()
+            } else {
+                f64::INFINITY
+            };
+            f64::max(worst, gap)
+        })
+    }
+
+    /// Si el mapa es justo: ambos llegan a todos los objetivos con una
+    /// diferencia de `distance_tolerance` pasos como mucho, con los mismos
+    /// estrechamientos por el camino, y las zonas seguras no difieren en más
+    /// de la fracción `area_tolerance` de la mayor.
+    pub fn fair(&self, distance_tolerance: f64, area_tolerance: f64) -> bool {
+        let (a, b) = (self.safe_area_a as f64, self.safe_area_b as f64);
+        self.max_distance_gap() <= distance_tolerance
+            && self.chokepoints_a == self.chokepoints_b
+            && (a - b).abs() <= area_tolerance * a.max(b)
+    }
+}
+
+/// Compara los puntos de aparición `a` y `b` frente a `objectives`:
+/// distancia a pie, estrechamientos en la ruta más corta (BFS) y zona
+/// segura. Las empatadas no son de ninguno.
+pub(crate) fn report(
+    grid: &Grid,
+    a: (usize, usize),
+    b: (usize, usize),
+    objectives: &[(usize, usize)],
+) -> BalanceReport {
+    let (from_a, _) = distance::bfs_distances(grid, &[a], u32::MAX);
+    let (from_b, _) = distance::bfs_distances(grid, &[b], u32::MAX);
+    let per_objective = |spawn: (usize, usize), dist: &[u32]| -> (Vec<f64>, Vec<u32>) {
+        objectives
+            .iter()
+            .map(|&goal| {
+                let d = if grid.in_bounds(goal) {
+                    dist[grid.idx(goal)]
+                } else {
+                    UNREACHED
+                };
+                if d == UNREACHED {
+                    return (f64::INFINITY, 0);
+                }
+                let target = grid.idx(goal);
+                let path = distance::bfs_to_goal(grid, None, spawn, |idx| idx == target).0;
+                (f64::from(d), path.map_or(0, |p| chokepoints(grid, &p)))
+            })
+            .unzip()
+    };
+    let (distances_a, chokepoints_a) = per_objective(a, &from_a);
+    let (distances_b, chokepoints_b) = per_objective(b, &from_b);
+    let closer = |mine: &[u32], theirs: &[u32]| {
+        mine.iter()
+            .zip(theirs)
+            .filter(|&(m, t)| *m != UNREACHED && m < t)
+            .count()
+    };
+    BalanceReport {
+        distances_a,
+        distances_b,
+        chokepoints_a,
+        chokepoints_b,
+        safe_area_a: closer(&from_a, &from_b),
+        safe_area_b: closer(&from_b, &from_a),
+    }
+}
+
+/// Tramos de celdas seguidas con pared a ambos lados en `path`, sin contar
+/// los extremos.
+fn chokepoints(grid: &Grid, path: &[usize]) -> u32 {
+    let inner = path.get(1..path.len().saturating_sub(1)).unwrap_or(&[]);
+    let narrow: Vec<bool> = inner.iter().map(|&idx| rooms::pinched(grid, idx)).collect();
+    let starts = narrow.iter().enumerate();
+    starts
+        .filter(|&(i, &n)| n && (i == 0 || !narrow[i - 1]))
+        .count() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: un mapa simétrico es justo; tapar el paso de un lado obliga a
+    /// un rodeo y deja de serlo
+    #[test]
+    fn balance_of_symmetric_and_broken_maps() {
+        #[rustfmt::skip]
+        let mut cells = vec![
+            1, 1, 1, 1, 1,
+            1, 0, 1, 0, 1,
+            1, 1, 1, 1, 1,
+            1, 0, 1, 0, 1,
+            1, 1, 1, 1, 1,
+        ];
+        let grid = Grid::square(cells.clone(), 5);
+        let fair = report(&grid, (0, 0), (4, 4), &[(2, 2)]);
+        assert_eq!(fair.distances_a(), vec![4.0]);
+        assert_eq!(fair.distances_a(), fair.distances_b());
+        assert_eq!(fair.safe_area_a(), fair.safe_area_b());
+        assert!(fair.fair(0.0, 0.0));
+
+        cells[2 * 5 + 3] = 0;
+        cells[3 * 5 + 2] = 0;
+        let grid = Grid::square(cells, 5);
+        let broken = report(&grid, (0, 0), (4, 4), &[(2, 2)]);
+        assert_eq!(broken.distances_b(), vec![8.0]);
+        assert_eq!(broken.max_distance_gap(), 4.0);
+        assert!(!broken.fair(3.0, 1.0));
+        assert!(broken.safe_area_a() > broken.safe_area_b());
+    }
+}
@@ -0,0 +1,74 @@
+use crate::distance::{self, UNREACHED};
+use crate::grid::Grid;
+use crate::rng::Rng;
+
+/// Reparte las celdas alcanzables desde `start` en franjas de distancia a
+/// pie de `band_width` pasos (la franja `i` va de `i * band_width` a
+/// `(i + 1) * band_width - 1`) y elige al azar `per_band[i]` celdas de cada
+/// una (todas si hay menos). Devuelve `(celda, franja)` por franja y, dentro
+/// de cada una, en el orden del sorteo; la misma `seed` da las mismas celdas.
+pub(crate) fn sample(
+    grid: &Grid,
+    start: (usize, usize),
+    band_width: u32,
+    per_band: &[usize],
+    seed: u32,
+) -> Vec<(usize, usize)> {
+    if per_band.is_empty() {
+        return vec![];
+    }
+    let band_width = band_width.max(1);
+    let depth = (per_band.len() as u32)
+        .saturating_mul(band_width)
+        .saturating_sub(1);
+    let (dist, order) = distance::bfs_distances(grid, &[start], depth);
+    let mut bands = vec![Vec::new(); per_band.len()];
+    for idx in order.into_iter().filter(|&idx| dist[idx] != UNREACHED) {
+        if let Some(band) = bands.get_mut((dist[idx] / band_width) as usize) {
+            band.push(idx);
+        }
+    }
+    let mut rng = Rng::new(u64::from(seed));
+    let mut picked = Vec::new();
+    for (band, (cells, &wanted)) in bands.iter_mut().zip(per_band).enumerate() {
+        cells.sort_unstable();
+        let wanted = wanted.min(cells.len());
+        for i in 0..wanted {
+            let j = i + rng.below(cells.len() - i);
+            cells.swap(i, j);
+            picked.push((cells[i], band));
+        }
+    }
+    picked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: cada celda sale de su franja de distancia a pie, no en línea
+    /// recta, y la semilla fija el sorteo
+    #[test]
+    fn loot_by_walking_distance() {
+        #[rustfmt::skip]
+        let cells = vec![
+            1, 1, 1, 1,
+            0, 0, 0, 1,
+            1, 1, 1, 1,
+        ];
+        let grid = Grid::new(cells, 4, 3);
+        let picked = sample(&grid, (0, 0), 3, &[2, 2, 5], 7);
+        let dist = distance::bfs_distances(&grid, &[(0, 0)], u32::MAX).0;
+        for &(idx, band) in &picked {
+            assert_eq!(dist[idx] / 3, band as u32);
+        }
+        let per_band: Vec<_> = (0..3)
+            .map(|b| picked.iter().filter(|p| p.1 == b).count())
+            .collect();
+        assert_eq!(per_band, vec![2, 2, 3]);
+        // (2, 0) está a dos filas del inicio pero a 8 pasos a pie.
+        assert!(picked.contains(&(8, 2)));
+        assert_eq!(picked, sample(&grid, (0, 0), 3, &[2, 2, 5], 7));
+        assert!(sample(&grid, (0, 0), 3, &[], 7).is_empty());
+    }
+}
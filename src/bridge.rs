@@ -0,0 +1,180 @@
+use std::collections::VecDeque;
+
+use crate::distance;
+use crate::grid::Grid;
+use crate::search::{self, Outcome};
+
+/// Coste de abrir una pared: su vida si tiene (`Grid::hit_points`), o 1.
+fn unblock_cost(grid: &Grid, idx: usize) -> f64 {
+    grid.hit_points
+        .as_ref()
+        .map(|hp| f64::from(hp[idx]))
+        .filter(|hp| hp.is_finite() && *hp > 0.0)
+        .unwrap_or(1.0)
+}
+
+/// Paredes más baratas de abrir para unir la región de `a` con la de `b`
+/// (índices de celda, en orden de `a` a `b`): Dijkstra en el que entrar en
+/// una celda libre cuesta 0 y en una pared `unblock_cost`. Vacío si ya están
+/// conectadas.
+pub(crate) fn cheapest_bridge(grid: &Grid, a: (usize, usize), b: (usize, usize)) -> Vec<usize> {
+    let enter = |idx: usize| {
+        if grid.cells[idx] == 1 {
+            0.0
+        } else {
+            unblock_cost(grid, idx)
+        }
+    };
+    let (source, target) = (grid.idx(a), grid.idx(b));
+    let edges = |idx| {
+        let neighbors = grid.neighbors(grid.coords(idx)).map(|next| grid.idx(next));
+        neighbors.map(|n| (n, enter(n)))
+    };
+    let seed = [(source, enter(source))];
+    let (_, parent) = distance::dijkstra_by(grid.cells.len(), seed, edges, |idx, _| idx != target);
+    walls_on_path(grid, &parent, source, target)
+}
+
+/// Mínimo número de paredes que hay que quitar para unir `start` con `goal`
+/// (índices de celda, en orden): BFS 0-1 en el que entrar en una pared
+/// cuesta 1 y en una celda libre 0. Vacío si ya hay ruta.
+pub(crate) fn fewest_walls(grid: &Grid, start: (usize, usize), goal: (usize, usize)) -> Vec<usize> {
+    let wall = |idx: usize| u32::from(grid.cells[idx] != 1);
+    let (source, target) = (grid.idx(start), grid.idx(goal));
+    let mut dist = vec![u32::MAX; grid.cells.len()];
+    let mut parent = vec![usize::MAX; grid.cells.len()];
+    dist[source] = wall(source);
+    let mut queue = VecDeque::from([source]);
+    while let Some(idx) = queue.pop_front() {
+        if idx == target {
+            break;
+        }
+        for next in grid.neighbors(grid.coords(idx)) {
+            let n = grid.idx(next);
+            let nd = dist[idx] + wall(n);
+            if nd < dist[n] {
+                dist[n] = nd;
+                parent[n] = idx;
+                if wall(n) == 0 {
+                    queue.push_front(n);
+                } else {
+                    queue.push_back(n);
+                }
+            }
+        }
+    }
+    walls_on_path(grid, &parent, source, target)
+}
+
+/// Ruta más corta de `start` a `goal` que atraviesa como mucho `k` paredes
+/// (agentes con bombas, análisis de si un nivel tiene solución). Busca sobre
+/// estados `(celda, paredes usadas)`; todos los pasos cuestan 1.
+pub(crate) fn breach_search(
+    grid: &Grid,
+    start: (usize, usize),
+    goal: (usize, usize),
+    k: usize,
+) -> Outcome<(usize, usize)> {
+    let k = k.min(grid.cells.len());
+    let wall = |cell: (usize, usize)| usize::from(!grid.walkable(cell));
+    if !grid.in_bounds(start) || !grid.in_bounds(goal) || wall(start) > k {
+        return Outcome::none();
+    }
+    search::astar(
+        (start, wall(start)),
+        |&(cell, _)| cell == goal,
+        |&(cell, used)| {
+            grid.neighbors(cell)
+                .map(move |n| ((n, used + wall(n)), 1.0))
+                .filter(move |&((_, used), _)| used <= k)
+        },
+        |&((x, y), _)| (x.abs_diff(goal.0) + y.abs_diff(goal.1)) as f64,
+    )
+    .map(|(cell, _)| cell)
+}
+
+/// Paredes de la ruta de `source` a `target` según `parent`, en orden.
+fn walls_on_path(grid: &Grid, parent: &[usize], source: usize, target: usize) -> Vec<usize> {
+    let mut walls = Vec::new();
+    let mut cur = target;
+    loop {
+        if grid.cells[cur] != 1 {
+            walls.push(cur);
+        }
+        if cur == source {
+            break;
+        }
+        cur = parent[cur];
+    }
+    walls.reverse();
+    walls
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: abre el muro por donde es más fino o más débil
+    #[test]
+    fn bridge_through_cheapest_wall() {
+        #[rustfmt::skip]
+        let cells = vec![
+            1, 0, 0, 1,
+            1, 0, 1, 1,
+            1, 0, 0, 1,
+        ];
+        let mut grid = Grid::new(cells, 4, 3);
+        assert_eq!(cheapest_bridge(&grid, (0, 0), (0, 3)), vec![5]);
+
+        let mut hp = vec![5.0; 12];
+        (hp[1], hp[2]) = (1.0, 1.0);
+        grid.hit_points = Some(hp);
+        assert_eq!(cheapest_bridge(&grid, (0, 0), (0, 3)), vec![1, 2]);
+        assert!(cheapest_bridge(&grid, (0, 0), (2, 0)).is_empty());
+    }
+
+    /// Test: cuenta las paredes mínimas aunque haya rutas más cortas con más
+    #[test]
+    fn fewest_walls_between_corners() {
+        #[rustfmt::skip]
+        let cells = vec![
+            1, 0, 0, 0,
+            1, 1, 1, 0,
+            0, 0, 1, 0,
+            0, 0, 1, 1,
+        ];
+        let grid = Grid::square(cells, 4);
+        assert!(fewest_walls(&grid, (0, 0), (3, 3)).is_empty());
+        let mut sealed = grid.clone();
+        sealed.cells[10] = 0;
+        assert_eq!(fewest_walls(&sealed, (0, 0), (3, 3)), vec![10]);
+        let blocked = Grid::square(vec![1, 0, 0, 0, 0, 0, 0, 0, 1], 3);
+        assert_eq!(fewest_walls(&blocked, (0, 0), (2, 2)).len(), 3);
+    }
+
+    /// Test: atravesar una pared acorta la ruta y sin permiso no hay salida
+    #[test]
+    fn breach_search_shortens_with_more_removals() {
+        #[rustfmt::skip]
+        let cells = vec![
+            1, 0, 1, 1, 1,
+            1, 0, 1, 0, 1,
+            1, 0, 1, 0, 1,
+            1, 1, 1, 0, 1,
+        ];
+        let grid = Grid::new(cells, 5, 4);
+        let cost = |k| {
+            breach_search(&grid, (0, 0), (0, 4), k)
+                .result
+                .map(|f| f.cost)
+        };
+        assert_eq!(cost(0), Some(10.0));
+        assert_eq!(cost(1), Some(4.0));
+        assert_eq!(cost(5), Some(4.0));
+        assert!(
+            breach_search(&Grid::square(vec![1, 0, 0, 1], 2), (0, 0), (1, 1), 0)
+                .result
+                .is_none()
+        );
+    }
+}
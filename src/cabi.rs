@@ -0,0 +1,287 @@
+use std::alloc::{self, Layout};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::slice;
+
+use crate::grid::Grid;
+use crate::policy::{self, Anomaly};
+use crate::{PathFinder, SearchOptions, clock, handles};
+
+/// Alineación de los búferes de `pf_alloc`, suficiente para `u32` y `f64`.
+const ALIGN: usize = 8;
+
+/// Nombre de la capa de coste de `pf_set_costs`.
+const COSTS: &str = "pf_set_costs";
+
+// Ninguna función `pf_*` llama a JS: las anomalías se devuelven como código
+// de error (ver `pf_last_error`) en vez de crear un `JsError`, y el reloj de
+// JS se apaga mientras corren (`clock::offline`). Así un host sin
+// wasm-bindgen puede enlazar los imports `__wbindgen_*` del módulo a stubs
+// que aborten: las demás exportaciones los usan, estas no.
+thread_local! {
+    static LAST_ERROR: RefCell<String> = const { RefCell::new(String::new()) };
+    // Opciones de `pf_set_option` por grid, las de `pf_query`.
+    static OPTIONS: RefCell<HashMap<u32, SearchOptions>> = RefCell::new(HashMap::new());
+}
+
+/// Ejecuta `f` sin el reloj de JS y guarda su error para `pf_last_error`
+/// (o lo borra si fue bien).
+fn call<T>(f: impl FnOnce() -> Result<T, Anomaly>) -> Option<T> {
+    let result = clock::offline(f);
+    LAST_ERROR.with(|e| {
+        let mut e = e.borrow_mut();
+        e.clear();
+        if let Err(Anomaly(message)) = &result {
+            e.push_str(message);
+        }
+    });
+    result.ok()
+}
+
+/// 1 si `done`, 0 si no y -1 si hubo error.
+fn status(done: Option<bool>) -> i32 {
+    done.map_or(-1, i32::from)
+}
+
+/// Reserva `len` bytes alineados a 8 en la memoria del módulo, para que el
+/// host escriba ahí el grid o reciba una ruta. Nulo si `len` es 0 o no hay
+/// memoria.
+#[unsafe(no_mangle)]
+pub extern "C" fn pf_alloc(len: usize) -> *mut u8 {
+    match Layout::from_size_align(len, ALIGN) {
+        Ok(layout) if len > 0 => unsafe { alloc::alloc(layout) },
+        _ => std::ptr::null_mut(),
+    }
+}
+
+/// Libera un búfer de `pf_alloc`.
+///
+/// # Safety
+///
+/// `ptr` debe venir de `pf_alloc(len)` con el mismo `len` y no haberse
+/// liberado ya (nulo no hace nada).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pf_free(ptr: *mut u8, len: usize) {
+    if let (false, Ok(layout)) = (ptr.is_null(), Layout::from_size_align(len, ALIGN)) {
+        unsafe { alloc::dealloc(ptr, layout) };
+    }
+}
+
+/// Copia en `out` (hasta `capacity` bytes, UTF-8 sin terminador) el error
+/// de la última función `pf_*` y devuelve su longitud entera; 0 si fue
+/// bien. Las anomalías siguen `set_policy`: en modo tolerante las funciones
+/// siguen con su alternativa y no hay error.
+///
+/// # Safety
+///
+/// `out` debe apuntar a `capacity` bytes escribibles (o ser nulo con
+/// `capacity` 0).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pf_last_error(out: *mut u8, capacity: usize) -> usize {
+    LAST_ERROR.with(|e| {
+        let e = e.borrow();
+        if !out.is_null() {
+            let out = unsafe { slice::from_raw_parts_mut(out, capacity) };
+            let n = e.len().min(capacity);
+            out[..n].copy_from_slice(&e.as_bytes()[..n]);
+        }
+        e.len()
+    })
+}
+
+/// `create_grid` con las `len` celdas de `cells`: un grid de `size` x
+/// `size`. Devuelve su identificador, o 0 si hubo error (también con `size`
+/// 0).
+///
+/// # Safety
+///
+/// `cells` debe apuntar a `len` bytes legibles (o ser nulo con `len` 0).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pf_create(cells: *const u8, len: usize, size: usize) -> u32 {
+    let cells = if cells.is_null() {
+        Vec::new()
+    } else {
+        unsafe { slice::from_raw_parts(cells, len) }.to_vec()
+    };
+    call(|| {
+        if size == 0 {
+            return Err(Anomaly("grid vacío".to_string()));
+        }
+        let Some(n) = size.checked_mul(size) else {
+            return Err(Anomaly("el grid es demasiado grande".to_string()));
+        };
+        let cells = policy::fit_cells(cells, n)?;
+        let pf = PathFinder::from_grid(Grid::square(cells, size));
+        Ok(handles::insert(pf))
+    })
+    .unwrap_or(0)
+}
+
+/// `destroy_grid`, junto con sus opciones.
+#[unsafe(no_mangle)]
+pub extern "C" fn pf_destroy(handle: u32) -> bool {
+    OPTIONS.with(|o| o.borrow_mut().remove(&handle));
+    handles::destroy_grid(handle)
+}
+
+/// `set_grid_cell`: 1 si cambió la celda, 0 si no y -1 si hubo error.
+#[unsafe(no_mangle)]
+pub extern "C" fn pf_set_cell(handle: u32, x: usize, y: usize, value: u8) -> i32 {
+    status(call(|| {
+        let set = handles::with_grid(handle, |pf| Ok::<_, Anomaly>(pf.set_cell(x, y, value)))?;
+        Ok(set.unwrap_or(false))
+    }))
+}
+
+/// Coste extra por celda (`PathFinder::add_cost_overlay` sin ventanas de
+/// tiempo) desde las `len` entradas de `costs`, que reemplaza al anterior;
+/// nulo lo quita. 1 si se aplicó, 0 si no y -1 si hubo error.
+///
+/// # Safety
+///
+/// `costs` debe apuntar a `len` valores `f32` legibles (o ser nulo).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pf_set_costs(handle: u32, costs: *const f32, len: usize) -> i32 {
+    let costs = (!costs.is_null()).then(|| unsafe { slice::from_raw_parts(costs, len) }.to_vec());
+    status(call(|| {
+        let applied = handles::with_grid(handle, |pf| match costs {
+            Some(costs) => pf.insert_overlay(COSTS, costs, &[]),
+            None => Ok(pf.remove_cost_overlay(COSTS)),
+        })?;
+        Ok(applied.unwrap_or(false))
+    }))
+}
+
+/// Fija una opción de búsqueda de las consultas del grid `handle` (como
+/// los métodos de `SearchOptions`): 0 = coste máximo (`set_max_cost`,
+/// negativo quita la cota), 1 = coste por punto de vida (`set_break_cost`),
+/// 2 = coste de rotación (`set_rotation_cost`), 3 = radio de relajación del
+/// destino (`set_goal_relaxation`), 4 = ancho mínimo (`set_min_width`) y
+/// 5 = sin media vuelta (`set_no_reverse`, distinto de 0 para activarla).
+/// 1 si se aplicó y -1 si el grid o la opción no existen o el valor no es
+/// un número.
+#[unsafe(no_mangle)]
+pub extern "C" fn pf_set_option(handle: u32, option: u32, value: f64) -> i32 {
+    status(call(|| {
+        let known = handles::with_grid(handle, |_| Ok::<_, Anomaly>(()))?.is_some();
+        if !known || value.is_nan() || option > 5 {
+            return Err(Anomaly("opción de búsqueda no válida".to_string()));
+        }
+        OPTIONS.with(|o| {
+            let mut o = o.borrow_mut();
+            let opts = o.entry(handle).or_default();
+            let whole = value.clamp(0.0, f64::from(u32::MAX)) as u32;
+            match option {
+                0 => opts.set_max_cost((value >= 0.0).then_some(value as f32)),
+                1 => opts.set_break_cost(value as f32),
+                2 => opts.set_rotation_cost(value as f32),
+                3 => opts.set_goal_relaxation(whole),
+                4 => opts.set_min_width(whole),
+                _ => opts.set_no_reverse(value != 0.0, None),
+            }
+        });
+        Ok(true)
+    }))
+}
+
+/// `query` de `(sx, sy)` a `(gx, gy)` con las opciones de `pf_set_option`:
+/// escribe la ruta como `[x0, y0, x1, y1, ...]` en `out`, hasta `capacity`
+/// valores, y devuelve cuántos tiene entera (0 si no hay camino, -1 si hubo
+/// error). Si es más que `capacity` la ruta queda cortada y se puede
+/// repetir con un búfer mayor.
+///
+/// # Safety
+///
+/// `out` debe apuntar a `capacity` valores `u32` escribibles (o ser nulo
+/// con `capacity` 0).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pf_query(
+    handle: u32,
+    sx: usize,
+    sy: usize,
+    gx: usize,
+    gy: usize,
+    out: *mut u32,
+    capacity: usize,
+) -> isize {
+    let options = OPTIONS.with(|o| o.borrow().get(&handle).cloned().unwrap_or_default());
+    let Some(path) = call(|| handles::route(handle, &options, (sx, sy), (gx, gy))) else {
+        return -1;
+    };
+    if !out.is_null() {
+        let out = unsafe { slice::from_raw_parts_mut(out, capacity) };
+        for (slot, &v) in out.iter_mut().zip(&path) {
+            *slot = v as u32;
+        }
+    }
+    path.len() as isize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{Policy, set_policy};
+
+    /// Test: el ciclo de vida completo por la API C con búferes del módulo
+    #[test]
+    fn c_abi_round_trip() {
+        let cells = pf_alloc(9);
+        unsafe { slice::from_raw_parts_mut(cells, 9) }
+            .copy_from_slice(&[1, 1, 1, 0, 0, 1, 1, 1, 1]);
+        let handle = unsafe { pf_create(cells, 9, 3) };
+        unsafe { pf_free(cells, 9) };
+
+        let mut out = [0u32; 4];
+        let needed = unsafe { pf_query(handle, 0, 0, 2, 2, out.as_mut_ptr(), out.len()) };
+        assert_eq!((needed, out), (10, [0, 0, 0, 1]));
+        let mut out = vec![0u32; needed as usize];
+        unsafe { pf_query(handle, 0, 0, 2, 2, out.as_mut_ptr(), out.len()) };
+        assert_eq!(out, vec![0, 0, 0, 1, 0, 2, 1, 2, 2, 2]);
+
+        assert_eq!(pf_set_cell(handle, 1, 2, 0), 1);
+        assert_eq!(
+            unsafe { pf_query(handle, 0, 0, 2, 2, std::ptr::null_mut(), 0) },
+            0
+        );
+        assert!(pf_destroy(handle) && !pf_destroy(handle));
+        assert!(pf_alloc(0).is_null());
+    }
+
+    /// Test: costes y opciones cambian la ruta, y los errores vuelven como
+    /// códigos sin pasar por wasm-bindgen (fuera de wasm un `JsError` o una
+    /// excepción de JS abortarían la prueba)
+    #[test]
+    fn c_abi_costs_options_and_errors() {
+        let handle = unsafe { pf_create([1u8; 9].as_ptr(), 9, 3) };
+        let query =
+            |out: &mut [u32]| unsafe { pf_query(handle, 0, 0, 0, 2, out.as_mut_ptr(), out.len()) };
+        let mut out = [0u32; 10];
+        assert_eq!(query(&mut out), 6);
+
+        let costs = [0.0, 9.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+        assert_eq!(unsafe { pf_set_costs(handle, costs.as_ptr(), 9) }, 1);
+        assert_eq!(query(&mut out), 10);
+        assert_eq!(pf_set_option(handle, 0, 2.0), 1);
+        assert_eq!(query(&mut out), 0);
+        assert_eq!(pf_set_option(handle, 0, -1.0), 1);
+        assert_eq!(unsafe { pf_set_costs(handle, std::ptr::null(), 0) }, 1);
+        assert_eq!(query(&mut out), 6);
+
+        assert_eq!(pf_set_option(handle, 9, 1.0), -1);
+        let mut message = [0u8; 64];
+        let len = unsafe { pf_last_error(message.as_mut_ptr(), message.len()) };
+        assert_eq!(&message[..len], "opción de búsqueda no válida".as_bytes());
+
+        set_policy(Policy::Strict);
+        assert_eq!(unsafe { pf_create([1u8; 4].as_ptr(), 4, 3) }, 0);
+        assert_eq!(pf_set_cell(handle + 100, 0, 0, 0), -1);
+        assert_eq!(query(&mut out[..0]), 6);
+        assert_eq!(unsafe { pf_last_error(std::ptr::null_mut(), 0) }, 0);
+        set_policy(Policy::Lenient);
+        assert!(pf_destroy(handle));
+
+        assert_eq!(unsafe { pf_create(std::ptr::null(), 0, 0) }, 0);
+        let len = unsafe { pf_last_error(message.as_mut_ptr(), message.len()) };
+        assert_eq!(&message[..len], "grid vacío".as_bytes());
+    }
+}
@@ -0,0 +1,85 @@
+use crate::floatcost;
+use crate::grid::Grid;
+
+/// Límites de los multiplicadores ajustados.
+const MIN_MULTIPLIER: f32 = 0.1;
+const MAX_MULTIPLIER: f32 = 10.0;
+/// Pasadas de la escalada y factor inicial de cada paso.
+const ROUNDS: usize = 40;
+const INITIAL_STEP: f32 = 1.25;
+
+/// Multiplicador de coste por tipo de terreno (de 0 al mayor de `terrain`)
+/// con el que las rutas observadas `paths` se parecen más a las óptimas:
+/// minimiza la suma de `coste observado / coste óptimo - 1` entre sus
+/// extremos, que es 0 cuando todas son óptimas y no cambia al escalar todos
+/// los multiplicadores a la vez. Escalada sencilla desde 1: en cada pasada
+/// prueba a multiplicar y dividir cada terreno por el paso y se queda con lo
+/// que mejora; si nada mejora, reduce el paso. Determinista.
+pub(crate) fn calibrate(grid: &Grid, terrain: &[u8], paths: &[Vec<(usize, usize)>]) -> Vec<f32> {
+    let labels = terrain.iter().max().map_or(0, |&m| usize::from(m) + 1);
+    let paths: Vec<&Vec<(usize, usize)>> = paths
+        .iter()
+        .filter(|p| p.len() >= 2 && p.iter().all(|&c| grid.in_bounds(c)))
+        .collect();
+    let mut weights = vec![1.0; labels];
+    let mut best = loss(grid, terrain, &paths, &weights);
+    let mut step = INITIAL_STEP;
+    for _ in 0..ROUNDS {
+        if best <= 0.0 {
+            break;
+        }
+        let mut improved = false;
+        for label in 0..labels {
+            for factor in [step, 1.0 / step] {
+                let mut trial = weights.clone();
+                trial[label] = (trial[label] * factor).clamp(MIN_MULTIPLIER, MAX_MULTIPLIER);
+                let score = loss(grid, terrain, &paths, &trial);
+                if score < best {
+                    (weights, best, improved) = (trial, score, true);
+                }
+            }
+        }
+        if !improved {
+            step = step.sqrt();
+        }
+    }
+    weights
+}
+
+/// Sobrecoste relativo total de las rutas observadas con `weights`. Las que
+/// no tienen ruta óptima (extremos bloqueados) no cuentan.
+fn loss(grid: &Grid, terrain: &[u8], paths: &[&Vec<(usize, usize)>], weights: &[f32]) -> f64 {
+    let costs: Vec<f32> = terrain.iter().map(|&t| weights[usize::from(t)]).collect();
+    paths
+        .iter()
+        .filter_map(|path| {
+            let (start, goal) = (path[0], path[path.len() - 1]);
+            let (_, optimum) = floatcost::cheapest_path(grid, &costs, start, goal).0?;
+            let observed: f64 = path[1..]
+                .iter()
+                .map(|&c| f64::from(costs[grid.idx(c)]))
+                .sum();
+            (optimum > 0.0).then(|| (observed / optimum - 1.0).max(0.0))
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: si la gente va por el camino aunque dé un rodeo, el camino sale
+    /// bastante más barato que la hierba
+    #[test]
+    fn calibrate_prefers_observed_terrain() {
+        let grid = Grid::square(vec![1; 9], 3);
+        let terrain = [0, 0, 0, 1, 1, 1, 0, 0, 0];
+        let observed = vec![(0, 0), (1, 0), (1, 1), (1, 2), (0, 2)];
+        let weights = calibrate(&grid, &terrain, std::slice::from_ref(&observed));
+        assert_eq!(weights.len(), 2);
+        assert!(weights[1] * 3.0 <= weights[0]);
+        assert_eq!(loss(&grid, &terrain, &[&observed], &weights), 0.0);
+
+        assert_eq!(calibrate(&grid, &terrain, &[]), vec![1.0, 1.0]);
+    }
+}
@@ -0,0 +1,116 @@
+use wasm_bindgen::prelude::*;
+
+use crate::SearchOptions;
+use crate::compare;
+use crate::generate;
+use crate::grid::Grid;
+
+/// Algoritmos de `PathFinder::compare`; todos salvo "greedy" dan la ruta
+/// más corta.
+const ALGORITHMS: [&str; 6] = ["bfs", "dijkstra", "astar", "01bfs", "greedy", "rectangles"];
+
+/// Qué trae este binario (`capabilities`), para detectar en tiempo de
+/// ejecución lo que falta en builds distintos y degradar con elegancia.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct Capabilities {
+    features: Vec<&'static str>,
+    failures: Vec<String>,
+}
+
+#[wasm_bindgen]
+impl Capabilities {
+    /// Funcionalidades compiladas: siempre "images" (`from_floorplan`),
+    /// "generators" y "costmaps", y según el build "threads" (atómicos de
+    /// wasm), "simd" (simd128), "debug" y "c-abi".
+    pub fn features(&self) -> Vec<String> {
+        self.features.iter().map(|f| f.to_string()).collect()
+    }
+
+    /// Algoritmos que acepta `PathFinder::compare`.
+    pub fn algorithms(&self) -> Vec<String> {
+        ALGORITHMS.iter().map(|a| a.to_string()).collect()
+    }
+
+    /// Si `name` es una funcionalidad o un algoritmo disponible.
+    pub fn has(&self, name: &str) -> bool {
+        self.features.contains(&name) || ALGORITHMS.contains(&name)
+    }
+
+    /// Si la autoprueba salió bien.
+    pub fn self_test_passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Qué falló en la autoprueba (nombres de algoritmo o de generador).
+    pub fn self_test_failures(&self) -> Vec<String> {
+        self.failures.clone()
+    }
+}
+
+/// Funcionalidades de este binario y el resultado de una autoprueba rápida:
+/// cada algoritmo resuelve un laberinto pequeño de solución conocida y el
+/// generador da el mismo mapa con la misma semilla.
+#[wasm_bindgen]
+pub fn capabilities() -> Capabilities {
+    let mut features = vec!["images", "generators", "costmaps"];
+    if cfg!(target_feature = "atomics") {
+        features.push("threads");
+    }
+    if cfg!(target_feature = "simd128") {
+        features.push("simd");
+    }
+    if cfg!(feature = "debug") {
+        features.push("debug");
+    }
+    if cfg!(feature = "c-abi") {
+        features.push("c-abi");
+    }
+    Capabilities {
+        features,
+        failures: self_test(),
+    }
+}
+
+/// Nombres de lo que no pasa la autoprueba.
+fn self_test() -> Vec<String> {
+    #[rustfmt::skip]
+    let cells = vec![
+        1, 1, 1, 1, 1,
+        0, 0, 0, 0, 1,
+        1, 1, 1, 1, 1,
+        1, 0, 0, 0, 0,
+        1, 1, 1, 1, 1,
+    ];
+    let grid = Grid::square(cells, 5);
+    let options = SearchOptions::default();
+    let mut failures: Vec<String> = ALGORITHMS
+        .iter()
+        .filter(|&&name| {
+            let found = compare::run(&grid, name, &options, (0, 0), (4, 4))
+                .and_then(|outcome| outcome.result);
+            found.is_none_or(|f| f.cost != 16.0 || f.states.len() != 17)
+        })
+        .map(|name| name.to_string())
+        .collect();
+    let map = generate::drunkard_walk(8, 2, 50, 0.5, 7);
+    if map != generate::drunkard_walk(8, 2, 50, 0.5, 7) || !map.contains(&1) {
+        failures.push("drunkard_walk".to_string());
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: la autoprueba pasa y se informa de lo compilado
+    #[test]
+    fn capabilities_report() {
+        let caps = capabilities();
+        assert!(caps.self_test_passed(), "{:?}", caps.self_test_failures());
+        assert!(caps.has("astar") && caps.has("images") && !caps.has("teleport"));
+        assert_eq!(caps.has("debug"), cfg!(feature = "debug"));
+        assert_eq!(caps.algorithms().len(), 6);
+    }
+}
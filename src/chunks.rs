@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::grid::DIRS;
+use crate::policy;
+use crate::search;
+
+type Cell = (i32, i32);
+
+/// Lado máximo de un trozo: así su área cabe en el `usize` de 32 bits de
+/// wasm y el lado en un `i32`.
+const MAX_CHUNK_SIZE: usize = 1 << 15;
+
+/// Mundo abierto por trozos cuadrados de `chunk_size` celdas de lado que se
+/// cargan y descargan en tiempo de ejecución, para mapas que nunca caben
+/// enteros en memoria. Las celdas usan coordenadas globales (que pueden ser
+/// negativas): el trozo `(cx, cy)` cubre las filas `cx * chunk_size..` y las
+/// columnas `cy * chunk_size..`. Las búsquedas cruzan las costuras entre
+/// trozos buscando cada vecino en el trozo que le toca; los trozos no
+/// cargados cuentan como pared.
+#[wasm_bindgen]
+pub struct World {
+    chunk_size: usize,
+    chunks: HashMap<(i32, i32), Vec<u8>>,
+    path: Vec<Cell>,
+    expanded: usize,
+}
+
+#[wasm_bindgen]
+impl World {
+    /// Mundo vacío. Un `chunk_size` de 0 o mayor que 32768 es una anomalía;
+    /// en modo tolerante se ajusta a ese rango.
+    #[wasm_bindgen(constructor)]
+    pub fn new(chunk_size: usize) -> Result<World, JsError> {
+        if !(1..=MAX_CHUNK_SIZE).contains(&chunk_size) {
+            policy::anomaly("el lado del trozo debe estar entre 1 y 32768")?;
+        }
+        Ok(Self {
+            chunk_size: chunk_size.clamp(1, MAX_CHUNK_SIZE),
+            chunks: HashMap::new(),
+            path: Vec::new(),
+            expanded: 0,
+        })
+    }
+
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Carga (o reemplaza) el trozo `(cx, cy)` con `chunk_size * chunk_size`
+    /// celdas (1 = transitable). Con otra longitud es una anomalía (ver
+    /// `set_policy`); en modo tolerante se ignora y devuelve `false`.
+    pub fn load_chunk(&mut self, cx: i32, cy: i32, cells: Vec<u8>) -> Result<bool, JsError> {
+        if cells.len() != self.chunk_size * self.chunk_size {
+            policy::anomaly("el trozo no tiene chunk_size * chunk_size celdas")?;
+            return Ok(false);
+        }
+        self.chunks.insert((cx, cy), cells);
+        Ok(true)
+    }
+
+    /// Descarga el trozo `(cx, cy)`. Devuelve si estaba cargado.
+    pub fn unload_chunk(&mut self, cx: i32, cy: i32) -> bool {
+        self.chunks.remove(&(cx, cy)).is_some()
+    }
+
+    pub fn is_loaded(&self, cx: i32, cy: i32) -> bool {
+        self.chunks.contains_key(&(cx, cy))
+    }
+
+    /// Trozos cargados como `[cx0, cy0, cx1, cy1, ...]`, ordenados.
+    pub fn loaded_chunks(&self) -> Vec<i32> {
+        let mut keys: Vec<_> = self.chunks.keys().copied().collect();
+        keys.sort_unstable();
+        keys.into_iter().flat_map(|(cx, cy)| [cx, cy]).collect()
+    }
+
+    /// Si la celda global `(x, y)` está en un trozo cargado y es transitable.
+    pub fn walkable(&self, x: i32, y: i32) -> bool {
+        self.walkable_cell((x, y))
+    }
+
+    /// Busca (A*) de `(sx, sy)` a `(gx, gy)` por los trozos cargados y guarda
+    /// la ruta para `path`. Devuelve si hay camino.
+    pub fn find_path(&mut self, sx: i32, sy: i32, gx: i32, gy: i32) -> bool {
+        let (start, goal) = ((sx, sy), (gx, gy));
+        self.path.clear();
+        self.expanded = 0;
+        if !self.walkable_cell(start) || !self.walkable_cell(goal) {
+            return false;
+        }
+        let outcome = search::astar(
+            start,
+            |&cell| cell == goal,
+            |&cell| self.successors(cell),
+            |&(x, y)| f64::from(x.abs_diff(goal.0)) + f64::from(y.abs_diff(goal.1)),
+        );
+        self.expanded = outcome.expanded.len();
+        self.path = outcome.result.map_or_else(Vec::new, |found| found.states);
+        !self.path.is_empty()
+    }
+
+    /// Ruta de la última `find_path` como `[x0, y0, x1, y1, ...]` en
+    /// coordenadas globales; vacía si no hubo camino.
+    pub fn path(&self) -> Vec<i32> {
+        self.path.iter().flat_map(|&(x, y)| [x, y]).collect()
+    }
+
+    /// Celdas expandidas por la última `find_path`.
+    pub fn expanded_count(&self) -> usize {
+        self.expanded
+    }
+}
+
+impl World {
+    /// Trozo que contiene la celda global y posición de la celda dentro de él.
+    fn locate(&self, (x, y): Cell) -> ((i32, i32), usize) {
+        let size = self.chunk_size as i32;
+        let chunk = (x.div_euclid(size), y.div_euclid(size));
+        let local = x.rem_euclid(size) as usize * self.chunk_size + y.rem_euclid(size) as usize;
+        (chunk, local)
+    }
+
+    fn walkable_cell(&self, cell: Cell) -> bool {
+        let (chunk, local) = self.locate(cell);
+        self.chunks
+            .get(&chunk)
+            .is_some_and(|cells| cells[local] == 1)
+    }
+
+    fn successors(&self, (x, y): Cell) -> Vec<(Cell, f64)> {
+        DIRS.iter()
+            .filter_map(|&(dx, dy)| Some((x.checked_add(dx as i32)?, y.checked_add(dy as i32)?)))
+            .filter(|&next| self.walkable_cell(next))
+            .map(|next| (next, 1.0))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: la ruta cruza costuras (también hacia coordenadas negativas) y
+    /// descargar un trozo la corta
+    #[test]
+    fn path_across_chunks() {
+        let mut world = World::new(3).unwrap();
+        assert!(
+            world
+                .load_chunk(0, 0, vec![1, 1, 1, 1, 0, 0, 1, 1, 1])
+                .unwrap()
+        );
+        assert!(
+            world
+                .load_chunk(0, 1, vec![1, 1, 1, 0, 0, 1, 1, 1, 1])
+                .unwrap()
+        );
+        assert!(world.load_chunk(-1, 1, vec![1; 9]).unwrap());
+        assert!(!world.load_chunk(1, 1, vec![1; 4]).unwrap());
+        assert_eq!(world.loaded_chunks(), vec![-1, 1, 0, 0, 0, 1]);
+
+        // Las celdas junto a la costura en la fila 1 son pared: se cruza
+        // por la fila 0 o la 2.
+        assert!(world.find_path(1, 0, 1, 5));
+        assert_eq!(world.path().len() / 2 - 1, 7);
+        assert!(world.path().chunks(2).all(|c| world.walkable(c[0], c[1])));
+
+        // Cerradas las dos, solo queda rodear por los trozos de arriba.
+        world.chunks.get_mut(&(0, 0)).unwrap()[2] = 0;
+        world.chunks.get_mut(&(0, 0)).unwrap()[8] = 0;
+        assert!(!world.find_path(1, 0, 1, 5));
+        assert!(world.load_chunk(-1, 0, vec![1; 9]).unwrap());
+        assert!(world.find_path(1, 0, 1, 5));
+        assert_eq!(world.path().len() / 2 - 1, 9);
+        assert!(world.unload_chunk(-1, 1));
+        assert!(!world.find_path(1, 0, 1, 5));
+        assert!(world.path().is_empty());
+    }
+
+    /// Test: el lado del trozo se ajusta a su rango y las celdas en el borde
+    /// de las coordenadas no desbordan
+    #[test]
+    fn chunk_size_and_coordinates_are_bounded() {
+        assert_eq!(World::new(0).unwrap().chunk_size(), 1);
+        assert_eq!(World::new(usize::MAX).unwrap().chunk_size(), MAX_CHUNK_SIZE);
+
+        let mut world = World::new(1).unwrap();
+        assert!(world.load_chunk(i32::MAX, 0, vec![1]).unwrap());
+        assert!(world.load_chunk(i32::MAX - 1, 0, vec![1]).unwrap());
+        assert!(world.find_path(i32::MAX, 0, i32::MAX - 1, 0));
+        assert!(world.load_chunk(i32::MIN, i32::MIN, vec![1]).unwrap());
+        assert!(!world.find_path(i32::MIN, i32::MIN, i32::MAX, 0));
+    }
+}
@@ -0,0 +1,63 @@
+#[cfg(feature = "c-abi")]
+use std::cell::Cell;
+
+/// Milisegundos desde un origen arbitrario, solo útil para medir intervalos.
+/// `std::time::Instant` no existe en `wasm32-unknown-unknown`, así que allí
+/// se usa el reloj de JS.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn now_ms() -> f64 {
+    #[cfg(feature = "c-abi")]
+    if OFFLINE.with(Cell::get) {
+        return 0.0;
+    }
+    js_sys::Date::now()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn now_ms() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static ORIGIN: OnceLock<Instant> = OnceLock::new();
+    ORIGIN.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
+#[cfg(feature = "c-abi")]
+thread_local! {
+    static OFFLINE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Ejecuta `f` sin el reloj de JS, para la API C (`cabi`), que no puede
+/// llamar a nada de JS: `now_ms` da 0 y los tiempos medidos quedan a 0.
+#[cfg(feature = "c-abi")]
+pub(crate) fn offline<T>(f: impl FnOnce() -> T) -> T {
+    let was = OFFLINE.replace(true);
+    let out = f();
+    OFFLINE.set(was);
+    out
+}
+
+/// Pasos máximos de un `PathFinder::advance`: cada uno propaga el peligro
+/// por todo el grid, así que más de golpe congelaría el fotograma.
+pub(crate) const MAX_ADVANCE: u32 = 1 << 16;
+
+/// Reloj de paso fijo de la simulación (`PathFinder::set_sim_clock`): la
+/// hora es `origin + tick * step`, calculada siempre desde el número de
+/// pasos para que dos simulaciones en lockstep den exactamente lo mismo.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct SimClock {
+    pub tick: u32,
+    pub step: f32,
+    pub origin: f32,
+}
+
+impl SimClock {
+    /// Si el paso es positivo y el paso y el origen son finitos.
+    pub fn is_valid(&self) -> bool {
+        self.step.is_finite() && self.step > 0.0 && self.origin.is_finite()
+    }
+
+    pub fn time(&self) -> f32 {
+        self.origin + self.tick as f32 * self.step
+    }
+}
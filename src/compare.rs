@@ -0,0 +1,235 @@
+use wasm_bindgen::prelude::*;
+
+use crate::SearchOptions;
+use crate::clock;
+use crate::cost::StepCost;
+use crate::distance;
+use crate::grid::Grid;
+use crate::rsr::Rectangles;
+use crate::search::{self, Outcome};
+
+/// Resultados de varios algoritmos sobre la misma consulta
+/// (`PathFinder::compare`), en el orden en que se pidieron.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct Comparison {
+    runs: Vec<Run>,
+}
+
+#[derive(Clone, Debug)]
+struct Run {
+    algorithm: String,
+    path: Vec<(usize, usize)>,
+    cost: f64,
+    expanded: usize,
+    time_ms: f64,
+}
+
+#[wasm_bindgen]
+impl Comparison {
+    pub fn len(&self) -> usize {
+        self.runs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+
+    /// Nombre del algoritmo `i` (vacío si no existe).
+    pub fn algorithm(&self, i: usize) -> String {
+        self.runs
+            .get(i)
+            .map_or_else(String::new, |r| r.algorithm.clone())
+    }
+
+    /// Ruta del algoritmo `i` como `[x0, y0, x1, y1, ...]` (vacía si no
+    /// encontró ruta o no existe).
+    pub fn path(&self, i: usize) -> Vec<usize> {
+        self.runs.get(i).map_or_else(Vec::new, |r| {
+            r.path.iter().flat_map(|&(x, y)| [x, y]).collect()
+        })
+    }
+
+    /// Coste de la ruta `i` (NaN si no existe).
+    pub fn cost(&self, i: usize) -> f64 {
+        self.runs.get(i).map_or(f64::NAN, |r| r.cost)
+    }
+
+    /// Celdas expandidas por el algoritmo `i`.
+    pub fn expanded(&self, i: usize) -> usize {
+        self.runs.get(i).map_or(0, |r| r.expanded)
+    }
+
+    pub fn time_ms(&self, i: usize) -> f64 {
+        self.runs.get(i).map_or(f64::NAN, |r| r.time_ms)
+    }
+}
+
+/// Ejecuta cada algoritmo de `algorithms` de `start` a `goal`: "bfs"
+/// (pasos, sin opciones), "dijkstra", "astar", "greedy" y "01bfs" (con los
+/// costes de `opts`; la última sin montículo si solo hay dos costes por
+/// celda, si no como "dijkstra") o "rectangles"
+/// (`PathFinder::search_rectangles`). Falla con el primer nombre
+/// desconocido.
+pub(crate) fn compare(
+    grid: &Grid,
+    algorithms: &[String],
+    opts: &SearchOptions,
+    start: (usize, usize),
+    goal: (usize, usize),
+) -> Result<Comparison, String> {
+    let mut comparison = Comparison::default();
+    for name in algorithms {
+        let started = clock::now_ms();
+        let outcome = run(grid, name, opts, start, goal)
+            .ok_or_else(|| format!("algoritmo desconocido: {name}"))?;
+        let (path, cost) = outcome.result.map_or((vec![], 0.0), |f| (f.states, f.cost));
+        comparison.runs.push(Run {
+            algorithm: name.clone(),
+            path,
+            cost,
+            expanded: outcome.expanded.len(),
+            time_ms: clock::now_ms() - started,
+        });
+    }
+    Ok(comparison)
+}
+
+/// Búsqueda con el algoritmo `name` (ver `compare`); `None` si no existe.
+pub(crate) fn run(
+    grid: &Grid,
+    name: &str,
+    opts: &SearchOptions,
+    start: (usize, usize),
+    goal: (usize, usize),
+) -> Option<Outcome<(usize, usize)>> {
+    let h_scale = StepCost::new(grid, opts).min_step();
+    let h =
+        move |(x, y): (usize, usize)| h_scale * (x.abs_diff(goal.0) + y.abs_diff(goal.1)) as f64;
+    Some(match name {
+        "bfs" => {
+            let target = grid.in_bounds(goal).then(|| grid.idx(goal));
+            let (path, expanded) = distance::bfs_to_goal(grid, None, start, |i| Some(i) == target);
+            let states: Vec<_> = path
+                .unwrap_or_default()
+                .into_iter()
+                .map(|i| grid.coords(i))
+                .collect();
+            Outcome {
+                result: (!states.is_empty()).then(|| search::Found {
+                    cost: (states.len() - 1) as f64,
+                    g: (0..states.len()).map(|i| i as f64).collect(),
+                    states,
+                }),
+                expanded: expanded.into_iter().map(|i| grid.coords(i)).collect(),
+                ..Outcome::none()
+            }
+        }
+        "dijkstra" => search::grid_search_by(grid, start, goal, opts, |_, g| g),
+        "astar" => search::grid_search(grid, start, goal, opts),
+        "01bfs" => search::two_cost_search(grid, start, goal, opts)
+            .unwrap_or_else(|| search::grid_search_by(grid, start, goal, opts, |_, g| g)),
+        "greedy" => search::grid_search_by(grid, start, goal, opts, |cell, _| h(cell)),
+        "rectangles" => Rectangles::decompose(grid).search(grid, start, goal),
+        _ => return None,
+    })
+}
+
+/// Algoritmo exacto más adecuado para la consulta: si `opts` o el grid
+/// hacen que los pasos no cuesten todos 1, "01bfs" cuando entrar en cada
+/// celda solo puede costar dos valores y "astar" si no; con coste unitario,
+/// "bfs" en grids pequeños o con extremos cercanos, "rectangles" (simetrías
+/// de campo abierto, el papel de JPS) si hay pocas paredes y "astar" en el
+/// resto. Con `set_max_cost` siempre "astar", el único que aplica la cota.
+pub(crate) fn choose(
+    grid: &Grid,
+    opts: &SearchOptions,
+    start: (usize, usize),
+    goal: (usize, usize),
+) -> &'static str {
+    if opts.max_cost.is_some() {
+        return "astar";
+    }
+    let weighted = grid.penalty.is_some()
+        || opts.noise > 0.0
+        || opts.slope.is_some()
+        || opts.max_clearance
+        || opts.min_turns
+        || opts.rotation_cost > 0.0
+        || opts.break_cost > 0.0;
+    // BFS no sabe de máscaras, ventanas, etiquetas, transiciones, anchos mínimos ni giros.
+    let restricted = opts.region.is_some()
+        || opts.window.is_some()
+        || opts.must_visit.is_some()
+        || opts.transitions.is_some()
+        || opts.min_clearance > 0
+        || opts.no_reverse;
+    if weighted || restricted {
+        return if search::has_two_costs(grid, opts) {
+            "01bfs"
+        } else {
+            "astar"
+        };
+    }
+    let cells = grid.cells.len();
+    let distance = start.0.abs_diff(goal.0) + start.1.abs_diff(goal.1);
+    if cells <= SMALL_GRID || distance <= NEAR {
+        return "bfs";
+    }
+    let walls = grid.cells.iter().filter(|&&c| c != 1).count();
+    if walls * 4 < cells {
+        "rectangles"
+    } else {
+        "astar"
+    }
+}
+
+/// Celdas por debajo de las que BFS gana por no tener sobrecoste.
+const SMALL_GRID: usize = 1024;
+/// Distancia Manhattan entre extremos por debajo de la que BFS gana.
+const NEAR: usize = 16;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: todos los exactos empatan en coste y A* expande menos que Dijkstra
+    #[test]
+    fn head_to_head() {
+        let grid = Grid::square(vec![1; 36], 6);
+        let names: Vec<String> = ["bfs", "dijkstra", "astar", "greedy", "rectangles"]
+            .map(String::from)
+            .to_vec();
+        let opts = SearchOptions::default();
+        let cmp = compare(&grid, &names, &opts, (0, 0), (0, 5)).unwrap();
+        assert_eq!(cmp.len(), 5);
+        assert_eq!(cmp.algorithm(2), "astar");
+        assert!((0..5).all(|i| cmp.cost(i) == 5.0 && cmp.path(i).len() == 12));
+        assert!(cmp.expanded(2) < cmp.expanded(1));
+        assert!(compare(&grid, &["jps".to_string()], &opts, (0, 0), (5, 5)).is_err());
+    }
+
+    /// Test: la elección depende de tamaño, paredes, costes y distancia
+    #[test]
+    fn auto_choice() {
+        let open = Grid::square(vec![1; 64 * 64], 64);
+        let mut opts = SearchOptions::default();
+        assert_eq!(choose(&open, &opts, (0, 0), (63, 63)), "rectangles");
+        assert_eq!(choose(&open, &opts, (0, 0), (3, 3)), "bfs");
+        let maze = Grid::square((0..64 * 64).map(|i| u8::from(i % 3 != 0)).collect(), 64);
+        assert_eq!(choose(&maze, &opts, (0, 0), (63, 63)), "astar");
+        assert_eq!(
+            choose(&Grid::square(vec![1; 36], 6), &opts, (0, 0), (5, 5)),
+            "bfs"
+        );
+        opts.set_rotation_cost(1.0);
+        assert_eq!(choose(&open, &opts, (0, 0), (3, 3)), "astar");
+        let mut roads = open.clone();
+        roads.set_penalty(Some((0..64 * 64).map(|i| (i % 2) as f32).collect()));
+        assert_eq!(choose(&roads, &opts, (0, 0), (3, 3)), "astar");
+        assert_eq!(
+            choose(&roads, &SearchOptions::default(), (0, 0), (3, 3)),
+            "01bfs"
+        );
+    }
+}
@@ -0,0 +1,28 @@
+/// Capa de congestión: cada agente que pasa por una celda suma 1 a su carga
+/// y en cada `tick` la carga se multiplica por `decay`. Entra en la
+/// penalización del grid multiplicada por `weight`, así los pasillos muy
+/// usados se encarecen y la multitud se reparte.
+#[derive(Clone, Debug)]
+pub(crate) struct Congestion {
+    pub weight: f32,
+    pub decay: f32,
+    pub load: Vec<f32>,
+}
+
+impl Congestion {
+    pub fn new(weight: f32, decay: f32, cells: usize) -> Self {
+        Self {
+            weight: weight.max(0.0),
+            decay: decay.clamp(0.0, 1.0),
+            load: vec![0.0; cells],
+        }
+    }
+
+    pub fn visit(&mut self, idx: usize) {
+        self.load[idx] += 1.0;
+    }
+
+    pub fn tick(&mut self) {
+        self.load.iter_mut().for_each(|l| *l *= self.decay);
+    }
+}
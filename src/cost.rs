@@ -0,0 +1,221 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::SearchOptions;
+use crate::distance;
+use crate::grid::{Grid, opposite};
+use crate::rng;
+
+/// Coste mínimo de un paso en bajada, para que los costes sigan siendo
+/// positivos y la heurística pueda escalarse.
+const MIN_STEP: f64 = 0.1;
+
+/// Modelo de energía sobre la capa de elevación.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SlopeModel {
+    pub uphill: f32,
+    pub downhill: f32,
+    pub max_slope: f32,
+}
+
+impl SlopeModel {
+    /// Factor del paso según el desnivel `dh`, o `None` si es demasiado
+    /// empinado o no es un número. Nunca baja de `MIN_STEP`, así que el
+    /// paso sigue costando algo.
+    fn factor(&self, dh: f64) -> Option<f64> {
+        if dh.is_nan() || dh.abs() > f64::from(self.max_slope) {
+            return None;
+        }
+        let rate = if dh >= 0.0 {
+            self.uphill
+        } else {
+            self.downhill
+        };
+        Some((1.0 + f64::from(rate) * dh).max(MIN_STEP))
+    }
+
+    /// Si los factores son números no negativos y hay pendiente máxima.
+    pub fn is_valid(&self) -> bool {
+        [self.uphill, self.downhill]
+            .iter()
+            .all(|f| f.is_finite() && *f >= 0.0)
+            && self.max_slope >= 0.0
+    }
+}
+
+/// Coste de cada paso según las capas del grid y las opciones de búsqueda.
+pub(crate) struct StepCost<'a> {
+    grid: &'a Grid,
+    /// Transitabilidad: las celdas del grid o las de otra capa.
+    cells: &'a [u8],
+    opts: &'a SearchOptions,
+    /// Mapa de holgura y holgura mínima exigida a cada celda de la ruta.
+    clearance: Option<(Vec<u32>, u32)>,
+}
+
+impl<'a> StepCost<'a> {
+    pub fn new(grid: &'a Grid, opts: &'a SearchOptions) -> Self {
+        Self::on_layer(grid, &grid.cells, opts)
+    }
+
+    /// Costes sobre la capa de transitabilidad `cells` (ver `Layers`) con
+    /// el resto de capas de `grid`, sin copiar el grid.
+    pub fn on_layer(grid: &'a Grid, cells: &'a [u8], opts: &'a SearchOptions) -> Self {
+        Self {
+            grid,
+            cells,
+            opts,
+            clearance: None,
+        }
+    }
+
+    /// Si la celda es transitable, está dentro de la región y la ventana de
+    /// búsqueda y cumple la holgura mínima. Con coste de demolición, las paredes con
+    /// puntos de vida cuentan como transitables.
+    pub fn allowed(&self, cell: (usize, usize)) -> bool {
+        let walkable = self.grid.in_bounds(cell) && self.cells.get(self.grid.idx(cell)) == Some(&1);
+        (walkable || self.breakable(cell).is_some())
+            && self.opts.in_region(self.grid.idx(cell))
+            && self.opts.in_window(cell)
+            && self
+                .clearance
+                .as_ref()
+                .is_none_or(|(map, min)| map[self.grid.idx(cell)] >= *min)
+    }
+
+    /// Puntos de vida de una pared que la búsqueda puede derribar.
+    fn breakable(&self, cell: (usize, usize)) -> Option<f32> {
+        if self.opts.break_cost <= 0.0 || !self.grid.in_bounds(cell) {
+            return None;
+        }
+        let idx = self.grid.idx(cell);
+        let hp = self.grid.hit_points.as_ref()?[idx];
+        (self.cells.get(idx) != Some(&1) && hp > 0.0 && hp.is_finite()).then_some(hp)
+    }
+
+    /// Aplica las restricciones que dependen de los extremos de la consulta.
+    pub fn prepare(&mut self, start: (usize, usize), goal: (usize, usize)) {
+        if self.opts.max_clearance || self.opts.min_clearance > 0 {
+            let map = self.endpoint_clearance(start, goal);
+            self.clearance = Some((map, self.opts.min_clearance));
+            if self.opts.max_clearance {
+                self.best_bottleneck(start, goal);
+            }
+        }
+    }
+
+    /// Mayor holgura alcanzable entre `start` y `goal` (ver `best_bottleneck`).
+    pub fn max_bottleneck(mut self, start: (usize, usize), goal: (usize, usize)) -> u32 {
+        self.clearance = Some((self.endpoint_clearance(start, goal), 0));
+        self.best_bottleneck(start, goal)
+    }
+
+    /// Mapa de holgura donde los extremos no cuentan: el agente ya está en uno
+    /// y debe llegar al otro, estén donde estén.
+    fn endpoint_clearance(&self, start: (usize, usize), goal: (usize, usize)) -> Vec<u32> {
+        let mut map = distance::layer_clearance(self.grid, self.cells);
+        map[self.grid.idx(start)] = u32::MAX;
+        map[self.grid.idx(goal)] = u32::MAX;
+        map
+    }
+
+    /// Mayor holgura `b` tal que existe ruta de `start` a `goal` usando solo
+    /// celdas con holgura `>= b` (búsqueda binaria sobre `connected`, con el
+    /// mapa de holgura ya puesto). Deja exigida esa holgura.
+    fn best_bottleneck(&mut self, start: (usize, usize), goal: (usize, usize)) -> u32 {
+        let Some((map, _)) = &self.clearance else {
+            return 0;
+        };
+        let cap = map
+            .iter()
+            .filter(|&&c| c != u32::MAX)
+            .max()
+            .copied()
+            .unwrap_or(0);
+        let (mut lo, mut hi) = (0, cap);
+        let require = |costs: &mut Self, b: u32| {
+            if let Some((_, min)) = &mut costs.clearance {
+                *min = b;
+            }
+        };
+        while lo < hi {
+            let mid = lo + (hi - lo).div_ceil(2);
+            require(self, mid);
+            if self.connected(start, goal) {
+                lo = mid;
+            } else {
+                hi = mid - 1;
+            }
+        }
+        require(self, lo);
+        lo
+    }
+
+    /// Si hay ruta de `start` a `goal` con los pasos que dejan estas
+    /// opciones: celdas `allowed` (paredes derribables incluidas), pasos con
+    /// coste (la pendiente máxima) y transiciones de terreno permitidas.
+    /// Con `no_reverse` se recorren pares (celda, dirección de llegada)
+    /// para no dar media vuelta.
+    fn connected(&self, start: (usize, usize), goal: (usize, usize)) -> bool {
+        let turn_back = self.opts.no_reverse;
+        let initial = (
+            start,
+            self.opts.heading.map(usize::from).filter(|_| turn_back),
+        );
+        let mut seen = HashSet::from([initial]);
+        let mut queue = VecDeque::from([initial]);
+        while let Some((cell, dir)) = queue.pop_front() {
+            if cell == goal {
+                return true;
+            }
+            for (d, next) in self.grid.steps(cell) {
+                let reverses = turn_back && dir.is_some_and(|dir| d == opposite(dir));
+                let passable = !reverses
+                    && self.allowed(next)
+                    && self
+                        .opts
+                        .allows_step(self.grid.idx(cell), self.grid.idx(next))
+                    && self.step(cell, next).is_some();
+                let state = (next, turn_back.then_some(d));
+                if passable && seen.insert(state) {
+                    queue.push_back(state);
+                }
+            }
+        }
+        false
+    }
+
+    /// Coste de moverse de `from` a `to` (celdas vecinas), o `None` si el
+    /// paso no está permitido.
+    pub fn step(&self, from: (usize, usize), to: (usize, usize)) -> Option<f64> {
+        let mut cost = 1.0;
+
+        if let (Some(slope), Some(elevation)) = (self.opts.slope, &self.grid.elevation) {
+            let dh = f64::from(elevation[self.grid.idx(to)] - elevation[self.grid.idx(from)]);
+            cost *= slope.factor(dh)?;
+        }
+
+        if let Some(penalty) = &self.grid.penalty {
+            cost += f64::from(penalty[self.grid.idx(to)].max(0.0));
+        }
+
+        if let Some(hp) = self.breakable(to) {
+            cost += f64::from(self.opts.break_cost * hp);
+        }
+
+        let noise = f64::from(self.opts.noise.max(0.0));
+        if noise > 0.0 {
+            let key = ((self.grid.idx(from) as u64) << 32) | self.grid.idx(to) as u64;
+            cost *= 1.0 + noise * rng::unit(u64::from(self.opts.seed), key);
+        }
+
+        Some(cost)
+    }
+
+    /// Cota inferior del coste de un paso, para escalar la heurística.
+    pub fn min_step(&self) -> f64 {
+        match self.opts.slope {
+            Some(slope) if slope.downhill > 0.0 && self.grid.elevation.is_some() => MIN_STEP,
+            _ => 1.0,
+        }
+    }
+}
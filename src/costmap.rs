@@ -0,0 +1,113 @@
+use wasm_bindgen::prelude::*;
+
+use crate::bytes::{Reader, Writer};
+use crate::grid::Grid;
+
+/// Valor de pared al exportar (el "letal" de los costmaps de robótica).
+pub(crate) const LETHAL: u8 = 254;
+
+/// Lee un costmap en el formato binario simple de otros planificadores:
+/// cabecera con ancho y alto (`u32`) y tamaño de celda (`f32`), en
+/// little-endian, y después un byte de coste por celda, fila a fila como las
+/// celdas del grid (`idx = x * width + y`). Los costes desde `lethal` son
+/// paredes (también el 255 de "desconocido") y el resto un coste extra de
+/// `coste * scale` al entrar en la celda. Devuelve el grid y ese coste; el
+/// tamaño de celda lo da `costmap_cell_size`.
+pub(crate) fn decode(
+    bytes: &[u8],
+    lethal: u8,
+    scale: f32,
+) -> Result<(Grid, Vec<f32>), &'static str> {
+    if !(scale.is_finite() && scale >= 0.0) {
+        return Err("escala de coste no válida");
+    }
+    let mut r = Reader::new(bytes);
+    let (Some(width), Some(height), Some(_)) = (r.u32(), r.u32(), r.f32()) else {
+        return Err("cabecera de costmap incompleta");
+    };
+    let (width, height) = (width as usize, height as usize);
+    let Some(raw) = width.checked_mul(height).and_then(|n| r.bytes(n)) else {
+        return Err("faltan celdas en el costmap");
+    };
+    if raw.is_empty() {
+        return Err("costmap vacío");
+    }
+    if !r.is_empty() {
+        return Err("el costmap no tiene el tamaño de su cabecera");
+    }
+    let cells = raw.iter().map(|&c| u8::from(c < lethal)).collect();
+    let costs = raw
+        .iter()
+        .map(|&c| {
+            if c < lethal {
+                f32::from(c) * scale
+            } else {
+                0.0
+            }
+        })
+        .collect();
+    Ok((Grid::new(cells, width, height), costs))
+}
+
+/// Escribe `grid` en el formato de `decode`: paredes como `LETHAL` y las
+/// celdas libres con su penalización dividida entre `scale`, redondeada y
+/// limitada a `LETHAL - 1`.
+pub(crate) fn encode(grid: &Grid, cell_size: f32, scale: f32) -> Vec<u8> {
+    let mut w = Writer::new();
+    w.u32(grid.width as u32);
+    w.u32(grid.height as u32);
+    w.f32(cell_size);
+    for (idx, &cell) in grid.cells.iter().enumerate() {
+        let penalty = grid.penalty.as_ref().map_or(0.0, |p| p[idx].max(0.0));
+        let cost = if cell != 1 {
+            LETHAL
+        } else if scale > 0.0 {
+            (penalty / scale).round().min(f32::from(LETHAL - 1)) as u8
+        } else {
+            0
+        };
+        w.u8(cost);
+    }
+    w.finish()
+}
+
+/// Tamaño de celda de la cabecera de un costmap (ver
+/// `PathFinder::from_costmap`), para pasar a las funciones en unidades del
+/// mundo; `None` si la cabecera está incompleta.
+#[wasm_bindgen]
+pub fn costmap_cell_size(bytes: &[u8]) -> Option<f32> {
+    let mut r = Reader::new(bytes);
+    r.bytes(8)?;
+    r.f32()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: un costmap se lee con paredes y costes y se vuelve a escribir
+    /// igual; una cabecera que no cuadra es un error
+    #[test]
+    fn costmap_round_trip() {
+        let mut w = Writer::new();
+        w.u32(3);
+        w.u32(2);
+        w.f32(0.05);
+        w.bytes(&[0, 10, 254, 255, 3, 0]);
+        let bytes = w.finish();
+
+        let (mut grid, costs) = decode(&bytes, 253, 0.5).unwrap();
+        assert_eq!((grid.width, grid.height), (3, 2));
+        assert_eq!(grid.cells, vec![1, 1, 0, 0, 1, 1]);
+        assert_eq!(costs, vec![0.0, 5.0, 0.0, 0.0, 1.5, 0.0]);
+        assert_eq!(costmap_cell_size(&bytes), Some(0.05));
+
+        grid.set_penalty(Some(costs));
+        let out = encode(&grid, 0.05, 0.5);
+        assert_eq!(&out[12..], &[0, 10, 254, 254, 3, 0]);
+        assert_eq!(out[..12], bytes[..12]);
+
+        assert!(decode(&bytes[..bytes.len() - 1], 253, 0.5).is_err());
+        assert!(decode(&bytes[..6], 253, 0.5).is_err());
+    }
+}
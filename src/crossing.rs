@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::pairs;
+
+/// Primer choque entre dos agentes que siguen sus rutas a un paso por
+/// unidad de tiempo (`path_conflict`).
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PathConflict {
+    time: usize,
+    a: (usize, usize),
+    b: (usize, usize),
+}
+
+#[wasm_bindgen]
+impl PathConflict {
+    /// Paso en que ocurre: en el que coinciden en la celda o, si se cruzan,
+    /// en el que salen hacia la del otro.
+    pub fn time(&self) -> usize {
+        self.time
+    }
+
+    /// Si se cruzan intercambiando sus celdas en vez de coincidir en una.
+    pub fn swap(&self) -> bool {
+        self.a != self.b
+    }
+
+    /// Celda `[x, y]` donde coinciden o, en un cruce, `[xa, ya, xb, yb]` con
+    /// la celda de la que sale cada uno.
+    pub fn cells(&self) -> Vec<usize> {
+        if self.swap() {
+            vec![self.a.0, self.a.1, self.b.0, self.b.1]
+        } else {
+            vec![self.a.0, self.a.1]
+        }
+    }
+}
+
+/// Celdas por las que pasan las dos rutas (`[x0, y0, x1, y1, ...]`), sin
+/// importar cuándo, p. ej. para que dos láseres no se corten. Devuelve
+/// `[x, y, i, j, ...]` con cada celda común y su posición `i` en `a` y `j`
+/// en `b`, por orden de `i` y luego de `j`; vacío si no se tocan.
+#[wasm_bindgen]
+pub fn path_intersections(a: &[usize], b: &[usize]) -> Vec<usize> {
+    intersections(&pairs(a), &pairs(b))
+        .into_iter()
+        .flat_map(|((x, y), i, j)| [x, y, i, j])
+        .collect()
+}
+
+/// Primer choque entre dos agentes que salen a la vez y avanzan una celda
+/// de su ruta por paso: ocupar la misma celda en el mismo paso o cruzarse
+/// intercambiando celdas entre un paso y el siguiente. El que llega antes se
+/// queda esperando en su destino. `None` si no chocan o alguna ruta está
+/// vacía.
+#[wasm_bindgen]
+pub fn path_conflict(a: &[usize], b: &[usize]) -> Option<PathConflict> {
+    conflict(&pairs(a), &pairs(b))
+}
+
+pub(crate) fn intersections(
+    a: &[(usize, usize)],
+    b: &[(usize, usize)],
+) -> Vec<((usize, usize), usize, usize)> {
+    let mut in_b: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    for (j, &cell) in b.iter().enumerate() {
+        in_b.entry(cell).or_default().push(j);
+    }
+    a.iter()
+        .enumerate()
+        .flat_map(|(i, cell)| {
+            let js = in_b.get(cell).map_or(&[][..], Vec::as_slice);
+            js.iter().map(move |&j| (*cell, i, j))
+        })
+        .collect()
+}
+
+pub(crate) fn conflict(a: &[(usize, usize)], b: &[(usize, usize)]) -> Option<PathConflict> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+    let at = |path: &[(usize, usize)], t: usize| path[t.min(path.len() - 1)];
+    let steps = a.len().max(b.len());
+    (0..steps).find_map(|t| {
+        let (a0, b0) = (at(a, t), at(b, t));
+        let swapped = at(a, t + 1) == b0 && at(b, t + 1) == a0;
+        (a0 == b0 || swapped).then_some(PathConflict {
+            time: t,
+            a: a0,
+            b: b0,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: cruces en el espacio y choques en el tiempo, por celda común o
+    /// por intercambio de celdas
+    #[test]
+    fn paths_cross_and_collide() {
+        let row = [0, 0, 0, 1, 0, 2, 0, 3];
+        let column = [1, 2, 0, 2, 9, 9];
+        assert_eq!(path_intersections(&row, &column), vec![0, 2, 2, 1]);
+        assert!(path_intersections(&row, &[5, 5]).is_empty());
+
+        // Coinciden en (0, 2) en el paso 2.
+        let hit = path_conflict(&row, &[2, 2, 1, 2, 0, 2]).unwrap();
+        assert_eq!(
+            (hit.time(), hit.swap(), hit.cells()),
+            (2, false, vec![0, 2])
+        );
+
+        // Intercambian (0, 1) y (0, 2) entre los pasos 1 y 2.
+        let back = [0, 3, 0, 2, 0, 1, 0, 0];
+        let swap = path_conflict(&row, &back).unwrap();
+        assert_eq!((swap.time(), swap.cells()), (1, vec![0, 1, 0, 2]));
+
+        // El que llega antes espera en su destino y el otro choca con él.
+        let waits = path_conflict(&row, &[1, 3, 0, 3]).unwrap();
+        assert_eq!((waits.time(), waits.cells()), (3, vec![0, 3]));
+        assert!(path_conflict(&row, &[]).is_none());
+    }
+}
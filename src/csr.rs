@@ -0,0 +1,150 @@
+use wasm_bindgen::prelude::*;
+
+use crate::SearchOptions;
+use crate::cost::StepCost;
+use crate::grid::Grid;
+use crate::search;
+
+/// Grafo en formato CSR: las aristas del nodo `i` son
+/// `targets[offsets[i]..offsets[i + 1]]` con sus `weights`.
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsrGraph {
+    offsets: Vec<u32>,
+    targets: Vec<u32>,
+    weights: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl CsrGraph {
+    /// Importa un grafo CSR externo. Devuelve error si los arrays no son
+    /// coherentes entre sí.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        offsets: Vec<u32>,
+        targets: Vec<u32>,
+        weights: Vec<f32>,
+    ) -> Result<CsrGraph, JsError> {
+        Self::checked(offsets, targets, weights).ok_or_else(|| JsError::new("grafo CSR inválido"))
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn offsets(&self) -> Vec<u32> {
+        self.offsets.clone()
+    }
+
+    pub fn targets(&self) -> Vec<u32> {
+        self.targets.clone()
+    }
+
+    pub fn weights(&self) -> Vec<f32> {
+        self.weights.clone()
+    }
+
+    /// Camino más corto (Dijkstra) entre dos nodos como lista de índices;
+    /// vacío si no hay.
+    pub fn shortest_path(&self, source: u32, target: u32) -> Vec<u32> {
+        if source as usize >= self.node_count() || target as usize >= self.node_count() {
+            return vec![];
+        }
+        search::astar(
+            source,
+            |&node| node == target,
+            |&node| self.edges(node),
+            |_| 0.0,
+        )
+        .result
+        .map_or_else(Vec::new, |found| found.states)
+    }
+}
+
+impl CsrGraph {
+    pub(crate) fn checked(offsets: Vec<u32>, targets: Vec<u32>, weights: Vec<f32>) -> Option<Self> {
+        let nodes = offsets.len().checked_sub(1)?;
+        let valid = offsets[0] == 0
+            && offsets.windows(2).all(|w| w[0] <= w[1])
+            && offsets[nodes] as usize == targets.len()
+            && weights.len() == targets.len()
+            && targets.iter().all(|&t| (t as usize) < nodes)
+            && weights.iter().all(|&w| w >= 0.0);
+        valid.then_some(Self {
+            offsets,
+            targets,
+            weights,
+        })
+    }
+
+    /// Exporta el grafo de transitabilidad que recorre la búsqueda: un nodo
+    /// por celda (índice `x * width + y`) y una arista por paso permitido con
+    /// su coste según `opts`.
+    pub(crate) fn from_grid(grid: &Grid, opts: &SearchOptions) -> Self {
+        let costs = StepCost::new(grid, opts);
+        let mut offsets = Vec::with_capacity(grid.cells.len() + 1);
+        let mut targets = Vec::new();
+        let mut weights = Vec::new();
+
+        offsets.push(0);
+        for idx in 0..grid.cells.len() {
+            let cell = grid.coords(idx);
+            if costs.allowed(cell) {
+                for next in grid.neighbors(cell).filter(|&n| costs.allowed(n)) {
+                    if let Some(w) = costs.step(cell, next) {
+                        targets.push(grid.idx(next) as u32);
+                        weights.push(w as f32);
+                    }
+                }
+            }
+            offsets.push(targets.len() as u32);
+        }
+
+        Self {
+            offsets,
+            targets,
+            weights,
+        }
+    }
+
+    fn edges(&self, node: u32) -> impl Iterator<Item = (u32, f64)> + '_ {
+        let range = self.offsets[node as usize] as usize..self.offsets[node as usize + 1] as usize;
+        self.targets[range.clone()]
+            .iter()
+            .zip(&self.weights[range])
+            .map(|(&t, &w)| (t, f64::from(w)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: exportar un grid 2x2 con una pared da las aristas esperadas
+    #[test]
+    fn csr_from_grid() {
+        let g = CsrGraph::from_grid(
+            &Grid::square(vec![1, 1, 0, 1], 2),
+            &SearchOptions::default(),
+        );
+        assert_eq!(g.offsets(), vec![0, 1, 3, 3, 4]);
+        assert_eq!(g.targets(), vec![1, 3, 0, 1]);
+        assert!(g.weights().iter().all(|&w| w == 1.0));
+    }
+
+    /// Test: el camino en el grafo exportado coincide con el del grid
+    #[test]
+    fn csr_shortest_path_matches_grid() {
+        let grid = Grid::square(vec![1, 1, 0, 0, 1, 1, 0, 1, 1], 3);
+        let g = CsrGraph::from_grid(&grid, &SearchOptions::default());
+        assert_eq!(g.shortest_path(0, 8).len(), 5);
+    }
+
+    /// Test: arrays incoherentes se rechazan al importar
+    #[test]
+    fn csr_rejects_invalid() {
+        assert!(CsrGraph::checked(vec![0, 2], vec![0], vec![1.0]).is_none());
+        assert!(CsrGraph::checked(vec![0, 1], vec![5], vec![1.0]).is_none());
+        assert!(CsrGraph::checked(vec![0, 1], vec![0], vec![1.0]).is_some());
+    }
+}
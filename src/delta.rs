@@ -0,0 +1,81 @@
+/// Registro de cambios del grid por generaciones: cada vez que se anotan
+/// celdas distintas de las de la última vez sube la generación y esas
+/// celdas quedan marcadas con ella. Solo se comparan las celdas cuando la
+/// revisión del grid (ver `PathFinder::touched`) se ha movido.
+#[derive(Clone, Debug)]
+pub(crate) struct ChangeLog {
+    pub generation: u32,
+    revision: u32,
+    stamps: Vec<u32>,
+    last: Vec<u8>,
+}
+
+impl ChangeLog {
+    pub fn new(cells: &[u8], revision: u32) -> Self {
+        Self {
+            generation: 0,
+            revision,
+            stamps: vec![0; cells.len()],
+            last: cells.to_vec(),
+        }
+    }
+
+    /// Si `revision` no es la de la última vez no hace nada; si no, compara
+    /// `cells` con lo anotado y, si algo cambió, abre una generación nueva.
+    /// Un cambio de tamaño marca todas las celdas.
+    pub fn record(&mut self, cells: &[u8], revision: u32) {
+        if revision == self.revision {
+            return;
+        }
+        self.revision = revision;
+        if cells.len() != self.last.len() {
+            self.generation += 1;
+            self.stamps = vec![self.generation; cells.len()];
+            self.last = cells.to_vec();
+            return;
+        }
+        let changed: Vec<usize> = (0..cells.len())
+            .filter(|&i| cells[i] != self.last[i])
+            .collect();
+        if changed.is_empty() {
+            return;
+        }
+        self.generation += 1;
+        for i in changed {
+            self.stamps[i] = self.generation;
+            self.last[i] = cells[i];
+        }
+    }
+
+    /// Índices de las celdas cambiadas después de la generación `since`.
+    pub fn since(&self, since: u32) -> Vec<usize> {
+        (0..self.stamps.len())
+            .filter(|&i| self.stamps[i] > since)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: cada generación devuelve solo lo cambiado desde entonces
+    #[test]
+    fn changes_by_generation() {
+        let mut log = ChangeLog::new(&[1, 1, 1, 1], 0);
+        log.record(&[1, 1, 1, 1], 1);
+        assert_eq!(log.generation, 0);
+        log.record(&[0, 1, 1, 1], 2);
+        log.record(&[0, 1, 0, 1], 3);
+        assert_eq!(log.generation, 2);
+
+        // Sin moverse la revisión no se vuelve a mirar el grid.
+        log.record(&[1, 1, 1, 1], 3);
+        assert_eq!(log.generation, 2);
+        assert_eq!(log.since(0), vec![0, 2]);
+        assert_eq!(log.since(1), vec![2]);
+        assert!(log.since(2).is_empty());
+        log.record(&[1; 2], 4);
+        assert_eq!(log.since(2), vec![0, 1]);
+    }
+}
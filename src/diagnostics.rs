@@ -0,0 +1,74 @@
+use wasm_bindgen::prelude::*;
+
+/// Precisión de una heurística a lo largo de una ruta (`PathFinder::
+/// heuristic_report`): en cada celda se compara la estimación con el coste
+/// real que queda hasta el destino. El error es `real - estimación`; uno
+/// negativo significa que la heurística sobreestima y deja de ser admisible.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HeuristicReport {
+    samples: usize,
+    mean_error: f64,
+    max_error: f64,
+    overestimates: usize,
+}
+
+#[wasm_bindgen]
+impl HeuristicReport {
+    /// Celdas comparadas (las de la ruta).
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    pub fn mean_error(&self) -> f64 {
+        self.mean_error
+    }
+
+    pub fn max_error(&self) -> f64 {
+        self.max_error
+    }
+
+    /// Celdas en las que la estimación supera el coste real.
+    pub fn overestimates(&self) -> usize {
+        self.overestimates
+    }
+}
+
+impl HeuristicReport {
+    /// Compara `estimate(i)` con `remaining[i]`, el coste real desde la
+    /// `i`-ésima celda de la ruta hasta el destino.
+    pub(crate) fn new(remaining: &[f64], mut estimate: impl FnMut(usize) -> f64) -> Self {
+        let errors: Vec<f64> = remaining
+            .iter()
+            .enumerate()
+            .map(|(i, &real)| real - estimate(i))
+            .collect();
+        if errors.is_empty() {
+            return Self::default();
+        }
+        Self {
+            samples: errors.len(),
+            mean_error: errors.iter().sum::<f64>() / errors.len() as f64,
+            max_error: errors.iter().fold(f64::NEG_INFINITY, |m, &e| m.max(e)),
+            overestimates: errors.iter().filter(|&&e| e < -1e-9).count(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: error medio y máximo, y sobreestimaciones
+    #[test]
+    fn report_errors() {
+        let remaining = [4.0, 3.0, 2.0, 1.0, 0.0];
+        let exact = HeuristicReport::new(&remaining, |i| remaining[i]);
+        assert_eq!((exact.mean_error, exact.max_error), (0.0, 0.0));
+        let weak = HeuristicReport::new(&remaining, |_| 0.0);
+        assert_eq!((weak.mean_error, weak.max_error), (2.0, 4.0));
+        let greedy = HeuristicReport::new(&remaining, |i| 2.0 * remaining[i]);
+        assert_eq!(greedy.overestimates, 4);
+        assert_eq!(HeuristicReport::new(&[], |_| 0.0).samples, 0);
+    }
+}
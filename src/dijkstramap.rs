@@ -0,0 +1,164 @@
+use wasm_bindgen::prelude::*;
+
+use crate::cost::StepCost;
+use crate::distance;
+use crate::grid::{DIRS, Grid};
+use crate::policy;
+
+/// Dirección de `DijkstraMap::descent` para las celdas sin vecino más bajo.
+const STAY: u8 = 4;
+
+/// Mapa de Dijkstra al estilo roguelike: el coste por celda hasta el
+/// objetivo más cercano (`PathFinder::dijkstra_map`), infinito donde no se
+/// llega o hay pared. Varios mapas (hacia el jugador, hacia el botín, huida
+/// del peligro) se escalan y suman aquí mismo, y el agente baja siguiendo
+/// `descent` sin sacar los valores a JS.
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DijkstraMap {
+    width: usize,
+    height: usize,
+    values: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl DijkstraMap {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Valor por celda (`idx = x * width + y`); infinito si no se llega.
+    pub fn values(&self) -> Vec<f32> {
+        self.values.clone()
+    }
+
+    /// Multiplica los valores alcanzables por `factor`; los infinitos siguen
+    /// siéndolo.
+    pub fn scale(&mut self, factor: f32) {
+        self.values
+            .iter_mut()
+            .filter(|v| v.is_finite())
+            .for_each(|v| *v *= factor);
+    }
+
+    /// Suma `weight` veces `other` a este mapa; una celda queda infinita si
+    /// lo es en cualquiera de los dos. Con otras dimensiones es una anomalía
+    /// (ver `set_policy`); en modo tolerante no cambia nada y devuelve
+    /// `false`.
+    pub fn add(&mut self, other: &DijkstraMap, weight: f32) -> Result<bool, JsError> {
+        if (other.width, other.height) != (self.width, self.height) {
+            policy::anomaly("los mapas de Dijkstra no tienen las mismas dimensiones")?;
+            return Ok(false);
+        }
+        for (v, &o) in self.values.iter_mut().zip(&other.values) {
+            *v = if v.is_finite() && o.is_finite() {
+                *v + weight * o
+            } else {
+                f32::INFINITY
+            };
+        }
+        Ok(true)
+    }
+
+    /// Dirección de bajada de cada celda: el índice (0 a 3: abajo, derecha,
+    /// arriba, izquierda) del vecino con menor valor, si es menor que el de
+    /// la celda, o 4 si no hay ninguno (mínimo local, pared o inalcanzable).
+    /// Con empate gana el primero en ese orden.
+    pub fn descent(&self) -> Vec<u8> {
+        (0..self.values.len())
+            .map(|idx| self.step_down(idx))
+            .collect()
+    }
+
+    /// `descent` de la celda `(x, y)`; 4 fuera del mapa.
+    pub fn descent_at(&self, x: usize, y: usize) -> u8 {
+        if x < self.height && y < self.width {
+            self.step_down(x * self.width + y)
+        } else {
+            STAY
+        }
+    }
+}
+
+impl DijkstraMap {
+    /// Dijkstra desde varias semillas `(celda, valor inicial)` a la vez con
+    /// los costes de `costs`. Los valores iniciales pueden ser negativos
+    /// (mapas de huida): cada celda acaba con el menor de su semilla y de
+    /// lo que cuesta llegar desde otra.
+    pub(crate) fn compute(grid: &Grid, costs: &StepCost, seeds: &[(usize, f32)]) -> Self {
+        let seeds = seeds
+            .iter()
+            .filter(|&&(idx, _)| idx < grid.cells.len() && costs.allowed(grid.coords(idx)))
+            .map(|&(idx, v)| (idx, f64::from(v)));
+        let edges = |idx| distance::step_edges(grid, costs, idx, false);
+        let (dist, _) = distance::dijkstra_by(grid.cells.len(), seeds, edges, |_, _| true);
+        Self {
+            width: grid.width,
+            height: grid.height,
+            values: dist.into_iter().map(|d| d as f32).collect(),
+        }
+    }
+
+    /// Semillas para volver a propagar el mapa: cada celda alcanzable con su
+    /// valor actual.
+    pub(crate) fn seeds(&self) -> Vec<(usize, f32)> {
+        let finite = self
+            .values
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| v.is_finite());
+        finite.map(|(idx, &v)| (idx, v)).collect()
+    }
+
+    fn step_down(&self, idx: usize) -> u8 {
+        if !self.values[idx].is_finite() {
+            return STAY;
+        }
+        let (x, y) = (idx / self.width, idx % self.width);
+        let mut best = (self.values[idx], STAY);
+        for (d, &(dx, dy)) in DIRS.iter().enumerate() {
+            let (Some(nx), Some(ny)) = (x.checked_add_signed(dx), y.checked_add_signed(dy)) else {
+                continue;
+            };
+            if nx < self.height && ny < self.width && self.values[nx * self.width + ny] < best.0 {
+                best = (self.values[nx * self.width + ny], d as u8);
+            }
+        }
+        best.1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SearchOptions;
+
+    /// Test: la suma ponderada mezcla dos mapas y la bajada lleva al mínimo
+    #[test]
+    fn combined_maps_descend() {
+        let grid = Grid::new(vec![1, 1, 1, 1, 0], 5, 1);
+        let opts = SearchOptions::default();
+        let costs = StepCost::new(&grid, &opts);
+        let mut player = DijkstraMap::compute(&grid, &costs, &[(0, 0.0)]);
+        assert_eq!(player.values()[3], 3.0);
+        assert!(player.values()[4].is_infinite());
+
+        let loot = DijkstraMap::compute(&grid, &costs, &[(3, 0.0)]);
+        player.scale(0.5);
+        assert!(player.add(&loot, 1.0).unwrap());
+        assert_eq!(player.values()[..4], [3.0, 2.5, 2.0, 1.5]);
+        assert_eq!(player.descent(), vec![1, 1, 1, STAY, STAY]);
+        assert_eq!(player.descent_at(0, 9), STAY);
+
+        let other = DijkstraMap {
+            width: 2,
+            height: 2,
+            values: vec![0.0; 4],
+        };
+        assert!(!player.add(&other, 1.0).unwrap());
+    }
+}
@@ -0,0 +1,318 @@
+use std::collections::{BinaryHeap, VecDeque};
+
+use crate::adjacency::Adjacency;
+use crate::cost::StepCost;
+use crate::grid::Grid;
+use crate::queue::MinEntry;
+
+/// Distancia de las celdas no alcanzadas.
+pub(crate) const UNREACHED: u32 = u32::MAX;
+
+/// BFS desde varias fuentes a la vez, sin pasar de `max_depth` pasos.
+///
+/// Devuelve la distancia en pasos por celda (`UNREACHED` si no se llega) y
+/// las celdas alcanzadas en orden de descubrimiento. Las fuentes no
+/// transitables se ignoran.
+pub(crate) fn bfs_distances(
+    grid: &Grid,
+    sources: &[(usize, usize)],
+    max_depth: u32,
+) -> (Vec<u32>, Vec<usize>) {
+    let mut dist = vec![UNREACHED; grid.cells.len()];
+    let mut order = Vec::new();
+    let mut queue = VecDeque::new();
+
+    for &src in sources.iter().filter(|&&s| grid.walkable(s)) {
+        let idx = grid.idx(src);
+        if dist[idx] == UNREACHED {
+            dist[idx] = 0;
+            order.push(idx);
+            queue.push_back(src);
+        }
+    }
+
+    while let Some(cell) = queue.pop_front() {
+        let d = dist[grid.idx(cell)];
+        if d >= max_depth {
+            continue;
+        }
+        for next in grid.neighbors(cell) {
+            let idx = grid.idx(next);
+            if grid.cells[idx] == 1 && dist[idx] == UNREACHED {
+                dist[idx] = d + 1;
+                order.push(idx);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    (dist, order)
+}
+
+/// BFS desde `start` que termina en la primera celda transitable para la que
+/// `is_goal(índice)` es cierto. Con `adjacency` (al día con `grid`) recorre
+/// sus vecinos precalculados.
+///
+/// Devuelve la ruta como índices de celda (`None` si ninguna cumple) y las
+/// celdas expandidas en orden, incluida la final.
+pub(crate) fn bfs_to_goal(
+    grid: &Grid,
+    adjacency: Option<&Adjacency>,
+    start: (usize, usize),
+    mut is_goal: impl FnMut(usize) -> bool,
+) -> (Option<Vec<usize>>, Vec<usize>) {
+    let mut expanded = Vec::new();
+    if !grid.walkable(start) {
+        return (None, expanded);
+    }
+
+    let adjacency = adjacency.filter(|a| a.fits(grid));
+    let mut parent = vec![usize::MAX; grid.cells.len()];
+    let mut queue = VecDeque::from([grid.idx(start)]);
+    let mut next = Vec::with_capacity(4);
+    parent[grid.idx(start)] = grid.idx(start);
+
+    while let Some(idx) = queue.pop_front() {
+        expanded.push(idx);
+        if is_goal(idx) {
+            let mut path = vec![idx];
+            while let Some(&last) = path.last().filter(|&&c| parent[c] != c) {
+                path.push(parent[last]);
+            }
+            path.reverse();
+            return (Some(path), expanded);
+        }
+        next.clear();
+        match adjacency {
+            Some(adjacency) => next.extend(adjacency.steps(idx).map(|(_, n)| n)),
+            None => next.extend(
+                grid.neighbors(grid.coords(idx))
+                    .map(|c| grid.idx(c))
+                    .filter(|&n| grid.cells[n] == 1),
+            ),
+        }
+        for &n in &next {
+            if parent[n] == usize::MAX {
+                parent[n] = idx;
+                queue.push_back(n);
+            }
+        }
+    }
+
+    (None, expanded)
+}
+
+/// Holgura de cada celda: pasos (4-vecindad) hasta la pared más cercana,
+/// contando el exterior del grid como pared. Las celdas bloqueadas valen 0 y
+/// una celda libre pegada a una pared o al borde vale 1.
+pub(crate) fn clearance_map(grid: &Grid) -> Vec<u32> {
+    layer_clearance(grid, &grid.cells)
+}
+
+/// `clearance_map` con la capa de transitabilidad `cells` en lugar de las
+/// celdas del grid.
+pub(crate) fn layer_clearance(grid: &Grid, cells: &[u8]) -> Vec<u32> {
+    let mut clearance: Vec<u32> = (0..grid.cells.len())
+        .map(|idx| {
+            let (x, y) = grid.coords(idx);
+            let to_border = x.min(y).min(grid.height - 1 - x).min(grid.width - 1 - y);
+            if cells.get(idx) == Some(&1) {
+                to_border as u32 + 1
+            } else {
+                0
+            }
+        })
+        .collect();
+
+    // Las paredes se propagan por todo el grid (también a través de otras
+    // paredes), así que la holgura es la distancia Manhattan a la más cercana.
+    let mut queue: VecDeque<usize> = (0..grid.cells.len())
+        .filter(|&idx| clearance[idx] == 0)
+        .collect();
+    while let Some(idx) = queue.pop_front() {
+        let d = clearance[idx] + 1;
+        for next in grid.neighbors(grid.coords(idx)) {
+            let n = grid.idx(next);
+            if clearance[n] > d {
+                clearance[n] = d;
+                queue.push_back(n);
+            }
+        }
+    }
+    clearance
+}
+
+/// Dijkstra sobre los índices de `0..len` desde `seeds` (`(celda, coste
+/// inicial)`, que puede ser negativo). `edges(celda)` da los vecinos con lo
+/// que cuesta llegar a cada uno y `settle(celda, coste)` se llama al asentar
+/// cada celda, de menor a mayor coste (con empate, menor índice); si
+/// devuelve `false` la búsqueda se detiene ahí. Devuelve el coste de cada
+/// celda (infinito si no se llega) y de qué celda se llegó (`usize::MAX` en
+/// las semillas y las no alcanzadas).
+pub(crate) fn dijkstra_by<I>(
+    len: usize,
+    seeds: impl IntoIterator<Item = (usize, f64)>,
+    mut edges: impl FnMut(usize) -> I,
+    mut settle: impl FnMut(usize, f64) -> bool,
+) -> (Vec<f64>, Vec<usize>)
+where
+    I: IntoIterator<Item = (usize, f64)>,
+{
+    let mut dist = vec![f64::INFINITY; len];
+    let mut parent = vec![usize::MAX; len];
+    let mut open = BinaryHeap::new();
+    for (idx, d) in seeds {
+        if d < dist[idx] {
+            dist[idx] = d;
+            open.push(MinEntry(d, idx));
+        }
+    }
+    while let Some(MinEntry(d, idx)) = open.pop() {
+        if d > dist[idx] {
+            continue;
+        }
+        if !settle(idx, d) {
+            break;
+        }
+        for (n, step) in edges(idx) {
+            if d + step < dist[n] {
+                dist[n] = d + step;
+                parent[n] = idx;
+                open.push(MinEntry(d + step, n));
+            }
+        }
+    }
+    (dist, parent)
+}
+
+/// Vecinos de la celda `idx` que se pueden pisar con `costs` y lo que
+/// cuesta el paso desde ella (o hacia ella con `reverse`), para
+/// `dijkstra_by`.
+pub(crate) fn step_edges<'a>(
+    grid: &'a Grid,
+    costs: &'a StepCost,
+    idx: usize,
+    reverse: bool,
+) -> impl Iterator<Item = (usize, f64)> + 'a {
+    let cell = grid.coords(idx);
+    grid.neighbors(cell)
+        .filter(|&next| costs.allowed(next))
+        .filter_map(move |next| {
+            let step = if reverse {
+                costs.step(next, cell)
+            } else {
+                costs.step(cell, next)
+            };
+            Some((grid.idx(next), step?))
+        })
+}
+
+/// Distancias de coste mínimo desde `source` (o hacia ella con `reverse`)
+/// usando los costes por celda de `costs`; infinito si no se llega.
+pub(crate) fn dijkstra(grid: &Grid, costs: &StepCost, source: usize, reverse: bool) -> Vec<f64> {
+    let edges = |idx| step_edges(grid, costs, idx, reverse);
+    dijkstra_by(grid.cells.len(), [(source, 0.0)], edges, |_, _| true).0
+}
+
+/// Los `k` destinos de `goals` más cercanos a `source` con los costes de
+/// `costs`, con su coste y de menor a mayor. Un único Dijkstra que se
+/// detiene en cuanto se han asentado `k` destinos; los repetidos cuentan
+/// una vez.
+pub(crate) fn nearest_goals(
+    grid: &Grid,
+    costs: &StepCost,
+    source: usize,
+    goals: &[usize],
+    k: usize,
+) -> Vec<(usize, f64)> {
+    let mut found = Vec::new();
+    if k == 0 || !costs.allowed(grid.coords(source)) {
+        return found;
+    }
+    let mut is_goal = vec![false; grid.cells.len()];
+    for &g in goals.iter().filter(|&&g| g < grid.cells.len()) {
+        is_goal[g] = true;
+    }
+    let edges = |idx| step_edges(grid, costs, idx, false);
+    dijkstra_by(grid.cells.len(), [(source, 0.0)], edges, |idx, d| {
+        if std::mem::take(&mut is_goal[idx]) {
+            found.push((idx, d));
+        }
+        found.len() < k
+    });
+    found
+}
+
+/// Borde de cada isócrona sobre el mapa de `dijkstra` desde `source`: por
+/// cada umbral `i` de `thresholds`, las celdas a coste `thresholds[i]` o
+/// menos con algún vecino alcanzable más allá, como `(celda, i)` en orden
+/// de umbral y de índice.
+pub(crate) fn isochrones(
+    grid: &Grid,
+    costs: &StepCost,
+    source: usize,
+    thresholds: &[f64],
+) -> Vec<(usize, usize)> {
+    if !costs.allowed(grid.coords(source)) {
+        return vec![];
+    }
+    let dist = dijkstra(grid, costs, source, false);
+    let mut rings = Vec::new();
+    for (i, &limit) in thresholds.iter().enumerate() {
+        for idx in (0..dist.len()).filter(|&idx| dist[idx] <= limit) {
+            let mut beyond = grid.neighbors(grid.coords(idx)).map(|n| dist[grid.idx(n)]);
+            if beyond.any(|d| d.is_finite() && d > limit) {
+                rings.push((idx, i));
+            }
+        }
+    }
+    rings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: la búsqueda por predicado se detiene en la coincidencia más cercana
+    #[test]
+    fn bfs_to_goal_stops_at_nearest_match() {
+        let grid = Grid::square(vec![1; 16], 4);
+        let (path, expanded) = bfs_to_goal(&grid, None, (0, 0), |idx| idx == 3 || idx == 12 + 3);
+        assert_eq!(path, Some(vec![0, 1, 2, 3]));
+        assert_eq!(expanded.last(), Some(&3));
+        assert_eq!(bfs_to_goal(&grid, None, (0, 0), |_| false).0, None);
+    }
+
+    /// Test: el radio limita la expansión y las paredes cortan el paso
+    #[test]
+    fn distances_respect_radius_and_walls() {
+        let grid = Grid::square(vec![1, 1, 1, 0, 0, 1, 1, 1, 1], 3);
+        let (dist, order) = bfs_distances(&grid, &[(0, 0)], 3);
+        assert_eq!(order, vec![0, 1, 2, 5]);
+        assert_eq!(dist[5], 3);
+        assert_eq!(dist[8], UNREACHED);
+    }
+
+    /// Test: la holgura cuenta el borde y las paredes interiores
+    #[test]
+    fn clearance_counts_walls_and_border() {
+        let mut cells = vec![1; 25];
+        cells[0] = 0;
+        let c = clearance_map(&Grid::square(cells, 5));
+        assert_eq!(c[0], 0);
+        assert_eq!(c[12], 3);
+        assert_eq!(c[6], 2);
+        assert_eq!(c[1], 1);
+    }
+
+    /// Test: cada isócrona bordea lo alcanzable con su coste
+    #[test]
+    fn isochrone_rings() {
+        let grid = Grid::square(vec![1; 9], 3);
+        let opts = crate::SearchOptions::default();
+        let costs = StepCost::new(&grid, &opts);
+        let rings = isochrones(&grid, &costs, 0, &[1.0, 3.0, 4.0]);
+        assert_eq!(rings, vec![(1, 0), (3, 0), (5, 1), (7, 1)]);
+        assert!(isochrones(&grid, &costs, 0, &[-1.0]).is_empty());
+    }
+}
@@ -0,0 +1,69 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::grid::Grid;
+use crate::search::Step;
+
+/// Árbol de búsqueda en formato DOT de Graphviz: un nodo por celda expandida
+/// (con su `g` y su prioridad `f` si la búsqueda los anotó) y una arista
+/// desde la celda por la que se llegó a ella. Las celdas de `path` salen
+/// rellenas.
+pub(crate) fn search_tree(
+    grid: &Grid,
+    expanded: &[usize],
+    steps: &[Step<usize>],
+    path: &[(usize, usize)],
+) -> String {
+    let on_path: HashSet<usize> = path.iter().map(|&c| grid.idx(c)).collect();
+    let mut nodes = HashSet::new();
+    let mut edges = HashSet::new();
+    let mut dot = String::from("digraph search {\n  node [shape=box];\n");
+    for (i, &idx) in expanded.iter().enumerate() {
+        let step = steps.get(i);
+        if nodes.insert(idx) {
+            let (x, y) = grid.coords(idx);
+            let _ = write!(dot, "  n{idx} [label=\"({x}, {y})");
+            if let Some(step) = step {
+                let _ = write!(dot, "\\ng={} f={}", step.g, step.priority);
+            }
+            dot.push('"');
+            if on_path.contains(&idx) {
+                dot.push_str(", style=filled, fillcolor=gold");
+            }
+            dot.push_str("];\n");
+        }
+        if let Some(parent) = step.and_then(|s| s.parent)
+            && parent != idx
+            && edges.insert((parent, idx))
+        {
+            let _ = writeln!(dot, "  n{parent} -> n{idx};");
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: nodos con sus costes, aristas al padre y la ruta resaltada
+    #[test]
+    fn tree_as_dot() {
+        let grid = Grid::square(vec![1; 4], 2);
+        let step = |parent, g: f64| Step {
+            parent,
+            g,
+            priority: g + 1.0,
+            order: 0,
+            tied: false,
+        };
+        let steps = [step(None, 0.0), step(Some(0), 1.0), step(Some(1), 2.0)];
+        let dot = search_tree(&grid, &[0, 1, 3], &steps, &[(0, 0), (0, 1), (1, 1)]);
+        assert!(dot.starts_with("digraph search {"));
+        assert!(dot.contains("n1 [label=\"(0, 1)\\ng=1 f=2\", style=filled"));
+        assert!(dot.contains("n0 -> n1;\n  n3") && dot.contains("n1 -> n3;"));
+        assert!(!dot.contains("n2"));
+        assert_eq!(search_tree(&grid, &[2], &[], &[]).matches("->").count(), 0);
+    }
+}
@@ -0,0 +1,176 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::grid::Grid;
+
+/// Diferencia de coste por debajo de la cual dos caminos se consideran
+/// empatados. Las sumas en coma flotante dependen del orden, así que sin
+/// este margen el desempate entre rutas equivalentes dependería del
+/// redondeo; con él sale siempre la celda que entró antes en la frontera.
+pub(crate) const TIE_EPSILON: f64 = 1e-6;
+
+/// Coste de entrar en una celda de una capa `f32`: NaN y los infinitos la
+/// hacen intransitable y un valor negativo cuenta como 0 (quien llama
+/// comprueba la capa antes con `has_negative`).
+pub(crate) fn entry_cost(value: f32) -> Option<f64> {
+    value.is_finite().then(|| f64::from(value.max(0.0)))
+}
+
+/// Si la capa tiene algún coste negativo.
+pub(crate) fn has_negative(costs: &[f32]) -> bool {
+    costs.iter().any(|&c| c < 0.0)
+}
+
+/// Entrada de la frontera: sale antes la de menor coste y, si los costes
+/// están en la misma franja de `TIE_EPSILON`, la que entró antes.
+struct Entry {
+    cost: f64,
+    order: u64,
+    idx: usize,
+}
+
+impl Entry {
+    fn band(&self) -> f64 {
+        (self.cost / TIE_EPSILON).round()
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .band()
+            .total_cmp(&self.band())
+            .then_with(|| other.order.cmp(&self.order))
+    }
+}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+/// Dijkstra de `start` a `goal` con un coste `f32` de entrada por celda
+/// (ver `entry_cost`); las paredes del grid tampoco se pueden pisar. Los
+/// costes se suman en `f64` y la frontera se ordena por el coste en franjas
+/// de `TIE_EPSILON` y después por orden de llegada, de modo que el resultado
+/// es el mismo en cualquier plataforma.
+///
+/// Devuelve la ruta como índices de celda con su coste (`None` si no hay) y
+/// las celdas expandidas en orden, incluida la final.
+pub(crate) fn cheapest_path(
+    grid: &Grid,
+    costs: &[f32],
+    start: (usize, usize),
+    goal: (usize, usize),
+) -> (Option<(Vec<usize>, f64)>, Vec<usize>) {
+    let mut expanded = Vec::new();
+    let cost = |idx: usize| {
+        if grid.cells[idx] == 1 {
+            entry_cost(costs[idx])
+        } else {
+            None
+        }
+    };
+    if !grid.in_bounds(start) || !grid.in_bounds(goal) {
+        return (None, expanded);
+    }
+    let (s, g) = (grid.idx(start), grid.idx(goal));
+    if cost(s).is_none() || cost(g).is_none() {
+        return (None, expanded);
+    }
+
+    let mut dist = vec![f64::INFINITY; grid.cells.len()];
+    let mut parent = vec![usize::MAX; grid.cells.len()];
+    let mut done = vec![false; grid.cells.len()];
+    let mut open = BinaryHeap::from([Entry {
+        cost: 0.0,
+        order: 0,
+        idx: s,
+    }]);
+    let mut order = 0u64;
+    dist[s] = 0.0;
+    parent[s] = s;
+
+    while let Some(Entry { idx, .. }) = open.pop() {
+        if done[idx] {
+            continue;
+        }
+        done[idx] = true;
+        expanded.push(idx);
+        if idx == g {
+            let mut path = vec![idx];
+            while let Some(&last) = path.last().filter(|&&c| parent[c] != c) {
+                path.push(parent[last]);
+            }
+            path.reverse();
+            return (Some((path, dist[g])), expanded);
+        }
+        for next in grid.neighbors(grid.coords(idx)).map(|c| grid.idx(c)) {
+            let Some(step) = cost(next).filter(|_| !done[next]) else {
+                continue;
+            };
+            let nd = dist[idx] + step;
+            // Solo cuenta como mejora si supera el margen de empate.
+            if nd < dist[next] - TIE_EPSILON {
+                dist[next] = nd;
+                parent[next] = idx;
+                order += 1;
+                open.push(Entry {
+                    cost: nd,
+                    order,
+                    idx: next,
+                });
+            }
+        }
+    }
+    (None, expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: NaN e infinito bloquean, los negativos cuentan como 0 y los
+    /// casi empates se deshacen por orden de llegada
+    #[test]
+    fn float_costs() {
+        let grid = Grid::square(vec![1; 9], 3);
+        let costs = [
+            0.5,
+            f32::NAN,
+            0.5,
+            0.25,
+            f32::INFINITY,
+            0.5,
+            -3.0,
+            0.75,
+            0.5,
+        ];
+        let (found, _) = cheapest_path(&grid, &costs, (0, 0), (0, 2));
+        let (path, total) = found.unwrap();
+        assert_eq!(path, vec![0, 3, 6, 7, 8, 5, 2]);
+        assert!((total - 2.5).abs() < 1e-9);
+
+        // Los dos caminos difieren en menos que el margen: gana la primera
+        // celda que entró en la frontera (abajo, según DIRS), no la más barata.
+        let grid = Grid::square(vec![1; 4], 2);
+        let costs = [0.0, 0.3, 0.300_000_1, 0.2];
+        let (path, _) = cheapest_path(&grid, &costs, (0, 0), (1, 1)).0.unwrap();
+        assert_eq!(path, vec![0, 2, 3]);
+
+        // Costes enormes siguen ordenándose por su valor.
+        let costs = [0.0, 2e13, 5e13, 0.0];
+        let (path, total) = cheapest_path(&grid, &costs, (0, 0), (1, 1)).0.unwrap();
+        assert_eq!((path, total), (vec![0, 1, 3], f64::from(2e13f32)));
+        assert!(has_negative(&[1.0, -3.0]) && !has_negative(&[0.0, f32::NAN]));
+    }
+}
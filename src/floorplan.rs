@@ -0,0 +1,153 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::Grid;
+use crate::policy;
+use crate::transform;
+
+/// Opciones para convertir un plano escaneado en grid
+/// (`PathFinder::from_floorplan`).
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct FloorplanOptions {
+    pub(crate) width: usize,
+    pub(crate) threshold: u8,
+    pub(crate) min_speck: usize,
+    pub(crate) radius: f32,
+}
+
+#[wasm_bindgen]
+impl FloorplanOptions {
+    /// Opciones para una imagen de `width` píxeles de ancho: umbral 128, sin
+    /// limpieza de motas ni inflado.
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: usize) -> Self {
+        Self {
+            width,
+            threshold: 128,
+            min_speck: 0,
+            radius: 0.0,
+        }
+    }
+
+    /// Los píxeles con luminancia menor que `threshold` (0 a 255) son pared.
+    pub fn set_threshold(&mut self, threshold: u8) {
+        self.threshold = threshold;
+    }
+
+    /// Borra las manchas de pared (4-conexas) de menos de `min_cells`
+    /// celdas, típicas del ruido del escáner.
+    pub fn set_despeckle(&mut self, min_cells: usize) {
+        self.min_speck = min_cells;
+    }
+
+    /// Radio del agente en celdas para inflar las paredes al final (como
+    /// `PathFinder::inflate_obstacles`). Con 0 no se inflan. Un radio que no
+    /// es finito es una anomalía (ver `set_policy`) y en modo tolerante se
+    /// ignora.
+    pub fn set_inflation(&mut self, radius: f32) -> Result<(), JsError> {
+        if !radius.is_finite() {
+            policy::anomaly("radio de inflado no finito")?;
+            return Ok(());
+        }
+        self.radius = radius;
+        Ok(())
+    }
+}
+
+/// Grid limpio a partir de una imagen RGBA (`ImageData.data`), un píxel por
+/// celda: umbral de luminancia (los píxeles transparentes cuentan como
+/// fondo blanco), limpieza de motas e inflado. `None` si los bytes no
+/// forman filas completas de `opts.width` píxeles.
+pub(crate) fn preprocess(rgba: &[u8], opts: &FloorplanOptions) -> Option<Grid> {
+    let pixels = rgba.len() / 4;
+    if opts.width == 0
+        || !rgba.len().is_multiple_of(4)
+        || pixels == 0
+        || !pixels.is_multiple_of(opts.width)
+    {
+        return None;
+    }
+    let cells = rgba
+        .chunks_exact(4)
+        .map(|p| {
+            let luma =
+                (299 * u32::from(p[0]) + 587 * u32::from(p[1]) + 114 * u32::from(p[2])) / 1000;
+            let alpha = u32::from(p[3]);
+            let over_white = (luma * alpha + 255 * (255 - alpha)) / 255;
+            u8::from(over_white >= u32::from(opts.threshold))
+        })
+        .collect();
+    let mut grid = Grid::new(cells, opts.width, pixels / opts.width);
+    despeckle(&mut grid, opts.min_speck);
+    if opts.radius > 0.0
+        && let Some(inflated) = transform::inflate(&grid, opts.radius)
+    {
+        grid = inflated;
+    }
+    Some(grid)
+}
+
+/// Libera las componentes de pared con menos de `min_cells` celdas.
+fn despeckle(grid: &mut Grid, min_cells: usize) {
+    let mut seen = vec![false; grid.cells.len()];
+    for start in 0..grid.cells.len() {
+        if seen[start] || grid.cells[start] == 1 {
+            continue;
+        }
+        seen[start] = true;
+        let mut component = vec![start];
+        let mut i = 0;
+        while let Some(&idx) = component.get(i) {
+            i += 1;
+            for next in grid.neighbors(grid.coords(idx)).map(|c| grid.idx(c)) {
+                if !seen[next] && grid.cells[next] != 1 {
+                    seen[next] = true;
+                    component.push(next);
+                }
+            }
+        }
+        if component.len() < min_cells {
+            for idx in component {
+                grid.cells[idx] = 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(gray: &[u8]) -> Vec<u8> {
+        gray.iter().flat_map(|&g| [g, g, g, 255]).collect()
+    }
+
+    /// Test: umbral, motas e inflado producen el grid esperado
+    #[test]
+    fn floorplan_pipeline() {
+        // 3 filas x 5 columnas: pared gris oscura a la izquierda y una mota.
+        let gray = [
+            20, 250, 250, 250, 250, //
+            30, 250, 250, 90, 250, //
+            20, 250, 250, 250, 250,
+        ];
+        let mut opts = FloorplanOptions::new(5);
+        let grid = preprocess(&image(&gray), &opts).unwrap();
+        assert_eq!((grid.height, grid.width), (3, 5));
+        assert_eq!(grid.cells.iter().filter(|&&c| c == 0).count(), 4);
+
+        opts.set_despeckle(2);
+        let grid = preprocess(&image(&gray), &opts).unwrap();
+        assert_eq!(grid.cells[8], 1);
+        assert_eq!(grid.cells[5], 0);
+
+        let mut transparent = image(&gray);
+        transparent[3] = 0;
+        assert_eq!(preprocess(&transparent, &opts).unwrap().cells[0], 1);
+
+        opts.set_inflation(1.0).unwrap();
+        let grid = preprocess(&image(&gray), &opts).unwrap();
+        assert_eq!(grid.cells[..3], [0, 0, 1]);
+        assert!(preprocess(&image(&gray[..7]), &opts).is_none());
+    }
+}
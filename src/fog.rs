@@ -0,0 +1,66 @@
+use crate::grid::Grid;
+
+/// Niebla de guerra: qué celdas ha visto ya el agente. Las no exploradas se
+/// tratan como desconocidas, ni libres ni bloqueadas.
+#[derive(Clone, Debug)]
+pub(crate) struct Fog {
+    pub explored: Vec<bool>,
+}
+
+impl Fog {
+    pub fn new(cells: usize) -> Self {
+        Self {
+            explored: vec![false; cells],
+        }
+    }
+
+    /// Marca como explorado el cuadrado de lado `2 * radius + 1` centrado en
+    /// `center` (recortado al grid).
+    pub fn reveal(&mut self, grid: &Grid, (cx, cy): (usize, usize), radius: usize) {
+        let rows = cx.saturating_sub(radius)..(cx + radius + 1).min(grid.height);
+        for x in rows {
+            for y in cy.saturating_sub(radius)..(cy + radius + 1).min(grid.width) {
+                self.explored[grid.idx((x, y))] = true;
+            }
+        }
+    }
+
+    /// Grid con solo lo conocido: las celdas sin explorar cuentan como
+    /// bloqueadas para planificar sobre ellas.
+    pub fn known(&self, grid: &Grid) -> Grid {
+        let mut known = grid.clone();
+        for (cell, &seen) in known.cells.iter_mut().zip(&self.explored) {
+            if !seen {
+                *cell = 0;
+            }
+        }
+        known
+    }
+
+    /// Si `idx` es una celda explorada y libre junto a otra sin explorar.
+    pub fn is_frontier(&self, grid: &Grid, idx: usize) -> bool {
+        let cell = grid.coords(idx);
+        self.explored[idx]
+            && grid.walkable(cell)
+            && grid.neighbors(cell).any(|n| !self.explored[grid.idx(n)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: la frontera es el borde libre de la zona revelada
+    #[test]
+    fn frontier_is_edge_of_revealed_area() {
+        let grid = Grid::square(vec![1; 25], 5);
+        let mut fog = Fog::new(25);
+        fog.reveal(&grid, (0, 0), 1);
+        let frontier: Vec<usize> = (0..25).filter(|&i| fog.is_frontier(&grid, i)).collect();
+        assert_eq!(frontier, vec![1, 5, 6]);
+        assert_eq!(
+            fog.known(&grid).cells.iter().filter(|&&c| c == 1).count(),
+            4
+        );
+    }
+}
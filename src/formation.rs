@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::distance;
+use crate::grid::Grid;
+
+/// Rutas coordinadas de un grupo en formación (`PathFinder::search_formation`).
+/// Todas tienen la misma longitud: la celda `t` de cada una es donde está ese
+/// miembro en el instante `t`. El miembro 0 es el líder.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct Formation {
+    paths: Vec<Vec<(usize, usize)>>,
+    single_file: Vec<bool>,
+}
+
+#[wasm_bindgen]
+impl Formation {
+    /// Miembros, líder incluido (0 si no hay ruta del líder).
+    pub fn members(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Instantes del plan.
+    pub fn ticks(&self) -> usize {
+        self.single_file.len()
+    }
+
+    /// Ruta del miembro `i` como `[x0, y0, x1, y1, ...]`.
+    pub fn path(&self, i: usize) -> Vec<usize> {
+        self.paths
+            .get(i)
+            .map_or_else(Vec::new, |p| p.iter().flat_map(|&(x, y)| [x, y]).collect())
+    }
+
+    /// 1 en los instantes en que la formación no cabe y el grupo va en fila
+    /// india tras el líder, 0 en los demás.
+    pub fn single_file(&self) -> Vec<u8> {
+        self.single_file.iter().map(|&s| u8::from(s)).collect()
+    }
+}
+
+/// Celda del miembro con desplazamiento `offset` (filas, columnas) respecto
+/// al líder en `leader`, si cabe: dentro del grid, transitable y con todo el
+/// rectángulo entre ella y el líder libre, para que no quede al otro lado de
+/// una pared.
+fn slot(grid: &Grid, leader: (usize, usize), offset: (isize, isize)) -> Option<(usize, usize)> {
+    let x = leader.0.checked_add_signed(offset.0)?;
+    let y = leader.1.checked_add_signed(offset.1)?;
+    if !grid.in_bounds((x, y)) {
+        return None;
+    }
+    let clear = (x.min(leader.0)..=x.max(leader.0))
+        .all(|rx| (y.min(leader.1)..=y.max(leader.1)).all(|ry| grid.walkable((rx, ry))));
+    clear.then_some((x, y))
+}
+
+/// Mueve al grupo por `leader` (ruta continua del líder) manteniendo los
+/// desplazamientos `offsets` mientras todas las celdas de la formación
+/// caben. Cuando alguna no cabe (un pasillo estrecho), el miembro `k` sigue
+/// la estela del líder `k` celdas por detrás, y al volver a haber sitio cada
+/// uno recupera su puesto. En cada instante un miembro avanza una celda hacia
+/// su puesto por un camino más corto cuya celda siguiente esté libre, o
+/// espera si no hay ninguna; el líder no espera. Al final del recorrido se
+/// siguen sumando instantes hasta que todos llegan a su puesto (con un
+/// límite por si alguno queda atascado). Al empezar, los miembros que no
+/// caben salen de la celda de inicio del líder. Hay un mapa de distancias
+/// por puesto distinto, que se conserva mientras siga siendo el puesto de
+/// alguien (la estela en fila india y los puestos finales se repiten).
+pub(crate) fn plan(
+    grid: &Grid,
+    leader: &[(usize, usize)],
+    offsets: &[(isize, isize)],
+) -> Formation {
+    let Some(&start) = leader.first() else {
+        return Formation::default();
+    };
+    let last = leader.len() - 1;
+    let limit = leader.len() + 2 * (grid.width + grid.height);
+    let targets = |t: usize| -> (Vec<(usize, usize)>, bool) {
+        let at = leader[t.min(last)];
+        let slots: Option<Vec<_>> = offsets.iter().map(|&o| slot(grid, at, o)).collect();
+        match slots {
+            Some(slots) => (slots, false),
+            None => (
+                (1..=offsets.len())
+                    .map(|k| leader[t.min(last).saturating_sub(k)])
+                    .collect(),
+                true,
+            ),
+        }
+    };
+
+    let (first, file) = targets(0);
+    let mut members = if file {
+        vec![start; offsets.len()]
+    } else {
+        first
+    };
+    let mut paths: Vec<Vec<(usize, usize)>> = std::iter::once(start)
+        .chain(members.iter().copied())
+        .map(|c| vec![c])
+        .collect();
+    let mut single_file = vec![file];
+    let mut fields: HashMap<(usize, usize), Vec<u32>> = HashMap::new();
+
+    for t in 1..limit {
+        let (goals, file) = targets(t);
+        let head = leader[t.min(last)];
+        if t >= last && members == goals {
+            break;
+        }
+        fields.retain(|goal, _| goals.contains(goal));
+        for m in 0..members.len() {
+            let here = members[m];
+            if here == goals[m] {
+                continue;
+            }
+            let dist = fields.entry(goals[m]).or_insert_with(|| {
+                distance::bfs_distances(grid, &[goals[m]], distance::UNREACHED).0
+            });
+            let next = grid
+                .neighbors(here)
+                .filter(|&n| dist[grid.idx(n)] < dist[grid.idx(here)])
+                .find(|&n| n != head && !members.contains(&n));
+            if let Some(next) = next {
+                members[m] = next;
+            }
+        }
+        paths[0].push(head);
+        for (path, &cell) in paths[1..].iter_mut().zip(&members) {
+            path.push(cell);
+        }
+        single_file.push(file);
+    }
+
+    Formation { paths, single_file }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: la pareja va en columna por el campo abierto, se pone en fila
+    /// india para cruzar la puerta y vuelve a su puesto al otro lado
+    #[test]
+    fn formation_squeezes_through_door() {
+        #[rustfmt::skip]
+        let cells = vec![
+            1, 1, 1, 1, 1, 1, 1,
+            1, 1, 1, 1, 1, 1, 1,
+            0, 0, 0, 1, 0, 0, 0,
+            1, 1, 1, 1, 1, 1, 1,
+            1, 1, 1, 1, 1, 1, 1,
+        ];
+        let grid = Grid::new(cells, 7, 5);
+        let leader = [(0, 3), (1, 3), (2, 3), (3, 3), (4, 3)];
+        let plan = plan(&grid, &leader, &[(0, 1)]);
+        assert_eq!(plan.members(), 2);
+        assert_eq!(plan.single_file(), vec![0, 0, 1, 0, 0, 0, 0]);
+        let follower = plan.path(1);
+        assert_eq!(follower, vec![0, 4, 1, 4, 1, 3, 2, 3, 3, 3, 3, 4, 4, 4]);
+        for t in 0..plan.ticks() {
+            assert_ne!(plan.path(0)[2 * t..2 * t + 2], follower[2 * t..2 * t + 2]);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use wasm_bindgen::prelude::*;
+
+use crate::SearchOptions;
+use crate::compare;
+use crate::grid::Grid;
+use crate::locks::Locks;
+use crate::overlays::Overlays;
+use crate::rng::Rng;
+use crate::scenario::{self, Scenario};
+use crate::search::{self, Outcome};
+use crate::verify::{self, PathVerdict};
+
+/// Algoritmos que deben dar siempre la ruta más corta.
+const EXACT: [&str; 5] = ["bfs", "dijkstra", "astar", "01bfs", "rectangles"];
+/// Lado máximo de los grids generados.
+const MAX_SIDE: usize = 12;
+
+/// Contraejemplos encontrados por `fuzz`: cada uno es una consulta en la que
+/// los algoritmos incumplen algún invariante.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct FuzzReport {
+    iterations: u32,
+    failures: Vec<Failure>,
+}
+
+#[derive(Clone, Debug)]
+struct Failure {
+    grid: Grid,
+    start: (usize, usize),
+    goal: (usize, usize),
+    message: String,
+}
+
+#[wasm_bindgen]
+impl FuzzReport {
+    /// Consultas probadas.
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+
+    pub fn len(&self) -> usize {
+        self.failures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Qué invariante falló en el contraejemplo `i` (vacío si no existe).
+    pub fn message(&self, i: usize) -> String {
+        self.failures
+            .get(i)
+            .map_or_else(String::new, |f| f.message.clone())
+    }
+
+    /// Contraejemplo `i` como escenario de `PathFinder::load_scenario`, con
+    /// la consulta en `queries` (vacío si no existe).
+    pub fn scenario(&self, i: usize) -> Vec<u8> {
+        self.failures.get(i).map_or_else(Vec::new, |f| {
+            scenario::encode(&Scenario {
+                grid: f.grid.clone(),
+                options: None,
+                queries: vec![(f.start, f.goal)],
+                fog: None,
+                locks: Locks::default(),
+                moving: vec![],
+                reuse: None,
+                hazard: None,
+                congestion: None,
+                overlays: Overlays::default(),
+                sim_clock: None,
+            })
+        })
+    }
+}
+
+/// Genera `iterations` grids y consultas aleatorias (reproducibles con
+/// `seed`), las resuelve con todos los algoritmos de `PathFinder::compare`
+/// y comprueba que las rutas son válidas y van de inicio a destino, que
+/// todos coinciden en si hay ruta y que los exactos dan la longitud mínima.
+/// Pensado para CI y para el navegador: sin contraejemplos, el informe
+/// está vacío.
+#[wasm_bindgen]
+pub fn fuzz(iterations: u32, seed: u32) -> FuzzReport {
+    let mut rng = Rng::new(u64::from(seed));
+    let mut report = FuzzReport {
+        iterations,
+        ..FuzzReport::default()
+    };
+    for _ in 0..iterations {
+        let width = 1 + rng.below(MAX_SIDE);
+        let height = 1 + rng.below(MAX_SIDE);
+        let walls = rng.below(45);
+        let cells = (0..width * height)
+            .map(|_| u8::from(rng.below(100) >= walls))
+            .collect();
+        let grid = Grid::new(cells, width, height);
+        let start = (rng.below(height), rng.below(width));
+        let goal = (rng.below(height), rng.below(width));
+        if let Some(message) = check(&grid, start, goal) {
+            report.failures.push(Failure {
+                grid,
+                start,
+                goal,
+                message,
+            });
+        }
+    }
+    report
+}
+
+/// Primer invariante incumplido en la consulta, si lo hay.
+fn check(grid: &Grid, start: (usize, usize), goal: (usize, usize)) -> Option<String> {
+    let opts = SearchOptions::default();
+    let optimum = search::grid_cost(grid, start, goal, &opts);
+    for name in EXACT.iter().chain(&["greedy"]) {
+        let outcome = compare::run(grid, name, &opts, start, goal).unwrap_or_else(Outcome::none);
+        let Some(found) = outcome.result else {
+            if optimum.is_some() {
+                return Some(format!("{name}: no encuentra ruta y la hay"));
+            }
+            continue;
+        };
+        let Some(best) = optimum else {
+            return Some(format!("{name}: devuelve ruta y no la hay"));
+        };
+        if found.states.first() != Some(&start) || found.states.last() != Some(&goal) {
+            return Some(format!("{name}: la ruta no une inicio y destino"));
+        }
+        let verdict = verify::verify(grid, &found.states);
+        let exact = EXACT.contains(name);
+        match verdict {
+            PathVerdict::Valid => {}
+            PathVerdict::NotShortest if !exact => {}
+            _ => return Some(format!("{name}: ruta inválida ({verdict:?})")),
+        }
+        if found.cost != (found.states.len() - 1) as f64 || (exact && found.cost != best) {
+            return Some(format!(
+                "{name}: coste {} con {} pasos y óptimo {best}",
+                found.cost,
+                found.states.len() - 1
+            ));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: los algoritmos actuales no dan contraejemplos
+    #[test]
+    fn no_counterexamples() {
+        let report = fuzz(300, 1);
+        assert_eq!(report.iterations(), 300);
+        assert!(report.is_empty(), "{}", report.message(0));
+        assert!(check(&Grid::square(vec![1, 0, 0, 1], 2), (0, 0), (1, 1)).is_none());
+    }
+}
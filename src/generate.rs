@@ -0,0 +1,199 @@
+use wasm_bindgen::prelude::*;
+
+use crate::distance;
+use crate::grid::{DIRS, Grid};
+use crate::rng::Rng;
+
+/// Genera un mapa de cuevas con el algoritmo del borracho (drunkard's walk):
+/// `walkers` caminantes salen del centro y excavan suelo (`1`) en pasos
+/// aleatorios hasta agotar `steps` cada uno o alcanzar `floor_ratio` de suelo.
+/// La misma `seed` produce siempre el mismo mapa.
+#[wasm_bindgen]
+pub fn drunkard_walk(
+    size: usize,
+    walkers: usize,
+    steps: usize,
+    floor_ratio: f32,
+    seed: u32,
+) -> Vec<u8> {
+    let mut grid = Grid::square(vec![0; size * size], size);
+    if size == 0 {
+        return grid.cells;
+    }
+
+    let mut rng = Rng::new(u64::from(seed));
+    let target =
+        (f64::from(floor_ratio.clamp(0.0, 1.0)) * grid.cells.len() as f64).round() as usize;
+    let center = (size / 2, size / 2);
+    let mut floor = 0;
+
+    let mut carve = |grid: &mut Grid, cell| {
+        let idx = grid.idx(cell);
+        if grid.cells[idx] == 0 {
+            grid.cells[idx] = 1;
+            floor += 1;
+        }
+        floor
+    };
+
+    'walk: for _ in 0..walkers {
+        let mut cell = center;
+        if carve(&mut grid, cell) >= target {
+            break;
+        }
+        for _ in 0..steps {
+            let (dx, dy) = DIRS[rng.below(DIRS.len())];
+            let (Some(x), Some(y)) = (cell.0.checked_add_signed(dx), cell.1.checked_add_signed(dy))
+            else {
+                continue;
+            };
+            if !grid.in_bounds((x, y)) {
+                continue;
+            }
+            cell = (x, y);
+            if carve(&mut grid, cell) >= target {
+                break 'walk;
+            }
+        }
+    }
+
+    grid.cells
+}
+
+/// Simetría de `symmetric_map`, que lleva la esquina `(0, 0)` del primer
+/// jugador a la opuesta del segundo.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Symmetry {
+    /// Giro de 180 grados alrededor del centro.
+    Rotational,
+    /// Reflejo sobre la antidiagonal (de la esquina superior derecha a la
+    /// inferior izquierda).
+    Mirror,
+}
+
+/// Mapa simétrico para partidas competitivas: una cueva de `drunkard_walk`
+/// con los mismos parámetros se hace simétrica (es suelo lo que lo es en
+/// alguna de las dos mitades) y se excava un pasillo en L de cada esquina
+/// al objetivo central, que es `(size / 2, size / 2)` con `Rotational` y
+/// `(size / 2, size - 1 - size / 2)` con `Mirror`. Antes de devolverlo se
+/// comprueba con mapas de distancia que cada celda está a la misma distancia
+/// a pie de un jugador que su simétrica del otro, así que ambos tardan lo
+/// mismo en llegar al objetivo. La simetría rotacional necesita un tamaño
+/// impar para que el objetivo caiga en una celda.
+#[wasm_bindgen]
+pub fn symmetric_map(
+    size: usize,
+    walkers: usize,
+    steps: usize,
+    floor_ratio: f32,
+    seed: u32,
+    symmetry: Symmetry,
+) -> Result<Vec<u8>, JsError> {
+    symmetric(size, walkers, steps, floor_ratio, seed, symmetry).map_err(JsError::new)
+}
+
+pub(crate) fn symmetric(
+    size: usize,
+    walkers: usize,
+    steps: usize,
+    floor_ratio: f32,
+    seed: u32,
+    symmetry: Symmetry,
+) -> Result<Vec<u8>, &'static str> {
+    if size == 0 {
+        return Ok(vec![]);
+    }
+    if symmetry == Symmetry::Rotational && size.is_multiple_of(2) {
+        return Err("la simetría rotacional necesita un tamaño impar");
+    }
+    let last = size - 1;
+    let image = |(x, y): (usize, usize)| match symmetry {
+        Symmetry::Rotational => (last - x, last - y),
+        Symmetry::Mirror => (last - y, last - x),
+    };
+    let objective = match symmetry {
+        Symmetry::Rotational => (size / 2, size / 2),
+        Symmetry::Mirror => (size / 2, last - size / 2),
+    };
+
+    let mut grid = Grid::square(drunkard_walk(size, walkers, steps, floor_ratio, seed), size);
+    let floor: Vec<bool> = (0..grid.cells.len())
+        .map(|idx| grid.cells[idx] == 1 || grid.cells[grid.idx(image(grid.coords(idx)))] == 1)
+        .collect();
+    for (cell, floor) in grid.cells.iter_mut().zip(floor) {
+        *cell = u8::from(floor);
+    }
+    let corridor = (0..=objective.0)
+        .map(|x| (x, 0))
+        .chain((0..=objective.1).map(|y| (objective.0, y)));
+    for cell in corridor {
+        for c in [cell, image(cell)] {
+            let idx = grid.idx(c);
+            grid.cells[idx] = 1;
+        }
+    }
+
+    let (first, _) = distance::bfs_distances(&grid, &[(0, 0)], u32::MAX);
+    let (second, _) = distance::bfs_distances(&grid, &[(last, last)], u32::MAX);
+    let fair =
+        (0..grid.cells.len()).all(|idx| first[idx] == second[grid.idx(image(grid.coords(idx)))]);
+    let goal = grid.idx(objective);
+    if !fair || first[goal] == distance::UNREACHED || first[goal] != second[goal] {
+        return Err("el mapa generado no es justo para los dos jugadores");
+    }
+    Ok(grid.cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: la misma semilla produce el mismo mapa y otra semilla otro
+    #[test]
+    fn drunkard_is_deterministic() {
+        let a = drunkard_walk(16, 4, 200, 1.0, 1);
+        assert_eq!(a, drunkard_walk(16, 4, 200, 1.0, 1));
+        assert_ne!(a, drunkard_walk(16, 4, 200, 1.0, 2));
+    }
+
+    /// Test: se detiene al alcanzar la proporción de suelo pedida
+    #[test]
+    fn drunkard_stops_at_ratio() {
+        let cells = drunkard_walk(20, 10, 10_000, 0.3, 7);
+        let floor = cells.iter().filter(|&&c| c == 1).count();
+        assert_eq!(floor, 120);
+    }
+
+    /// Test: los mapas simétricos lo son y los dos jugadores están a la
+    /// misma distancia del objetivo
+    #[test]
+    fn symmetric_maps_are_fair() {
+        for (size, symmetry) in [(15, Symmetry::Rotational), (14, Symmetry::Mirror)] {
+            let cells = symmetric(size, 3, 80, 0.4, 5, symmetry).unwrap();
+            let grid = Grid::square(cells, size);
+            let last = size - 1;
+            for x in 0..size {
+                for y in 0..size {
+                    let image = match symmetry {
+                        Symmetry::Rotational => (last - x, last - y),
+                        Symmetry::Mirror => (last - y, last - x),
+                    };
+                    assert_eq!(grid.walkable((x, y)), grid.walkable(image));
+                }
+            }
+            assert!(grid.walkable((0, 0)) && grid.walkable((last, last)));
+        }
+        assert!(symmetric(14, 3, 80, 0.4, 5, Symmetry::Rotational).is_err());
+    }
+
+    /// Test: el suelo excavado es una única región conexa
+    #[test]
+    fn drunkard_is_connected() {
+        let cells = drunkard_walk(12, 3, 150, 1.0, 3);
+        let grid = Grid::square(cells, 12);
+        let labels = crate::session::components(&grid);
+        let center = labels[grid.idx((6, 6))];
+        assert!((0..grid.cells.len()).all(|i| grid.cells[i] == 0 || labels[i] == center));
+    }
+}
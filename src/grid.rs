@@ -0,0 +1,192 @@
+use crate::bytes::{self, Reader, Writer};
+
+/// Movimientos permitidos: abajo, derecha, arriba, izquierda (sin diagonales).
+pub(crate) const DIRS: [(isize, isize); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+/// Dirección opuesta a `d` dentro de `DIRS`.
+pub(crate) fn opposite(d: usize) -> usize {
+    (d + 2) % 4
+}
+
+/// Índice en `DIRS` del paso de `from` a la celda vecina `to`.
+pub(crate) fn direction(from: (usize, usize), to: (usize, usize)) -> Option<usize> {
+    let delta = (
+        to.0 as isize - from.0 as isize,
+        to.1 as isize - from.1 as isize,
+    );
+    DIRS.iter().position(|&d| d == delta)
+}
+
+/// Grid plano: `x` es la fila (`0..height`) e `y` la columna (`0..width`),
+/// igual que en el BFS original (`idx = x * width + y`).
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Grid {
+    pub cells: Vec<u8>,
+    pub width: usize,
+    pub height: usize,
+    /// Altura por celda, usada por el modelo de pendiente.
+    pub elevation: Option<Vec<f32>>,
+    /// Coste extra (no negativo) por entrar en cada celda, compuesto por el
+    /// `PathFinder` a partir de sus capas dinámicas.
+    pub penalty: Option<Vec<f32>>,
+    /// Puntos de vida de las paredes que se pueden derribar.
+    pub hit_points: Option<Vec<f32>>,
+    /// Valores de `penalty` y el índice del de cada celda cuando toma dos o
+    /// menos; lo mantiene `set_penalty`.
+    levels: Option<(Vec<f32>, Vec<u8>)>,
+}
+
+impl Grid {
+    pub fn new(cells: Vec<u8>, width: usize, height: usize) -> Self {
+        Self {
+            cells,
+            width,
+            height,
+            elevation: None,
+            penalty: None,
+            hit_points: None,
+            levels: None,
+        }
+    }
+
+    pub fn square(cells: Vec<u8>, size: usize) -> Self {
+        Self::new(cells, size, size)
+    }
+
+    pub fn idx(&self, (x, y): (usize, usize)) -> usize {
+        x * self.width + y
+    }
+
+    pub fn coords(&self, idx: usize) -> (usize, usize) {
+        (idx / self.width, idx % self.width)
+    }
+
+    pub fn in_bounds(&self, (x, y): (usize, usize)) -> bool {
+        x < self.height && y < self.width
+    }
+
+    /// Cambia la penalización e indexa sus valores si solo toma dos (ver
+    /// `penalty_levels`), para no recorrer el grid en cada consulta.
+    pub fn set_penalty(&mut self, penalty: Option<Vec<f32>>) {
+        self.levels = penalty.as_ref().and_then(|penalty| {
+            let mut values: Vec<f32> = Vec::new();
+            let mut entry = Vec::with_capacity(penalty.len());
+            for &p in penalty {
+                let p = p.max(0.0);
+                let level = match values.iter().position(|&v| v == p) {
+                    Some(i) => i,
+                    None if values.len() < 2 => {
+                        values.push(p);
+                        values.len() - 1
+                    }
+                    None => return None,
+                };
+                entry.push(level as u8);
+            }
+            Some((values, entry))
+        });
+        self.penalty = penalty;
+    }
+
+    /// Valores distintos de la penalización (como mucho dos) y el índice del
+    /// de cada celda; `None` si no hay penalización o toma más valores.
+    pub fn penalty_levels(&self) -> Option<(&[f32], &[u8])> {
+        self.levels
+            .as_ref()
+            .map(|(v, e)| (v.as_slice(), e.as_slice()))
+    }
+
+    pub fn walkable(&self, cell: (usize, usize)) -> bool {
+        self.in_bounds(cell) && self.cells.get(self.idx(cell)) == Some(&1)
+    }
+
+    /// Vecinos dentro del grid (transitables o no), en el orden de `DIRS`.
+    pub fn neighbors(&self, cell: (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.steps(cell).map(|(_, next)| next)
+    }
+
+    /// Igual que `neighbors` pero junto al índice de la dirección en `DIRS`.
+    pub fn steps(
+        &self,
+        (x, y): (usize, usize),
+    ) -> impl Iterator<Item = (usize, (usize, usize))> + '_ {
+        DIRS.iter().enumerate().filter_map(move |(d, &(dx, dy))| {
+            let nx = x.checked_add_signed(dx)?;
+            let ny = y.checked_add_signed(dy)?;
+            self.in_bounds((nx, ny)).then_some((d, (nx, ny)))
+        })
+    }
+
+    /// Huella FNV-1a de 64 bits de dimensiones y celdas, estable entre builds.
+    pub fn fingerprint(&self) -> u64 {
+        let dims = [self.width as u64, self.height as u64];
+        let bytes = dims.iter().flat_map(|d| d.to_le_bytes());
+        bytes::fnv1a(bytes.chain(self.cells.iter().copied()))
+    }
+
+    /// Huella FNV-1a de todo lo que influye en las rutas: dimensiones,
+    /// celdas y capas, con el mismo formato que `write_to`.
+    pub fn content_hash(&self) -> u64 {
+        let mut w = Writer::new();
+        self.write_to(&mut w);
+        bytes::fnv1a(w.finish())
+    }
+
+    /// Celdas `(índice, valor nuevo)` que cambian respecto a `other`, o `None`
+    /// si los tamaños no coinciden.
+    pub fn diff(&self, other: &[u8]) -> Option<Vec<(usize, u8)>> {
+        (other.len() == self.cells.len()).then(|| {
+            self.cells
+                .iter()
+                .zip(other)
+                .enumerate()
+                .filter(|(_, (a, b))| a != b)
+                .map(|(idx, (_, &v))| (idx, v))
+                .collect()
+        })
+    }
+
+    /// Serializa dimensiones, celdas y capas de elevación, penalización y
+    /// puntos de vida.
+    pub fn write_to(&self, w: &mut Writer) {
+        w.u32(self.width as u32);
+        w.u32(self.height as u32);
+        w.bytes(&self.cells);
+        write_layer(w, self.elevation.as_deref());
+        write_layer(w, self.penalty.as_deref());
+        write_layer(w, self.hit_points.as_deref());
+    }
+
+    pub fn read_from(r: &mut Reader) -> Option<Self> {
+        let width = r.u32()? as usize;
+        let height = r.u32()? as usize;
+        let len = width.checked_mul(height)?;
+        let mut grid = Self::new(r.bytes(len)?.to_vec(), width, height);
+        grid.elevation = read_layer(r, len)?;
+        grid.set_penalty(read_layer(r, len)?);
+        grid.hit_points = read_layer(r, len)?;
+        Some(grid)
+    }
+}
+
+fn write_layer(w: &mut Writer, layer: Option<&[f32]>) {
+    match layer {
+        Some(values) => {
+            w.u8(1);
+            values.iter().for_each(|&v| w.f32(v));
+        }
+        None => w.u8(0),
+    }
+}
+
+/// Capa opcional de `len` valores: `Some(None)` si no estaba presente y
+/// `None` si los datos están truncados.
+fn read_layer(r: &mut Reader, len: usize) -> Option<Option<Vec<f32>>> {
+    match r.u8()? {
+        0 => Some(None),
+        _ => (0..len)
+            .map(|_| r.f32())
+            .collect::<Option<Vec<_>>>()
+            .map(Some),
+    }
+}
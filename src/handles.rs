@@ -0,0 +1,151 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::policy::{self, Anomaly};
+use crate::{PathFinder, SearchOptions};
+
+/// Buscadores creados con `create_grid`, por identificador.
+#[derive(Default)]
+struct Registry {
+    next: u32,
+    grids: HashMap<u32, PathFinder>,
+}
+
+thread_local! {
+    // Como la política: wasm tiene un solo hilo y en nativo cada hilo tiene
+    // su propio registro.
+    static REGISTRY: RefCell<Registry> = RefCell::new(Registry::default());
+}
+
+/// Ejecuta `f` sobre el buscador `handle` y devuelve su resultado; un
+/// identificador desconocido es una anomalía y en modo tolerante da `None`.
+/// El buscador sale del registro mientras `f` corre, así el registro no
+/// queda prestado si `f` vuelve a usarlo o falla.
+pub(crate) fn with_grid<T, E: From<Anomaly>>(
+    handle: u32,
+    f: impl FnOnce(&mut PathFinder) -> Result<T, E>,
+) -> Result<Option<T>, E> {
+    let Some(mut pf) = REGISTRY.with(|r| r.borrow_mut().grids.remove(&handle)) else {
+        policy::anomaly("identificador de grid desconocido")?;
+        return Ok(None);
+    };
+    let result = f(&mut pf);
+    REGISTRY.with(|r| r.borrow_mut().grids.insert(handle, pf));
+    result.map(Some)
+}
+
+/// API por identificadores, alternativa a los objetos `PathFinder` para
+/// motores que prefieren guardar números en sus componentes ECS en vez de
+/// objetos de wasm-bindgen: crea un buscador sobre un grid de `size` x
+/// `size` (como `PathFinder::new`) y devuelve su identificador, que nunca es
+/// 0. Se libera con `destroy_grid`.
+#[wasm_bindgen]
+pub fn create_grid(grid: Vec<u8>, size: usize) -> Result<u32, JsError> {
+    Ok(insert(PathFinder::new(grid, size)?))
+}
+
+/// Guarda `pf` en el registro y devuelve su identificador nuevo.
+pub(crate) fn insert(pf: PathFinder) -> u32 {
+    REGISTRY.with(|r| {
+        let mut r = r.borrow_mut();
+        r.next = r.next.wrapping_add(1).max(1);
+        while r.grids.contains_key(&r.next) {
+            r.next = r.next.wrapping_add(1).max(1);
+        }
+        let handle = r.next;
+        r.grids.insert(handle, pf);
+        handle
+    })
+}
+
+/// Libera el grid `handle`. Devuelve si existía.
+#[wasm_bindgen]
+pub fn destroy_grid(handle: u32) -> bool {
+    REGISTRY.with(|r| r.borrow_mut().grids.remove(&handle).is_some())
+}
+
+/// `PathFinder::set_cell` sobre el grid `handle`.
+#[wasm_bindgen]
+pub fn set_grid_cell(handle: u32, x: usize, y: usize, value: u8) -> Result<bool, JsError> {
+    let set = with_grid(handle, |pf| Ok::<_, JsError>(pf.set_cell(x, y, value)))?;
+    Ok(set.unwrap_or(false))
+}
+
+/// Ruta más corta de `(sx, sy)` a `(gx, gy)` en el grid `handle` como
+/// `[x0, y0, x1, y1, ...]`; vacía si no hay camino.
+#[wasm_bindgen]
+pub fn query(
+    handle: u32,
+    sx: usize,
+    sy: usize,
+    gx: usize,
+    gy: usize,
+) -> Result<Vec<usize>, JsError> {
+    query_with(handle, &SearchOptions::default(), sx, sy, gx, gy)
+}
+
+/// Como `query` pero con los costes y restricciones de `options` (ver
+/// `PathFinder::search_between`).
+#[wasm_bindgen]
+pub fn query_with(
+    handle: u32,
+    options: &SearchOptions,
+    sx: usize,
+    sy: usize,
+    gx: usize,
+    gy: usize,
+) -> Result<Vec<usize>, JsError> {
+    Ok(route(handle, options, (sx, sy), (gx, gy))?)
+}
+
+/// `query_with` con la anomalía sin convertir en excepción, para la API C.
+pub(crate) fn route(
+    handle: u32,
+    options: &SearchOptions,
+    start: (usize, usize),
+    goal: (usize, usize),
+) -> Result<Vec<usize>, Anomaly> {
+    let found = with_grid(handle, |pf| {
+        pf.search_cells(options, start, goal).map(|_| pf.path())
+    })?;
+    Ok(found.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: cada identificador tiene su grid y deja de valer al liberarlo
+    #[test]
+    fn handles_are_independent() {
+        let a = create_grid(vec![1; 9], 3).unwrap();
+        let b = create_grid(vec![1, 0, 0, 1, 1, 0, 0, 1, 1], 3).unwrap();
+        assert!(a != 0 && a != b);
+        assert_eq!(query(a, 0, 0, 0, 2).unwrap(), vec![0, 0, 0, 1, 0, 2]);
+        assert!(query(b, 0, 0, 0, 2).unwrap().is_empty());
+        assert_eq!(query(b, 0, 0, 2, 2).unwrap().len(), 10);
+
+        assert!(set_grid_cell(a, 0, 1, 0).unwrap());
+        assert_eq!(query(a, 0, 0, 0, 2).unwrap().len(), 10);
+        assert!(destroy_grid(a) && !destroy_grid(a));
+        assert!(query(a, 0, 0, 0, 2).unwrap().is_empty());
+        assert!(!set_grid_cell(a, 0, 1, 1).unwrap());
+    }
+
+    /// Test: mientras se usa un grid el registro sigue libre para los demás
+    #[test]
+    fn registry_is_not_borrowed_during_calls() {
+        let a = create_grid(vec![1; 4], 2).unwrap();
+        let b = create_grid(vec![1; 4], 2).unwrap();
+        let inner = with_grid(a, |_| Ok::<_, Anomaly>(query(b, 0, 0, 1, 1).unwrap().len()));
+        assert_eq!(inner, Ok(Some(6)));
+        assert_eq!(query(a, 0, 0, 1, 1).unwrap().len(), 6);
+        assert_eq!(
+            with_grid(a, |_| Err::<(), _>(Anomaly("x".into()))),
+            Err(Anomaly("x".into()))
+        );
+        assert!(destroy_grid(a) && destroy_grid(b));
+    }
+}
@@ -0,0 +1,65 @@
+use crate::grid::Grid;
+
+/// Capa de peligro dinámico (fuego, gas). En cada `tick` una celda libre
+/// toma el máximo entre su valor y `spread` veces el de su vecino más
+/// caliente, y después pierde una fracción `decay`. Las paredes ni arden
+/// ni propagan. Entra en la penalización del grid multiplicada por `weight`.
+#[derive(Clone, Debug)]
+pub(crate) struct Hazard {
+    pub spread: f32,
+    pub decay: f32,
+    pub weight: f32,
+    pub values: Vec<f32>,
+}
+
+impl Hazard {
+    pub fn new(spread: f32, decay: f32, weight: f32, cells: usize) -> Self {
+        Self {
+            spread: spread.clamp(0.0, 1.0),
+            decay: decay.clamp(0.0, 1.0),
+            weight: weight.max(0.0),
+            values: vec![0.0; cells],
+        }
+    }
+
+    pub fn tick(&mut self, grid: &Grid) {
+        let next = (0..self.values.len())
+            .map(|idx| {
+                let cell = grid.coords(idx);
+                if !grid.walkable(cell) {
+                    return 0.0;
+                }
+                let hottest = grid
+                    .neighbors(cell)
+                    .filter(|&n| grid.walkable(n))
+                    .map(|n| self.values[grid.idx(n)])
+                    .fold(0.0, f32::max);
+                self.values[idx].max(self.spread * hottest) * (1.0 - self.decay)
+            })
+            .collect();
+        self.values = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: el fuego avanza una celda por tick, se atenúa y no cruza paredes
+    #[test]
+    fn hazard_spreads_and_decays() {
+        let grid = Grid::square(vec![1, 1, 0, 1, 1, 0, 1, 1, 1], 3);
+        let mut hazard = Hazard::new(0.5, 0.0, 1.0, 9);
+        hazard.values[0] = 1.0;
+        hazard.tick(&grid);
+        assert_eq!(hazard.values[1], 0.5);
+        assert_eq!(hazard.values[4], 0.0);
+        hazard.tick(&grid);
+        assert_eq!(hazard.values[4], 0.25);
+        assert_eq!(hazard.values[2], 0.0);
+
+        hazard.decay = 0.5;
+        hazard.tick(&grid);
+        assert_eq!(hazard.values[0], 0.5);
+    }
+}
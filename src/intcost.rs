@@ -0,0 +1,96 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::grid::Grid;
+
+/// Dijkstra de `start` a `goal` con costes enteros de entrada por celda:
+/// `cost(índice)` es lo que cuesta entrar en la celda, o `None` si no se
+/// puede. Las paredes del grid tampoco se pueden pisar. La suma va en `u64`
+/// para que rutas largas sobre costes de 32 bits no se desborden; con
+/// empates sale antes la celda que entró antes en la frontera.
+///
+/// Devuelve la ruta como índices de celda con su coste (`None` si no hay) y
+/// las celdas expandidas en orden, incluida la final.
+pub(crate) fn cheapest_path(
+    grid: &Grid,
+    start: (usize, usize),
+    goal: (usize, usize),
+    cost: impl Fn(usize) -> Option<u64>,
+) -> (Option<(Vec<usize>, u64)>, Vec<usize>) {
+    let mut expanded = Vec::new();
+    let passable = |idx: usize| grid.cells[idx] == 1 && cost(idx).is_some();
+    if !grid.in_bounds(start) || !grid.in_bounds(goal) {
+        return (None, expanded);
+    }
+    let (s, g) = (grid.idx(start), grid.idx(goal));
+    if !passable(s) || !passable(g) {
+        return (None, expanded);
+    }
+
+    let mut dist = vec![u64::MAX; grid.cells.len()];
+    let mut parent = vec![usize::MAX; grid.cells.len()];
+    let mut open = BinaryHeap::from([Reverse((0, 0, s))]);
+    let mut order = 0u64;
+    dist[s] = 0;
+    parent[s] = s;
+
+    while let Some(Reverse((d, _, idx))) = open.pop() {
+        if d > dist[idx] {
+            continue;
+        }
+        expanded.push(idx);
+        if idx == g {
+            let mut path = vec![idx];
+            while let Some(&last) = path.last().filter(|&&c| parent[c] != c) {
+                path.push(parent[last]);
+            }
+            path.reverse();
+            return (Some((path, d)), expanded);
+        }
+        for next in grid.neighbors(grid.coords(idx)).map(|c| grid.idx(c)) {
+            if grid.cells[next] != 1 {
+                continue;
+            }
+            let Some(step) = cost(next) else {
+                continue;
+            };
+            let nd = d.saturating_add(step);
+            if nd < dist[next] {
+                dist[next] = nd;
+                parent[next] = idx;
+                order += 1;
+                open.push(Reverse((nd, order, next)));
+            }
+        }
+    }
+    (None, expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: rodea la celda cara, respeta el centinela y no se desborda
+    #[test]
+    fn integer_costs_accumulate_in_u64() {
+        let grid = Grid::square(vec![1; 9], 3);
+        let costs = [1u32, 9, 1, 1, 9, 1, 1, 1, 1];
+        let cost = |i: usize| Some(u64::from(costs[i]));
+        let (found, _) = cheapest_path(&grid, (0, 0), (0, 2), cost);
+        let (path, total) = found.unwrap();
+        assert_eq!(path, vec![0, 3, 6, 7, 8, 5, 2]);
+        assert_eq!(total, 6);
+
+        let blocked = |i: usize| (i != 1 && i != 4).then_some(1);
+        assert_eq!(
+            cheapest_path(&grid, (0, 0), (0, 2), blocked).0.unwrap().1,
+            6
+        );
+        let walled = |i: usize| (i % 3 != 1).then_some(1);
+        assert!(cheapest_path(&grid, (0, 0), (0, 2), walled).0.is_none());
+
+        let huge = |_| Some(u64::from(u32::MAX - 1));
+        let (_, total) = cheapest_path(&grid, (0, 0), (2, 2), huge).0.unwrap();
+        assert_eq!(total, 4 * u64::from(u32::MAX - 1));
+    }
+}
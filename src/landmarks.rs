@@ -0,0 +1,178 @@
+use crate::SearchOptions;
+use crate::bytes::{Reader, Writer};
+use crate::cost::StepCost;
+use crate::distance::dijkstra;
+use crate::grid::Grid;
+
+/// Heurística ALT: distancias exactas desde y hacia unas pocas celdas
+/// (landmarks) con las que la desigualdad triangular da una cota inferior
+/// de la distancia entre dos celdas cualesquiera.
+///
+/// Solo es admisible para el grid y las opciones con los que se calculó; por
+/// eso guarda una copia del grid para comprobarlo antes de usarla.
+#[derive(Clone, Debug)]
+pub(crate) struct Landmarks {
+    pub grid: Grid,
+    pub opts: SearchOptions,
+    pub cells: Vec<usize>,
+    from: Vec<Vec<f64>>,
+    to: Vec<Vec<f64>>,
+}
+
+impl Landmarks {
+    /// Elige hasta `count` landmarks por el método del punto más lejano: el
+    /// primero es la celda más alejada de la primera transitable y cada
+    /// siguiente la más alejada de los ya elegidos.
+    pub fn build(grid: &Grid, opts: &SearchOptions, count: usize) -> Self {
+        let costs = StepCost::new(grid, opts);
+        let mut lm = Self {
+            grid: grid.clone(),
+            opts: opts.clone(),
+            cells: Vec::new(),
+            from: Vec::new(),
+            to: Vec::new(),
+        };
+        let Some(seed) = (0..grid.cells.len()).find(|&i| costs.allowed(grid.coords(i))) else {
+            return lm;
+        };
+
+        let mut nearest = dijkstra(grid, &costs, seed, false);
+        while lm.cells.len() < count {
+            let far = (0..nearest.len())
+                .filter(|&i| nearest[i].is_finite() && !lm.cells.contains(&i))
+                .max_by(|&a, &b| nearest[a].total_cmp(&nearest[b]));
+            let Some(landmark) = far else {
+                break;
+            };
+            let from = dijkstra(grid, &costs, landmark, false);
+            for (n, d) in nearest.iter_mut().zip(&from) {
+                *n = if lm.cells.is_empty() { *d } else { n.min(*d) };
+            }
+            lm.to.push(dijkstra(grid, &costs, landmark, true));
+            lm.from.push(from);
+            lm.cells.push(landmark);
+        }
+        lm
+    }
+
+    pub fn valid_for(&self, grid: &Grid) -> bool {
+        &self.grid == grid
+    }
+
+    /// Si sus cotas valen para `grid` con las opciones `opts`.
+    pub fn fits(&self, grid: &Grid, opts: &SearchOptions) -> bool {
+        let encode = |opts: &SearchOptions| {
+            let mut w = Writer::new();
+            opts.write_to(&mut w);
+            w.finish()
+        };
+        self.valid_for(grid) && encode(&self.opts) == encode(opts)
+    }
+
+    /// Datos para la sección `SECTION_LANDMARKS` de `Artifacts`: longitud
+    /// en bytes de las opciones y sus bytes de cuatro en cuatro, número de
+    /// landmarks, sus celdas y las distancias desde y hacia cada uno (cada
+    /// `f64` en dos palabras, la baja primero).
+    pub fn to_words(&self) -> Vec<u32> {
+        let mut w = Writer::new();
+        self.opts.write_to(&mut w);
+        let opts = w.finish();
+        let mut words = vec![opts.len() as u32];
+        words.extend(opts.chunks(4).map(|c| {
+            let mut word = [0; 4];
+            word[..c.len()].copy_from_slice(c);
+            u32::from_le_bytes(word)
+        }));
+        words.push(self.cells.len() as u32);
+        words.extend(self.cells.iter().map(|&c| c as u32));
+        for distances in self.from.iter().zip(&self.to).flat_map(|(f, t)| [f, t]) {
+            words.extend(distances.iter().flat_map(|d| {
+                let bits = d.to_bits();
+                [bits as u32, (bits >> 32) as u32]
+            }));
+        }
+        words
+    }
+
+    /// Inversa de `to_words` para `grid`; `None` si los datos no cuadran.
+    pub fn from_words(grid: &Grid, words: &[u32]) -> Option<Self> {
+        let mut words = words.iter().copied();
+        let len = words.next()? as usize;
+        let packed: Vec<u8> = words
+            .by_ref()
+            .take(len.div_ceil(4))
+            .flat_map(u32::to_le_bytes)
+            .collect();
+        let mut r = Reader::new(packed.get(..len)?);
+        let opts = SearchOptions::read_from(&mut r).filter(|_| r.is_empty())?;
+        let count = words.next()? as usize;
+        let n = grid.cells.len();
+        let cells: Vec<usize> = words.by_ref().take(count).map(|c| c as usize).collect();
+        if cells.len() != count || cells.iter().any(|&c| c >= n) {
+            return None;
+        }
+        let mut distances = || -> Option<Vec<f64>> {
+            (0..n)
+                .map(|_| {
+                    let (lo, hi) = (words.next()?, words.next()?);
+                    Some(f64::from_bits(u64::from(lo) | u64::from(hi) << 32))
+                })
+                .collect()
+        };
+        let (mut from, mut to) = (Vec::new(), Vec::new());
+        for _ in 0..count {
+            from.push(distances()?);
+            to.push(distances()?);
+        }
+        words.next().is_none().then(|| Self {
+            grid: grid.clone(),
+            opts,
+            cells,
+            from,
+            to,
+        })
+    }
+
+    /// Cota inferior del coste de `cell` a `goal` (índices de celda).
+    pub fn lower_bound(&self, cell: usize, goal: usize) -> f64 {
+        self.from
+            .iter()
+            .zip(&self.to)
+            .map(|(from, to)| {
+                let ahead = from[goal] - from[cell];
+                let behind = to[cell] - to[goal];
+                ahead.max(behind)
+            })
+            .filter(|h| h.is_finite())
+            .fold(0.0, f64::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: la cota ALT nunca supera la distancia real y en un pasillo es exacta
+    #[test]
+    fn lower_bound_is_admissible() {
+        // Pasillo en S: la distancia real es mucho mayor que la Manhattan.
+        #[rustfmt::skip]
+        let cells = vec![
+            1, 1, 1, 1, 1,
+            0, 0, 0, 0, 1,
+            1, 1, 1, 1, 1,
+            1, 0, 0, 0, 0,
+            1, 1, 1, 1, 1,
+        ];
+        let grid = Grid::square(cells, 5);
+        let opts = SearchOptions::default();
+        let lm = Landmarks::build(&grid, &opts, 2);
+        assert_eq!(lm.cells.len(), 2);
+        let costs = StepCost::new(&grid, &opts);
+        let exact = dijkstra(&grid, &costs, 0, false);
+        for idx in (0..25).filter(|&i| exact[i].is_finite()) {
+            assert!(lm.lower_bound(0, idx) <= exact[idx] + 1e-9);
+        }
+        assert_eq!(lm.lower_bound(0, 24), 16.0);
+    }
+}
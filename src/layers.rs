@@ -0,0 +1,380 @@
+use crate::SearchOptions;
+use crate::cost::StepCost;
+use crate::grid::Grid;
+use crate::queue::OpenList;
+use crate::search::{self, Outcome};
+
+/// Nombre de la capa con la que se construye un `PathFinder`.
+pub(crate) const DEFAULT_LAYER: &str = "ground";
+
+/// Capas de transitabilidad con nombre (suelo, aire, agua...) que comparten
+/// dimensiones. La activa vive en `Grid::cells` y el resto queda aparcado
+/// aquí; cambiar de capa intercambia los vectores sin copiar celdas.
+#[derive(Clone, Debug)]
+pub(crate) struct Layers {
+    active: String,
+    parked: Vec<(String, Vec<u8>)>,
+    transitions: Vec<Transition>,
+    elevators: Vec<Elevator>,
+}
+
+/// Paso entre capas (escalera, ascensor, rampa) de `from` a `to` con `cost`.
+#[derive(Clone, Debug)]
+struct Transition {
+    from: (String, (usize, usize)),
+    to: (String, (usize, usize)),
+    cost: f64,
+}
+
+/// Ascensor con nombre que para en `stops` (capa y celda, en orden de
+/// planta). Ir de una parada a otra cuesta `wait` por usarlo más
+/// `per_stop` por cada parada recorrida. `capacity` son los bits de lo que
+/// puede llevar (p. ej. sillas de ruedas o carga); los pasos normales no
+/// llevan ninguno.
+#[derive(Clone, Debug)]
+struct Elevator {
+    name: String,
+    wait: f64,
+    per_stop: f64,
+    capacity: u32,
+    stops: Vec<(String, (usize, usize))>,
+}
+
+/// Paso utilizable en una búsqueda, con el nombre con el que se informa:
+/// el del ascensor o `"origen->destino"` para los pasos normales.
+struct Link {
+    from: (String, (usize, usize)),
+    to: (String, (usize, usize)),
+    cost: f64,
+    name: String,
+}
+
+/// Estado de la búsqueda entre capas: índice en `Layers::names` y celda.
+pub(crate) type LayerCell = (usize, (usize, usize));
+
+impl Default for Layers {
+    fn default() -> Self {
+        Self {
+            active: DEFAULT_LAYER.to_string(),
+            parked: Vec::new(),
+            transitions: Vec::new(),
+            elevators: Vec::new(),
+        }
+    }
+}
+
+impl Layers {
+    pub fn active(&self) -> &str {
+        &self.active
+    }
+
+    /// Nombres de todas las capas, empezando por la activa.
+    pub fn names(&self) -> Vec<String> {
+        std::iter::once(&self.active)
+            .chain(self.parked.iter().map(|(name, _)| name))
+            .cloned()
+            .collect()
+    }
+
+    /// Celdas de la capa `name`, esté activa o aparcada.
+    fn cells<'a>(&'a self, grid: &'a Grid, name: &str) -> Option<&'a [u8]> {
+        if name == self.active {
+            return Some(&grid.cells);
+        }
+        self.parked
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, cells)| &cells[..])
+    }
+
+    /// Registra un paso de `from` a `to` (capa y celda). Con `two_way` vale
+    /// también en sentido contrario. Las capas pueden registrarse después.
+    pub fn connect(
+        &mut self,
+        from: (&str, (usize, usize)),
+        to: (&str, (usize, usize)),
+        cost: f64,
+        two_way: bool,
+    ) {
+        let mut add = |a: (&str, (usize, usize)), b: (&str, (usize, usize))| {
+            self.transitions.push(Transition {
+                from: (a.0.to_string(), a.1),
+                to: (b.0.to_string(), b.1),
+                cost: cost.max(0.0),
+            });
+        };
+        add(from, to);
+        if two_way {
+            add(to, from);
+        }
+    }
+
+    /// Registra o reconfigura el ascensor `name`, conservando sus paradas.
+    pub fn add_elevator(&mut self, name: &str, wait: f64, per_stop: f64, capacity: u32) {
+        let (wait, per_stop) = (wait.max(0.0), per_stop.max(0.0));
+        match self.elevators.iter_mut().find(|e| e.name == name) {
+            Some(e) => (e.wait, e.per_stop, e.capacity) = (wait, per_stop, capacity),
+            None => self.elevators.push(Elevator {
+                name: name.to_string(),
+                wait,
+                per_stop,
+                capacity,
+                stops: Vec::new(),
+            }),
+        }
+    }
+
+    /// Añade una parada al final del ascensor `name`. `false` si no existe.
+    pub fn add_elevator_stop(&mut self, name: &str, layer: &str, cell: (usize, usize)) -> bool {
+        let Some(elevator) = self.elevators.iter_mut().find(|e| e.name == name) else {
+            return false;
+        };
+        elevator.stops.push((layer.to_string(), cell));
+        true
+    }
+
+    /// Pasos que puede usar un agente que necesita los bits `required`: los
+    /// normales solo sin requisitos y los ascensores que los cubran.
+    fn links(&self, required: u32) -> Vec<Link> {
+        let plain = self
+            .transitions
+            .iter()
+            .filter(|_| required == 0)
+            .map(|t| Link {
+                from: t.from.clone(),
+                to: t.to.clone(),
+                cost: t.cost,
+                name: format!("{}->{}", t.from.0, t.to.0),
+            });
+        let rides = self
+            .elevators
+            .iter()
+            .filter(|e| e.capacity & required == required)
+            .flat_map(|e| {
+                let stops = e.stops.iter().enumerate();
+                stops.flat_map(move |(i, from)| {
+                    let others = e.stops.iter().enumerate().filter(move |&(j, _)| j != i);
+                    others.map(move |(j, to)| Link {
+                        from: from.clone(),
+                        to: to.clone(),
+                        cost: e.wait + e.per_stop * i.abs_diff(j) as f64,
+                        name: e.name.clone(),
+                    })
+                })
+            });
+        plain.chain(rides).collect()
+    }
+
+    /// Nombres de los pasos entre capas que usa la ruta `states` de
+    /// `layered_search` con las mismas opciones, en orden.
+    pub fn route_links(&self, opts: &SearchOptions, states: &[LayerCell]) -> Vec<String> {
+        let names = self.names();
+        let links = self.links(opts.capacity);
+        let state = |(layer, cell): &(String, (usize, usize))| {
+            names.iter().position(|n| n == layer).map(|l| (l, *cell))
+        };
+        states
+            .windows(2)
+            .filter_map(|w| {
+                links
+                    .iter()
+                    .filter(|l| state(&l.from) == Some(w[0]) && state(&l.to) == Some(w[1]))
+                    .min_by(|a, b| a.cost.total_cmp(&b.cost))
+                    .map(|l| l.name.clone())
+            })
+            .collect()
+    }
+
+    /// Registra o reemplaza la capa `name`; falla si no tiene una celda por
+    /// cada celda del grid.
+    pub fn insert(
+        &mut self,
+        grid: &mut Grid,
+        name: &str,
+        cells: Vec<u8>,
+    ) -> Result<(), &'static str> {
+        if cells.len() != grid.cells.len() {
+            return Err("la capa tiene otro tamaño que el grid");
+        }
+        if name == self.active {
+            grid.cells = cells;
+        } else if let Some((_, slot)) = self.parked.iter_mut().find(|(n, _)| n == name) {
+            *slot = cells;
+        } else {
+            self.parked.push((name.to_string(), cells));
+        }
+        Ok(())
+    }
+
+    /// Quita una capa aparcada y sus pasos; la activa no se puede quitar.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.parked.len();
+        self.parked.retain(|(n, _)| n != name);
+        let removed = self.parked.len() != before;
+        if removed {
+            self.transitions
+                .retain(|t| t.from.0 != name && t.to.0 != name);
+            for elevator in &mut self.elevators {
+                elevator.stops.retain(|(layer, _)| layer != name);
+            }
+        }
+        removed
+    }
+
+    /// Activa la capa `name`, aparcando la actual. `false` si no existe.
+    pub fn select(&mut self, grid: &mut Grid, name: &str) -> bool {
+        if name == self.active {
+            return true;
+        }
+        let Some(slot) = self.parked.iter_mut().find(|(n, _)| n == name) else {
+            return false;
+        };
+        std::mem::swap(&mut slot.1, &mut grid.cells);
+        std::mem::swap(&mut slot.0, &mut self.active);
+        true
+    }
+}
+
+/// Búsqueda con costes (Dijkstra) a través de todas las capas, usando los
+/// pasos registrados con `Layers::connect`. Las capas se identifican por su
+/// posición en `Layers::names`. Solo se usan los pasos y ascensores que
+/// cubren `SearchOptions::set_required_capacity`. Las opciones que dependen
+/// de la dirección (`no_reverse`, `min_turns`) no se aplican.
+pub(crate) fn layered_search(
+    grid: &Grid,
+    layers: &Layers,
+    opts: &SearchOptions,
+    start: LayerCell,
+    goal: LayerCell,
+) -> Outcome<LayerCell> {
+    let names = layers.names();
+    let mut costs: Vec<StepCost> = names
+        .iter()
+        .map(|name| StepCost::on_layer(grid, layers.cells(grid, name).unwrap_or_default(), opts))
+        .collect();
+    let allowed = |costs: &[StepCost], (layer, cell): LayerCell| {
+        costs.get(layer).is_some_and(|c| c.allowed(cell))
+    };
+    if !allowed(&costs, start) || !allowed(&costs, goal) {
+        return Outcome::none();
+    }
+    for c in &mut costs {
+        c.prepare(start.1, goal.1);
+    }
+
+    let index = |name: &str| names.iter().position(|n| n == name);
+    let links: Vec<(LayerCell, LayerCell, f64)> = layers
+        .links(opts.capacity)
+        .iter()
+        .filter_map(|t| {
+            let from = (index(&t.from.0)?, t.from.1);
+            let to = (index(&t.to.0)?, t.to.1);
+            Some((from, to, t.cost))
+        })
+        .collect();
+
+    search::best_first(
+        start,
+        |&state| state == goal,
+        |&(layer, cell)| {
+            let within = grid.neighbors(cell).filter_map(|next| {
+                let c = &costs[layer];
+                c.allowed(next)
+                    .then(|| c.step(cell, next))
+                    .flatten()
+                    .map(|step| ((layer, next), step))
+            });
+            let across = links
+                .iter()
+                .filter(|(from, to, _)| *from == (layer, cell) && allowed(&costs, *to))
+                .map(|&(_, to, cost)| (to, cost));
+            within.chain(across).collect::<Vec<_>>()
+        },
+        |_, g| g,
+        f64::INFINITY,
+        f64::INFINITY,
+        OpenList::default(),
+        opts.annotate,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: cambiar de capa intercambia las celdas y conserva las demás
+    #[test]
+    fn select_swaps_layers() {
+        let mut grid = Grid::square(vec![1, 1, 0, 1], 2);
+        let mut layers = Layers::default();
+        layers.insert(&mut grid, "air", vec![1; 4]).unwrap();
+        assert!(layers.insert(&mut grid, "water", vec![1; 3]).is_err());
+
+        assert!(layers.select(&mut grid, "air"));
+        assert_eq!(grid.cells, vec![1; 4]);
+        assert_eq!(layers.names(), vec!["air", "ground"]);
+
+        assert!(!layers.select(&mut grid, "water"));
+        assert!(layers.select(&mut grid, "ground"));
+        assert_eq!(grid.cells, vec![1, 1, 0, 1]);
+    }
+
+    /// Test: la ruta sube por la escalera cuando la planta baja está cortada
+    #[test]
+    fn layered_search_uses_stairs() {
+        // Planta baja cortada por una pared; la primera planta está libre y
+        // se llega a ella por una escalera en (0, 0) y se baja en (0, 2).
+        let mut grid = Grid::square(vec![1, 0, 1, 1, 0, 1, 1, 0, 1], 3);
+        let mut layers = Layers::default();
+        layers.insert(&mut grid, "floor1", vec![1; 9]).unwrap();
+        layers.connect(("ground", (0, 0)), ("floor1", (0, 0)), 3.0, true);
+        layers.connect(("floor1", (0, 2)), ("ground", (0, 2)), 3.0, false);
+
+        let opts = SearchOptions::default();
+        let found = layered_search(&grid, &layers, &opts, (0, (0, 0)), (0, (2, 2)))
+            .result
+            .unwrap();
+        assert_eq!(found.cost, 3.0 + 2.0 + 3.0 + 2.0);
+        let floors: Vec<usize> = found.states.iter().map(|&(l, _)| l).collect();
+        assert_eq!(floors, vec![0, 1, 1, 1, 0, 0, 0]);
+
+        assert!(layers.remove("floor1"));
+        let outcome = layered_search(&grid, &layers, &opts, (0, (0, 0)), (0, (2, 2)));
+        assert!(outcome.result.is_none());
+    }
+
+    /// Test: con prisa se sube por la escalera; en silla de ruedas solo
+    /// sirve el ascensor, aunque cueste esperar
+    #[test]
+    fn elevator_or_stairs() {
+        let mut grid = Grid::square(vec![1, 0, 1, 1, 0, 1, 1, 0, 1], 3);
+        let mut layers = Layers::default();
+        layers.insert(&mut grid, "floor1", vec![1; 9]).unwrap();
+        layers.connect(("ground", (0, 0)), ("floor1", (0, 0)), 3.0, true);
+        layers.connect(("floor1", (0, 2)), ("ground", (0, 2)), 3.0, true);
+        layers.add_elevator("lift", 5.0, 1.0, 1);
+        assert!(layers.add_elevator_stop("lift", "ground", (2, 0)));
+        assert!(layers.add_elevator_stop("lift", "floor1", (2, 0)));
+        assert!(!layers.add_elevator_stop("other", "ground", (0, 0)));
+
+        let mut opts = SearchOptions::default();
+        let route = |layers: &Layers, opts: &SearchOptions| {
+            let found = layered_search(&grid, layers, opts, (0, (2, 0)), (1, (2, 2)));
+            found
+                .result
+                .map(|f| (f.cost, layers.route_links(opts, &f.states)))
+        };
+        let (cost, links) = route(&layers, &opts).unwrap();
+        assert_eq!((cost, links), (8.0, vec!["lift".to_string()]));
+
+        layers.add_elevator("lift", 20.0, 1.0, 1);
+        let (cost, links) = route(&layers, &opts).unwrap();
+        assert_eq!(cost, 2.0 + 3.0 + 4.0);
+        assert_eq!(links, vec!["ground->floor1"]);
+
+        opts.set_required_capacity(1);
+        assert_eq!(route(&layers, &opts).unwrap().1, vec!["lift"]);
+        opts.set_required_capacity(2);
+        assert!(route(&layers, &opts).is_none());
+    }
+}
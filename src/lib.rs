@@ -1,34 +1,3037 @@
 use std::collections::{HashMap, VecDeque};
 use wasm_bindgen::prelude::*;
 
+mod adjacency;
+mod allocation;
+mod artifacts;
+mod balance;
+mod ballistics;
+mod bands;
+mod bridge;
+mod bytes;
+#[cfg(feature = "c-abi")]
+mod cabi;
+mod calibrate;
+mod capabilities;
+mod chunks;
+mod clock;
+mod compare;
+mod congestion;
+mod cost;
+mod costmap;
+mod crossing;
+mod csr;
+mod delta;
+mod diagnostics;
+mod dijkstramap;
+mod distance;
+mod dot;
+mod floatcost;
+mod floorplan;
+mod fog;
+mod formation;
+mod fuzz;
+mod generate;
+mod grid;
+mod handles;
+mod hazard;
+mod intcost;
+mod landmarks;
+mod layers;
+mod lighting;
+mod loader;
+mod locks;
+mod memo;
+mod memory;
+mod metrics;
+mod minimap;
+mod narration;
+mod options;
+mod overlays;
+mod pareto;
+mod pathdiff;
+mod policy;
+mod propagation;
+mod queue;
+mod raster;
+mod reach;
+mod replay;
+mod requests;
+mod result;
+mod reuse;
+mod reveal;
+mod rng;
+mod rooms;
+mod rsr;
+mod safety;
+mod scenario;
+mod scoring;
+mod search;
+mod session;
+mod smoothing;
+mod spacetime;
+mod spawns;
+mod teaching;
+mod textmap;
+mod transform;
+mod travel;
+mod verify;
+mod world;
+
+use adjacency::Adjacency;
+use artifacts::{Artifacts, SECTION_LANDMARKS};
+use congestion::Congestion;
+use delta::ChangeLog;
+use fog::Fog;
+use grid::Grid;
+use hazard::Hazard;
+use landmarks::Landmarks;
+use layers::Layers;
+use locks::Locks;
+use memo::CostMemo;
+use metrics::Metrics;
+use overlays::{Overlay, Overlays};
+use policy::Anomaly;
+use reach::Reach;
+use requests::RequestQueue;
+use result::Status;
+use reuse::RouteReuse;
+use rng::Rng;
+use rsr::Rectangles;
+use spacetime::MovingObstacle;
+
+pub use allocation::{Allocation, AllocationObjective};
+pub use balance::BalanceReport;
+pub use capabilities::{Capabilities, capabilities};
+pub use chunks::World;
+pub use compare::Comparison;
+pub use costmap::costmap_cell_size;
+pub use crossing::{PathConflict, path_conflict, path_intersections};
+pub use csr::CsrGraph;
+pub use diagnostics::HeuristicReport;
+pub use dijkstramap::DijkstraMap;
+pub use floorplan::FloorplanOptions;
+pub use formation::Formation;
+pub use fuzz::{FuzzReport, fuzz};
+pub use generate::{Symmetry, drunkard_walk, symmetric_map};
+pub use handles::{create_grid, destroy_grid, query, query_with, set_grid_cell};
+pub use loader::GridLoader;
+pub use memory::AgentMemory;
+pub use options::SearchOptions;
+pub use pareto::ParetoFront;
+pub use pathdiff::PathDiff;
+pub use policy::{Policy, policy, set_policy};
+pub use queue::OpenList;
+pub use requests::ProcessedRequests;
+pub use result::PathResult;
+pub use reveal::RevealOrder;
+pub use rooms::Rooms;
+pub use scoring::RouteScore;
+pub use session::Session;
+pub use spawns::SpawnConstraints;
+pub use teaching::ExpansionLog;
+pub use transform::{DownsamplePolicy, GridOp};
+pub use verify::PathVerdict;
+
 #[wasm_bindgen]
 pub struct PathFinder {
+    grid: Grid,
     path: Vec<(usize, usize)>,
+    path_costs: Vec<f64>,
+    cost: f64,
+    integer_cost: u64,
+    options: Option<SearchOptions>,
+    expanded: Vec<usize>,
+    steps: Vec<search::Step<usize>>,
+    #[cfg(feature = "debug")]
+    open: Vec<(usize, search::Step<usize>)>,
+    cells_per_frame: usize,
+    moving: Vec<MovingObstacle>,
+    reuse: Option<RouteReuse>,
+    hazard: Option<Hazard>,
+    congestion: Option<Congestion>,
+    overlays: Overlays,
+    sim_clock: Option<clock::SimClock>,
+    metrics: Metrics,
+    status: Status,
+    layers: Layers,
+    path_layers: Vec<String>,
+    path_transitions: Vec<String>,
+    path_kind: PathKind,
+    segment_costs: Vec<f64>,
+    blocking: Vec<usize>,
+    locks: Locks,
+    fog: Option<Fog>,
+    landmarks: Option<Landmarks>,
+    rectangles: Option<Rectangles>,
+    rng: Rng,
+    changes: ChangeLog,
+    revision: u32,
+    adjacency: Adjacency,
+    cost_memo: CostMemo,
+    reach: Option<Reach>,
+    queries: Vec<((usize, usize), (usize, usize))>,
+    requests: RequestQueue,
 }
 
-#[wasm_bindgen]
-impl PathFinder {
-    #[wasm_bindgen(constructor)]
-    pub fn new(grid: Vec<u8>, size: usize) -> Self {
-        let path = bfs(grid, size);
-        Self { path }
+#[wasm_bindgen]
+impl PathFinder {
+    /// Buscador sobre un grid de `size` x `size`. Si `grid` no tiene
+    /// `size * size` celdas es una anomalía (ver `set_policy`); en modo
+    /// tolerante se recorta o se completa con paredes.
+    #[wasm_bindgen(constructor)]
+    pub fn new(grid: Vec<u8>, size: usize) -> Result<PathFinder, JsError> {
+        let cells = policy::fit_cells(grid, size * size)?;
+        Ok(Self::from_grid(Grid::square(cells, size)))
+    }
+
+    /// Repite la búsqueda de `(0,0)` a `(n-1,n-1)` con costes y las opciones
+    /// dadas, reemplazando la ruta actual. Devuelve si encontró camino; un
+    /// extremo bloqueado es una anomalía (ver `set_policy`).
+    pub fn search_with(&mut self, options: &SearchOptions) -> Result<bool, JsError> {
+        let goal = (self.grid.height - 1, self.grid.width - 1);
+        self.check_endpoints((0, 0), goal, options)?;
+        let options = &self.seeded(options);
+        self.prepare_search();
+        let started = clock::now_ms();
+        self.adjacency.sync(&self.grid, self.revision);
+        let (found, relaxed) =
+            search::relaxed_search(&self.grid, Some(&self.adjacency), (0, 0), goal, options);
+        let found = self.apply(found, options, started);
+        self.status.relaxed_goal = relaxed;
+        Ok(found)
+    }
+
+    /// Como `search_with` pero partiendo de una ruta anterior
+    /// `[x0, y0, x1, y1, ...]` (normalmente `path()` antes de un cambio
+    /// pequeño del grid) como solución de partida: solo se exploran las
+    /// celdas que pueden mejorarla, y el resultado nunca es más caro que
+    /// ella. Si ya no es válida se busca desde cero. Sin relajación del
+    /// destino.
+    pub fn search_warm(
+        &mut self,
+        options: &SearchOptions,
+        previous_path: &[usize],
+    ) -> Result<bool, JsError> {
+        let goal = (self.grid.height - 1, self.grid.width - 1);
+        self.check_endpoints((0, 0), goal, options)?;
+        let options = &self.seeded(options);
+        self.prepare_search();
+        let started = clock::now_ms();
+        let incumbent = pairs(previous_path);
+        let found = search::warm_search(&self.grid, (0, 0), goal, options, &incumbent);
+        Ok(self.apply(found, options, started))
+    }
+
+    /// Como `search_with` pero entre posiciones de mundo arbitrarias, con
+    /// celdas de `cell_size` unidades: inicio y destino se ajustan a la celda
+    /// transitable más cercana. La ruta en mundo sale de `world_path`.
+    pub fn search_world(
+        &mut self,
+        options: &SearchOptions,
+        start_x: f32,
+        start_y: f32,
+        goal_x: f32,
+        goal_y: f32,
+        cell_size: f32,
+    ) -> Result<bool, JsError> {
+        let start = world::snap(&self.grid, start_x, start_y, cell_size);
+        let goal = world::snap(&self.grid, goal_x, goal_y, cell_size);
+        if let (Some(start), Some(goal)) = (start, goal) {
+            self.check_endpoints(start, goal, options)?;
+        }
+        let options = &self.seeded(options);
+        let (Some(start), Some(goal)) = (start, goal) else {
+            self.path.clear();
+            self.path_costs.clear();
+            self.cost = 0.0;
+            self.status = Status::default();
+            self.path_layers.clear();
+            self.path_transitions.clear();
+            self.path_kind = PathKind::Plain;
+            self.segment_costs.clear();
+            self.blocking.clear();
+            return Ok(false);
+        };
+        self.prepare_search();
+        let started = clock::now_ms();
+        self.adjacency.sync(&self.grid, self.revision);
+        let (found, relaxed) =
+            search::relaxed_search(&self.grid, Some(&self.adjacency), start, goal, options);
+        let found = self.apply(found, options, started);
+        self.status.relaxed_goal = relaxed;
+        Ok(found)
+    }
+
+    /// Como `search_with` pero de `(sx, sy)` a `(gx, gy)`. Con
+    /// `SearchOptions::set_window` planifica dentro de un trozo del grid sin
+    /// copiarlo; la ruta sigue en coordenadas del grid completo.
+    pub fn search_between(
+        &mut self,
+        options: &SearchOptions,
+        sx: usize,
+        sy: usize,
+        gx: usize,
+        gy: usize,
+    ) -> Result<bool, JsError> {
+        Ok(self.search_cells(options, (sx, sy), (gx, gy))?)
+    }
+
+    /// Como `search_between` sin alejarse más de `max_distance` pasos (a pie)
+    /// de la ruta de referencia `reference` (`[x0, y0, x1, y1, ...]`), por
+    /// ejemplo para escoltar a otro agente: la búsqueda se limita al pasillo
+    /// de celdas a esa distancia, combinado con la región de `options` si la
+    /// hay. Si inicio o destino quedan fuera del pasillo no hay ruta.
+    #[allow(clippy::too_many_arguments)]
+    pub fn search_escorting(
+        &mut self,
+        options: &SearchOptions,
+        reference: &[usize],
+        max_distance: u32,
+        sx: usize,
+        sy: usize,
+        gx: usize,
+        gy: usize,
+    ) -> Result<bool, JsError> {
+        let mut options = self.seeded(options);
+        let reference = pairs(reference);
+        let reference: Vec<_> = reference
+            .into_iter()
+            .filter(|&c| self.grid.in_bounds(c))
+            .collect();
+        let corridor = distance::bfs_distances(&self.grid, &reference, max_distance).0;
+        let mask = corridor
+            .iter()
+            .enumerate()
+            .map(|(idx, &d)| u8::from(d != distance::UNREACHED && options.in_region(idx)))
+            .collect();
+        options.region = Some(mask);
+        self.check_endpoints((sx, sy), (gx, gy), &options)?;
+        self.prepare_search();
+        Ok(self.between(&options, (sx, sy), (gx, gy)))
+    }
+
+    /// Como `search_between` sumando a los costes lo que recuerda `memory`
+    /// de las celdas que el agente pisó hace poco (ver `AgentMemory`), así
+    /// un NPC que deambula evita volver sobre sus pasos. El coste de la ruta
+    /// incluye esas penalizaciones. Estas búsquedas no se pueden reproducir
+    /// con `replay`.
+    pub fn search_remembering(
+        &mut self,
+        options: &SearchOptions,
+        memory: &AgentMemory,
+        sx: usize,
+        sy: usize,
+        gx: usize,
+        gy: usize,
+    ) -> Result<bool, JsError> {
+        self.check_endpoints((sx, sy), (gx, gy), options)?;
+        let options = &self.seeded(options);
+        self.prepare_search();
+        memory.add_to(&mut self.grid);
+        let found = self.between(options, (sx, sy), (gx, gy));
+        self.compose_penalty();
+        self.path_kind = PathKind::Remembering;
+        Ok(found)
+    }
+
+    /// Ruta de `(0,0)` a `(n-1,n-1)` pasando en orden por los puntos de paso
+    /// `[x0, y0, x1, y1, ...]` (p. ej. una patrulla por varias salas), unión
+    /// de los tramos óptimos entre cada par. Los costes de los tramos quedan
+    /// en `segment_costs`; si algún tramo no tiene ruta no hay camino. Estas
+    /// búsquedas no se pueden reproducir con `replay`.
+    pub fn find_path_via(&mut self, options: &SearchOptions, points: &[usize]) -> bool {
+        let options = &self.seeded(options);
+        self.prepare_search();
+        let started = clock::now_ms();
+        let mut stops = vec![(0, 0)];
+        stops.extend(pairs(points));
+        stops.push((self.grid.height - 1, self.grid.width - 1));
+        let (found, segments) = search::via_search(&self.grid, &stops, options);
+        let found = self.apply(found, options, started);
+        self.segment_costs = segments;
+        self.path_kind = PathKind::Via;
+        found
+    }
+
+    /// Fija el estado del generador aleatorio de la instancia (semillas de
+    /// `SearchOptions::set_noise_from_rng`), p. ej. desde la semilla de la
+    /// simulación del juego.
+    pub fn set_rng_state(&mut self, state: u64) {
+        self.rng = Rng::new(state);
+    }
+
+    /// Estado actual del generador, para guardarlo y continuar la misma
+    /// secuencia con `set_rng_state`.
+    pub fn rng_state(&self) -> u64 {
+        self.rng.state()
+    }
+
+    /// Coste de cada tramo de la última `find_path_via`, en orden.
+    pub fn segment_costs(&self) -> Vec<f64> {
+        self.segment_costs.clone()
+    }
+
+    /// Para editores de mapas: paredes más baratas de abrir para conectar la
+    /// región de `(ax, ay)` con la de `(bx, by)`, como `[x0, y0, ...]` en
+    /// orden de una a otra. Cada pared cuesta su vida
+    /// (`set_wall_hit_points`) o 1. Vacío si ya están conectadas.
+    pub fn bridge_regions(
+        &self,
+        ax: usize,
+        ay: usize,
+        bx: usize,
+        by: usize,
+    ) -> Result<Vec<usize>, JsError> {
+        let (a, b) = ((ax, ay), (bx, by));
+        if !self.grid.in_bounds(a) || !self.grid.in_bounds(b) {
+            return Err(JsError::new("celda fuera del grid"));
+        }
+        Ok(self.flatten(&bridge::cheapest_bridge(&self.grid, a, b)))
+    }
+
+    /// Cuando no hay ruta de `(sx, sy)` a `(gx, gy)`: el mínimo de paredes que
+    /// habría que quitar para crearla, como `[x0, y0, ...]` en orden (hay
+    /// `len / 2`). Vacío si ya existe ruta.
+    pub fn min_obstacle_removal(
+        &self,
+        sx: usize,
+        sy: usize,
+        gx: usize,
+        gy: usize,
+    ) -> Result<Vec<usize>, JsError> {
+        let (start, goal) = ((sx, sy), (gx, gy));
+        if !self.grid.in_bounds(start) || !self.grid.in_bounds(goal) {
+            return Err(JsError::new("celda fuera del grid"));
+        }
+        Ok(self.flatten(&bridge::fewest_walls(&self.grid, start, goal)))
+    }
+
+    /// Como `search_with` pero sin entrar nunca a `distance` pasos o menos de
+    /// las amenazas `[x0, y0, ...]`. Si no hay ruta, `blocking_region`
+    /// devuelve las celdas de la zona de peligro que la cortan. Estas
+    /// búsquedas no se pueden reproducir con `replay`.
+    pub fn search_safe(
+        &mut self,
+        options: &SearchOptions,
+        threats: &[usize],
+        distance: u32,
+    ) -> bool {
+        let options = &self.seeded(options);
+        self.prepare_search();
+        let started = clock::now_ms();
+        let goal = (self.grid.height - 1, self.grid.width - 1);
+        let threats = pairs(threats);
+        let (found, blocking) =
+            safety::safe_search(&self.grid, (0, 0), goal, options, &threats, distance);
+        let found = self.apply(found, options, started);
+        self.blocking = blocking;
+        self.path_kind = PathKind::Safe;
+        found
+    }
+
+    /// Celdas `[x0, y0, ...]` de la zona de peligro por las que pasaría la
+    /// ruta sin la restricción, tras una `search_safe` fallida. Vacío si no
+    /// hay ruta ni siquiera ignorando las amenazas.
+    pub fn blocking_region(&self) -> Vec<usize> {
+        self.flatten(&self.blocking)
+    }
+
+    /// Ruta actual como centros de celda en mundo (`[wx0, wy0, ...]`).
+    pub fn world_path(&self, cell_size: f32) -> Vec<f32> {
+        world::to_world(&self.path, cell_size)
+    }
+
+    /// Registra (o reemplaza) una capa de transitabilidad con nombre, p. ej.
+    /// "air" o "water", con una celda por celda del grid. La capa inicial se
+    /// llama "ground".
+    pub fn add_layer(&mut self, name: &str, cells: Vec<u8>) -> Result<(), JsError> {
+        self.touched();
+        self.layers
+            .insert(&mut self.grid, name, cells)
+            .map_err(JsError::new)
+    }
+
+    /// Quita una capa que no esté activa.
+    pub fn remove_layer(&mut self, name: &str) -> bool {
+        self.layers.remove(name)
+    }
+
+    /// Activa la capa `name` para las búsquedas siguientes. Devuelve `false`
+    /// si no existe. La ruta actual no se recalcula.
+    pub fn select_layer(&mut self, name: &str) -> bool {
+        self.touched();
+        self.layers.select(&mut self.grid, name)
+    }
+
+    pub fn active_layer(&self) -> String {
+        self.layers.active().to_string()
+    }
+
+    /// Nombres de las capas, empezando por la activa.
+    pub fn layer_names(&self) -> Vec<String> {
+        self.layers.names()
+    }
+
+    /// Conecta la celda `(x, y)` de la capa `from` con `(to_x, to_y)` de la
+    /// capa `to` (escalera, ascensor, rampa) con coste `cost`; con `two_way`
+    /// el paso sirve en ambos sentidos.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_transition(
+        &mut self,
+        from: &str,
+        x: usize,
+        y: usize,
+        to: &str,
+        to_x: usize,
+        to_y: usize,
+        cost: f64,
+        two_way: bool,
+    ) {
+        self.layers
+            .connect((from, (x, y)), (to, (to_x, to_y)), cost, two_way);
+    }
+
+    /// Busca entre la esquina `(0, 0)` de la capa `start_layer` y la opuesta
+    /// de `goal_layer`, cambiando de capa por los pasos registrados. La capa
+    /// de cada celda de la ruta sale de `path_layers`. Estas búsquedas no se
+    /// pueden reproducir con `replay`.
+    pub fn search_layers(
+        &mut self,
+        options: &SearchOptions,
+        start_layer: &str,
+        goal_layer: &str,
+    ) -> bool {
+        let options = &self.seeded(options);
+        self.prepare_search();
+        let started = clock::now_ms();
+        let names = self.layers.names();
+        let index = |name: &str| names.iter().position(|n| n == name);
+        let (Some(from), Some(to)) = (index(start_layer), index(goal_layer)) else {
+            return self.apply_layered(None, started);
+        };
+        let goal = (self.grid.height - 1, self.grid.width - 1);
+        let found = layers::layered_search(
+            &self.grid,
+            &self.layers,
+            options,
+            (from, (0, 0)),
+            (to, goal),
+        );
+        let states = found.result.as_ref().map_or(&[][..], |f| &f.states[..]);
+        let transitions = self.layers.route_links(options, states);
+        let found = self.apply_layered(Some(found), started);
+        self.path_transitions = transitions;
+        found
+    }
+
+    /// Registra (o reconfigura, conservando las paradas) el ascensor `name`
+    /// para `search_layers`: ir entre dos de sus paradas cuesta `wait` por la
+    /// espera más `per_stop` por cada parada recorrida, así la ruta elige
+    /// entre escalera y ascensor según lo que ahorre. `capacity` son los
+    /// bits de lo que puede llevar (ver
+    /// `SearchOptions::set_required_capacity`).
+    pub fn add_elevator(&mut self, name: &str, wait: f64, per_stop: f64, capacity: u32) {
+        self.layers.add_elevator(name, wait, per_stop, capacity);
+    }
+
+    /// Añade la celda `(x, y)` de la capa `layer` como siguiente parada del
+    /// ascensor `name`. Devuelve `false` si el ascensor no existe.
+    pub fn add_elevator_stop(&mut self, name: &str, layer: &str, x: usize, y: usize) -> bool {
+        self.layers.add_elevator_stop(name, layer, (x, y))
+    }
+
+    /// Pasos entre capas que usa la ruta de la última `search_layers`, en
+    /// orden: el nombre del ascensor o `"origen->destino"` para los pasos de
+    /// `add_transition`.
+    pub fn path_transitions(&self) -> Vec<String> {
+        self.path_transitions.clone()
+    }
+
+    /// Capa de cada celda de la ruta actual (la activa salvo tras
+    /// `search_layers`).
+    pub fn path_layers(&self) -> Vec<String> {
+        if !self.path_layers.is_empty() {
+            return self.path_layers.clone();
+        }
+        vec![self.layers.active().to_string(); self.path.len()]
+    }
+
+    /// Marca `(x, y)` como puerta con etiqueta `label` (de 0 a 31): solo se
+    /// cruza tras recoger la llave con la misma etiqueta. Devuelve `false` si
+    /// la celda o la etiqueta no son válidas.
+    pub fn set_door(&mut self, x: usize, y: usize, label: u8) -> bool {
+        self.lock_cell((x, y), label).is_some_and(|idx| {
+            self.locks.doors.insert(idx, label);
+            true
+        })
+    }
+
+    /// Coloca en `(x, y)` la llave de la etiqueta `label`; se recoge al pasar.
+    pub fn set_key(&mut self, x: usize, y: usize, label: u8) -> bool {
+        self.lock_cell((x, y), label).is_some_and(|idx| {
+            self.locks.keys.insert(idx, label);
+            true
+        })
+    }
+
+    pub fn clear_locks(&mut self) {
+        self.locks = Locks::default();
+    }
+
+    /// Como `search_with` pero respetando puertas y llaves: la ruta puede
+    /// desviarse a recoger llaves, así que un `false` indica que el nivel no
+    /// tiene solución. Estas búsquedas no se pueden reproducir con `replay`.
+    pub fn search_with_keys(&mut self, options: &SearchOptions) -> bool {
+        let options = &self.seeded(options);
+        self.prepare_search();
+        let started = clock::now_ms();
+        let goal = (self.grid.height - 1, self.grid.width - 1);
+        let found = locks::keyed_search(&self.grid, &self.locks, options, (0, 0), goal);
+        let found = found.map(|(cell, _)| cell);
+        let found = self.apply(found, options, started);
+        self.path_kind = PathKind::Keys;
+        found
+    }
+
+    /// Carga los puntos de vida de las paredes (uno por celda; 0, negativo o
+    /// infinito es indestructible). Solo se usan con
+    /// `SearchOptions::set_break_cost`. Si su longitud no coincide con el
+    /// grid es una anomalía; en modo tolerante se ignora y devuelve `false`.
+    pub fn set_wall_hit_points(&mut self, hit_points: Vec<f32>) -> Result<bool, JsError> {
+        if hit_points.len() != self.grid.cells.len() {
+            policy::anomaly("la capa de vida tiene otro tamaño")?;
+            return Ok(false);
+        }
+        self.grid.hit_points = Some(hit_points);
+        self.cost_memo.clear();
+        Ok(true)
+    }
+
+    /// Paredes que la ruta actual atraviesa y hay que derribar, como
+    /// `[x0, y0, x1, y1, ...]` en orden de paso.
+    pub fn destroyed_cells(&self) -> Vec<usize> {
+        let walls: Vec<usize> = self
+            .path
+            .iter()
+            .map(|&c| self.grid.idx(c))
+            .filter(|&idx| self.grid.cells[idx] != 1)
+            .collect();
+        self.flatten(&walls)
+    }
+
+    /// Estampa como paredes un segmento de muro (p. ej. de un plano o una
+    /// herramienta de dibujo) en coordenadas continuas de celda: la celda
+    /// `(x, y)` ocupa `[x, x + 1) × [y, y + 1)`. Se bloquea toda celda que el
+    /// segmento toca o que queda a `thickness / 2` o menos. Devuelve cuántas
+    /// celdas libres se bloquearon.
+    pub fn block_segment(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, thickness: f32) -> usize {
+        self.touched();
+        raster::block_segment(&mut self.grid, (x0, y0), (x1, y1), thickness)
+    }
+
+    /// Rellena como paredes el polígono de vértices `[x0, y0, x1, y1, ...]`
+    /// (mismas coordenadas que `block_segment`): se bloquea cada celda cuyo
+    /// centro cae dentro. Devuelve cuántas celdas libres se bloquearon.
+    pub fn block_polygon(&mut self, points: &[f32]) -> usize {
+        let points: Vec<(f32, f32)> = points.chunks_exact(2).map(|c| (c[0], c[1])).collect();
+        self.touched();
+        raster::block_polygon(&mut self.grid, &points)
+    }
+
+    /// Carga la capa de elevación (una altura por celda). Si su longitud no
+    /// coincide con el grid es una anomalía; en modo tolerante se ignora y
+    /// devuelve `false`.
+    pub fn set_elevation(&mut self, heights: Vec<f32>) -> Result<bool, JsError> {
+        if heights.len() != self.grid.cells.len() {
+            policy::anomaly("la capa de elevación tiene otro tamaño")?;
+            return Ok(false);
+        }
+        self.grid.elevation = Some(heights);
+        self.cost_memo.clear();
+        Ok(true)
+    }
+
+    /// Si un proyectil (granada, flecha) lanzado de `(x0, y0)` a `(x1, y1)`
+    /// pasa por encima de todo lo que hay en medio: la trayectoria es una
+    /// parábola que sube `max_height` sobre la recta entre el suelo de ambas
+    /// celdas (la elevación cargada, o 0) y las paredes miden `wall_height`
+    /// sobre su suelo. Ver `ballistics::clear_arc`.
+    pub fn clear_arc(
+        &self,
+        x0: usize,
+        y0: usize,
+        x1: usize,
+        y1: usize,
+        max_height: f32,
+        wall_height: f32,
+    ) -> bool {
+        ballistics::clear_arc(&self.grid, (x0, y0), (x1, y1), max_height, wall_height)
+    }
+
+    /// Valor de `layer` (uno por celda; sin ella, la elevación) en cada celda
+    /// de la ruta actual, para dibujar su perfil de altura o de coste. Vacío
+    /// si no hay elevación cargada.
+    pub fn path_profile(&self, layer: Option<Vec<f32>>) -> Result<Vec<f32>, JsError> {
+        let Some(values) = layer.as_ref().or(self.grid.elevation.as_ref()) else {
+            return Ok(vec![]);
+        };
+        if values.len() != self.grid.cells.len() {
+            return Err(JsError::new("la capa tiene otro tamaño"));
+        }
+        Ok(self
+            .path
+            .iter()
+            .map(|&c| values[self.grid.idx(c)])
+            .collect())
+    }
+
+    /// Hora de llegada a cada punto de `path` (`[x0, y0, x1, y1, ...]`)
+    /// partiendo en 0: `terrain` da el tipo de terreno de cada celda y
+    /// `speeds[tipo]` la velocidad del agente sobre él en celdas por unidad
+    /// de tiempo. Un terreno sin velocidad positiva hace infinito el resto.
+    pub fn eta(&self, path: &[usize], terrain: &[u8], speeds: &[f32]) -> Result<Vec<f32>, JsError> {
+        if terrain.len() != self.grid.cells.len() {
+            return Err(JsError::new("la capa de terreno tiene otro tamaño"));
+        }
+        Ok(travel::cumulative_times(
+            &self.grid,
+            &pairs(path),
+            terrain,
+            speeds,
+        ))
+    }
+
+    /// Ajusta un multiplicador de coste por tipo de terreno (índice = valor de
+    /// `terrain`, una entrada por celda) para que las rutas planificadas se
+    /// parezcan a las que sigue la gente de verdad. `observed_paths` son las
+    /// rutas observadas seguidas (`[x0, y0, x1, y1, ...]`) y `lengths` el
+    /// número de celdas de cada una. Los multiplicadores sirven, p. ej., como
+    /// costes de `search_costs_f32` dando a cada celda el de su terreno.
+    pub fn calibrate(
+        &self,
+        terrain: &[u8],
+        observed_paths: &[usize],
+        lengths: &[usize],
+    ) -> Result<Vec<f32>, JsError> {
+        if terrain.len() != self.grid.cells.len() {
+            return Err(JsError::new("la capa de terreno tiene otro tamaño"));
+        }
+        if lengths.iter().sum::<usize>() * 2 != observed_paths.len() {
+            return Err(JsError::new(
+                "las longitudes no cuadran con las rutas observadas",
+            ));
+        }
+        let cells = pairs(observed_paths);
+        let mut rest = &cells[..];
+        let paths: Vec<Vec<(usize, usize)>> = lengths
+            .iter()
+            .map(|&n| {
+                let (path, tail) = rest.split_at(n);
+                rest = tail;
+                path.to_vec()
+            })
+            .collect();
+        Ok(calibrate::calibrate(&self.grid, terrain, &paths))
+    }
+
+    /// Ruta para convoyes: como `search_with` pero sin pasar por pasos de
+    /// menos de `min_width` celdas de ancho (medido con el mapa de holgura,
+    /// ancho `2 * holgura - 1`). Si la encuentra devuelve el ancho del paso
+    /// más estrecho de la ruta; si no, deja la ruta vacía y devuelve el
+    /// máximo ancho con el que sí habría ruta.
+    pub fn convoy_path(&mut self, options: &SearchOptions, min_width: u32) -> Result<u32, JsError> {
+        let mut opts = options.clone();
+        opts.set_min_width(min_width);
+        if !self.search_with(&opts)? {
+            let goal = (self.grid.height - 1, self.grid.width - 1);
+            let best = cost::StepCost::new(&self.grid, &opts).max_bottleneck((0, 0), goal);
+            return Ok(options::width_for_clearance(best));
+        }
+        // Los extremos no cuentan salvo que la ruta no tenga celdas interiores.
+        let map = distance::clearance_map(&self.grid);
+        let inner = match self.path.len() {
+            0..=2 => &self.path[..],
+            n => &self.path[1..n - 1],
+        };
+        let narrowest = inner.iter().map(|&c| map[self.grid.idx(c)]).min();
+        Ok(options::width_for_clearance(narrowest.unwrap_or(0)))
+    }
+
+    /// Precalcula hasta `count` landmarks para la heurística ALT con los
+    /// costes de `options`; devuelve cuántos se han elegido. Sirve mientras el
+    /// grid (incluidas sus capas) no cambie.
+    pub fn build_landmarks(&mut self, options: &SearchOptions, count: usize) -> usize {
+        let options = &self.seeded(options);
+        self.prepare_search();
+        let landmarks = Landmarks::build(&self.grid, options, count);
+        let chosen = landmarks.cells.len();
+        self.landmarks = Some(landmarks);
+        chosen
+    }
+
+    /// Celdas elegidas como landmarks, como `[x0, y0, x1, y1, ...]`.
+    pub fn landmark_cells(&self) -> Vec<usize> {
+        self.landmarks
+            .as_ref()
+            .map_or_else(Vec::new, |lm| self.flatten(&lm.cells))
+    }
+
+    /// Guarda los landmarks de `build_landmarks` en el mismo contenedor de
+    /// preprocesado que `Session::save_preprocessing`, para calcularlos en un
+    /// paso de build y cargarlos con `load_landmarks`. Vacío si no hay.
+    pub fn save_landmarks(&self) -> Vec<u8> {
+        self.landmarks.as_ref().map_or_else(Vec::new, |landmarks| {
+            Artifacts {
+                fingerprint: landmarks.grid.content_hash(),
+                sections: vec![(SECTION_LANDMARKS, landmarks.to_words())],
+            }
+            .encode()
+        })
+    }
+
+    /// Carga landmarks guardados con `save_landmarks` en lugar de
+    /// calcularlos; devuelve cuántos hay. Falla si son de otro mapa (celdas
+    /// o capas) o están dañados.
+    pub fn load_landmarks(&mut self, artifacts: &[u8]) -> Result<usize, JsError> {
+        self.restore_landmarks(artifacts).map_err(JsError::new)
+    }
+
+    /// Búsqueda A* con las opciones de `build_landmarks` y la heurística ALT,
+    /// que en mapas estáticos expande muchas menos celdas que la Manhattan.
+    /// Si el grid ha cambiado desde el precálculo usa la Manhattan. Estas
+    /// búsquedas no se pueden reproducir con `replay`.
+    pub fn search_with_landmarks(&mut self) -> bool {
+        let Some(landmarks) = self.landmarks.take() else {
+            return false;
+        };
+        self.prepare_search();
+        let started = clock::now_ms();
+        let options = landmarks.opts.clone();
+        let goal = (self.grid.height - 1, self.grid.width - 1);
+        let found = if landmarks.valid_for(&self.grid) {
+            let grid = &self.grid;
+            let h_scale = cost::StepCost::new(grid, &options).min_step();
+            let target = grid.idx(goal);
+            search::grid_search_by(grid, (0, 0), goal, &options, |(x, y), g| {
+                let manhattan = h_scale * (x.abs_diff(goal.0) + y.abs_diff(goal.1)) as f64;
+                g + manhattan.max(landmarks.lower_bound(grid.idx((x, y)), target))
+            })
+        } else {
+            search::grid_search(&self.grid, (0, 0), goal, &options)
+        };
+        self.landmarks = Some(landmarks);
+        self.apply(found, &options, started)
+    }
+
+    /// Divide el espacio libre en rectángulos uniformes para
+    /// `search_rectangles`; devuelve cuántos hay.
+    pub fn build_rectangles(&mut self) -> usize {
+        let rectangles = Rectangles::decompose(&self.grid);
+        let count = rectangles.rects.len();
+        self.rectangles = Some(rectangles);
+        count
+    }
+
+    /// Rectángulos de `build_rectangles` como `[x, y, alto, ancho, ...]`.
+    pub fn rectangles(&self) -> Vec<usize> {
+        self.rectangles.as_ref().map_or_else(Vec::new, |r| {
+            r.rects.iter().flat_map(|r| [r.x, r.y, r.h, r.w]).collect()
+        })
+    }
+
+    /// Ruta más corta (coste unitario, sin opciones) expandiendo solo el
+    /// perímetro de los rectángulos de `build_rectangles`, que se recalculan
+    /// si el grid ha cambiado. Mismo coste que el BFS con muchas menos
+    /// expansiones en campo abierto.
+    pub fn search_rectangles(&mut self) -> bool {
+        let started = clock::now_ms();
+        if !self
+            .rectangles
+            .as_ref()
+            .is_some_and(|r| r.valid_for(&self.grid))
+        {
+            self.build_rectangles();
+        }
+        let goal = (self.grid.height - 1, self.grid.width - 1);
+        let found = self
+            .rectangles
+            .as_ref()
+            .map(|r| r.search(&self.grid, (0, 0), goal));
+        let found = found.unwrap_or_else(search::Outcome::none);
+        self.apply(found, &SearchOptions::default(), started)
+    }
+
+    /// Ruta más corta de `(0,0)` a `(n-1,n-1)` que puede atravesar hasta `k`
+    /// paredes, todas con coste 1; las atravesadas salen en
+    /// `destroyed_cells`. Estas búsquedas no se pueden reproducir con
+    /// `replay`.
+    pub fn path_with_removals(&mut self, k: u32) -> bool {
+        let started = clock::now_ms();
+        let goal = (self.grid.height - 1, self.grid.width - 1);
+        let found = bridge::breach_search(&self.grid, (0, 0), goal, k as usize);
+        let found = self.apply(found, &SearchOptions::default(), started);
+        self.path_kind = PathKind::Breach;
+        found
+    }
+
+    /// Búsqueda primero-el-mejor con prioridad definida en JS: `priority` se
+    /// llama como `priority(celda, g, destino)` (celdas como índice
+    /// `x * width + y`) y se expande primero el menor valor devuelto. Un valor
+    /// no numérico o una excepción cuentan como prioridad infinita. Estas
+    /// búsquedas no se pueden reproducir con `replay`.
+    pub fn search_custom(&mut self, options: &SearchOptions, priority: &js_sys::Function) -> bool {
+        let options = &self.seeded(options);
+        self.prepare_search();
+        let started = clock::now_ms();
+        let goal = (self.grid.height - 1, self.grid.width - 1);
+        let goal_js = JsValue::from(self.grid.idx(goal) as u32);
+        let grid = &self.grid;
+        let found = search::grid_search_by(grid, (0, 0), goal, options, |cell, g| {
+            let cell = JsValue::from(grid.idx(cell) as u32);
+            priority
+                .call3(&JsValue::NULL, &cell, &JsValue::from(g), &goal_js)
+                .ok()
+                .and_then(|v| v.as_f64())
+                .filter(|p| !p.is_nan())
+                .unwrap_or(f64::INFINITY)
+        });
+        let found = self.apply(found, options, started);
+        self.mark_suboptimal();
+        found
+    }
+
+    /// Ruta más corta desde `(x, y)` hasta la celda transitable más cercana
+    /// cuyo valor en `terrain` (uno por celda) es `id`, p. ej. "ir al agua
+    /// más cercana". Estas búsquedas no se pueden reproducir con `replay`.
+    pub fn path_to_terrain(
+        &mut self,
+        x: usize,
+        y: usize,
+        terrain: &[u8],
+        id: u8,
+    ) -> Result<bool, JsError> {
+        if terrain.len() != self.grid.cells.len() {
+            return Err(JsError::new("la capa de terreno tiene otro tamaño"));
+        }
+        Ok(self.path_to_goal((x, y), |idx| terrain[idx] == id))
+    }
+
+    /// Como `path_to_terrain` pero con el destino decidido en JS: la búsqueda
+    /// termina en la primera celda para la que `is_goal(celda)` (índice
+    /// `x * width + y`) devuelve un valor verdadero. Una excepción cuenta
+    /// como falso.
+    pub fn path_to_match(&mut self, x: usize, y: usize, is_goal: &js_sys::Function) -> bool {
+        self.path_to_goal((x, y), |idx| {
+            is_goal
+                .call1(&JsValue::NULL, &JsValue::from(idx as u32))
+                .is_ok_and(|v| v.is_truthy())
+        })
+    }
+
+    /// Marca como exploradas las celdas a distancia de Chebyshev `radius` o
+    /// menos de `(x, y)`, activando la niebla de guerra si no lo estaba.
+    pub fn reveal(&mut self, x: usize, y: usize, radius: usize) {
+        if !self.grid.in_bounds((x, y)) {
+            return;
+        }
+        let cells = self.grid.cells.len();
+        let fog = self.fog.get_or_insert_with(|| Fog::new(cells));
+        fog.reveal(&self.grid, (x, y), radius);
+    }
+
+    /// Sustituye la niebla por `explored` (distinto de 0 = explorada, una
+    /// entrada por celda). Si la longitud no coincide es una anomalía; en
+    /// modo tolerante se ignora y devuelve `false`.
+    pub fn set_explored(&mut self, explored: Vec<u8>) -> Result<bool, JsError> {
+        if explored.len() != self.grid.cells.len() {
+            policy::anomaly("la niebla tiene otro tamaño")?;
+            return Ok(false);
+        }
+        self.fog = Some(Fog {
+            explored: explored.iter().map(|&e| e != 0).collect(),
+        });
+        Ok(true)
+    }
+
+    pub fn clear_fog(&mut self) {
+        self.fog = None;
+    }
+
+    /// Ruta desde `(x, y)` por terreno ya explorado hasta la celda de
+    /// frontera más cercana (libre, explorada y junto a una sin explorar).
+    /// Si la más cercana queda a más de `max_steps` pasos no devuelve ruta y
+    /// el resultado se marca como truncado.
+    pub fn suggest_exploration_target(&mut self, x: usize, y: usize, max_steps: u32) -> bool {
+        let started = clock::now_ms();
+        let Some(fog) = &self.fog else {
+            return self.store_bfs((None, vec![]), started);
+        };
+        let known = fog.known(&self.grid);
+        let (path, expanded) =
+            distance::bfs_to_goal(&known, None, (x, y), |idx| fog.is_frontier(&self.grid, idx));
+        let too_far = path
+            .as_ref()
+            .is_some_and(|p| p.len() - 1 > max_steps as usize);
+        let path = path.filter(|_| !too_far);
+        let found = self.store_bfs((path, expanded), started);
+        self.status.truncated = too_far;
+        found
+    }
+
+    /// Coste óptimo de `(sx, sy)` a `(gx, gy)` con las opciones de la última
+    /// búsqueda con costes (o las de por defecto), sin construir la ruta ni
+    /// cambiar el resultado actual. Infinito si no hay ruta. Los costes se
+    /// guardan mientras no cambien el grid ni las opciones, así que repetir
+    /// las mismas consultas en cada tick no vuelve a buscar.
+    pub fn path_cost(&mut self, sx: usize, sy: usize, gx: usize, gy: usize) -> f64 {
+        let default = SearchOptions::default();
+        let opts = self.options.as_ref().unwrap_or(&default);
+        let (start, goal) = ((sx, sy), (gx, gy));
+        if !self.grid.in_bounds(start) || !self.grid.in_bounds(goal) {
+            return f64::INFINITY;
+        }
+        let (from, to) = (self.grid.idx(start), self.grid.idx(goal));
+        let cached = self.cost_memo.get(self.revision, from, to);
+        self.metrics.record_lookup(cached.is_some());
+        if let Some(cost) = cached {
+            return cost;
+        }
+        let cost = search::grid_cost(&self.grid, start, goal, opts).unwrap_or(f64::INFINITY);
+        self.cost_memo.insert(from, to, cost);
+        cost
+    }
+
+    /// Celdas a `radius` pasos o menos de `(x, y)` caminando (BFS limitado),
+    /// en orden de distancia, como `[x0, y0, x1, y1, ...]`. Incluye el origen.
+    pub fn cells_within_steps(&self, x: usize, y: usize, radius: u32) -> Vec<usize> {
+        let (_, order) = distance::bfs_distances(&self.grid, &[(x, y)], radius);
+        self.flatten(&order)
+    }
+
+    /// Celdas a exactamente `d` pasos caminando desde `(x, y)` (distancia BFS,
+    /// no euclídea), como `[x0, y0, x1, y1, ...]`.
+    pub fn ring_at_distance(&self, x: usize, y: usize, d: u32) -> Vec<usize> {
+        let (dist, order) = distance::bfs_distances(&self.grid, &[(x, y)], d);
+        let ring: Vec<usize> = order.into_iter().filter(|&idx| dist[idx] == d).collect();
+        self.flatten(&ring)
+    }
+
+    /// Los `k` destinos más cercanos a `(x, y)` caminando, entre las celdas
+    /// `goals` (`[x0, y0, x1, y1, ...]`), con los costes de `options`: p. ej.
+    /// "los 3 recursos más cercanos". Devuelve `[x0, y0, coste0, ...]` de
+    /// menor a mayor coste, con menos de `k` si no se alcanzan más. Hace un
+    /// único Dijkstra que para en cuanto tiene los `k`.
+    pub fn nearest_goals(
+        &mut self,
+        options: &SearchOptions,
+        x: usize,
+        y: usize,
+        goals: &[usize],
+        k: usize,
+    ) -> Vec<f64> {
+        let options = &self.seeded(options);
+        if !self.grid.in_bounds((x, y)) {
+            return vec![];
+        }
+        let goals: Vec<usize> = pairs(goals)
+            .into_iter()
+            .filter(|&c| self.grid.in_bounds(c))
+            .map(|c| self.grid.idx(c))
+            .collect();
+        let costs = cost::StepCost::new(&self.grid, options);
+        distance::nearest_goals(&self.grid, &costs, self.grid.idx((x, y)), &goals, k)
+            .into_iter()
+            .flat_map(|(idx, cost)| {
+                let (x, y) = self.grid.coords(idx);
+                [x as f64, y as f64, cost]
+            })
+            .collect()
+    }
+
+    /// Mapa de Dijkstra hacia los objetivos `sources` (`[x0, y0, x1, y1,
+    /// ...]`) con los costes de `options`: cada celda vale lo que cuesta
+    /// llegar al objetivo más cercano (ver `DijkstraMap`).
+    pub fn dijkstra_map(&mut self, options: &SearchOptions, sources: &[usize]) -> DijkstraMap {
+        let options = &self.seeded(options);
+        self.distance_map(options, sources)
+    }
+
+    /// Mapa de huida de `sources`: el de `dijkstra_map` multiplicado por
+    /// `-factor` (el clásico es 1.2) y propagado otra vez, de modo que bajar
+    /// por él aleja de los objetivos sin meterse en callejones sin salida
+    /// si hay una escapatoria mejor.
+    pub fn flee_map(
+        &mut self,
+        options: &SearchOptions,
+        sources: &[usize],
+        factor: f32,
+    ) -> DijkstraMap {
+        let options = &self.seeded(options);
+        self.fleeing(options, sources, factor)
+    }
+
+    /// Mapa de retirada a cubierto de `threats`: el de `flee_map` restando
+    /// `cover` a las celdas libres pegadas a una pared (el borde del grid no
+    /// cubre) y propagado otra vez, de modo que bajar por él aleja de las
+    /// amenazas buscando dónde resguardarse. Con `cover` 0 es `flee_map`.
+    pub fn retreat_map(
+        &mut self,
+        options: &SearchOptions,
+        threats: &[usize],
+        factor: f32,
+        cover: f32,
+    ) -> DijkstraMap {
+        let options = &self.seeded(options);
+        let flee = self.fleeing(options, threats, factor);
+        let covered = |idx: usize| {
+            let cell = self.grid.coords(idx);
+            self.grid.neighbors(cell).any(|n| !self.grid.walkable(n))
+        };
+        let seeds: Vec<(usize, f32)> = flee
+            .seeds()
+            .into_iter()
+            .map(|(idx, v)| {
+                if covered(idx) {
+                    (idx, v - cover)
+                } else {
+                    (idx, v)
+                }
+            })
+            .collect();
+        let costs = cost::StepCost::new(&self.grid, options);
+        DijkstraMap::compute(&self.grid, &costs, &seeds)
+    }
+
+    /// Mapa de intensidad de sonido u olor para la IA de sigilo: los
+    /// emisores `emitters` (`[x0, y0, x1, y1, ...]`) emiten con la intensidad
+    /// de `strengths` y se oye en cada celda lo que llega más fuerte. Cada
+    /// celda libre deja pasar la fracción `open` y cada pared la fracción
+    /// `wall`; por debajo de `threshold` no se oye nada (ver
+    /// `propagation::propagate`). Una intensidad por celda.
+    pub fn propagation_map(
+        &self,
+        emitters: &[usize],
+        strengths: &[f32],
+        open: f32,
+        wall: f32,
+        threshold: f32,
+    ) -> Result<Vec<f32>, JsError> {
+        let emitters = pairs(emitters);
+        if emitters.len() != strengths.len() {
+            return Err(JsError::new("hace falta una intensidad por emisor"));
+        }
+        let emitters: Vec<(usize, f32)> = emitters
+            .into_iter()
+            .zip(strengths)
+            .filter(|(c, _)| self.grid.in_bounds(*c))
+            .map(|(c, &s)| (self.grid.idx(c), s))
+            .collect();
+        Ok(propagation::propagate(
+            &self.grid, &emitters, open, wall, threshold,
+        ))
+    }
+
+    /// Nivel de luz por celda (0 a 1) de las luces `lights` (`[x0, y0, x1,
+    /// y1, ...]`), cada una con su radio en celdas en `radii` y su brillo en
+    /// `brightness`: la luz va en línea recta, las paredes tapan lo que hay
+    /// detrás y se apaga con la distancia (ver `lighting::light_map`).
+    pub fn light_map(
+        &self,
+        lights: &[usize],
+        radii: &[u32],
+        brightness: &[f32],
+    ) -> Result<Vec<f32>, JsError> {
+        let cells = pairs(lights);
+        if cells.len() != radii.len() || cells.len() != brightness.len() {
+            return Err(JsError::new("hace falta un radio y un brillo por luz"));
+        }
+        let lights: Vec<lighting::Light> = cells
+            .into_iter()
+            .zip(radii.iter().zip(brightness))
+            .map(|(cell, (&radius, &bright))| (cell, radius, bright))
+            .collect();
+        Ok(lighting::light_map(&self.grid, &lights))
+    }
+
+    /// Divide el espacio transitable en salas, pasillos y puertas (las de
+    /// `set_door`) según la holgura: una sala es una zona amplia con holgura
+    /// `min_clearance` o más en al menos `min_cells` celdas, más sus bordes
+    /// (ver `rooms::detect`). Los números de zona son estables para el mismo
+    /// mapa.
+    pub fn detect_rooms(&self, min_clearance: u32, min_cells: usize) -> Rooms {
+        let mut doors: Vec<usize> = self.locks.doors.keys().copied().collect();
+        doors.sort_unstable();
+        rooms::detect(&self.grid, &doors, min_clearance, min_cells)
+    }
+
+    /// Dónde poner puertas en un mapa generado: las celdas de pasillo que
+    /// separan una sala de un pasillo, con las salas de `detect_rooms` para
+    /// los mismos parámetros. Devuelve `[x, y, sala, pasillo, ...]` con los
+    /// números de zona de `detect_rooms`.
+    pub fn suggest_doors(&self, min_clearance: u32, min_cells: usize) -> Vec<usize> {
+        let rooms = self.detect_rooms(min_clearance, min_cells);
+        rooms::suggest_doors(&self.grid, &rooms)
+            .into_iter()
+            .flat_map(|(idx, room, corridor)| {
+                let (x, y) = self.grid.coords(idx);
+                [x, y, room, corridor]
+            })
+            .collect()
+    }
+
+    /// Elige hasta `count` puntos de aparición que cumplan `constraints`
+    /// (separación a pie, distancia al inicio, holgura) para repartirlos de
+    /// forma justa en una partida. Devuelve `[x0, y0, x1, y1, ...]`; pueden
+    /// ser menos de `count` si no caben (ver `spawns::select`).
+    pub fn select_spawns(&self, count: usize, constraints: &SpawnConstraints) -> Vec<usize> {
+        spawns::select(&self.grid, count, constraints)
+            .into_iter()
+            .flat_map(|idx| {
+                let (x, y) = self.grid.coords(idx);
+                [x, y]
+            })
+            .collect()
+    }
+
+    /// Sorteo de celdas por franjas de distancia a pie desde `(x, y)` para
+    /// repartir botín u objetivos: la franja `i` cubre `band_width` pasos y
+    /// de ella salen `per_band[i]` celdas, así la dificultad crece con el
+    /// camino de verdad y no con la distancia en línea recta. Devuelve
+    /// `[x0, y0, franja0, x1, y1, franja1, ...]`; la misma `seed` da las
+    /// mismas celdas (ver `bands::sample`).
+    pub fn sample_by_distance(
+        &self,
+        x: usize,
+        y: usize,
+        band_width: u32,
+        per_band: &[usize],
+        seed: u32,
+    ) -> Vec<usize> {
+        bands::sample(&self.grid, (x, y), band_width, per_band, seed)
+            .into_iter()
+            .flat_map(|(idx, band)| {
+                let (x, y) = self.grid.coords(idx);
+                [x, y, band]
+            })
+            .collect()
+    }
+
+    /// Informe de equilibrio entre los puntos de aparición `(ax, ay)` y
+    /// `(bx, by)` frente a los objetivos `objectives` (`[x0, y0, x1, y1,
+    /// ...]`): distancias a pie, estrechamientos en el camino y tamaño de la
+    /// zona a la que cada uno llega antes. `BalanceReport::fair` decide si
+    /// el mapa se acepta.
+    pub fn validate_balance(
+        &self,
+        ax: usize,
+        ay: usize,
+        bx: usize,
+        by: usize,
+        objectives: &[usize],
+    ) -> BalanceReport {
+        balance::report(&self.grid, (ax, ay), (bx, by), &pairs(objectives))
+    }
+
+    /// Orden de revelación del mapa desde `(x, y)`: las celdas alcanzables
+    /// por distancia a pie, para leerlo por trozos al animar la niebla que se
+    /// levanta (ver `RevealOrder`).
+    pub fn reveal_order(&self, x: usize, y: usize) -> RevealOrder {
+        reveal::order(&self.grid, (x, y))
+    }
+
+    /// Encola una petición de ruta de `(sx, sy)` a `(gx, gy)` con `options`
+    /// para resolverla más tarde con `process`; las de mayor `priority` se
+    /// atienden antes y, entre iguales, por orden de llegada. Devuelve el
+    /// número de la petición (nunca 0). Un extremo bloqueado es una anomalía
+    /// al encolar; si se bloquea después, la petición sale sin ruta.
+    pub fn enqueue(
+        &mut self,
+        options: &SearchOptions,
+        sx: usize,
+        sy: usize,
+        gx: usize,
+        gy: usize,
+        priority: f64,
+    ) -> Result<u32, JsError> {
+        self.check_endpoints((sx, sy), (gx, gy), options)?;
+        let options = self.seeded(options);
+        Ok(self.requests.push(priority, (sx, sy), (gx, gy), options))
+    }
+
+    /// Resuelve peticiones encoladas, por prioridad, mientras quede tiempo de
+    /// los `budget_ms` milisegundos del fotograma; las demás esperan al
+    /// siguiente `process`. Siempre atiende al menos una, para que un
+    /// presupuesto muy justo no deje la cola parada. Cada búsqueda es como
+    /// `search_between`, así que la última queda además como ruta actual.
+    pub fn process(&mut self, budget_ms: f64) -> ProcessedRequests {
+        let started = clock::now_ms();
+        let mut done = ProcessedRequests::default();
+        while done.is_empty() || clock::now_ms() - started < budget_ms {
+            let Some(request) = self.requests.pop() else {
+                break;
+            };
+            self.prepare_search();
+            self.between(&request.options, request.start, request.goal);
+            done.push(request.ticket, self.result());
+        }
+        done.set_remaining(self.requests.len());
+        done
+    }
+
+    /// Peticiones encoladas sin atender.
+    pub fn pending_requests(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// Retira la petición `ticket` de la cola. Devuelve si estaba pendiente.
+    pub fn cancel_request(&mut self, ticket: u32) -> bool {
+        self.requests.cancel(ticket)
+    }
+
+    /// Isócronas desde `(x, y)` con los costes de `options` ("alcanzable en
+    /// 5/10/15 minutos"): para cada umbral `i` de `thresholds`, las celdas de
+    /// borde alcanzables con coste `thresholds[i]` o menos, como
+    /// `[x0, y0, i0, x1, y1, i1, ...]` en orden de umbral.
+    pub fn isochrones(
+        &mut self,
+        options: &SearchOptions,
+        x: usize,
+        y: usize,
+        thresholds: &[f64],
+    ) -> Vec<usize> {
+        let options = &self.seeded(options);
+        if !self.grid.in_bounds((x, y)) {
+            return vec![];
+        }
+        let costs = cost::StepCost::new(&self.grid, options);
+        distance::isochrones(&self.grid, &costs, self.grid.idx((x, y)), thresholds)
+            .into_iter()
+            .flat_map(|(idx, i)| {
+                let (x, y) = self.grid.coords(idx);
+                [x, y, i]
+            })
+            .collect()
+    }
+
+    /// Activa la penalización por reutilizar rutas en `search_with`: cada ruta
+    /// devuelta suma `penalty` al coste de sus celdas y antes de cada consulta
+    /// lo acumulado se multiplica por `decay` (entre 0 y 1; fuera de rango es
+    /// una anomalía y en modo tolerante se ajusta). Con `penalty` 0 se
+    /// desactiva.
+    pub fn set_route_reuse_penalty(&mut self, penalty: f32, decay: f32) -> Result<(), JsError> {
+        let decay = policy::unit(decay, "la atenuación")?;
+        self.reuse =
+            (penalty > 0.0).then(|| RouteReuse::new(penalty, decay, self.grid.cells.len()));
+        self.compose_penalty();
+        Ok(())
+    }
+
+    /// Configura la capa de peligro dinámico (fuego, gas): en cada `tick` el
+    /// peligro se propaga a las celdas libres vecinas multiplicado por
+    /// `spread` y se atenúa en una fracción `decay` (ambos entre 0 y 1). Las
+    /// búsquedas con costes suman `weight` por unidad de peligro de cada
+    /// celda en la que entran. Conserva los valores actuales. Un `spread` o
+    /// `decay` fuera de rango es una anomalía; en modo tolerante se ajusta.
+    pub fn set_hazard_rules(
+        &mut self,
+        spread: f32,
+        decay: f32,
+        weight: f32,
+    ) -> Result<(), JsError> {
+        let spread = policy::unit(spread, "la propagación")?;
+        let decay = policy::unit(decay, "la atenuación")?;
+        let cells = self.grid.cells.len();
+        let mut hazard = Hazard::new(spread, decay, weight, cells);
+        if let Some(old) = self.hazard.take() {
+            hazard.values = old.values;
+        }
+        self.hazard = Some(hazard);
+        self.compose_penalty();
+        Ok(())
+    }
+
+    /// Fija el peligro de `(x, y)` a `intensity`, p. ej. para prender fuego.
+    /// Sin reglas configuradas usa propagación 0.5, atenuación 0.1 y peso 1.
+    pub fn ignite(&mut self, x: usize, y: usize, intensity: f32) -> bool {
+        if !self.grid.in_bounds((x, y)) {
+            return false;
+        }
+        let cells = self.grid.cells.len();
+        let hazard = self
+            .hazard
+            .get_or_insert_with(|| Hazard::new(0.5, 0.1, 1.0, cells));
+        hazard.values[self.grid.idx((x, y))] = intensity.max(0.0);
+        self.compose_penalty();
+        true
+    }
+
+    /// Avanza un paso las capas dinámicas (propagación del peligro y
+    /// atenuación de la congestión) y actualiza los costes.
+    pub fn tick(&mut self) {
+        if let Some(hazard) = &mut self.hazard {
+            hazard.tick(&self.grid);
+        }
+        if let Some(congestion) = &mut self.congestion {
+            congestion.tick();
+        }
+        self.compose_penalty();
+    }
+
+    /// Peligro actual por celda (vacío si no hay capa de peligro).
+    pub fn hazard(&self) -> Vec<f32> {
+        self.hazard
+            .as_ref()
+            .map_or_else(Vec::new, |h| h.values.clone())
+    }
+
+    pub fn clear_hazard(&mut self) {
+        self.hazard = None;
+        self.compose_penalty();
+    }
+
+    /// Activa la capa de congestión: cada agente que pasa por una celda
+    /// (`report_agents`) suma `weight` al coste de entrar en ella, y en cada
+    /// `tick` lo acumulado se multiplica por `decay` (entre 0 y 1; fuera de
+    /// rango es una anomalía y en modo tolerante se ajusta). Con `weight` 0
+    /// se desactiva.
+    pub fn set_congestion(&mut self, weight: f32, decay: f32) -> Result<(), JsError> {
+        let decay = policy::unit(decay, "la atenuación")?;
+        self.congestion =
+            (weight > 0.0).then(|| Congestion::new(weight, decay, self.grid.cells.len()));
+        self.compose_penalty();
+        Ok(())
+    }
+
+    /// Anota el paso de agentes por las celdas `[x0, y0, x1, y1, ...]`; las
+    /// que quedan fuera del grid se ignoran.
+    pub fn report_agents(&mut self, positions: &[usize]) {
+        let Some(congestion) = &mut self.congestion else {
+            return;
+        };
+        for cell in pairs(positions) {
+            if self.grid.in_bounds(cell) {
+                congestion.visit(self.grid.idx(cell));
+            }
+        }
+        self.compose_penalty();
+    }
+
+    /// Carga de congestión por celda (vacío si no está activa).
+    pub fn congestion(&self) -> Vec<f32> {
+        self.congestion
+            .as_ref()
+            .map_or_else(Vec::new, |c| c.load.clone())
+    }
+
+    /// Registra (o reemplaza) la capa de coste `name`, con un coste extra por
+    /// celda, que solo cuenta dentro de las ventanas de tiempo `schedule`
+    /// (`[inicio0, fin0, inicio1, fin1, ...]`, cada una `[inicio, fin)`) según
+    /// la hora de `set_time`; sin ventanas cuenta siempre. P. ej. una plaza
+    /// de mercado abarrotada de 9 a 17 con la hora del día como tiempo. Si
+    /// `costs` no tiene una entrada por celda, alguna es negativa o no finita,
+    /// o `schedule` tiene longitud impar es una anomalía; en modo tolerante
+    /// se ignora y devuelve `false`.
+    pub fn add_cost_overlay(
+        &mut self,
+        name: &str,
+        costs: Vec<f32>,
+        schedule: &[f32],
+    ) -> Result<bool, JsError> {
+        Ok(self.insert_overlay(name, costs, schedule)?)
+    }
+
+    pub fn remove_cost_overlay(&mut self, name: &str) -> bool {
+        let removed = self.overlays.remove(name);
+        if removed {
+            self.compose_penalty();
+        }
+        removed
+    }
+
+    /// Fija la hora con la que se eligen las capas de `add_cost_overlay`
+    /// activas. Los costes solo se recomponen si cambia alguna. Con el reloj
+    /// de `set_sim_clock` activo la hora es suya y esto es una anomalía; en
+    /// modo tolerante no hace nada y devuelve `false`.
+    pub fn set_time(&mut self, t: f32) -> Result<bool, JsError> {
+        if self.sim_clock.is_some() {
+            policy::anomaly("set_time con el reloj de simulación activo")?;
+            return Ok(false);
+        }
+        self.switch_overlays(t);
+        Ok(true)
+    }
+
+    /// Activa el reloj de paso fijo de la simulación: cada paso de `advance`
+    /// dura `step` unidades de la hora de `set_time` y el paso 0 es la hora
+    /// `origin`. Así la propagación del peligro, la atenuación de la
+    /// congestión y las capas programadas avanzan al ritmo de la simulación
+    /// del juego y, con los mismos pasos, dan siempre lo mismo. El reloj se
+    /// guarda con `save_scenario`. Un `step` que no sea finito y positivo o
+    /// un `origin` no finito es una anomalía; en modo tolerante el reloj no
+    /// cambia y devuelve `false`.
+    pub fn set_sim_clock(&mut self, step: f32, origin: f32) -> Result<bool, JsError> {
+        let clock = clock::SimClock {
+            tick: 0,
+            step,
+            origin,
+        };
+        if !clock.is_valid() {
+            policy::anomaly("reloj de simulación con paso u origen no válidos")?;
+            return Ok(false);
+        }
+        self.sim_clock = Some(clock);
+        self.switch_overlays(origin);
+        Ok(true)
+    }
+
+    pub fn clear_sim_clock(&mut self) {
+        self.sim_clock = None;
+    }
+
+    /// Paso actual del reloj de la simulación (`None` si no está activo).
+    pub fn sim_tick(&self) -> Option<u32> {
+        self.sim_clock.map(|c| c.tick)
+    }
+
+    /// Avanza `ticks` pasos el reloj de la simulación: cada paso propaga el
+    /// peligro y atenúa la congestión como `tick`, y al final la hora de las
+    /// capas programadas pasa a la del reloj. Los costes se recomponen una
+    /// sola vez. Sin reloj activo (`set_sim_clock`) o con más de 65536 pasos
+    /// es una anomalía; en modo tolerante, sin reloj no hace nada y devuelve
+    /// `false`, y los pasos se limitan a ese máximo. El reloj no pasa de
+    /// `u32::MAX` pasos y el peligro se detiene con él.
+    pub fn advance(&mut self, ticks: u32) -> Result<bool, JsError> {
+        let Some(clock) = &mut self.sim_clock else {
+            policy::anomaly("advance sin reloj de simulación")?;
+            return Ok(false);
+        };
+        if ticks > clock::MAX_ADVANCE {
+            policy::anomaly("demasiados pasos en un solo advance")?;
+        }
+        let ticks = ticks.min(clock::MAX_ADVANCE).min(u32::MAX - clock.tick);
+        clock.tick += ticks;
+        let time = clock.time();
+        for _ in 0..ticks {
+            if let Some(hazard) = &mut self.hazard {
+                hazard.tick(&self.grid);
+            }
+            if let Some(congestion) = &mut self.congestion {
+                congestion.tick();
+            }
+        }
+        self.overlays.set_time(time);
+        self.compose_penalty();
+        Ok(true)
+    }
+
+    /// Nombres de las capas de coste activas a la hora actual.
+    pub fn active_overlays(&self) -> Vec<String> {
+        self.overlays.active().map(|o| o.name.clone()).collect()
+    }
+
+    /// Celdas que están en *alguna* ruta más corta de `(0,0)` a `(n-1,n-1)`
+    /// (distancia desde el inicio + distancia al destino = óptimo), como
+    /// `[x0, y0, x1, y1, ...]` en orden de índice. Vacío si no hay camino.
+    pub fn shortest_path_corridor(&self) -> Vec<usize> {
+        let goal = (self.grid.height - 1, self.grid.width - 1);
+        let (forward, _) = distance::bfs_distances(&self.grid, &[(0, 0)], u32::MAX);
+        let (backward, _) = distance::bfs_distances(&self.grid, &[goal], u32::MAX);
+        let best = forward[self.grid.idx(goal)];
+        if best == distance::UNREACHED {
+            return vec![];
+        }
+        let corridor: Vec<usize> = (0..self.grid.cells.len())
+            .filter(|&i| forward[i] != distance::UNREACHED && backward[i] != distance::UNREACHED)
+            .filter(|&i| forward[i] + backward[i] == best)
+            .collect();
+        self.flatten(&corridor)
+    }
+
+    /// Telemetría acumulada de la instancia como JSON: consultas ejecutadas,
+    /// tasa de aciertos de caché, expansiones medias y percentil 95 del tiempo
+    /// por consulta en milisegundos.
+    pub fn metrics_json(&self) -> String {
+        self.metrics.to_json()
+    }
+
+    /// Coste de la ruta actual (número de pasos en el BFS por defecto).
+    pub fn cost(&self) -> f64 {
+        self.cost
+    }
+
+    /// Ruta de coste mínimo de `(sx, sy)` a `(gx, gy)` con un coste entero
+    /// por celda (`Uint16Array`): lo que cuesta entrar en ella. El valor
+    /// 65535 (`0xFFFF`) marca la celda como intransitable, igual que una
+    /// pared del grid. Los costes se suman en 64 bits: el valor exacto sale
+    /// en `integer_cost` y `cost()` lo da como número. Si `costs` no tiene
+    /// una entrada por celda es una anomalía; en modo tolerante devuelve
+    /// `false`.
+    pub fn search_costs_u16(
+        &mut self,
+        costs: &[u16],
+        sx: usize,
+        sy: usize,
+        gx: usize,
+        gy: usize,
+    ) -> Result<bool, JsError> {
+        let cost = |i: usize| (costs[i] != u16::MAX).then(|| u64::from(costs[i]));
+        Ok(self.search_integer_costs(costs.len(), (sx, sy), (gx, gy), cost)?)
+    }
+
+    /// Como `search_costs_u16` con costes de 32 bits (`Uint32Array`); el
+    /// centinela de intransitable es 4294967295 (`0xFFFFFFFF`).
+    pub fn search_costs_u32(
+        &mut self,
+        costs: &[u32],
+        sx: usize,
+        sy: usize,
+        gx: usize,
+        gy: usize,
+    ) -> Result<bool, JsError> {
+        let cost = |i: usize| (costs[i] != u32::MAX).then(|| u64::from(costs[i]));
+        Ok(self.search_integer_costs(costs.len(), (sx, sy), (gx, gy), cost)?)
+    }
+
+    /// Como `search_costs_u16` con costes reales (`Float32Array`), p. ej.
+    /// sacados de probabilidades o de un modelo físico. NaN e infinito
+    /// marcan la celda como intransitable; un coste negativo es una anomalía
+    /// y en modo tolerante cuenta como 0. Dos caminos cuyo coste difiere en
+    /// menos de 1e-6 se consideran empatados y se desempata por orden de
+    /// exploración, así que el resultado no depende del redondeo.
+    pub fn search_costs_f32(
+        &mut self,
+        costs: &[f32],
+        sx: usize,
+        sy: usize,
+        gx: usize,
+        gy: usize,
+    ) -> Result<bool, JsError> {
+        if costs.len() != self.grid.cells.len() {
+            policy::anomaly("la capa de costes tiene otro tamaño")?;
+            return Ok(false);
+        }
+        if floatcost::has_negative(costs) {
+            policy::anomaly("coste negativo en la capa de costes")?;
+        }
+        let started = clock::now_ms();
+        let (found, expanded) = floatcost::cheapest_path(&self.grid, costs, (sx, sy), (gx, gy));
+        let total = found.as_ref().map_or(0.0, |&(_, total)| total);
+        let found = self.store_bfs((found.map(|(path, _)| path), expanded), started);
+        let mut g = 0.0;
+        self.path_costs = self
+            .path
+            .iter()
+            .enumerate()
+            .map(|(i, &cell)| {
+                if i > 0 {
+                    g += floatcost::entry_cost(costs[self.grid.idx(cell)]).unwrap_or(0.0);
+                }
+                g
+            })
+            .collect();
+        self.cost = total;
+        self.path_kind = PathKind::CostLayer;
+        Ok(found)
+    }
+
+    /// Coste exacto de la última búsqueda con costes enteros (0 tras
+    /// cualquier otra búsqueda o si no hubo ruta).
+    pub fn integer_cost(&self) -> u64 {
+        self.integer_cost
+    }
+
+    pub fn has_path(&self) -> bool {
+        !self.path.is_empty()
+    }
+    pub fn path(&self) -> Vec<usize> {
+        self.path.iter().flat_map(|(x, y)| vec![*x, *y]).collect()
+    }
+
+    /// Orientación del agente en cada celda de la ruta (índice de 0 a 3:
+    /// abajo, derecha, arriba, izquierda), la del paso con el que llega a
+    /// ella. En la primera celda es la orientación inicial de las opciones
+    /// o, si no la hay, la del primer paso.
+    pub fn path_facing(&self) -> Vec<u8> {
+        let steps: Vec<u8> = self
+            .path
+            .windows(2)
+            .filter_map(|w| grid::direction(w[0], w[1]))
+            .map(|d| d as u8)
+            .collect();
+        let initial = self.options.as_ref().and_then(|o| o.heading);
+        let Some(first) = initial.or(steps.first().copied()) else {
+            return vec![0; self.path.len()];
+        };
+        std::iter::once(first).chain(steps).collect()
+    }
+
+    /// Compara con el coste real restante, celda a celda de la ruta actual,
+    /// la heurística `heuristic`: "manhattan" (la de `search_with`),
+    /// "octile" o "landmarks" (requiere `build_landmarks` sobre este grid).
+    pub fn heuristic_report(&self, heuristic: &str) -> Result<HeuristicReport, JsError> {
+        let opts = self.options.clone().unwrap_or_default();
+        let scale = cost::StepCost::new(&self.grid, &opts).min_step();
+        let goal = self.path.last().copied().unwrap_or_default();
+        let estimate: Box<dyn Fn((usize, usize)) -> f64> = match heuristic {
+            "manhattan" => {
+                Box::new(|(x, y)| scale * (x.abs_diff(goal.0) + y.abs_diff(goal.1)) as f64)
+            }
+            "octile" => Box::new(|(x, y)| {
+                let (dx, dy) = (x.abs_diff(goal.0) as f64, y.abs_diff(goal.1) as f64);
+                scale * (dx.max(dy) + (std::f64::consts::SQRT_2 - 1.0) * dx.min(dy))
+            }),
+            "landmarks" => {
+                let lm = self
+                    .landmarks
+                    .as_ref()
+                    .filter(|lm| lm.valid_for(&self.grid))
+                    .ok_or_else(|| JsError::new("no hay landmarks para este grid"))?;
+                let goal = self.grid.idx(goal);
+                Box::new(move |cell| lm.lower_bound(self.grid.idx(cell), goal))
+            }
+            _ => return Err(JsError::new("heurística desconocida")),
+        };
+        let remaining = self.remaining_costs(&opts);
+        Ok(HeuristicReport::new(&remaining, |i| estimate(self.path[i])))
+    }
+
+    /// Como `heuristic_report` con una heurística de JS, llamada como
+    /// `estimate(celda, destino)` con celdas como índice `x * width + y`.
+    /// Un valor no numérico cuenta como 0.
+    pub fn heuristic_report_with(&self, estimate: &js_sys::Function) -> HeuristicReport {
+        let opts = self.options.clone().unwrap_or_default();
+        let goal = self
+            .path
+            .last()
+            .map(|&g| JsValue::from(self.grid.idx(g) as u32));
+        let remaining = self.remaining_costs(&opts);
+        HeuristicReport::new(&remaining, |i| {
+            let cell = JsValue::from(self.grid.idx(self.path[i]) as u32);
+            estimate
+                .call2(
+                    &JsValue::NULL,
+                    &cell,
+                    goal.as_ref().unwrap_or(&JsValue::NULL),
+                )
+                .ok()
+                .and_then(|v| v.as_f64())
+                .unwrap_or(0.0)
+        })
+    }
+
+    /// Tras mover al agente fuera de la ruta (empujón, teletransporte),
+    /// comprueba si el resto de la ruta actual sirve desde `(x, y)`: si la
+    /// celda está en la ruta, o al lado de ella y es transitable, la ruta se
+    /// recorta para empezar ahí (en el punto más avanzado posible) y el coste
+    /// pasa a ser el del tramo que queda. Si no, o si lo que queda ya no es
+    /// transitable con el grid actual, la ruta no cambia, se marca
+    /// `PathResult::needs_replan` y devuelve `false`.
+    pub fn reanchor(&mut self, x: usize, y: usize) -> bool {
+        let Some((i, joined)) = self.anchor((x, y)) else {
+            self.status.needs_replan = true;
+            return false;
+        };
+        // Coste del paso que se añade para volver a la ruta (`anchor` ya ha
+        // comprobado que se puede dar).
+        let join = match &self.options {
+            _ if !joined => 0.0,
+            Some(opts) => cost::StepCost::new(&self.grid, opts)
+                .step((x, y), self.path[i])
+                .unwrap_or(0.0),
+            None => 1.0,
+        };
+        self.path.drain(..i);
+        self.path_costs.drain(..i);
+        let base = self.path_costs[0] - join;
+        if joined {
+            self.path.insert(0, (x, y));
+            self.path_costs.insert(0, base);
+        }
+        self.path_costs.iter_mut().for_each(|g| *g -= base);
+        self.cost = self.path_costs[self.path_costs.len() - 1];
+        self.status.needs_replan = false;
+        if joined {
+            self.mark_suboptimal();
+        }
+        true
+    }
+
+    /// Ruta, coste y estado de la última búsqueda en un solo objeto.
+    pub fn result(&self) -> PathResult {
+        let steps = self.path_costs.windows(2).map(|w| w[1] - w[0]).collect();
+        PathResult::new(self.status, self.cost, self.path.clone(), steps)
+    }
+
+    /// Ruta suavizada con Catmull-Rom como polilínea plana `[x0, y0, x1, y1, ...]`.
+    /// Las muestras que caen en un obstáculo se reemplazan por el punto del
+    /// segmento recto entre los dos waypoints, que siempre es transitable.
+    pub fn smooth_spline(&self, samples_per_segment: usize) -> Vec<f32> {
+        smoothing::catmull_rom(&self.path, samples_per_segment, |x, y| {
+            self.grid.walkable((x, y))
+        })
+    }
+
+    /// Imagen RGBA (`width * height * 4` bytes, lista para `ImageData`) con las
+    /// celdas expandidas en la última búsqueda pintadas con `rgba_for_visited`
+    /// y la ruta con `rgba_for_path`. Los colores van empaquetados como
+    /// `0xRRGGBBAA`; el resto de celdas queda transparente.
+    pub fn visited_image(&self, rgba_for_visited: u32, rgba_for_path: u32) -> Vec<u8> {
+        let mut image = vec![0u8; self.grid.cells.len() * 4];
+        let mut paint = |idx: usize, rgba: u32| {
+            image[idx * 4..idx * 4 + 4].copy_from_slice(&rgba.to_be_bytes());
+        };
+        for &idx in &self.expanded {
+            paint(idx, rgba_for_visited);
+        }
+        for &cell in &self.path {
+            paint(self.grid.idx(cell), rgba_for_path);
+        }
+        image
+    }
+
+    /// Indicaciones de la ruta actual ("sal hacia el este 12 casillas, gira
+    /// al norte en el cruce, ...") como `[código, rumbo, casillas, x, y,
+    /// cruce, ...]`, seis valores por indicación. Los códigos son 0 salida,
+    /// 1 giro a la izquierda, 2 giro a la derecha, 3 media vuelta y 4
+    /// llegada; el rumbo es el índice de 0 a 3 (sur, este, norte, oeste) tras
+    /// la maniobra, `(x, y)` la celda donde se hace y `cruce` 1 si tiene tres
+    /// o más vecinos transitables. Con ellos cada aplicación compone sus
+    /// propios textos traducidos.
+    pub fn instructions(&self) -> Vec<usize> {
+        narration::instructions(&self.grid, &self.path)
+            .iter()
+            .flat_map(|s| {
+                let (x, y) = s.at;
+                [s.code, s.heading, s.cells, x, y, usize::from(s.junction)]
+            })
+            .collect()
+    }
+
+    /// Las indicaciones de `instructions` redactadas en español.
+    pub fn instruction_texts(&self) -> Vec<String> {
+        narration::instructions(&self.grid, &self.path)
+            .iter()
+            .map(narration::describe)
+            .collect()
+    }
+
+    /// Grid y ruta actual como texto con medios bloques Unicode (dos filas
+    /// por carácter) y la ruta como `•`, para echar un vistazo a mapas
+    /// grandes en una terminal.
+    pub fn render_half_blocks(&self) -> String {
+        textmap::half_blocks(&self.grid, &self.path)
+    }
+
+    /// Como `render_half_blocks` pero en Braille (4 x 2 celdas por carácter),
+    /// aún más compacto.
+    pub fn render_braille(&self) -> String {
+        textmap::braille(&self.grid, &self.path)
+    }
+
+    /// Minimapa RGBA (lista para `ImageData`) del grid reducido `scale` veces
+    /// por lado, de `ceil(width / scale)` x `ceil(height / scale)` píxeles:
+    /// paredes, espacio libre, celdas expandidas en la última búsqueda y la
+    /// ruta actual, en una sola llamada. Con `scale` 0 se usa 1.
+    pub fn render_minimap(&self, scale: usize) -> Vec<u8> {
+        minimap::render(&self.grid, &self.expanded, &self.path, scale)
+    }
+
+    /// Agrupa las expansiones de la última búsqueda en fotogramas de
+    /// `cells_per_frame` celdas para reproducir la animación.
+    pub fn set_cells_per_frame(&mut self, cells_per_frame: usize) {
+        self.cells_per_frame = cells_per_frame.max(1);
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.expanded.len().div_ceil(self.cells_per_frame)
+    }
+
+    /// Celdas reveladas en el fotograma `i` como `[x0, y0, x1, y1, ...]`;
+    /// vacío si `i` está fuera de rango.
+    pub fn frame(&self, i: usize) -> Vec<usize> {
+        self.expanded
+            .chunks(self.cells_per_frame)
+            .nth(i)
+            .map_or_else(Vec::new, |cells| self.flatten(cells))
+    }
+
+    /// Celdas que difieren entre el grid actual y `other_grid`, como
+    /// `[x0, y0, valor0, x1, y1, valor1, ...]` con el valor de `other_grid`.
+    pub fn diff(&self, other_grid: &[u8]) -> Result<Vec<usize>, JsError> {
+        let changes = self
+            .grid
+            .diff(other_grid)
+            .ok_or_else(|| JsError::new("el grid a comparar tiene otro tamaño"))?;
+        Ok(changes
+            .into_iter()
+            .flat_map(|(idx, v)| {
+                let (x, y) = self.grid.coords(idx);
+                [x, y, usize::from(v)]
+            })
+            .collect())
+    }
+
+    /// Compara la ruta actual con `previous_path` (`[x0, y0, x1, y1, ...]`) para
+    /// empalmar una ruta replanificada a mitad de camino sin saltos.
+    pub fn path_diff(&self, previous_path: &[usize]) -> PathDiff {
+        pathdiff::diff_paths(&pairs(previous_path), &self.path)
+    }
+
+    /// Puntúa una ruta cualquiera (`[x0, y0, x1, y1, ...]`, p. ej. dibujada
+    /// por el usuario) por longitud, peligro (la capa de
+    /// `set_hazard_rules`), giros y holgura, combinados con `weights` =
+    /// `[longitud, peligro, giros, holgura]` en `RouteScore::total`, para
+    /// compararla con la del planificador. Falla si la ruta está vacía, no es
+    /// continua o pisa una pared, o si no hay cuatro pesos.
+    pub fn score_route(&self, path: &[usize], weights: &[f64]) -> Result<RouteScore, JsError> {
+        let weights: [f64; 4] = weights
+            .try_into()
+            .map_err(|_| JsError::new("hacen falta cuatro pesos"))?;
+        let path = pairs(path);
+        match verify::verify(&self.grid, &path) {
+            PathVerdict::Valid | PathVerdict::NotShortest => {}
+            _ => return Err(JsError::new("ruta inválida")),
+        }
+        let danger = self.hazard.as_ref().map(|h| &h.values[..]);
+        Ok(scoring::score(&self.grid, &path, danger, weights))
+    }
+
+    /// Reparte los destinos `goals` entre los agentes `agents` (ambos como
+    /// `[x0, y0, x1, y1, ...]`) con los costes de `options`: calcula la
+    /// matriz de costes agente-destino con un Dijkstra por agente (con las
+    /// mismas opciones que `search_between`, ver `search::goal_costs`), la
+    /// resuelve según `objective` y busca la ruta de cada agente a su
+    /// destino. Cada destino va a un agente como mucho; los que sobren (o no
+    /// alcancen ninguno) se quedan sin destino. No cambia la ruta actual.
+    pub fn allocate_tasks(
+        &mut self,
+        options: &SearchOptions,
+        agents: &[usize],
+        goals: &[usize],
+        objective: AllocationObjective,
+    ) -> Allocation {
+        let options = &self.seeded(options);
+        let agents: Vec<(usize, usize)> = pairs(agents);
+        let goals: Vec<(usize, usize)> = pairs(goals);
+        let matrix: Vec<Vec<f64>> = agents
+            .iter()
+            .map(|&agent| {
+                let costs = search::goal_costs(&self.grid, agent, &goals, options);
+                costs
+                    .into_iter()
+                    .map(|c| c.unwrap_or(f64::INFINITY))
+                    .collect()
+            })
+            .collect();
+        let chosen = allocation::assign(&matrix, objective);
+        let (costs, paths) = agents
+            .iter()
+            .zip(&chosen)
+            .map(|(&agent, goal)| {
+                let found = goal
+                    .and_then(|g| search::grid_search(&self.grid, agent, goals[g], options).result);
+                found.map_or((f64::NAN, vec![]), |f| (f.cost, f.states))
+            })
+            .unzip();
+        Allocation::new(chosen, costs, paths)
+    }
+
+    /// Planifica un grupo en formación de `(sx, sy)` a `(gx, gy)`: el líder
+    /// sigue la ruta de `options` y cada miembro mantiene su desplazamiento
+    /// de `offsets` (`[dx0, dy0, dx1, dy1, ...]` en filas y columnas respecto
+    /// al líder) mientras quepa; en los pasos estrechos el grupo pasa en fila
+    /// india. Sin ruta del líder devuelve una formación vacía. No cambia la
+    /// ruta actual.
+    pub fn search_formation(
+        &mut self,
+        options: &SearchOptions,
+        sx: usize,
+        sy: usize,
+        gx: usize,
+        gy: usize,
+        offsets: &[i32],
+    ) -> Formation {
+        let options = &self.seeded(options);
+        let offsets: Vec<(isize, isize)> = offsets
+            .chunks_exact(2)
+            .map(|c| (c[0] as isize, c[1] as isize))
+            .collect();
+        let leader = search::grid_search(&self.grid, (sx, sy), (gx, gy), options).result;
+        leader.map_or_else(Formation::default, |found| {
+            formation::plan(&self.grid, &found.states, &offsets)
+        })
+    }
+
+    /// Rutas no dominadas entre coste (con `options`) y peligro, con hasta
+    /// `limit` rutas, para que el usuario elija el compromiso. `danger` da un
+    /// valor por celda que se suma al entrar en ella.
+    pub fn pareto_paths(
+        &mut self,
+        options: &SearchOptions,
+        danger: Vec<f32>,
+        limit: usize,
+    ) -> Result<ParetoFront, JsError> {
+        let options = &self.seeded(options);
+        if danger.len() != self.grid.cells.len() {
+            return Err(JsError::new("la capa de peligro tiene otro tamaño"));
+        }
+        let goal = (self.grid.height - 1, self.grid.width - 1);
+        Ok(pareto::pareto_search(
+            &self.grid,
+            (0, 0),
+            goal,
+            options,
+            &danger,
+            limit,
+        ))
+    }
+
+    /// Ejecuta varios algoritmos sobre la misma consulta de `(sx, sy)` a
+    /// `(gx, gy)` y devuelve ruta, coste, expansiones y tiempo de cada uno,
+    /// para comparativas lado a lado. Nombres válidos: "bfs", "dijkstra",
+    /// "astar", "greedy", "01bfs" (dos costes por celda, sin montículo) y
+    /// "rectangles"; todos salvo el primero y el último usan los costes de
+    /// `options`. No cambia la ruta actual.
+    pub fn compare(
+        &mut self,
+        algorithms: Vec<String>,
+        options: &SearchOptions,
+        sx: usize,
+        sy: usize,
+        gx: usize,
+        gy: usize,
+    ) -> Result<Comparison, JsError> {
+        let options = &self.seeded(options);
+        compare::compare(&self.grid, &algorithms, options, (sx, sy), (gx, gy))
+            .map_err(|e| JsError::new(&e))
+    }
+
+    /// Como `search_with` pero eligiendo el algoritmo según la consulta
+    /// (tamaño del grid, densidad de paredes, si hay costes y distancia entre
+    /// extremos): BFS, A*, la BFS 0-1 si solo hay dos costes por celda o la
+    /// búsqueda por rectángulos. El elegido sale en
+    /// `PathResult::algorithm`. Todas son exactas, así que el coste es el de
+    /// `search_with`; la relajación del destino no se aplica.
+    pub fn search_auto(&mut self, options: &SearchOptions) -> Result<bool, JsError> {
+        let goal = (self.grid.height - 1, self.grid.width - 1);
+        self.check_endpoints((0, 0), goal, options)?;
+        let options = &self.seeded(options);
+        self.prepare_search();
+        let started = clock::now_ms();
+        let algorithm = compare::choose(&self.grid, options, (0, 0), goal);
+        let found = compare::run(&self.grid, algorithm, options, (0, 0), goal)
+            .unwrap_or_else(search::Outcome::none);
+        let found = self.apply(found, options, started);
+        self.status.algorithm = algorithm;
+        Ok(found)
+    }
+
+    /// Exporta el grafo de transitabilidad en CSR con los costes de `options`.
+    pub fn export_csr(&mut self, options: &SearchOptions) -> CsrGraph {
+        let options = &self.seeded(options);
+        CsrGraph::from_grid(&self.grid, options)
+    }
+
+    /// Árbol de la última búsqueda en formato DOT de Graphviz (celdas
+    /// expandidas con su `g` y su prioridad, aristas al padre y la ruta
+    /// resaltada) para inspeccionarlo fuera de línea. Las búsquedas sin
+    /// costes o sin `SearchOptions::set_annotate` solo listan los nodos.
+    pub fn to_dot(&self) -> String {
+        dot::search_tree(&self.grid, &self.expanded, &self.steps, &self.path)
+    }
+
+    /// Por qué se eligió cada expansión de la última búsqueda con costes
+    /// (`g`, `h`, `f` y desempates), para recorrerla paso a paso. Vacío tras
+    /// búsquedas sin costes o sin `SearchOptions::set_annotate`.
+    pub fn explain(&self) -> ExpansionLog {
+        ExpansionLog::new(&self.grid, &self.expanded, &self.steps)
+    }
+
+    /// Frontera que dejó la última búsqueda con costes, en el orden en que
+    /// se habría seguido expandiendo, como `[x0, y0, f0, g0, h0, ...]`. Solo
+    /// con la feature `debug`; sirve para ajustar heurísticas viendo la forma
+    /// real de la frontera.
+    #[cfg(feature = "debug")]
+    pub fn open_list(&self) -> Vec<f64> {
+        self.open
+            .iter()
+            .flat_map(|&(idx, step)| {
+                let (x, y) = self.grid.coords(idx);
+                let (f, g) = (step.priority, step.g);
+                [x as f64, y as f64, f, g, f - g]
+            })
+            .collect()
+    }
+
+    /// Registro binario de la última búsqueda (grid, opciones y cada
+    /// expansión) para adjuntar a un reporte de error.
+    pub fn record(&self) -> Vec<u8> {
+        replay::encode(&replay::SearchLog {
+            grid: self.grid.clone(),
+            options: self.options.clone(),
+            expanded: self.expanded.clone(),
+        })
+    }
+
+    /// Reconstruye el buscador a partir de un registro de `record()`, repite la
+    /// búsqueda y comprueba que las expansiones coinciden una a una.
+    pub fn replay(log: &[u8]) -> Result<PathFinder, JsError> {
+        let log = replay::decode(log).ok_or_else(|| JsError::new("registro inválido"))?;
+        if log.grid.cells.is_empty() {
+            return Err(JsError::new("registro inválido: grid vacío"));
+        }
+
+        let mut pf = PathFinder::from_grid(log.grid);
+        if let Some(options) = &log.options {
+            pf.search_with(options)?;
+        }
+
+        let (ours, theirs) = (&pf.expanded, &log.expanded);
+        let diverged = ours
+            .iter()
+            .zip(theirs)
+            .position(|(a, b)| a != b)
+            .or_else(|| (ours.len() != theirs.len()).then(|| ours.len().min(theirs.len())));
+        match diverged {
+            Some(i) => Err(JsError::new(&format!(
+                "la búsqueda diverge en la expansión {i}"
+            ))),
+            None => Ok(pf),
+        }
+    }
+
+    /// Consultas de inicio y destino que acompañan al escenario, como
+    /// `[sx0, sy0, gx0, gy0, ...]`; las que se salen del grid se ignoran.
+    /// El buscador no las usa: viajan con `save_scenario`.
+    pub fn set_queries(&mut self, queries: &[usize]) {
+        self.queries = queries
+            .chunks_exact(4)
+            .map(|q| ((q[0], q[1]), (q[2], q[3])))
+            .filter(|&(start, goal)| self.grid.in_bounds(start) && self.grid.in_bounds(goal))
+            .collect();
+    }
+
+    pub fn queries(&self) -> Vec<usize> {
+        self.queries
+            .iter()
+            .flat_map(|&((sx, sy), (gx, gy))| [sx, sy, gx, gy])
+            .collect()
+    }
+
+    /// Escenario completo en un único bloque binario versionado: grid y
+    /// capas, opciones de la última búsqueda, `queries`, niebla, puertas y
+    /// llaves, obstáculos móviles y penalizaciones dinámicas. Sirve de
+    /// reproducción de un fallo o de fichero de nivel.
+    pub fn save_scenario(&self) -> Vec<u8> {
+        scenario::encode(&scenario::Scenario {
+            grid: self.grid.clone(),
+            options: self.options.clone(),
+            queries: self.queries.clone(),
+            fog: self.fog.clone(),
+            locks: self.locks.clone(),
+            moving: self.moving.clone(),
+            reuse: self.reuse.clone(),
+            hazard: self.hazard.clone(),
+            congestion: self.congestion.clone(),
+            overlays: self.overlays.clone(),
+            sim_clock: self.sim_clock,
+        })
+    }
+
+    /// Buscador a partir de un bloque de `save_scenario`. Si se guardó tras
+    /// una búsqueda con opciones, se repite sobre lo cargado; el calor de
+    /// reutilización queda como estaba al guardar.
+    pub fn load_scenario(bytes: &[u8]) -> Result<PathFinder, JsError> {
+        let s = scenario::decode(bytes).ok_or_else(|| JsError::new("escenario inválido"))?;
+        if s.grid.cells.is_empty() {
+            return Err(JsError::new("escenario inválido: grid vacío"));
+        }
+        let mut pf = PathFinder::from_grid(s.grid);
+        pf.queries = s.queries;
+        pf.fog = s.fog;
+        pf.locks = s.locks;
+        pf.moving = s.moving;
+        pf.reuse = s.reuse.clone();
+        pf.hazard = s.hazard;
+        pf.congestion = s.congestion;
+        pf.overlays = s.overlays;
+        pf.sim_clock = s.sim_clock;
+        if let Some(options) = &s.options {
+            pf.search_with(options)?;
+            pf.reuse = s.reuse;
+        }
+        Ok(pf)
+    }
+
+    /// Buscador sobre un plano escaneado para prototipos de navegación en
+    /// interiores: `rgba` es la imagen (`ImageData.data`, un píxel por celda)
+    /// y `options` decide el umbral, la limpieza de motas y el inflado.
+    pub fn from_floorplan(rgba: &[u8], options: &FloorplanOptions) -> Result<PathFinder, JsError> {
+        floorplan::preprocess(rgba, options)
+            .map(PathFinder::from_grid)
+            .ok_or_else(|| JsError::new("la imagen no tiene filas completas del ancho indicado"))
+    }
+
+    /// Buscador a partir de un costmap de otro planificador: cabecera con
+    /// ancho y alto (`u32`) y tamaño de celda (`f32`, ver
+    /// `costmap_cell_size`) en little-endian, y un byte de coste por celda
+    /// fila a fila (`idx = x * width + y`). Los costes desde `lethal` son
+    /// paredes y el resto queda como la capa de coste "costmap" con
+    /// `coste * scale` (ver `add_cost_overlay`).
+    pub fn from_costmap(bytes: &[u8], lethal: u8, scale: f32) -> Result<PathFinder, JsError> {
+        let (grid, costs) = costmap::decode(bytes, lethal, scale).map_err(JsError::new)?;
+        let mut pf = PathFinder::from_grid(grid);
+        if costs.iter().any(|&c| c > 0.0) {
+            pf.add_cost_overlay("costmap", costs, &[])?;
+        }
+        Ok(pf)
+    }
+
+    /// Exporta el grid en el formato de `from_costmap` con celdas de
+    /// `cell_size`: las paredes valen 254 y las celdas libres su
+    /// penalización actual (la suma de las capas activas) entre `scale`,
+    /// hasta 253.
+    pub fn to_costmap(&self, cell_size: f32, scale: f32) -> Vec<u8> {
+        costmap::encode(&self.grid, cell_size, scale)
+    }
+
+    /// Registra un obstáculo móvil que recorre `path` (`[x0, y0, x1, y1, ...]`)
+    /// a `speed` celdas por paso de tiempo. Una ruta vacía o una velocidad
+    /// que no es un número positivo es una anomalía (ver `set_policy`) y en
+    /// modo tolerante se ignora.
+    pub fn add_moving_obstacle(&mut self, path: Vec<usize>, speed: f32) -> Result<(), JsError> {
+        match MovingObstacle::new(pairs(&path), speed) {
+            Some(obstacle) => self.moving.push(obstacle),
+            None => policy::anomaly("obstáculo móvil sin ruta o con velocidad no válida")?,
+        }
+        Ok(())
+    }
+
+    pub fn clear_moving_obstacles(&mut self) {
+        self.moving.clear();
+    }
+
+    /// Ruta de `(0,0)` a `(n-1,n-1)` evitando los obstáculos móviles, como
+    /// `[x0, y0, t0, x1, y1, t1, ...]`. Las esperas repiten la celda.
+    pub fn timed_path(&self) -> Vec<usize> {
+        let goal = (self.grid.height - 1, self.grid.width - 1);
+        spacetime::timed_search(&self.grid, (0, 0), goal, &self.moving)
+            .into_iter()
+            .flat_map(|(x, y, t)| [x, y, t])
+            .collect()
+    }
+
+    /// Huella estable de 64 bits del grid (dimensiones, celdas y capas de
+    /// elevación, penalización y vida) para usarla como clave de caché o
+    /// detectar que cliente y servidor no tienen el mismo mapa.
+    pub fn grid_hash(&self) -> u64 {
+        self.grid.content_hash()
+    }
+
+    /// Como `grid_hash` pero incluyendo además las opciones de búsqueda, tal
+    /// como vienen: con `set_noise_from_rng` no se saca ninguna semilla del
+    /// generador.
+    pub fn grid_hash_with(&self, options: &SearchOptions) -> u64 {
+        let hash = self.grid.content_hash();
+        let mut w = bytes::Writer::new();
+        w.u32(hash as u32);
+        w.u32((hash >> 32) as u32);
+        options.write_to(&mut w);
+        bytes::fnv1a(w.finish())
+    }
+
+    /// Antitrampas: comprueba que la ruta `[x0, y0, ...]` enviada por un
+    /// cliente con el mapa de huella `grid_hash` es continua, transitable y
+    /// de longitud mínima en este grid. Da el mismo veredicto en el build
+    /// nativo del servidor y en el wasm del cliente.
+    pub fn verify_path_against(&self, grid_hash: u64, path: &[usize]) -> PathVerdict {
+        if grid_hash != self.grid_hash() {
+            return PathVerdict::GridMismatch;
+        }
+        verify::verify(&self.grid, &pairs(path))
+    }
+
+    /// Aserción en tiempo de ejecución para validar opciones nuevas: vuelve
+    /// a resolver la última consulta (mismos extremos y opciones, grid
+    /// actual) con una referencia sencilla, Dijkstra o BFS si la búsqueda
+    /// fue sin costes, y falla si la ruta actual no tiene su coste. Sin ruta
+    /// no hay nada que comprobar. Las búsquedas que resuelven otro problema
+    /// (por capas, con llaves, atravesando paredes, por puntos de paso,
+    /// evitando amenazas, con memoria o con una capa de costes propia) no
+    /// tienen referencia y dan error.
+    pub fn verify_optimal(&self) -> Result<(), JsError> {
+        self.check_optimal().map_err(|e| JsError::new(&e))
+    }
+
+    /// Generación actual del grid: sube cada vez que, al consultarla, las
+    /// celdas han cambiado desde la consulta anterior. Sin ediciones entre
+    /// consultas no recorre el grid. Sirve de referencia para
+    /// `snapshot_delta`.
+    pub fn generation(&mut self) -> u32 {
+        self.changes.record(&self.grid.cells, self.revision);
+        self.changes.generation
+    }
+
+    /// Celdas cambiadas desde la generación `since_generation`, como
+    /// `[x0, y0, valor0, x1, y1, valor1, ...]` (mismo formato que `diff`),
+    /// para difundir a los clientes solo lo que ha cambiado en el mapa.
+    pub fn snapshot_delta(&mut self, since_generation: u32) -> Vec<usize> {
+        self.changes.record(&self.grid.cells, self.revision);
+        self.changes
+            .since(since_generation)
+            .into_iter()
+            .flat_map(|idx| {
+                let (x, y) = self.grid.coords(idx);
+                [x, y, usize::from(self.grid.cells[idx])]
+            })
+            .collect()
+    }
+
+    /// Código de autotile de cada celda para renderers con spritesheets por
+    /// vecindad: el bit `d` (1 abajo, 2 derecha, 4 arriba, 8 izquierda) indica
+    /// que ese vecino es pared o cae fuera del grid. Sale de las mismas
+    /// máscaras de vecinos que usan las búsquedas.
+    pub fn autotile_codes(&mut self) -> Vec<u8> {
+        self.adjacency.sync(&self.grid, self.revision);
+        (0..self.grid.cells.len())
+            .map(|idx| self.adjacency.walls(idx))
+            .collect()
+    }
+
+    /// Códigos de autotile que pueden haber cambiado desde la generación
+    /// `since_generation` (ver `generation`): las celdas cambiadas y sus
+    /// vecinas, como `[x0, y0, código0, x1, y1, código1, ...]` en orden de
+    /// índice, para repintar solo esas baldosas.
+    pub fn autotile_delta(&mut self, since_generation: u32) -> Vec<usize> {
+        self.changes.record(&self.grid.cells, self.revision);
+        self.adjacency.sync(&self.grid, self.revision);
+        let mut touched: Vec<usize> = self
+            .changes
+            .since(since_generation)
+            .into_iter()
+            .flat_map(|idx| {
+                let cell = self.grid.coords(idx);
+                std::iter::once(cell).chain(self.grid.neighbors(cell))
+            })
+            .map(|cell| self.grid.idx(cell))
+            .collect();
+        touched.sort_unstable();
+        touched.dedup();
+        touched
+            .into_iter()
+            .flat_map(|idx| {
+                let (x, y) = self.grid.coords(idx);
+                [x, y, usize::from(self.adjacency.walls(idx))]
+            })
+            .collect()
+    }
+
+    /// Cambia la celda `(x, y)` a `value` (1 = transitable). Devuelve si
+    /// existe. Con `track_reachable` activo la región alcanzable se ajusta
+    /// solo en torno a la celda.
+    pub fn set_cell(&mut self, x: usize, y: usize, value: u8) -> bool {
+        if !self.grid.in_bounds((x, y)) {
+            return false;
+        }
+        let idx = self.grid.idx((x, y));
+        self.grid.cells[idx] = value;
+        self.touched();
+        self.adjacency.update(&self.grid, idx, self.revision);
+        if let Some(reach) = &mut self.reach {
+            reach.update(&self.grid, idx, self.revision);
+        }
+        true
+    }
+
+    /// Empieza a mantener la región alcanzable desde `(x, y)` para resaltar
+    /// en vivo las zonas inaccesibles de un editor: cada `set_cell` la
+    /// actualiza tocando solo las celdas afectadas. Los cambios del grid por
+    /// otras vías se aplican en la siguiente consulta.
+    pub fn track_reachable(&mut self, x: usize, y: usize) {
+        self.reach = Some(Reach::new(&self.grid, (x, y), self.revision));
+    }
+
+    pub fn stop_tracking_reachable(&mut self) {
+        self.reach = None;
+    }
+
+    /// Si `(x, y)` está en la región de `track_reachable` (`false` sin
+    /// seguimiento).
+    pub fn is_reachable(&mut self, x: usize, y: usize) -> bool {
+        let (grid, revision) = (&self.grid, self.revision);
+        grid.in_bounds((x, y))
+            && self.reach.as_mut().is_some_and(|reach| {
+                reach.sync(grid, revision);
+                reach.reachable(grid.idx((x, y)))
+            })
+    }
+
+    /// Celdas que entraron o salieron de la región de `track_reachable`
+    /// desde la llamada anterior, como `[x0, y0, alcanzable0, ...]` con 1 o
+    /// 0; la primera vez, todas las alcanzables. Solo se informa del cambio
+    /// neto de cada celda.
+    pub fn reachable_changes(&mut self) -> Vec<usize> {
+        let Some(reach) = &mut self.reach else {
+            return vec![];
+        };
+        reach.sync(&self.grid, self.revision);
+        reach
+            .take_changes()
+            .into_iter()
+            .flat_map(|(idx, now)| {
+                let (x, y) = self.grid.coords(idx);
+                [x, y, usize::from(now)]
+            })
+            .collect()
+    }
+
+    /// Celdas del grid (fila a fila, 1 = transitable).
+    pub fn cells(&self) -> Vec<u8> {
+        self.grid.cells.clone()
+    }
+
+    pub fn width(&self) -> usize {
+        self.grid.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.grid.height
+    }
+
+    /// Nuevo buscador con el grid girado `quarter_turns` veces 90° en sentido
+    /// horario. Las capas de elevación, penalización y vida se transforman
+    /// con él.
+    pub fn rotated(&self, quarter_turns: u32) -> PathFinder {
+        PathFinder::from_grid(transform::rotate(&self.grid, quarter_turns))
+    }
+
+    /// Nuevo buscador con el grid reflejado de arriba abajo (`vertical`) o de
+    /// izquierda a derecha.
+    pub fn mirrored(&self, vertical: bool) -> PathFinder {
+        PathFinder::from_grid(transform::mirror(&self.grid, vertical))
+    }
+
+    /// Nuevo buscador con la ventana de `height` x `width` celdas que empieza
+    /// en `(x, y)`.
+    pub fn cropped(
+        &self,
+        x: usize,
+        y: usize,
+        height: usize,
+        width: usize,
+    ) -> Result<PathFinder, JsError> {
+        transform::crop(&self.grid, (x, y), height, width)
+            .map(PathFinder::from_grid)
+            .ok_or_else(|| JsError::new("la ventana se sale del grid"))
+    }
+
+    /// Nuevo buscador cuyo grid combina estos obstáculos con los de `other`
+    /// (mismo tamaño y formato), p. ej. paredes estáticas ∪ unidades del
+    /// frame actual sin mezclar los arrays en JS.
+    pub fn combined(&self, other: &[u8], op: GridOp) -> Result<PathFinder, JsError> {
+        transform::combine(&self.grid, other, op)
+            .map(PathFinder::from_grid)
+            .ok_or_else(|| JsError::new("el grid a combinar tiene otro tamaño"))
+    }
+
+    /// Nuevo buscador con el contenido desplazado `dx` filas y `dy`
+    /// columnas; lo que entra por los bordes queda bloqueado.
+    pub fn translated(&self, dx: i32, dy: i32) -> PathFinder {
+        PathFinder::from_grid(transform::translate(&self.grid, dx as isize, dy as isize))
+    }
+
+    /// Engorda los obstáculos `n` celdas (vecindad cuadrada) en el propio
+    /// grid y descarta la ruta guardada.
+    pub fn dilate(&mut self, n: usize) {
+        self.reshape(transform::dilate(&self.grid, n));
+    }
+
+    /// Nuevo buscador para planificar con margen de seguridad: las paredes
+    /// crecen un disco de `radius` celdas (el radio del agente), de modo que
+    /// cualquier ruta encontrada deja ese hueco con los obstáculos. Un radio
+    /// mayor que la diagonal del grid cuenta como la diagonal; uno que no es
+    /// finito es un error.
+    pub fn inflate_obstacles(&self, radius: f32) -> Result<PathFinder, JsError> {
+        transform::inflate(&self.grid, radius)
+            .map(PathFinder::from_grid)
+            .ok_or_else(|| JsError::new("el radio de inflado no es finito"))
+    }
+
+    /// Nuevo buscador con el grid reducido `factor` veces (minimapas,
+    /// planificación jerárquica); `policy` decide qué bloques quedan
+    /// bloqueados.
+    pub fn downsample(
+        &self,
+        factor: usize,
+        policy: DownsamplePolicy,
+    ) -> Result<PathFinder, JsError> {
+        transform::downsample(&self.grid, factor, policy)
+            .map(PathFinder::from_grid)
+            .ok_or_else(|| JsError::new("el factor de reducción debe ser al menos 1"))
+    }
+
+    /// Traduce una ruta `[x0, y0, ...]` del grid reducido `factor` veces a
+    /// puntos de paso transitables de este grid, con el mismo formato.
+    pub fn upsample_path(&self, coarse_path: &[usize], factor: usize) -> Vec<usize> {
+        let coarse = pairs(coarse_path);
+        let fine = transform::upsample_path(&self.grid, factor, &coarse);
+        fine.into_iter().flat_map(|(x, y)| [x, y]).collect()
+    }
+
+    /// Adelgaza los obstáculos `n` celdas en el propio grid.
+    pub fn erode(&mut self, n: usize) {
+        self.reshape(transform::erode(&self.grid, n));
+    }
+}
+
+impl PathFinder {
+    /// Construye el buscador sobre un grid ya armado (cuadrado o no) y ejecuta
+    /// el BFS de esquina a esquina.
+    pub(crate) fn from_grid(grid: Grid) -> Self {
+        let started = clock::now_ms();
+        let mut expanded = Vec::new();
+        let path = bfs_traced(&grid.cells, grid.height, grid.width, &mut expanded);
+        let mut metrics = Metrics::default();
+        metrics.record_query(expanded.len(), clock::now_ms() - started);
+        Self {
+            changes: ChangeLog::new(&grid.cells, 0),
+            revision: 0,
+            adjacency: Adjacency::new(&grid, 0),
+            grid,
+            cost: path.len().saturating_sub(1) as f64,
+            integer_cost: 0,
+            path_costs: unit_costs(path.len()),
+            path,
+            options: None,
+            expanded,
+            steps: Vec::new(),
+            #[cfg(feature = "debug")]
+            open: Vec::new(),
+            cells_per_frame: 1,
+            moving: Vec::new(),
+            reuse: None,
+            hazard: None,
+            congestion: None,
+            overlays: Overlays::default(),
+            sim_clock: None,
+            metrics,
+            status: Status::default(),
+            layers: Layers::default(),
+            path_layers: Vec::new(),
+            path_transitions: Vec::new(),
+            path_kind: PathKind::Plain,
+            segment_costs: Vec::new(),
+            blocking: Vec::new(),
+            locks: Locks::default(),
+            fog: None,
+            landmarks: None,
+            rectangles: None,
+            rng: Rng::new(0),
+            cost_memo: CostMemo::default(),
+            reach: None,
+            queries: Vec::new(),
+            requests: RequestQueue::default(),
+        }
+    }
+
+    /// Sustituye el grid manteniendo la configuración del buscador; la ruta
+    /// y las expansiones anteriores dejan de valer.
+    fn reshape(&mut self, grid: Grid) {
+        self.grid = grid;
+        self.touched();
+        self.path.clear();
+        self.path_costs.clear();
+        self.expanded.clear();
+        self.steps.clear();
+        #[cfg(feature = "debug")]
+        self.open.clear();
+        self.cost = 0.0;
+    }
+
+    /// BFS hasta la primera celda que cumple `is_goal`, guardada como
+    /// resultado actual.
+    fn path_to_goal(&mut self, start: (usize, usize), is_goal: impl FnMut(usize) -> bool) -> bool {
+        let started = clock::now_ms();
+        self.adjacency.sync(&self.grid, self.revision);
+        let found = distance::bfs_to_goal(&self.grid, Some(&self.adjacency), start, is_goal);
+        self.store_bfs(found, started)
+    }
+
+    /// Búsqueda de `search_costs_u16` y `search_costs_u32` con `len`
+    /// costes, guardada como resultado actual.
+    fn search_integer_costs(
+        &mut self,
+        len: usize,
+        start: (usize, usize),
+        goal: (usize, usize),
+        cost: impl Fn(usize) -> Option<u64>,
+    ) -> Result<bool, Anomaly> {
+        if len != self.grid.cells.len() {
+            policy::anomaly("la capa de costes tiene otro tamaño")?;
+            return Ok(false);
+        }
+        let started = clock::now_ms();
+        let (found, expanded) = intcost::cheapest_path(&self.grid, start, goal, &cost);
+        let total = found.as_ref().map_or(0, |&(_, total)| total);
+        let found = self.store_bfs((found.map(|(path, _)| path), expanded), started);
+        let mut g = 0u64;
+        self.path_costs = self
+            .path
+            .iter()
+            .enumerate()
+            .map(|(i, &cell)| {
+                if i > 0 {
+                    g = g.saturating_add(cost(self.grid.idx(cell)).unwrap_or(0));
+                }
+                g as f64
+            })
+            .collect();
+        self.cost = total as f64;
+        self.integer_cost = total;
+        self.path_kind = PathKind::CostLayer;
+        Ok(found)
+    }
+
+    /// Guarda como estado actual el resultado de `distance::bfs_to_goal`.
+    fn store_bfs(
+        &mut self,
+        (path, expanded): (Option<Vec<usize>>, Vec<usize>),
+        started: f64,
+    ) -> bool {
+        self.metrics
+            .record_query(expanded.len(), clock::now_ms() - started);
+        self.options = None;
+        self.cost_memo.use_options(&SearchOptions::default());
+        self.status = Status::default();
+        self.path_layers.clear();
+        self.path_transitions.clear();
+        self.path_kind = PathKind::Plain;
+        self.integer_cost = 0;
+        self.segment_costs.clear();
+        self.blocking.clear();
+        self.expanded = expanded;
+        self.steps.clear();
+        #[cfg(feature = "debug")]
+        self.open.clear();
+        self.path = path
+            .unwrap_or_default()
+            .into_iter()
+            .map(|idx| self.grid.coords(idx))
+            .collect();
+        self.path_costs = unit_costs(self.path.len());
+        self.cost = self.path.len().saturating_sub(1) as f64;
+        !self.path.is_empty()
+    }
+
+    /// Guarda el resultado de `search_layers` como estado actual.
+    fn apply_layered(
+        &mut self,
+        found: Option<search::Outcome<layers::LayerCell>>,
+        started: f64,
+    ) -> bool {
+        let found = found.unwrap_or_else(search::Outcome::none);
+        self.metrics
+            .record_query(found.expanded.len(), clock::now_ms() - started);
+        self.options = None;
+        self.cost_memo.use_options(&SearchOptions::default());
+        self.status = Status::default();
+        self.path_kind = PathKind::Layers;
+        self.segment_costs.clear();
+        self.integer_cost = 0;
+        self.blocking.clear();
+        let idx = |(_, c): layers::LayerCell| self.grid.idx(c);
+        self.expanded = found.expanded.iter().map(|&s| idx(s)).collect();
+        self.steps = found.steps.iter().map(|s| s.map(idx)).collect();
+        #[cfg(feature = "debug")]
+        {
+            self.open = found
+                .open
+                .iter()
+                .map(|&(c, s)| (idx(c), s.map(idx)))
+                .collect();
+        }
+        let names = self.layers.names();
+        let (states, g, cost) = found
+            .result
+            .map_or((vec![], vec![], 0.0), |f| (f.states, f.g, f.cost));
+        self.path = states.iter().map(|&(_, cell)| cell).collect();
+        self.path_costs = g;
+        self.path_layers = states.iter().map(|&(l, _)| names[l].clone()).collect();
+        self.cost = cost;
+        !self.path.is_empty()
+    }
+
+    /// Anomalía si `start` o `goal` no se pueden pisar con `opts` (salvo que
+    /// la relajación del destino vaya a buscar otro).
+    fn check_endpoints(
+        &self,
+        start: (usize, usize),
+        goal: (usize, usize),
+        opts: &SearchOptions,
+    ) -> Result<(), Anomaly> {
+        let costs = cost::StepCost::new(&self.grid, opts);
+        if !costs.allowed(start) {
+            policy::anomaly("el inicio está bloqueado")
+        } else if !costs.allowed(goal) && opts.goal_radius == 0 {
+            policy::anomaly("el destino está bloqueado")
+        } else {
+            Ok(())
+        }
+    }
+
+    /// `search_between` con la anomalía sin convertir en excepción (la API C
+    /// no puede crear errores de JS).
+    fn search_cells(
+        &mut self,
+        options: &SearchOptions,
+        start: (usize, usize),
+        goal: (usize, usize),
+    ) -> Result<bool, Anomaly> {
+        self.check_endpoints(start, goal, options)?;
+        let options = &self.seeded(options);
+        self.prepare_search();
+        Ok(self.between(options, start, goal))
+    }
+
+    /// `add_cost_overlay` con la anomalía sin convertir en excepción.
+    fn insert_overlay(
+        &mut self,
+        name: &str,
+        costs: Vec<f32>,
+        schedule: &[f32],
+    ) -> Result<bool, Anomaly> {
+        if costs.len() != self.grid.cells.len() || !schedule.len().is_multiple_of(2) {
+            policy::anomaly("capa de coste con otro tamaño o ventanas incompletas")?;
+            return Ok(false);
+        }
+        if costs.iter().any(|c| !(c.is_finite() && *c >= 0.0)) {
+            policy::anomaly("capa de coste con costes negativos o no finitos")?;
+            return Ok(false);
+        }
+        self.overlays.insert(Overlay {
+            name: name.to_string(),
+            costs,
+            windows: schedule.chunks_exact(2).map(|w| (w[0], w[1])).collect(),
+        });
+        self.compose_penalty();
+        Ok(true)
+    }
+
+    /// `options` con una semilla de ruido concreta sacada del generador de la
+    /// instancia si la piden (`set_noise_from_rng`).
+    fn seeded(&mut self, options: &SearchOptions) -> SearchOptions {
+        let mut options = options.clone();
+        if options.rng_seed {
+            options.seed = self.rng.next_u64() as u32;
+            options.rng_seed = false;
+        }
+        options
+    }
+
+    /// Cambia la hora de las capas programadas y recompone los costes si
+    /// cambia alguna activa.
+    fn switch_overlays(&mut self, t: f32) {
+        if self.overlays.set_time(t) {
+            self.compose_penalty();
+        }
+    }
+
+    /// `dijkstra_map` con las opciones ya sembradas.
+    fn distance_map(&self, options: &SearchOptions, sources: &[usize]) -> DijkstraMap {
+        let seeds: Vec<(usize, f32)> = pairs(sources)
+            .into_iter()
+            .filter(|&c| self.grid.in_bounds(c))
+            .map(|c| (self.grid.idx(c), 0.0))
+            .collect();
+        let costs = cost::StepCost::new(&self.grid, options);
+        DijkstraMap::compute(&self.grid, &costs, &seeds)
+    }
+
+    /// `flee_map` con las opciones ya sembradas.
+    fn fleeing(&self, options: &SearchOptions, sources: &[usize], factor: f32) -> DijkstraMap {
+        let mut map = self.distance_map(options, sources);
+        map.scale(-factor);
+        let costs = cost::StepCost::new(&self.grid, options);
+        DijkstraMap::compute(&self.grid, &costs, &map.seeds())
+    }
+
+    /// Índice de una celda válida para una puerta o llave.
+    fn lock_cell(&self, cell: (usize, usize), label: u8) -> Option<usize> {
+        (self.grid.in_bounds(cell) && label < locks::MAX_LABELS).then(|| self.grid.idx(cell))
     }
 
-    pub fn has_path(&self) -> bool {
-        !self.path.is_empty()
+    /// Índices de celda como `[x0, y0, x1, y1, ...]`.
+    fn flatten(&self, cells: &[usize]) -> Vec<usize> {
+        cells
+            .iter()
+            .flat_map(|&idx| {
+                let (x, y) = self.grid.coords(idx);
+                [x, y]
+            })
+            .collect()
     }
-    pub fn path(&self) -> Vec<usize> {
-        self.path.iter().flat_map(|(x, y)| vec![*x, *y]).collect()
+
+    /// Cuerpo de `search_between` una vez comprobados los extremos y
+    /// preparadas las capas de coste.
+    fn between(
+        &mut self,
+        options: &SearchOptions,
+        start: (usize, usize),
+        goal: (usize, usize),
+    ) -> bool {
+        let started = clock::now_ms();
+        self.adjacency.sync(&self.grid, self.revision);
+        let (found, relaxed) =
+            search::relaxed_search(&self.grid, Some(&self.adjacency), start, goal, options);
+        let found = self.apply(found, options, started);
+        self.status.relaxed_goal = relaxed;
+        found
+    }
+
+    /// Anota que han cambiado las celdas del grid. Lo llama todo lo que las
+    /// modifica; lo calculado para otra revisión deja de valer.
+    fn touched(&mut self) {
+        self.revision = self.revision.wrapping_add(1);
+    }
+
+    /// Actualiza las capas dinámicas antes de una búsqueda con costes y
+    /// compone con ellas la penalización del grid.
+    fn prepare_search(&mut self) {
+        if let Some(reuse) = &mut self.reuse {
+            reuse.decay();
+        }
+        self.compose_penalty();
+    }
+
+    /// Penalización del grid: suma de las capas dinámicas activas.
+    fn compose_penalty(&mut self) {
+        self.cost_memo.clear();
+        let heat = self.reuse.as_ref().map(|r| &r.heat);
+        let hazard = self.hazard.as_ref();
+        let congestion = self.congestion.as_ref();
+        let overlays: Vec<&Overlay> = self.overlays.active().collect();
+        if heat.is_none() && hazard.is_none() && congestion.is_none() && overlays.is_empty() {
+            self.grid.set_penalty(None);
+            return;
+        }
+        let penalty = (0..self.grid.cells.len()).map(|i| {
+            heat.map_or(0.0, |h| h[i])
+                + hazard.map_or(0.0, |h| h.weight * h.values[i])
+                + congestion.map_or(0.0, |c| c.weight * c.load[i])
+                + overlays.iter().map(|o| o.costs[i]).sum::<f32>()
+        });
+        self.grid.set_penalty(Some(penalty.collect()));
+    }
+
+    /// Coste real desde cada celda de la ruta actual hasta su final, paso a
+    /// paso con el modelo de costes de `opts` (sin los costes de giro).
+    fn remaining_costs(&self, opts: &SearchOptions) -> Vec<f64> {
+        let costs = cost::StepCost::new(&self.grid, opts);
+        let mut remaining = vec![0.0; self.path.len()];
+        for i in (0..self.path.len().saturating_sub(1)).rev() {
+            let step = costs.step(self.path[i], self.path[i + 1]).unwrap_or(0.0);
+            remaining[i] = remaining[i + 1] + step;
+        }
+        remaining
+    }
+
+    /// Posición de la ruta desde la que seguir estando en `cell` y si hace
+    /// falta un paso previo para llegar a ella, o `None` si la ruta no sirve.
+    fn anchor(&self, cell: (usize, usize)) -> Option<(usize, bool)> {
+        if !self.path_layers.is_empty() || !self.grid.in_bounds(cell) {
+            return None;
+        }
+        let default = SearchOptions::default();
+        let costs = cost::StepCost::new(&self.grid, self.options.as_ref().unwrap_or(&default));
+        let (i, joined) = match self.path.iter().rposition(|&c| c == cell) {
+            Some(i) => (i, false),
+            None => (
+                self.path
+                    .iter()
+                    .rposition(|&c| grid::direction(cell, c).is_some())?,
+                true,
+            ),
+        };
+        let mut rest = self.path[i..].to_vec();
+        if joined {
+            rest.insert(0, cell);
+        }
+        let usable = rest.iter().all(|&c| costs.allowed(c))
+            && rest.windows(2).all(|w| costs.step(w[0], w[1]).is_some());
+        usable.then_some((i, joined))
+    }
+
+    /// Comprobación de `verify_optimal`, con el fallo como texto.
+    fn check_optimal(&self) -> Result<(), String> {
+        let (Some(&start), Some(&goal)) = (self.path.first(), self.path.last()) else {
+            return Ok(());
+        };
+        if let Some(kind) = self.path_kind.unverifiable() {
+            return Err(format!("las búsquedas {kind} no se pueden verificar"));
+        }
+        let reference = match &self.options {
+            Some(opts) => search::grid_search_by(&self.grid, start, goal, opts, |_, g| g)
+                .result
+                .map(|found| found.cost),
+            None => distance::bfs_to_goal(&self.grid, None, start, |i| i == self.grid.idx(goal))
+                .0
+                .map(|path| path.len().saturating_sub(1) as f64),
+        };
+        match reference {
+            Some(best) if (self.cost - best).abs() <= 1e-9 * best.max(1.0) => Ok(()),
+            Some(best) => Err(format!(
+                "la ruta cuesta {} y la referencia {best}",
+                self.cost
+            )),
+            None => Err("la referencia no encuentra ruta".to_string()),
+        }
+    }
+
+    fn restore_landmarks(&mut self, bytes: &[u8]) -> Result<usize, &'static str> {
+        let artifacts = Artifacts::decode(bytes).ok_or("artefactos de preprocesado inválidos")?;
+        self.prepare_search();
+        if artifacts.fingerprint != self.grid.content_hash() {
+            return Err("los artefactos corresponden a otro mapa");
+        }
+        let landmarks = artifacts
+            .section(SECTION_LANDMARKS)
+            .and_then(|words| Landmarks::from_words(&self.grid, words))
+            .ok_or("faltan los landmarks")?;
+        let count = landmarks.cells.len();
+        self.landmarks = Some(landmarks);
+        Ok(count)
+    }
+
+    /// Marca la ruta actual como no óptima y acota cuánto se aleja del
+    /// óptimo sin volver a buscar: divide su coste entre una cota inferior
+    /// del coste mínimo entre sus extremos (la distancia Manhattan por el
+    /// paso más barato o, si valen para este grid y estas opciones, los
+    /// landmarks de `build_landmarks`). El valor exacto sale de comparar con
+    /// `path_cost`.
+    fn mark_suboptimal(&mut self) {
+        self.status.optimal = false;
+        self.status.suboptimality = f64::INFINITY;
+        let (Some(&start), Some(&goal), Some(opts)) =
+            (self.path.first(), self.path.last(), &self.options)
+        else {
+            return;
+        };
+        let manhattan = (start.0.abs_diff(goal.0) + start.1.abs_diff(goal.1)) as f64;
+        let mut best = manhattan * cost::StepCost::new(&self.grid, opts).min_step();
+        if let Some(landmarks) = self.landmarks.as_ref().filter(|l| l.fits(&self.grid, opts)) {
+            let (from, to) = (self.grid.idx(start), self.grid.idx(goal));
+            best = best.max(landmarks.lower_bound(from, to));
+        }
+        self.status.suboptimality = if best > 0.0 { self.cost / best } else { 1.0 };
+    }
+
+    /// Guarda el resultado de una búsqueda con opciones como estado actual.
+    fn apply(
+        &mut self,
+        found: search::Outcome<(usize, usize)>,
+        options: &SearchOptions,
+        started: f64,
+    ) -> bool {
+        self.metrics
+            .record_query(found.expanded.len(), clock::now_ms() - started);
+        self.options = Some(options.clone());
+        self.cost_memo.use_options(options);
+        self.status = Status::default();
+        self.integer_cost = 0;
+        self.status.truncated = found.pruned && found.result.is_none();
+        self.path_layers.clear();
+        self.path_transitions.clear();
+        self.path_kind = PathKind::Plain;
+        self.segment_costs.clear();
+        self.blocking.clear();
+        self.expanded = found.expanded.iter().map(|&c| self.grid.idx(c)).collect();
+        self.steps = found
+            .steps
+            .iter()
+            .map(|s| s.map(|c| self.grid.idx(c)))
+            .collect();
+        #[cfg(feature = "debug")]
+        {
+            let idx = |c| self.grid.idx(c);
+            self.open = found
+                .open
+                .iter()
+                .map(|&(c, s)| (idx(c), s.map(idx)))
+                .collect();
+        }
+        (self.path, self.path_costs, self.cost) = found
+            .result
+            .map_or((vec![], vec![], 0.0), |f| (f.states, f.g, f.cost));
+        if let Some(reuse) = &mut self.reuse {
+            reuse.add_path(self.path.iter().map(|&c| self.grid.idx(c)));
+        }
+        self.has_path()
+    }
+}
+
+/// Clase de búsqueda que dejó la ruta actual, para que `verify_optimal`
+/// solo la compare con una referencia que resuelva el mismo problema.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PathKind {
+    /// Entre dos celdas con `options` (o BFS sin ellas).
+    Plain,
+    Layers,
+    Keys,
+    Breach,
+    Via,
+    Safe,
+    Remembering,
+    CostLayer,
+}
+
+impl PathKind {
+    /// Cómo se nombran en el error las búsquedas sin referencia; `None` si
+    /// se pueden verificar.
+    fn unverifiable(self) -> Option<&'static str> {
+        match self {
+            PathKind::Plain => None,
+            PathKind::Layers => Some("por capas"),
+            PathKind::Keys => Some("con llaves"),
+            PathKind::Breach => Some("que atraviesan paredes"),
+            PathKind::Via => Some("por puntos de paso"),
+            PathKind::Safe => Some("que evitan amenazas"),
+            PathKind::Remembering => Some("con memoria"),
+            PathKind::CostLayer => Some("con capa de costes"),
+        }
     }
 }
 
-fn bfs(grid: Vec<u8>, n: usize) -> Vec<(usize, usize)> {
-    if grid[0] == 0 || grid[n * n - 1] == 0 {
+/// Coste acumulado hasta cada celda de una ruta de `len` celdas con pasos de
+/// coste 1.
+fn unit_costs(len: usize) -> Vec<f64> {
+    (0..len).map(|i| i as f64).collect()
+}
+
+/// Convierte `[x0, y0, x1, y1, ...]` en celdas; un valor suelto al final se ignora.
+fn pairs(flat: &[usize]) -> Vec<(usize, usize)> {
+    flat.chunks_exact(2).map(|c| (c[0], c[1])).collect()
+}
+
+#[cfg(test)]
+fn bfs(grid: &[u8], n: usize) -> Vec<(usize, usize)> {
+    bfs_traced(grid, n, n, &mut Vec::new())
+}
+
+/// BFS sobre un grid de `rows` filas y `cols` columnas que además anota en
+/// `expanded` cada celda sacada de la cola, en orden.
+fn bfs_traced(
+    grid: &[u8],
+    rows: usize,
+    cols: usize,
+    expanded: &mut Vec<usize>,
+) -> Vec<(usize, usize)> {
+    if grid.is_empty() || grid[0] == 0 || grid[rows * cols - 1] == 0 {
         return vec![];
     }
 
     let mut queue = VecDeque::new();
-    let mut visited = vec![false; n * n];
+    let mut visited = vec![false; rows * cols];
     let mut parent: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
 
     queue.push_back((0, 0));
@@ -37,7 +3040,8 @@ fn bfs(grid: Vec<u8>, n: usize) -> Vec<(usize, usize)> {
     let dirs = [(1, 0), (0, 1), (-1, 0), (0, -1)];
 
     while let Some((x, y)) = queue.pop_front() {
-        if (x, y) == (n - 1, n - 1) {
+        expanded.push(x * cols + y);
+        if (x, y) == (rows - 1, cols - 1) {
             return build_path(parent, (x, y));
         }
 
@@ -47,9 +3051,9 @@ fn bfs(grid: Vec<u8>, n: usize) -> Vec<(usize, usize)> {
 
             if nx >= 0 && ny >= 0 {
                 let (nx, ny) = (nx as usize, ny as usize);
-                let idx = nx * n + ny;
+                let idx = nx * cols + ny;
 
-                if nx < n && ny < n && grid[idx] == 1 && !visited[idx] {
+                if nx < rows && ny < cols && grid[idx] == 1 && !visited[idx] {
                     visited[idx] = true;
                     parent.insert((nx, ny), (x, y));
                     queue.push_back((nx, ny));
@@ -82,7 +3086,7 @@ mod tests {
     #[test]
     fn path_exists_simple() {
         let grid = vec![1, 1, 1, 1];
-        let path = bfs(grid, 2);
+        let path = bfs(&grid, 2);
         assert!(!path.is_empty());
     }
 
@@ -90,7 +3094,7 @@ mod tests {
     #[test]
     fn no_path_start_blocked() {
         let grid = vec![0, 1, 1, 1];
-        let path = bfs(grid, 2);
+        let path = bfs(&grid, 2);
         assert!(path.is_empty());
     }
 
@@ -98,16 +3102,1025 @@ mod tests {
     #[test]
     fn no_path_end_blocked() {
         let grid = vec![1, 1, 1, 0];
-        let path = bfs(grid, 2);
+        let path = bfs(&grid, 2);
         assert!(path.is_empty());
     }
 
+    /// Test: un grid vacío no tiene ruta y no hace fallar al constructor
+    #[test]
+    fn empty_grid_has_no_path() {
+        assert!(bfs(&[], 0).is_empty());
+        assert!(!PathFinder::new(vec![], 0).unwrap().has_path());
+    }
+
     /// Test: grid 3x3 con ruta válida mínima
     #[test]
     fn path_exists_complex() {
         let grid = vec![1, 1, 0, 0, 1, 1, 0, 1, 1];
-        let path = bfs(grid, 3);
+        let path = bfs(&grid, 3);
         assert_eq!(path.first(), Some(&(0, 0)));
         assert_eq!(path.last(), Some(&(2, 2)));
     }
+
+    /// Test: la imagen de visitados pinta ruta y expandidos con sus colores
+    #[test]
+    fn visited_image_colors() {
+        let pf = PathFinder::new(vec![1, 1, 0, 1], 2).unwrap();
+        let image = pf.visited_image(0x0000_FFFF, 0x00FF_00FF);
+        assert_eq!(image.len(), 16);
+        assert_eq!(&image[0..4], &[0, 255, 0, 255]);
+        assert_eq!(&image[8..12], &[0, 0, 0, 0]);
+        assert_eq!(&image[12..16], &[0, 255, 0, 255]);
+    }
+
+    /// Test: los fotogramas reparten las expansiones en bloques
+    #[test]
+    fn frames_split_expansions() {
+        let mut pf = PathFinder::new(vec![1; 9], 3).unwrap();
+        pf.set_cells_per_frame(4);
+        assert_eq!(pf.frame_count(), 3);
+        assert_eq!(pf.frame(0)[..2], [0, 0]);
+        assert_eq!(pf.frame(2), vec![2, 2]);
+        assert!(pf.frame(3).is_empty());
+    }
+
+    /// Test: un registro se reproduce con las mismas expansiones
+    #[test]
+    fn record_and_replay() {
+        let mut pf = PathFinder::new(vec![1; 16], 4).unwrap();
+        let mut opts = SearchOptions::new();
+        opts.set_noise(0.3, 9);
+        pf.search_with(&opts).unwrap();
+        let replayed = PathFinder::replay(&pf.record()).unwrap();
+        assert_eq!(replayed.expanded, pf.expanded);
+        assert_eq!(replayed.path, pf.path);
+    }
+
+    /// Test: diff lista solo las celdas cambiadas con su nuevo valor
+    #[test]
+    fn diff_lists_changed_cells() {
+        let pf = PathFinder::new(vec![1, 1, 1, 1], 2).unwrap();
+        assert_eq!(pf.diff(&[1, 0, 1, 1]).unwrap(), vec![0, 1, 0]);
+        assert!(pf.diff(&[1, 1, 1, 1]).unwrap().is_empty());
+        assert!(pf.grid.diff(&[1, 1]).is_none());
+    }
+
+    /// Test: radio 1 devuelve el origen y sus vecinos transitables
+    #[test]
+    fn within_steps_radius_one() {
+        let pf = PathFinder::new(vec![1, 1, 0, 1, 1, 1, 0, 1, 1], 3).unwrap();
+        assert_eq!(
+            pf.cells_within_steps(1, 1, 1),
+            vec![1, 1, 2, 1, 1, 2, 0, 1, 1, 0]
+        );
+        assert!(pf.cells_within_steps(0, 2, 5).is_empty());
+    }
+
+    /// Test: el anillo sigue la distancia caminando alrededor de la pared
+    #[test]
+    fn ring_follows_walking_distance() {
+        let pf = PathFinder::new(vec![1, 0, 1, 1, 0, 1, 1, 1, 1], 3).unwrap();
+        assert_eq!(pf.ring_at_distance(0, 0, 2), vec![2, 0]);
+        assert_eq!(pf.ring_at_distance(0, 0, 6), vec![0, 2]);
+        assert!(pf.ring_at_distance(0, 0, 7).is_empty());
+    }
+
+    /// Test: agentes sucesivos se reparten por rutas distintas
+    #[test]
+    fn route_reuse_spreads_agents() {
+        let mut pf = PathFinder::new(vec![1; 25], 5).unwrap();
+        pf.set_route_reuse_penalty(2.0, 0.9).unwrap();
+        let opts = SearchOptions::new();
+        pf.search_with(&opts).unwrap();
+        let first = pf.path.clone();
+        pf.search_with(&opts).unwrap();
+        assert_ne!(pf.path, first);
+        assert_eq!(pf.path.len(), first.len());
+    }
+
+    /// Test: la memoria del agente lo aparta del pasillo por el que vino y
+    /// no deja rastro en el grid
+    #[test]
+    fn memory_avoids_retracing() {
+        let mut pf = PathFinder::new(vec![1; 9], 3).unwrap();
+        let opts = SearchOptions::new();
+        let mut memory = AgentMemory::new(3.0, 0.9).unwrap();
+        for y in 0..3 {
+            memory.visit(1, y);
+        }
+        assert!(pf.search_remembering(&opts, &memory, 1, 2, 1, 0).unwrap());
+        assert!(!pf.path.contains(&(1, 1)));
+        assert_eq!(pf.path.len(), 5);
+        assert!(pf.grid.penalty.is_none());
+
+        assert!(
+            pf.search_remembering(&opts, &AgentMemory::new(3.0, 0.9).unwrap(), 1, 2, 1, 0)
+                .unwrap()
+        );
+        assert_eq!(pf.path, vec![(1, 2), (1, 1), (1, 0)]);
+    }
+
+    /// Test: el mapa de huida aleja del peligro hacia la salida más lejana
+    #[test]
+    fn flee_map_runs_away() {
+        let mut pf = PathFinder::new(vec![1; 9], 3).unwrap();
+        let opts = SearchOptions::new();
+        let chase = pf.dijkstra_map(&opts, &[0, 0]);
+        assert_eq!(chase.descent_at(2, 2), 2);
+        let flee = pf.flee_map(&opts, &[0, 0], 1.2);
+        assert!((flee.values()[8] + 4.8).abs() < 1e-5);
+        assert_eq!(flee.descent_at(0, 0), 0);
+        assert_eq!(flee.descent_at(2, 2), 4);
+    }
+
+    /// Test: con peso de cubierta la retirada acaba junto al pilar en vez de
+    /// en la esquina más lejana
+    #[test]
+    fn retreat_prefers_cover() {
+        let mut cells = vec![1; 25];
+        cells[12] = 0;
+        let mut pf = PathFinder::new(cells, 5).unwrap();
+        let opts = SearchOptions::new();
+        let lowest = |map: &DijkstraMap| {
+            let values = map.values();
+            (0..values.len()).min_by(|&a, &b| values[a].total_cmp(&values[b]))
+        };
+        let plain = pf.retreat_map(&opts, &[0, 4], 1.2, 0.0);
+        assert_eq!(plain, pf.flee_map(&opts, &[0, 4], 1.2));
+        assert_eq!(lowest(&plain), Some(20));
+        let covered = pf.retreat_map(&opts, &[0, 4], 1.2, 10.0);
+        assert_eq!(lowest(&covered), Some(11));
+        assert_eq!((plain.descent_at(4, 0), covered.descent_at(4, 0)), (4, 1));
+    }
+
+    /// Test: los códigos marcan paredes y bordes y el delta cubre la celda
+    /// cambiada y sus vecinas
+    #[test]
+    fn autotile_codes_follow_edits() {
+        let mut pf = PathFinder::new(vec![1; 9], 3).unwrap();
+        let codes = pf.autotile_codes();
+        assert_eq!(codes[0], 4 | 8);
+        assert_eq!(codes[4], 0);
+        let generation = pf.generation();
+
+        pf.set_cell(1, 1, 0);
+        assert_eq!(pf.autotile_codes()[1], 4 | 1);
+        assert_eq!(
+            pf.autotile_delta(generation),
+            vec![0, 1, 5, 1, 0, 10, 1, 1, 0, 1, 2, 10, 2, 1, 5]
+        );
+        let generation = pf.generation();
+        assert!(pf.autotile_delta(generation).is_empty());
+    }
+
+    /// Test: las indicaciones de la ruta acaban en la llegada
+    #[test]
+    fn route_instructions() {
+        let mut pf = PathFinder::new(vec![1, 0, 0, 1, 1, 0, 0, 1, 1], 3).unwrap();
+        pf.search_with(&SearchOptions::new()).unwrap();
+        assert_eq!(pf.instructions().len(), 5 * 6);
+        let texts = pf.instruction_texts();
+        assert_eq!(texts[0], "Sal hacia el sur y avanza 1 casilla");
+        assert_eq!(texts.last().unwrap(), "Has llegado a tu destino");
+    }
+
+    /// Test: el reparto manda a cada agente al destino que le toca y da su
+    /// ruta
+    #[test]
+    fn allocate_goals_to_agents() {
+        let mut pf = PathFinder::new(vec![1; 16], 4).unwrap();
+        let opts = SearchOptions::new();
+        let agents = [0, 0, 3, 3, 1, 1];
+        let goals = [3, 2, 0, 1];
+        let plan = pf.allocate_tasks(&opts, &agents, &goals, AllocationObjective::TotalDistance);
+        assert_eq!(plan.assignments(), vec![1, 0, -1]);
+        assert_eq!((plan.total(), plan.makespan()), (2.0, 1.0));
+        assert_eq!(plan.path(1), vec![3, 3, 3, 2]);
+        assert!(plan.cost(2).is_nan());
+    }
+
+    /// Test: en campo abierto todo el rectángulo es corredor; un desvío no
+    #[test]
+    fn corridor_of_all_shortest_paths() {
+        let pf = PathFinder::new(vec![1; 9], 3).unwrap();
+        assert_eq!(pf.shortest_path_corridor().len(), 18);
+
+        let pf = PathFinder::new(vec![1, 1, 1, 0, 0, 1, 1, 1, 1], 3).unwrap();
+        assert_eq!(
+            pf.shortest_path_corridor(),
+            vec![0, 0, 0, 1, 0, 2, 1, 2, 2, 2]
+        );
+    }
+
+    /// Test: el convoy rechaza el pasillo estrecho e informa del ancho máximo
+    #[test]
+    fn convoy_reports_max_width() {
+        // Grid 7x7 abierto con un tabique de una sola abertura en la fila 3.
+        let mut cells = vec![1; 49];
+        for y in 0..7 {
+            if y != 3 {
+                cells[3 * 7 + y] = 0;
+            }
+        }
+        let mut pf = PathFinder::new(cells, 7).unwrap();
+        let opts = SearchOptions::new();
+        assert_eq!(pf.convoy_path(&opts, 3).unwrap(), 1);
+        assert!(!pf.has_path());
+        assert_eq!(pf.convoy_path(&opts, 1).unwrap(), 1);
+        assert!(pf.has_path());
+    }
+
+    /// Test: la ruta a un terreno acaba en la celda más cercana con ese id
+    #[test]
+    fn path_to_nearest_terrain() {
+        let mut pf = PathFinder::new(vec![1; 25], 5).unwrap();
+        let mut terrain = [0; 25];
+        terrain[4 * 5 + 4] = 7;
+        terrain[2 * 5 + 1] = 7;
+        assert!(pf.path_to_goal((0, 0), |idx| terrain[idx] == 7));
+        assert_eq!(pf.path().len(), 2 * 4);
+        assert_eq!(pf.path()[6..], [2, 1]);
+        assert!(!pf.path_to_goal((0, 0), |idx| terrain[idx] == 9));
+        assert!(!pf.has_path());
+    }
+
+    /// Test: búsqueda entre posiciones de mundo con ajuste a celdas libres
+    #[test]
+    fn search_between_world_positions() {
+        let mut pf = PathFinder::new(vec![1, 1, 1, 0, 0, 1, 1, 1, 1], 3).unwrap();
+        let opts = SearchOptions::new();
+        // El destino cae sobre la pared (1, 0) y se ajusta a (2, 0).
+        assert!(pf.search_world(&opts, 0.5, 0.5, 1.6, 0.2, 1.0).unwrap());
+        assert_eq!(pf.path(), vec![0, 0, 0, 1, 0, 2, 1, 2, 2, 2, 2, 1, 2, 0]);
+        assert_eq!(pf.world_path(2.0)[..2], [1.0, 1.0]);
+        assert!(!pf.search_world(&opts, 0.5, 0.5, 1.0, 1.0, -1.0).unwrap());
+    }
+
+    /// Test: la escolta no se aparta de la ruta de referencia más de lo
+    /// permitido
+    #[test]
+    fn escort_stays_near_reference() {
+        let mut pf = PathFinder::new(vec![1; 25], 5).unwrap();
+        let opts = SearchOptions::new();
+        let reference = [0, 0, 0, 1, 0, 2, 0, 3, 0, 4, 1, 4, 2, 4, 3, 4, 4, 4];
+        assert!(
+            pf.search_escorting(&opts, &reference, 0, 0, 0, 4, 4)
+                .unwrap()
+        );
+        assert_eq!(pf.path(), reference.to_vec());
+        assert!(
+            pf.search_escorting(&opts, &reference, 1, 1, 0, 4, 3)
+                .unwrap()
+        );
+        assert_eq!(pf.path().len(), 2 * 7);
+        assert!(pf.path().chunks(2).all(|c| c[0] <= 1 || c[1] >= 3));
+        assert!(
+            !pf.search_escorting(&opts, &reference, 1, 2, 1, 4, 4)
+                .unwrap()
+        );
+    }
+
+    /// Test: sin presupuesto se atiende una petición por fotograma, la más
+    /// prioritaria; con presupuesto de sobra, todas
+    #[test]
+    fn request_queue_within_budget() {
+        let mut pf = PathFinder::new(vec![1; 25], 5).unwrap();
+        let opts = SearchOptions::new();
+        let low = pf.enqueue(&opts, 0, 0, 4, 4, 1.0).unwrap();
+        let high = pf.enqueue(&opts, 0, 0, 0, 2, 9.0).unwrap();
+        let dropped = pf.enqueue(&opts, 4, 4, 0, 0, 5.0).unwrap();
+        assert!(pf.cancel_request(dropped));
+
+        let first = pf.process(0.0);
+        assert_eq!((first.tickets(), first.remaining()), (vec![high], 1));
+        assert_eq!(first.result(0).unwrap().path(), vec![0, 0, 0, 1, 0, 2]);
+        let rest = pf.process(1e9);
+        assert_eq!((rest.tickets(), rest.remaining()), (vec![low], 0));
+        assert_eq!(rest.result(0).unwrap().cost(), 8.0);
+        assert!(pf.process(1e9).is_empty());
+        assert_eq!(pf.pending_requests(), 0);
+    }
+
+    /// Test: la posición a lo largo de la ruta avanza más despacio por los
+    /// pasos caros
+    #[test]
+    fn position_along_result() {
+        let mut pf = PathFinder::new(vec![1, 1, 1, 0, 0, 0, 0, 0, 0], 3).unwrap();
+        let mut costs = vec![0.0; 9];
+        costs[2] = 3.0;
+        assert!(pf.add_cost_overlay("barro", costs, &[]).unwrap());
+        assert!(
+            pf.search_between(&SearchOptions::new(), 0, 0, 0, 2)
+                .unwrap()
+        );
+        let res = pf.result();
+        assert_eq!(res.cost(), 5.0);
+        assert_eq!(res.position_at(-1.0), vec![0.0, 0.0]);
+        assert_eq!(res.position_at(0.5), vec![0.0, 0.5]);
+        assert_eq!(res.position_at(3.0), vec![0.0, 1.5]);
+        assert_eq!(res.position_at(9.0), vec![0.0, 2.0]);
+
+        // Los pasos son los de la búsqueda aunque la capa cambie después.
+        assert!(pf.remove_cost_overlay("barro"));
+        assert_eq!(pf.result().position_at(3.0), vec![0.0, 1.5]);
+
+        // Los giros también cuentan: hasta el coste total no se llega al final.
+        let mut turns = PathFinder::new(vec![1; 9], 3).unwrap();
+        let mut opts = SearchOptions::new();
+        opts.set_rotation_cost(2.0);
+        assert!(turns.search_with(&opts).unwrap());
+        let res = turns.result();
+        assert_eq!(res.cost(), 6.0);
+        assert_ne!(res.position_at(5.0), vec![2.0, 2.0]);
+        assert_eq!(res.position_at(6.0), vec![2.0, 2.0]);
+
+        pf.set_cell(0, 2, 0);
+        assert!(
+            !pf.search_between(&SearchOptions::new(), 0, 0, 0, 2)
+                .unwrap()
+        );
+        assert!(pf.result().position_at(1.0).is_empty());
+    }
+
+    /// Test: el resultado agrupa ruta, coste y estado
+    #[test]
+    fn result_reports_status() {
+        let mut pf = PathFinder::new(vec![1; 9], 3).unwrap();
+        let res = pf.result();
+        assert!(res.found() && res.optimal() && !res.truncated() && !res.cancelled());
+        assert_eq!(res.cost(), 4.0);
+        assert_eq!(res.path(), pf.path());
+
+        pf.set_cell(2, 2, 0);
+        assert!(!pf.search_with(&SearchOptions::new()).unwrap());
+        assert!(!pf.result().found());
+    }
+
+    /// Test: la búsqueda en caliente expande menos y no empeora la ruta
+    #[test]
+    fn warm_search_reuses_previous_path() {
+        let mut pf = PathFinder::new(vec![1; 100], 10).unwrap();
+        let opts = SearchOptions::new();
+        assert!(pf.search_with(&opts).unwrap());
+        let cold = pf.expanded.len();
+        let previous = pf.path();
+        assert!(pf.search_warm(&opts, &previous).unwrap());
+        assert!(pf.expanded.len() < cold);
+        assert_eq!((pf.path(), pf.cost()), (previous.clone(), 18.0));
+
+        let (x, y) = (previous[10], previous[11]);
+        pf.set_cell(x, y, 0);
+        assert!(pf.search_warm(&opts, &previous).unwrap());
+        assert_eq!(pf.cost(), 18.0);
+        assert_ne!(pf.path(), previous);
+    }
+
+    /// Test: la región alcanzable sigue a las celdas cambiadas
+    #[test]
+    fn reachable_region_follows_edits() {
+        let mut pf = PathFinder::new(vec![1; 9], 3).unwrap();
+        pf.track_reachable(0, 0);
+        assert_eq!(pf.reachable_changes().len(), 9 * 3);
+        assert!(pf.set_cell(0, 1, 0) && pf.set_cell(1, 0, 0));
+        assert!(!pf.is_reachable(2, 2));
+        assert_eq!(pf.reachable_changes().len(), 8 * 3);
+        assert!(!pf.set_cell(3, 0, 1));
+        pf.grid.cells[3] = 1;
+        pf.touched();
+        assert!(pf.is_reachable(2, 2) && !pf.is_reachable(0, 1));
+        assert_eq!(pf.reachable_changes()[..3], [0, 2, 1]);
+    }
+
+    /// Test: un escenario guardado se carga con sus capas y consultas
+    #[test]
+    fn scenario_save_and_load() {
+        let mut pf = PathFinder::new(vec![1; 16], 4).unwrap();
+        pf.set_queries(&[0, 0, 3, 3, 1, 1, 9, 9]);
+        pf.set_door(1, 1, 2);
+        pf.set_hazard_rules(0.5, 0.1, 4.0).unwrap();
+        pf.ignite(2, 2, 1.0);
+        let mut opts = SearchOptions::new();
+        opts.set_rotation_cost(0.25);
+        pf.search_with(&opts).unwrap();
+        let loaded = PathFinder::load_scenario(&pf.save_scenario()).unwrap();
+        assert_eq!(loaded.queries(), vec![0, 0, 3, 3]);
+        assert_eq!(loaded.locks.doors.get(&5), Some(&2));
+        assert_eq!(loaded.hazard(), pf.hazard());
+        assert_eq!((loaded.path(), loaded.cost()), (pf.path(), pf.cost()));
+    }
+
+    /// Test: la verificación acepta las rutas óptimas y rechaza las que no
+    #[test]
+    fn verify_optimal_checks_cost() {
+        let mut pf = PathFinder::new(vec![1; 25], 5).unwrap();
+        assert!(pf.check_optimal().is_ok());
+        let mut opts = SearchOptions::new();
+        opts.set_min_turns(true);
+        pf.search_with(&opts).unwrap();
+        assert!(pf.check_optimal().is_ok());
+        pf.cost += 1.0;
+        assert_eq!(
+            pf.check_optimal(),
+            Err("la ruta cuesta 9 y la referencia 8".to_string())
+        );
+    }
+
+    /// Test: las búsquedas que resuelven otro problema no se comparan con
+    /// la referencia de `search_with` y lo dicen
+    #[test]
+    fn verify_optimal_refuses_other_problems() {
+        let mut pf = PathFinder::new(vec![1; 25], 5).unwrap();
+        let opts = SearchOptions::new();
+        let mut memory = AgentMemory::new(3.0, 0.5).unwrap();
+        memory.visit(0, 1);
+        type Query = fn(&mut PathFinder, &SearchOptions, &AgentMemory) -> bool;
+        let queries: [(&str, Query); 7] = [
+            ("por capas", |pf, opts, _| {
+                pf.search_layers(opts, "ground", "ground")
+            }),
+            ("con llaves", |pf, opts, _| {
+                pf.set_door(2, 2, 0);
+                pf.search_with_keys(opts)
+            }),
+            ("que atraviesan paredes", |pf, _, _| {
+                pf.path_with_removals(1)
+            }),
+            ("por puntos de paso", |pf, opts, _| {
+                pf.find_path_via(opts, &[0, 4, 4, 0])
+            }),
+            ("que evitan amenazas", |pf, opts, _| {
+                pf.search_safe(opts, &[2, 2], 1)
+            }),
+            ("con memoria", |pf, opts, memory| {
+                pf.search_remembering(opts, memory, 0, 0, 4, 4).unwrap()
+            }),
+            ("con capa de costes", |pf, _, _| {
+                pf.search_costs_u16(&[5; 25], 0, 0, 4, 4).unwrap()
+            }),
+        ];
+        for (kind, query) in queries {
+            assert!(query(&mut pf, &opts, &memory), "{kind}");
+            assert_eq!(
+                pf.check_optimal(),
+                Err(format!("las búsquedas {kind} no se pueden verificar"))
+            );
+            pf.search_with(&opts).unwrap();
+            assert!(pf.check_optimal().is_ok(), "{kind}");
+        }
+    }
+
+    /// Test: con `max_cost` un destino lejano se descarta enseguida
+    #[test]
+    fn max_cost_stops_early() {
+        let mut pf = PathFinder::new(vec![1; 400], 20).unwrap();
+        let mut opts = SearchOptions::new();
+        opts.set_max_cost(Some(20.0));
+        assert!(!pf.search_with(&opts).unwrap());
+        assert!(pf.result().truncated() && pf.expanded.len() == 1);
+        assert_eq!(pf.path_cost(0, 0, 19, 5), f64::INFINITY);
+        assert_eq!(pf.path_cost(0, 0, 10, 10), 20.0);
+        opts.set_max_cost(Some(38.0));
+        assert!(pf.search_with(&opts).unwrap() && !pf.result().truncated());
+    }
+
+    /// Test: la búsqueda automática informa del algoritmo elegido
+    #[test]
+    fn search_auto_reports_algorithm() {
+        let mut pf = PathFinder::new(vec![1; 64 * 64], 64).unwrap();
+        assert!(pf.search_auto(&SearchOptions::new()).unwrap());
+        assert_eq!(pf.result().algorithm(), "rectangles");
+        assert_eq!(pf.cost(), 126.0);
+        let mut opts = SearchOptions::new();
+        opts.set_rotation_cost(0.5);
+        assert!(pf.search_auto(&opts).unwrap());
+        assert_eq!(pf.result().algorithm(), "astar");
+        pf.search_with(&opts).unwrap();
+        assert_eq!(pf.result().algorithm(), "");
+    }
+
+    /// Test: la búsqueda automática respeta la cota de coste
+    #[test]
+    fn search_auto_honours_max_cost() {
+        let mut pf = PathFinder::new(vec![1; 64 * 64], 64).unwrap();
+        let mut opts = SearchOptions::new();
+        opts.set_max_cost(Some(100.0));
+        assert!(!pf.search_auto(&opts).unwrap());
+        assert!(pf.result().truncated());
+        assert_eq!(pf.result().algorithm(), "astar");
+        opts.set_max_cost(Some(126.0));
+        assert!(pf.search_auto(&opts).unwrap());
+        assert_eq!(pf.cost(), 126.0);
+    }
+
+    /// Test: Manhattan es exacta en campo abierto y octile la subestima
+    #[test]
+    fn heuristic_report_compares_estimates() {
+        let pf = PathFinder::new(vec![1; 16], 4).unwrap();
+        let manhattan = pf.heuristic_report("manhattan").unwrap();
+        assert_eq!(manhattan.samples(), 7);
+        assert_eq!(manhattan.max_error(), 0.0);
+        let octile = pf.heuristic_report("octile").unwrap();
+        assert!(octile.mean_error() > 0.0 && octile.overestimates() == 0);
+    }
+
+    /// Test: una ruta no óptima informa de una cota de cuánto se aleja del
+    /// coste mínimo
+    #[test]
+    fn suboptimal_route_reports_ratio() {
+        let mut pf = PathFinder::new(vec![1; 9], 3).unwrap();
+        assert_eq!(pf.result().suboptimality(), 1.0);
+        pf.search_with(&SearchOptions::new()).unwrap();
+        // Desvío de 6 pasos en lugar de 4, como dejaría una búsqueda voraz.
+        pf.path = vec![(0, 0), (0, 1), (1, 1), (1, 0), (2, 0), (2, 1), (2, 2)];
+        pf.cost = 6.0;
+        pf.mark_suboptimal();
+        let res = pf.result();
+        assert!(!res.optimal());
+        assert_eq!(res.suboptimality(), 1.5);
+        // Tras una pared la distancia Manhattan se queda corta y la cota es
+        // holgada; con landmarks se ajusta al coste real.
+        let mut pf = PathFinder::new(vec![1, 1, 1, 0, 0, 1, 1, 1, 1], 3).unwrap();
+        let opts = SearchOptions::new();
+        pf.search_with(&opts).unwrap();
+        pf.path = vec![(0, 0), (0, 1), (0, 2), (1, 2), (2, 2), (2, 1), (2, 0)];
+        pf.cost = 6.0;
+        pf.mark_suboptimal();
+        assert_eq!(pf.result().suboptimality(), 3.0);
+        pf.build_landmarks(&opts, 2);
+        pf.mark_suboptimal();
+        assert_eq!(pf.result().suboptimality(), 1.0);
+    }
+
+    /// Test: cada capa da su propia ruta sobre el mismo PathFinder
+    #[test]
+    fn search_per_layer() {
+        let mut pf = PathFinder::new(vec![1, 0, 0, 1], 2).unwrap();
+        assert!(!pf.has_path());
+        pf.layers.insert(&mut pf.grid, "air", vec![1; 4]).unwrap();
+        assert!(pf.select_layer("air"));
+        assert!(pf.search_with(&SearchOptions::new()).unwrap());
+        assert!(pf.select_layer("ground"));
+        assert!(!pf.search_with(&SearchOptions::new()).unwrap());
+    }
+
+    /// Test: el nivel con puerta solo tiene solución si su llave es alcanzable
+    #[test]
+    fn keys_make_level_solvable() {
+        let mut pf = PathFinder::new(vec![1; 9], 3).unwrap();
+        assert!(pf.set_door(2, 2, 0));
+        assert!(!pf.set_key(0, 3, 0));
+        assert!(!pf.search_with_keys(&SearchOptions::new()));
+        assert!(pf.set_key(0, 2, 0));
+        assert!(pf.search_with_keys(&SearchOptions::new()));
+        assert_eq!(pf.path()[..6], [0, 0, 0, 1, 0, 2]);
+    }
+
+    /// Test: la orientación por paso sigue a la ruta
+    #[test]
+    fn facing_along_path() {
+        let mut pf = PathFinder::new(vec![1, 1, 0, 1], 2).unwrap();
+        assert_eq!(pf.path_facing(), vec![1, 1, 0]);
+        let mut opts = SearchOptions::new();
+        opts.set_facing(Some(2));
+        pf.search_with(&opts).unwrap();
+        assert_eq!(pf.path_facing(), vec![2, 1, 0]);
+    }
+
+    /// Test: la ruta se aparta del fuego cuando este se propaga
+    #[test]
+    fn route_avoids_spreading_fire() {
+        let mut pf = PathFinder::new(vec![1; 25], 5).unwrap();
+        pf.set_hazard_rules(0.9, 0.0, 10.0).unwrap();
+        assert!(pf.ignite(0, 4, 1.0));
+        pf.tick();
+        pf.tick();
+        assert!(pf.search_with(&SearchOptions::new()).unwrap());
+        assert!(!pf.path.contains(&(0, 4)) && !pf.path.contains(&(0, 3)));
+        assert_eq!(pf.path.len(), 9);
+        pf.clear_hazard();
+        assert!(pf.grid.penalty.is_none());
+    }
+
+    /// Test: derribar una pared débil compensa frente a un rodeo largo
+    #[test]
+    fn breaks_through_weak_wall() {
+        // Dos tabiques en zigzag: columna 1 abierta abajo y columna 3 arriba.
+        let mut cells = vec![1; 25];
+        for x in 0..4 {
+            cells[x * 5 + 1] = 0;
+            cells[(x + 1) * 5 + 3] = 0;
+        }
+        let mut pf = PathFinder::new(cells, 5).unwrap();
+        let mut hp = vec![0.0; 25];
+        hp[4 * 5 + 3] = 2.0;
+        assert!(pf.set_wall_hit_points(hp).unwrap());
+
+        let mut opts = SearchOptions::new();
+        assert!(pf.search_with(&opts).unwrap());
+        assert!(pf.destroyed_cells().is_empty());
+        assert_eq!(pf.cost(), 16.0);
+
+        opts.set_break_cost(0.5);
+        assert!(pf.search_with(&opts).unwrap());
+        assert_eq!(pf.destroyed_cells(), vec![4, 3]);
+        assert_eq!(pf.cost(), 8.0 + 1.0);
+    }
+
+    /// Test: el objetivo de exploración es la frontera más cercana
+    #[test]
+    fn exploration_target_is_nearest_frontier() {
+        let mut pf = PathFinder::new(vec![1; 25], 5).unwrap();
+        assert!(!pf.suggest_exploration_target(0, 0, 10));
+        pf.reveal(0, 0, 2);
+        assert!(pf.suggest_exploration_target(0, 0, 10));
+        assert_eq!(pf.path().len() / 2 - 1, 2);
+        assert!(!pf.suggest_exploration_target(0, 0, 1));
+        assert!(pf.result().truncated());
+    }
+
+    /// Test: un pasillo congestionado pierde atractivo y se recupera con ticks
+    #[test]
+    fn congestion_diverts_and_decays() {
+        let mut pf = PathFinder::new(vec![1; 9], 3).unwrap();
+        pf.set_congestion(5.0, 0.1).unwrap();
+        let opts = SearchOptions::new();
+        pf.search_with(&opts).unwrap();
+        let first = pf.path();
+        pf.report_agents(&first);
+        pf.search_with(&opts).unwrap();
+        assert_ne!(pf.path(), first);
+
+        pf.tick();
+        pf.tick();
+        assert!(pf.congestion().iter().all(|&l| l < 0.02));
+        pf.search_with(&opts).unwrap();
+        assert!(pf.cost() < 4.1);
+    }
+
+    /// Test: `path_cost` no toca la ruta actual
+    #[test]
+    fn path_cost_leaves_path_untouched() {
+        let mut pf = PathFinder::new(vec![1, 1, 0, 1], 2).unwrap();
+        assert_eq!(pf.path_cost(0, 0, 1, 1), 2.0);
+        assert_eq!(pf.path_cost(0, 1, 1, 0), f64::INFINITY);
+        assert_eq!(pf.path().len(), 6);
+    }
+
+    /// Test: `path_cost` repetido sale de la caché hasta que cambia el grid
+    #[test]
+    fn path_cost_is_memoized() {
+        let mut pf = PathFinder::new(vec![1; 9], 3).unwrap();
+        assert_eq!(pf.path_cost(0, 0, 2, 2), 4.0);
+        assert_eq!(pf.path_cost(0, 0, 2, 2), 4.0);
+        assert!(pf.metrics_json().contains("\"cache_hit_rate\":0.5"));
+        pf.set_cell(0, 1, 0);
+        pf.set_cell(1, 1, 0);
+        pf.set_cell(2, 1, 0);
+        assert_eq!(pf.path_cost(0, 0, 2, 2), f64::INFINITY);
+        assert!(pf.set_elevation(vec![0.0; 9]).unwrap());
+        assert_eq!(pf.path_cost(0, 0, 2, 0), 2.0);
+    }
+
+    /// Test: con ventana solo se expanden sus celdas y la ruta la respeta
+    #[test]
+    fn window_limits_search() {
+        let mut pf = PathFinder::new(vec![1; 100], 10).unwrap();
+        let mut opts = SearchOptions::new();
+        opts.set_window(2, 3, 4, 5);
+        assert!(pf.search_between(&opts, 2, 3, 5, 7).unwrap());
+        assert_eq!(pf.cost(), 7.0);
+        let window = |(x, y): (usize, usize)| (2..6).contains(&x) && (3..8).contains(&y);
+        assert!(pf.expanded.iter().all(|&i| window(pf.grid.coords(i))));
+        assert!(pf.path.iter().all(|&c| window(c)));
+
+        // La pared corta la ventana aunque por fuera del trozo haya paso.
+        for x in 2..6 {
+            pf.set_cell(x, 5, 0);
+        }
+        assert!(!pf.search_between(&opts, 2, 3, 5, 7).unwrap());
+        opts.clear_window();
+        assert!(pf.search_between(&opts, 2, 3, 5, 7).unwrap());
+
+        // Una ventana que se sale del rango de `usize` llega hasta el final.
+        opts.set_window(1, 1, usize::MAX, usize::MAX);
+        assert!(pf.search_between(&opts, 2, 3, 5, 7).unwrap());
+        assert!(!pf.search_between(&opts, 0, 3, 5, 7).unwrap());
+    }
+
+    /// Test: `reanchor` recorta la ruta o pide replanificar
+    #[test]
+    fn reanchor_trims_or_flags() {
+        let mut pf =
+            PathFinder::new(vec![1, 1, 1, 1, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1], 4).unwrap();
+        assert_eq!(pf.path(), vec![0, 0, 0, 1, 0, 2, 0, 3, 1, 3, 2, 3, 3, 3]);
+        assert!(pf.reanchor(0, 3));
+        assert_eq!(pf.path(), vec![0, 3, 1, 3, 2, 3, 3, 3]);
+        assert_eq!(pf.cost(), 3.0);
+
+        // Empujado a una celda junto a la ruta: se engancha al punto más avanzado.
+        assert!(pf.reanchor(2, 2));
+        assert_eq!(pf.path(), vec![2, 2, 2, 3, 3, 3]);
+        assert_eq!(pf.cost(), 2.0);
+        assert!(!pf.result().optimal());
+
+        // Lejos de la ruta, o con la ruta cortada, hay que replanificar.
+        assert!(!pf.reanchor(3, 0));
+        assert!(pf.result().needs_replan());
+        pf.set_cell(3, 3, 0);
+        assert!(!pf.reanchor(2, 3));
+        assert_eq!(pf.path(), vec![2, 2, 2, 3, 3, 3]);
+        pf.set_cell(3, 3, 1);
+        assert!(pf.search_with(&SearchOptions::new()).unwrap());
+        assert!(!pf.result().needs_replan());
+    }
+
+    /// Test: la capa programada solo encarece la ruta en su horario
+    #[test]
+    fn scheduled_overlay_follows_time() {
+        let mut pf = PathFinder::new(vec![1; 9], 3).unwrap();
+        let mut crowded = vec![0.0; 9];
+        crowded[1] = 10.0;
+        crowded[3] = 10.0;
+        assert!(
+            pf.add_cost_overlay("mercado", crowded, &[9.0, 17.0])
+                .unwrap()
+        );
+        assert!(!pf.add_cost_overlay("roto", vec![1.0; 9], &[9.0]).unwrap());
+        for bad in [f32::NAN, -1.0] {
+            let mut costs = vec![1.0; 9];
+            costs[4] = bad;
+            assert!(!pf.add_cost_overlay("roto", costs, &[]).unwrap());
+        }
+        let opts = SearchOptions::new();
+        pf.search_with(&opts).unwrap();
+        assert_eq!(pf.cost(), 4.0);
+
+        assert!(pf.set_time(12.0).unwrap());
+        assert_eq!(pf.active_overlays(), vec!["mercado"]);
+        pf.search_with(&opts).unwrap();
+        assert_eq!(pf.cost(), 14.0);
+
+        let restored = PathFinder::load_scenario(&pf.save_scenario()).unwrap();
+        assert_eq!(restored.active_overlays(), vec!["mercado"]);
+        assert!(pf.set_time(18.0).unwrap());
+        pf.search_with(&opts).unwrap();
+        assert_eq!(pf.cost(), 4.0);
+        assert!(pf.remove_cost_overlay("mercado"));
+    }
+
+    /// Test: `advance` mueve peligro y capas programadas con el reloj, igual
+    /// que los pasos sueltos, y el reloj se guarda en el escenario
+    #[test]
+    fn sim_clock_drives_dynamic_costs() {
+        let setup = || {
+            let mut pf = PathFinder::new(vec![1; 9], 3).unwrap();
+            pf.set_hazard_rules(0.5, 0.1, 1.0).unwrap();
+            pf.ignite(1, 1, 1.0);
+            assert!(
+                pf.add_cost_overlay("mercado", vec![1.0; 9], &[9.0, 17.0])
+                    .unwrap()
+            );
+            pf
+        };
+        let mut pf = setup();
+        assert!(!pf.advance(1).unwrap());
+        assert!(!pf.set_sim_clock(f32::NAN, 8.0).unwrap());
+        assert!(!pf.set_sim_clock(-0.5, 8.0).unwrap());
+        assert_eq!(pf.sim_tick(), None);
+        assert!(pf.set_sim_clock(0.5, 8.0).unwrap());
+        assert!(pf.active_overlays().is_empty());
+        assert!(pf.advance(2).unwrap());
+        assert_eq!(pf.sim_tick(), Some(2));
+        assert_eq!(pf.active_overlays(), vec!["mercado"]);
+        // La hora es del reloj mientras esté activo.
+        assert!(!pf.set_time(20.0).unwrap());
+        assert_eq!(pf.active_overlays(), vec!["mercado"]);
+
+        let mut manual = setup();
+        manual.tick();
+        manual.tick();
+        assert_eq!(pf.hazard(), manual.hazard());
+
+        let restored = PathFinder::load_scenario(&pf.save_scenario()).unwrap();
+        assert_eq!(restored.sim_tick(), Some(2));
+
+        // Los pasos de un `advance` tienen tope.
+        assert!(pf.advance(u32::MAX).unwrap());
+        assert_eq!(pf.sim_tick(), Some(2 + clock::MAX_ADVANCE));
+    }
+
+    /// Test: costes de 16 y 32 bits con su centinela de intransitable
+    #[test]
+    fn integer_cost_grids() {
+        let mut pf = PathFinder::new(vec![1; 9], 3).unwrap();
+        let wide = [1, u32::MAX, 1, 1, 4_000_000_000, 1, 1, 4_000_000_000, 1];
+        assert!(pf.search_costs_u32(&wide, 0, 0, 2, 2).unwrap());
+        assert_eq!(pf.integer_cost(), 4_000_000_003);
+        assert_eq!(pf.path().len(), 10);
+
+        let narrow = [1, 1, 1, u16::MAX, u16::MAX, 1, 1, 1, 1];
+        assert!(pf.search_costs_u16(&narrow, 0, 0, 2, 0).unwrap());
+        assert_eq!(pf.integer_cost(), 6);
+        assert!(!pf.search_costs_u16(&narrow[..4], 0, 0, 2, 0).unwrap());
+        pf.search_with(&SearchOptions::new()).unwrap();
+        assert_eq!(pf.integer_cost(), 0);
+    }
+
+    /// Test: costes reales, con NaN como intransitable
+    #[test]
+    fn float_cost_grid() {
+        let mut pf = PathFinder::new(vec![1; 4], 2).unwrap();
+        assert!(
+            pf.search_costs_f32(&[0.0, f32::NAN, 0.5, 0.25], 0, 0, 1, 1)
+                .unwrap()
+        );
+        assert_eq!(pf.path(), vec![0, 0, 1, 0, 1, 1]);
+        assert_eq!(pf.cost(), 0.75);
+        assert!(
+            !pf.search_costs_f32(&[0.0, f32::NAN, f32::NAN, 0.25], 0, 0, 1, 1)
+                .unwrap()
+        );
+    }
+
+    /// Test: los k destinos más cercanos caminando, no en línea recta
+    #[test]
+    fn nearest_goals_by_walking() {
+        let mut pf = PathFinder::new(vec![1, 0, 1, 1, 0, 1, 1, 1, 1], 3).unwrap();
+        let opts = SearchOptions::new();
+        let goals = [0, 2, 2, 0, 1, 2, 0, 2];
+        assert_eq!(
+            pf.nearest_goals(&opts, 0, 0, &goals, 2),
+            vec![2.0, 0.0, 2.0, 1.0, 2.0, 5.0]
+        );
+        let all = pf.nearest_goals(&opts, 0, 0, &goals, 10);
+        assert_eq!(all.len(), 9);
+        assert_eq!(all[6..], [0.0, 2.0, 6.0]);
+    }
+
+    /// Test: el perfil sigue la ruta sobre la elevación o la capa dada
+    #[test]
+    fn profile_along_path() {
+        let mut pf = PathFinder::new(vec![1, 1, 0, 1], 2).unwrap();
+        assert!(pf.path_profile(None).unwrap().is_empty());
+        assert!(pf.set_elevation(vec![1.0, 2.0, 3.0, 4.0]).unwrap());
+        assert_eq!(pf.path_profile(None).unwrap(), vec![1.0, 2.0, 4.0]);
+        let cost = vec![0.5, 0.0, 9.0, 1.5];
+        assert_eq!(pf.path_profile(Some(cost)).unwrap(), vec![0.5, 0.0, 1.5]);
+    }
+
+    /// Test: con relajación, un destino bloqueado se sustituye por el más cercano
+    #[test]
+    fn blocked_goal_is_relaxed() {
+        let mut cells = vec![1; 9];
+        cells[8] = 0;
+        let mut pf = PathFinder::new(cells, 3).unwrap();
+        let mut opts = SearchOptions::new();
+        assert!(!pf.search_with(&opts).unwrap());
+        opts.set_goal_relaxation(1);
+        assert!(pf.search_with(&opts).unwrap());
+        let res = pf.result();
+        assert_eq!(res.relaxed_goal(), vec![1, 2]);
+        assert_eq!(pf.path()[pf.path().len() - 2..], [1, 2]);
+
+        pf.set_cell(2, 2, 1);
+        assert!(pf.search_with(&opts).unwrap());
+        assert!(pf.result().relaxed_goal().is_empty());
+    }
+
+    /// Test: el ruido con el generador inyectado se repite con el mismo estado
+    #[test]
+    fn injected_rng_drives_noise() {
+        let mut pf = PathFinder::new(vec![1; 36], 6).unwrap();
+        let mut opts = SearchOptions::new();
+        opts.set_noise_from_rng(0.5);
+        pf.set_rng_state(99);
+        let routes: Vec<_> = (0..4)
+            .map(|_| (pf.search_with(&opts).unwrap(), pf.path()))
+            .collect();
+        let after = pf.rng_state();
+        assert_ne!(after, 99);
+
+        pf.set_rng_state(99);
+        let again: Vec<_> = (0..4)
+            .map(|_| (pf.search_with(&opts).unwrap(), pf.path()))
+            .collect();
+        assert_eq!(again, routes);
+        assert_eq!(pf.rng_state(), after);
+        assert!(routes.windows(2).any(|w| w[0] != w[1]));
+        let replayed = PathFinder::replay(&pf.record()).unwrap();
+        assert_eq!(replayed.path, pf.path);
+
+        // Los mapas también sacan su semilla del generador.
+        pf.set_rng_state(99);
+        let map = pf.dijkstra_map(&opts, &[0, 0]).values();
+        assert_ne!(pf.rng_state(), 99);
+        pf.set_rng_state(99);
+        assert_eq!(pf.dijkstra_map(&opts, &[0, 0]).values(), map);
+    }
+
+    /// Test: la huella cambia con el grid, sus capas y las opciones
+    #[test]
+    fn grid_hash_tracks_content() {
+        let mut pf = PathFinder::new(vec![1; 9], 3).unwrap();
+        let base = pf.grid_hash();
+        assert_eq!(PathFinder::new(vec![1; 9], 3).unwrap().grid_hash(), base);
+        pf.set_elevation(vec![0.0; 9]).unwrap();
+        assert_ne!(pf.grid_hash(), base);
+        assert_ne!(
+            PathFinder::new(vec![1, 1, 1, 1, 0, 1, 1, 1, 1], 3)
+                .unwrap()
+                .grid_hash(),
+            base
+        );
+
+        let mut opts = SearchOptions::new();
+        let plain = pf.grid_hash_with(&opts);
+        assert_ne!(plain, pf.grid_hash());
+        opts.set_noise(0.1, 3);
+        assert_ne!(pf.grid_hash_with(&opts), plain);
+    }
+
+    /// Test: el delta solo trae lo cambiado desde la generación pedida
+    #[test]
+    fn delta_since_generation() {
+        let mut pf = PathFinder::new(vec![1; 9], 3).unwrap();
+        assert_eq!(pf.generation(), 0);
+        assert!(pf.snapshot_delta(0).is_empty());
+        pf.block_segment(0.5, 1.5, 0.5, 1.5, 0.0);
+        assert_eq!(pf.snapshot_delta(0), vec![0, 1, 0]);
+        let seen = pf.generation();
+        pf.block_segment(2.5, 1.5, 2.5, 1.5, 0.0);
+        assert_eq!(pf.snapshot_delta(seen), vec![2, 1, 0]);
+        assert_eq!(pf.snapshot_delta(0).len(), 6);
+        assert_eq!(pf.generation(), seen + 1);
+    }
+
+    /// Test: la verificación rechaza rutas de otro mapa
+    #[test]
+    fn verify_checks_grid_hash() {
+        let pf = PathFinder::new(vec![1; 4], 2).unwrap();
+        let path = pf.path();
+        assert_eq!(
+            pf.verify_path_against(pf.grid_hash(), &path),
+            PathVerdict::Valid
+        );
+        assert_eq!(
+            pf.verify_path_against(pf.grid_hash() ^ 1, &path),
+            PathVerdict::GridMismatch
+        );
+    }
+
+    /// Test: la frontera restante sale ordenada con f = g + h
+    #[cfg(feature = "debug")]
+    #[test]
+    fn open_list_dump() {
+        let mut pf = PathFinder::new(vec![1; 9], 3).unwrap();
+        // De (0, 0) a (0, 2): las celdas de la fila 1 quedan en la frontera.
+        pf.search_world(&SearchOptions::new(), 0.5, 0.5, 0.5, 2.5, 1.0)
+            .unwrap();
+        let dump = pf.open_list();
+        assert_eq!(dump[..5], [1.0, 0.0, 4.0, 1.0, 3.0]);
+        for entry in dump.chunks(5) {
+            assert_eq!(entry[2], entry[3] + entry[4]);
+        }
+        assert!(
+            dump.chunks(5)
+                .zip(dump.chunks(5).skip(1))
+                .all(|(a, b)| a[2] <= b[2])
+        );
+    }
+
+    /// Test: ALT da el mismo coste expandiendo menos celdas, también con los
+    /// landmarks guardados y cargados en otro buscador
+    #[test]
+    fn landmarks_expand_less() {
+        // Muro casi completo con el hueco en el lado contrario al destino:
+        // la Manhattan empuja hacia el muro y ALT no.
+        let n = 15;
+        let mut cells = vec![1; n * n];
+        for y in 1..n {
+            cells[7 * n + y] = 0;
+        }
+        let mut pf = PathFinder::new(cells, n).unwrap();
+        let opts = SearchOptions::new();
+        pf.search_with(&opts).unwrap();
+        let (plain_cost, plain_expanded) = (pf.cost(), pf.expanded.len());
+
+        assert_eq!(pf.build_landmarks(&opts, 3), 3);
+        assert!(pf.search_with_landmarks());
+        assert_eq!(pf.cost(), plain_cost);
+        assert!(pf.expanded.len() < plain_expanded);
+        let alt_expanded = pf.expanded.len();
+
+        // Guardados y cargados en otro buscador del mismo mapa valen igual.
+        let saved = pf.save_landmarks();
+        let mut other = PathFinder::new(pf.cells(), n).unwrap();
+        assert_eq!(other.restore_landmarks(&saved), Ok(3));
+        assert_eq!(other.landmark_cells(), pf.landmark_cells());
+        assert!(other.search_with_landmarks());
+        assert_eq!(other.expanded.len(), alt_expanded);
+        other.set_cell(0, 3, 0);
+        assert!(other.restore_landmarks(&saved).is_err());
+        assert!(other.restore_landmarks(&saved[..saved.len() - 1]).is_err());
+    }
 }
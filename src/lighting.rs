@@ -0,0 +1,91 @@
+use crate::grid::Grid;
+
+/// Una luz: celda, radio en celdas y brillo en su celda.
+pub(crate) type Light = ((usize, usize), u32, f32);
+
+/// Nivel de luz por celda (0 a 1) de `lights`. Cada luz alumbra en línea
+/// recta las celdas cuyo centro queda a `radio` o menos del suyo, si la
+/// línea de Bresenham entre ambas no pasa por una pared, y se apaga
+/// linealmente: a distancia `d` da `brillo * (1 - d / (radio + 1))`. Las
+/// paredes a la vista también reciben luz, para que se vean, pero tapan lo
+/// que hay detrás. Las luces se suman y el total se limita a 1; una luz
+/// sobre una pared no alumbra.
+pub(crate) fn light_map(grid: &Grid, lights: &[Light]) -> Vec<f32> {
+    let mut level = vec![0.0f32; grid.cells.len()];
+    for &(cell, radius, brightness) in lights {
+        if !grid.in_bounds(cell) || grid.cells[grid.idx(cell)] != 1 {
+            continue;
+        }
+        let reach = radius as usize;
+        let rows = cell.0.saturating_sub(reach)..cell.0.saturating_add(reach + 1).min(grid.height);
+        let cols = cell.1.saturating_sub(reach)..cell.1.saturating_add(reach + 1).min(grid.width);
+        for x in rows {
+            for y in cols.clone() {
+                let d = (x as f32 - cell.0 as f32).hypot(y as f32 - cell.1 as f32);
+                if d > radius as f32 || !in_sight(grid, cell, (x, y)) {
+                    continue;
+                }
+                let idx = grid.idx((x, y));
+                let value = brightness * (1.0 - d / (radius as f32 + 1.0));
+                level[idx] = (level[idx] + value).min(1.0);
+            }
+        }
+    }
+    level
+}
+
+/// Si ninguna celda entre `from` y `to` (sin contar los extremos) en la
+/// línea de Bresenham es pared.
+fn in_sight(grid: &Grid, from: (usize, usize), to: (usize, usize)) -> bool {
+    let (mut x, mut y) = (from.0 as i64, from.1 as i64);
+    let (tx, ty) = (to.0 as i64, to.1 as i64);
+    let (dx, dy) = ((tx - x).abs(), -(ty - y).abs());
+    let (sx, sy) = ((tx - x).signum(), (ty - y).signum());
+    let mut err = dx + dy;
+    loop {
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+        if (x, y) == (tx, ty) {
+            return true;
+        }
+        if grid.cells[grid.idx((x as usize, y as usize))] != 1 {
+            return false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: la luz se apaga con la distancia, ilumina la pared pero no lo
+    /// que hay detrás, y dos luces se suman
+    #[test]
+    fn light_is_occluded_by_walls() {
+        let grid = Grid::new(vec![1, 1, 1, 0, 1], 5, 1);
+        let level = light_map(&grid, &[((0, 0), 3, 1.0)]);
+        assert_eq!(level, vec![1.0, 0.75, 0.5, 0.25, 0.0]);
+        let level = light_map(&grid, &[((0, 0), 3, 1.0), ((0, 2), 1, 0.5)]);
+        assert_eq!(level, vec![1.0, 1.0, 1.0, 0.5, 0.0]);
+        assert_eq!(light_map(&grid, &[((0, 3), 3, 1.0)]), vec![0.0; 5]);
+    }
+
+    /// Test: la luz no rodea una pared aunque el rodeo quepa en el radio
+    #[test]
+    fn light_needs_line_of_sight() {
+        let mut cells = vec![1; 25];
+        cells[11] = 0;
+        let grid = Grid::square(cells, 5);
+        let level = light_map(&grid, &[((2, 0), 4, 1.0)]);
+        assert_eq!(level[grid.idx((2, 1))], 0.8);
+        assert_eq!(level[grid.idx((2, 2))], 0.0);
+        assert!(level[grid.idx((1, 2))] > 0.0);
+    }
+}
@@ -0,0 +1,91 @@
+use wasm_bindgen::prelude::*;
+
+use crate::PathFinder;
+use crate::grid::Grid;
+use crate::policy;
+
+/// Carga incremental de mapas enormes: el grid se reserva una sola vez en
+/// memoria wasm y se rellena por tramos, sin un único array JS gigante.
+#[wasm_bindgen]
+pub struct GridLoader {
+    grid: Grid,
+}
+
+#[wasm_bindgen]
+impl GridLoader {
+    /// Empieza un grid de `height` filas por `width` columnas. Si sus celdas
+    /// no caben en memoria es una anomalía; en modo tolerante el cargador
+    /// queda vacío y `finish_grid` falla.
+    pub fn begin_grid(width: usize, height: usize) -> Result<GridLoader, JsError> {
+        let mut cells = Vec::new();
+        let reserved = width
+            .checked_mul(height)
+            .is_some_and(|len| cells.try_reserve_exact(len).is_ok());
+        if !reserved {
+            policy::anomaly("el grid es demasiado grande")?;
+            return Ok(GridLoader {
+                grid: Grid::new(Vec::new(), 0, 0),
+            });
+        }
+        Ok(GridLoader {
+            grid: Grid::new(cells, width, height),
+        })
+    }
+
+    /// Añade bytes a continuación de los anteriores (no hace falta que sean
+    /// filas completas). Devuelve cuántas filas completas hay ya cargadas; lo
+    /// que sobrepase el tamaño declarado se descarta.
+    pub fn append_rows(&mut self, bytes: &[u8]) -> usize {
+        let room = self.grid.width * self.grid.height - self.grid.cells.len();
+        self.grid
+            .cells
+            .extend_from_slice(&bytes[..bytes.len().min(room)]);
+        self.grid.cells.len() / self.grid.width.max(1)
+    }
+
+    /// Cierra la carga y crea el `PathFinder`. Falla si faltan celdas.
+    pub fn finish_grid(self) -> Result<PathFinder, JsError> {
+        self.finish()
+            .map_err(|missing| JsError::new(&format!("faltan {missing} celdas por cargar")))
+    }
+}
+
+impl GridLoader {
+    /// Como `finish_grid`, devolviendo en el error el número de celdas que faltan.
+    pub(crate) fn finish(self) -> Result<PathFinder, usize> {
+        let expected = self.grid.width * self.grid.height;
+        if expected == 0 || self.grid.cells.len() < expected {
+            return Err(expected.max(1) - self.grid.cells.len());
+        }
+        Ok(PathFinder::from_grid(self.grid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: un grid rectangular cargado por tramos encuentra la ruta
+    #[test]
+    fn loader_streams_rows() {
+        let mut loader = GridLoader::begin_grid(4, 2).unwrap();
+        assert_eq!(loader.append_rows(&[1, 1, 0]), 0);
+        assert_eq!(loader.append_rows(&[0, 0, 1, 1, 1]), 2);
+        let pf = loader.finish().unwrap();
+        assert!(pf.has_path());
+        assert_eq!(pf.path().last(), Some(&3));
+    }
+
+    /// Test: cerrar antes de tiempo informa de lo que falta
+    #[test]
+    fn loader_incomplete() {
+        let mut loader = GridLoader::begin_grid(3, 3).unwrap();
+        loader.append_rows(&[1; 4]);
+        assert_eq!(loader.finish().err(), Some(5));
+
+        // Un tamaño que desborda no reserva nada y no se puede cerrar.
+        let mut huge = GridLoader::begin_grid(usize::MAX, 2).unwrap();
+        assert_eq!(huge.append_rows(&[1; 4]), 0);
+        assert!(huge.finish().is_err());
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use crate::SearchOptions;
+use crate::cost::StepCost;
+use crate::grid::Grid;
+use crate::search::{self, Outcome};
+
+/// Etiquetas distintas de puertas y llaves (una por bit del estado).
+pub(crate) const MAX_LABELS: u8 = 32;
+
+/// Puertas y llaves con etiqueta, por índice de celda. Una puerta solo se
+/// cruza después de pasar por una celda con la llave de su etiqueta.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Locks {
+    pub doors: HashMap<usize, u8>,
+    pub keys: HashMap<usize, u8>,
+}
+
+impl Locks {
+    /// Llaves que se tienen tras entrar en `idx` con `held`.
+    fn pick_up(&self, idx: usize, held: u32) -> u32 {
+        self.keys.get(&idx).map_or(held, |&label| held | 1 << label)
+    }
+
+    fn opens(&self, idx: usize, held: u32) -> bool {
+        self.doors
+            .get(&idx)
+            .is_none_or(|&label| held & (1 << label) != 0)
+    }
+}
+
+/// Búsqueda con costes cuyo estado es `(celda, llaves recogidas)`: la ruta
+/// puede volver sobre sus pasos para ir a por una llave. Las opciones que
+/// dependen de la dirección no se aplican.
+pub(crate) fn keyed_search(
+    grid: &Grid,
+    locks: &Locks,
+    opts: &SearchOptions,
+    start: (usize, usize),
+    goal: (usize, usize),
+) -> Outcome<((usize, usize), u32)> {
+    let mut costs = StepCost::new(grid, opts);
+    let initial = locks.pick_up(grid.idx(start), 0);
+    if !costs.allowed(start) || !costs.allowed(goal) || !locks.opens(grid.idx(start), initial) {
+        return Outcome::none();
+    }
+    costs.prepare(start, goal);
+    let h_scale = costs.min_step();
+
+    search::astar(
+        (start, initial),
+        |&(cell, _)| cell == goal,
+        |&(cell, held)| {
+            grid.neighbors(cell)
+                .filter(|&next| costs.allowed(next) && locks.opens(grid.idx(next), held))
+                .filter_map(|next| {
+                    let step = costs.step(cell, next)?;
+                    Some(((next, locks.pick_up(grid.idx(next), held)), step))
+                })
+                .collect::<Vec<_>>()
+        },
+        |&((x, y), _)| h_scale * (x.abs_diff(goal.0) + y.abs_diff(goal.1)) as f64,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: la ruta se desvía a por la llave antes de cruzar la puerta
+    #[test]
+    fn keyed_search_fetches_key() {
+        // Pasillo horizontal con una puerta en (0, 2) y su llave al fondo
+        // del ramal (2, 0).
+        #[rustfmt::skip]
+        let cells = vec![
+            1, 1, 1, 1,
+            1, 0, 0, 0,
+            1, 0, 0, 0,
+        ];
+        let grid = Grid::new(cells, 4, 3);
+        let mut locks = Locks::default();
+        locks.doors.insert(2, 3);
+        let opts = SearchOptions::default();
+        assert!(
+            keyed_search(&grid, &locks, &opts, (0, 0), (0, 3))
+                .result
+                .is_none()
+        );
+
+        locks.keys.insert(8, 3);
+        let found = keyed_search(&grid, &locks, &opts, (0, 0), (0, 3))
+            .result
+            .unwrap();
+        assert_eq!(found.cost, 2.0 + 2.0 + 3.0);
+        assert!(found.states.iter().any(|&(cell, _)| cell == (2, 0)));
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+
+use crate::SearchOptions;
+use crate::bytes::Writer;
+
+/// Costes ya calculados por `PathFinder::path_cost`, por índices de inicio
+/// y destino. Solo valen para una revisión de las celdas del grid y unas
+/// opciones: las opciones se anotan con `use_options` al guardar cada
+/// búsqueda y la revisión se comprueba en cada consulta, sin recorrer el
+/// grid. Los cambios de capas que no son celdas (elevación, penalización,
+/// vida de las paredes) se avisan con `clear`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CostMemo {
+    revision: u32,
+    options: Vec<u8>,
+    costs: HashMap<u64, f64>,
+}
+
+impl CostMemo {
+    /// Coste guardado de `start` a `goal`, después de vaciar lo calculado
+    /// con otra revisión del grid.
+    pub fn get(&mut self, revision: u32, start: usize, goal: usize) -> Option<f64> {
+        if revision != self.revision {
+            self.costs.clear();
+            self.revision = revision;
+        }
+        self.costs.get(&key(start, goal)).copied()
+    }
+
+    pub fn insert(&mut self, start: usize, goal: usize, cost: f64) {
+        self.costs.insert(key(start, goal), cost);
+    }
+
+    /// Opciones de las consultas siguientes; si no son las de antes se
+    /// vacía.
+    pub fn use_options(&mut self, options: &SearchOptions) {
+        let mut w = Writer::new();
+        options.write_to(&mut w);
+        let options = w.finish();
+        if options != self.options {
+            self.costs.clear();
+            self.options = options;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.costs.clear();
+    }
+}
+
+fn key(start: usize, goal: usize) -> u64 {
+    (start as u64) << 32 | goal as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: se vacía al cambiar la revisión o las opciones
+    #[test]
+    fn invalidated_by_revision_and_options() {
+        let mut memo = CostMemo::default();
+        let opts = SearchOptions::default();
+        memo.use_options(&opts);
+        assert_eq!(memo.get(0, 1, 2), None);
+        memo.insert(1, 2, 5.0);
+        assert_eq!(memo.get(0, 1, 2), Some(5.0));
+        assert_eq!(memo.get(0, 2, 1), None);
+        memo.use_options(&opts.clone());
+        assert_eq!(memo.get(0, 1, 2), Some(5.0));
+        assert_eq!(memo.get(1, 1, 2), None);
+
+        memo.insert(1, 2, 5.0);
+        let mut other = SearchOptions::default();
+        other.set_rotation_cost(0.5);
+        memo.use_options(&other);
+        assert_eq!(memo.get(1, 1, 2), None);
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::grid::Grid;
+use crate::policy;
+
+/// Penalización por debajo de la cual una celda se olvida.
+const FORGET_BELOW: f32 = 1e-3;
+
+/// Memoria de un agente: las celdas que ha pisado hace poco, cada una con
+/// una penalización que se atenúa a cada paso nuevo. Se pasa a
+/// `PathFinder::search_remembering` para que un NPC que deambula no vuelva
+/// por el pasillo del que acaba de salir. Las celdas van en coordenadas del
+/// grid, así que la misma memoria sirve aunque el grid cambie.
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct AgentMemory {
+    penalty: f32,
+    decay: f32,
+    cells: HashMap<(usize, usize), f32>,
+}
+
+#[wasm_bindgen]
+impl AgentMemory {
+    /// Cada celda visitada cuesta `penalty` más y lo recordado se multiplica
+    /// por `decay` (entre 0 y 1; fuera de rango es una anomalía y en modo
+    /// tolerante se ajusta) en cada visita posterior.
+    #[wasm_bindgen(constructor)]
+    pub fn new(penalty: f32, decay: f32) -> Result<AgentMemory, JsError> {
+        Ok(Self {
+            penalty: penalty.max(0.0),
+            decay: policy::unit(decay, "la atenuación")?,
+            cells: HashMap::new(),
+        })
+    }
+
+    /// Anota que el agente ha pisado `(x, y)`: atenúa lo recordado, olvida
+    /// lo que ya no pesa y deja la celda con la penalización completa.
+    pub fn visit(&mut self, x: usize, y: usize) {
+        let decay = self.decay;
+        self.cells.retain(|_, p| {
+            *p *= decay;
+            *p >= FORGET_BELOW
+        });
+        self.cells.insert((x, y), self.penalty);
+    }
+
+    /// Penalización recordada de `(x, y)`; 0 si no se recuerda.
+    pub fn penalty_at(&self, x: usize, y: usize) -> f32 {
+        self.cells.get(&(x, y)).copied().unwrap_or(0.0)
+    }
+
+    /// Celdas que se recuerdan.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn forget(&mut self) {
+        self.cells.clear();
+    }
+}
+
+impl AgentMemory {
+    /// Suma lo recordado a la penalización del grid; las celdas fuera del
+    /// grid se ignoran.
+    pub(crate) fn add_to(&self, grid: &mut Grid) {
+        if self.cells.is_empty() {
+            return;
+        }
+        let mut penalty = grid
+            .penalty
+            .take()
+            .unwrap_or_else(|| vec![0.0; grid.cells.len()]);
+        for (&cell, &p) in &self.cells {
+            if grid.in_bounds(cell) {
+                penalty[grid.idx(cell)] += p;
+            }
+        }
+        grid.set_penalty(Some(penalty));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: las visitas antiguas se atenúan hasta olvidarse
+    #[test]
+    fn memory_decays_and_forgets() {
+        let mut memory = AgentMemory::new(2.0, 0.5).unwrap();
+        memory.visit(0, 0);
+        memory.visit(0, 1);
+        assert_eq!(memory.penalty_at(0, 0), 1.0);
+        assert_eq!(memory.penalty_at(0, 1), 2.0);
+        for y in 2..20 {
+            memory.visit(0, y);
+        }
+        assert_eq!(memory.penalty_at(0, 0), 0.0);
+        assert!(memory.len() < 20);
+
+        let mut grid = Grid::square(vec![1; 4], 2);
+        memory.forget();
+        memory.visit(1, 1);
+        memory.visit(5, 5);
+        memory.add_to(&mut grid);
+        assert_eq!(grid.penalty, Some(vec![0.0, 0.0, 0.0, 1.0]));
+    }
+}
@@ -0,0 +1,73 @@
+/// Tiempos que se conservan para el percentil; los más antiguos se descartan.
+const TIME_WINDOW: usize = 1024;
+
+/// Contadores acumulados durante la vida de un `PathFinder`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Metrics {
+    queries: u64,
+    expansions: u64,
+    cache_hits: u64,
+    cache_lookups: u64,
+    times_ms: Vec<f64>,
+}
+
+impl Metrics {
+    pub fn record_query(&mut self, expansions: usize, elapsed_ms: f64) {
+        self.queries += 1;
+        self.expansions += expansions as u64;
+        if self.times_ms.len() == TIME_WINDOW {
+            self.times_ms.remove(0);
+        }
+        self.times_ms.push(elapsed_ms);
+    }
+
+    /// Anota una consulta a una caché, acertada o no.
+    pub fn record_lookup(&mut self, hit: bool) {
+        self.cache_lookups += 1;
+        self.cache_hits += u64::from(hit);
+    }
+
+    pub fn to_json(&self) -> String {
+        let hit_rate = match self.cache_lookups {
+            0 => 0.0,
+            n => self.cache_hits as f64 / n as f64,
+        };
+        let avg_expansions = match self.queries {
+            0 => 0.0,
+            n => self.expansions as f64 / n as f64,
+        };
+        format!(
+            "{{\"queries\":{},\"cache_hit_rate\":{hit_rate},\"avg_expansions\":{avg_expansions},\"p95_ms\":{}}}",
+            self.queries,
+            self.p95_ms()
+        )
+    }
+
+    fn p95_ms(&self) -> f64 {
+        if self.times_ms.is_empty() {
+            return 0.0;
+        }
+        let mut sorted = self.times_ms.clone();
+        sorted.sort_by(f64::total_cmp);
+        let rank = (sorted.len() as f64 * 0.95).ceil() as usize;
+        sorted[rank.clamp(1, sorted.len()) - 1]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: promedio de expansiones y percentil 95 de los tiempos
+    #[test]
+    fn metrics_aggregate() {
+        let mut m = Metrics::default();
+        for i in 1..=20 {
+            m.record_query(10 * i, i as f64);
+        }
+        assert_eq!(
+            m.to_json(),
+            "{\"queries\":20,\"cache_hit_rate\":0,\"avg_expansions\":105,\"p95_ms\":19}"
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use crate::grid::Grid;
+
+/// Colores del minimapa, empaquetados como `0xRRGGBBAA`.
+const WALL: u32 = 0x2020_28FF;
+const FREE: u32 = 0xD0D0_C8FF;
+const VISITED: u32 = 0x5A8C_DCFF;
+const PATH: u32 = 0xF0A0_20FF;
+
+/// Minimapa RGBA de `grid` reducido `scale` veces por lado: cada píxel
+/// cubre un bloque de `scale` x `scale` celdas (menos en los bordes) y es
+/// pared si lo son más de la mitad de ellas, espacio libre si no, y encima
+/// se pintan los bloques con celdas expandidas (`expanded`, índices) y los
+/// de la ruta. Una sola pasada por las celdas más la ruta y las expansiones.
+pub(crate) fn render(
+    grid: &Grid,
+    expanded: &[usize],
+    path: &[(usize, usize)],
+    scale: usize,
+) -> Vec<u8> {
+    let scale = scale.max(1);
+    let width = grid.width.div_ceil(scale);
+    let block = |(x, y): (usize, usize)| (x / scale) * width + y / scale;
+    let pixels = width * grid.height.div_ceil(scale);
+
+    let mut walls = vec![0; pixels];
+    let mut sizes = vec![0; pixels];
+    for (idx, &cell) in grid.cells.iter().enumerate() {
+        let b = block(grid.coords(idx));
+        sizes[b] += 1;
+        walls[b] += usize::from(cell != 1);
+    }
+    let mut colors: Vec<u32> = (0..pixels)
+        .map(|b| if walls[b] * 2 > sizes[b] { WALL } else { FREE })
+        .collect();
+    for &idx in expanded {
+        colors[block(grid.coords(idx))] = VISITED;
+    }
+    for &cell in path {
+        colors[block(cell)] = PATH;
+    }
+    colors.iter().flat_map(|c| c.to_be_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: cada bloque toma el color de la capa más alta que lo toca
+    #[test]
+    fn minimap_blocks() {
+        let grid = Grid::new(vec![0, 0, 1, 1, 1, 0, 1, 1, 1, 1, 1, 1], 4, 3);
+        let image = render(&grid, &[], &[], 2);
+        assert_eq!(image.len(), 2 * 2 * 4);
+        let color = |image: &[u8], i: usize| {
+            u32::from_be_bytes(image[i * 4..i * 4 + 4].try_into().unwrap())
+        };
+        assert_eq!(color(&image, 0), WALL);
+        assert_eq!(color(&image, 1), FREE);
+
+        let image = render(&grid, &[10], &[(2, 0), (2, 1)], 2);
+        assert_eq!(color(&image, 2), PATH);
+        assert_eq!(color(&image, 3), VISITED);
+        assert_eq!(render(&grid, &[], &[], 0).len(), 12 * 4);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::grid::{self, Grid};
+
+/// Códigos de maniobra de `PathFinder::instructions`, estables para que cada
+/// aplicación ponga sus propios textos traducidos.
+pub(crate) const DEPART: usize = 0;
+pub(crate) const TURN_LEFT: usize = 1;
+pub(crate) const TURN_RIGHT: usize = 2;
+pub(crate) const U_TURN: usize = 3;
+pub(crate) const ARRIVE: usize = 4;
+
+/// Puntos cardinales de cada dirección de `DIRS` (las filas crecen hacia el
+/// sur y las columnas hacia el este).
+const HEADINGS: [&str; 4] = ["sur", "este", "norte", "oeste"];
+
+/// Una indicación de la ruta: la maniobra, el rumbo tras ella, las casillas
+/// que se avanzan con ese rumbo, la celda donde se hace y si esa celda es un
+/// cruce (tres o más vecinos transitables).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Instruction {
+    pub code: usize,
+    pub heading: usize,
+    pub cells: usize,
+    pub at: (usize, usize),
+    pub junction: bool,
+}
+
+/// Indicaciones paso a paso de `path`: salida, un giro por cada cambio de
+/// rumbo y llegada. Una ruta de menos de dos celdas no tiene indicaciones.
+pub(crate) fn instructions(grid: &Grid, path: &[(usize, usize)]) -> Vec<Instruction> {
+    let mut steps: Vec<Instruction> = Vec::new();
+    for pair in path.windows(2) {
+        let Some(heading) = grid::direction(pair[0], pair[1]) else {
+            continue;
+        };
+        match steps.last_mut() {
+            Some(last) if last.heading == heading => last.cells += 1,
+            last => {
+                let code = match last.map(|l| (heading + 4 - l.heading) % 4) {
+                    None => DEPART,
+                    Some(1) => TURN_LEFT,
+                    Some(3) => TURN_RIGHT,
+                    _ => U_TURN,
+                };
+                let junction = grid
+                    .neighbors(pair[0])
+                    .filter(|&n| grid.walkable(n))
+                    .count()
+                    >= 3;
+                steps.push(Instruction {
+                    code,
+                    heading,
+                    cells: 1,
+                    at: pair[0],
+                    junction,
+                });
+            }
+        }
+    }
+    if let (Some(last), Some(&goal)) = (steps.last().copied(), path.last()) {
+        steps.push(Instruction {
+            code: ARRIVE,
+            heading: last.heading,
+            cells: 0,
+            at: goal,
+            junction: false,
+        });
+    }
+    steps
+}
+
+/// Texto en español de una indicación.
+pub(crate) fn describe(step: &Instruction) -> String {
+    let heading = HEADINGS[step.heading];
+    let cells = match step.cells {
+        1 => "1 casilla".to_string(),
+        n => format!("{n} casillas"),
+    };
+    let place = if step.junction { " en el cruce" } else { "" };
+    match step.code {
+        DEPART => format!("Sal hacia el {heading} y avanza {cells}"),
+        TURN_LEFT => format!("Gira a la izquierda{place} hacia el {heading} y avanza {cells}"),
+        TURN_RIGHT => format!("Gira a la derecha{place} hacia el {heading} y avanza {cells}"),
+        U_TURN => format!("Da media vuelta{place} hacia el {heading} y avanza {cells}"),
+        _ => "Has llegado a tu destino".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: los tramos rectos se agrupan y cada giro sabe su lado y si es
+    /// un cruce
+    #[test]
+    fn path_to_instructions() {
+        let grid = Grid::square(vec![1, 1, 1, 0, 1, 1, 0, 1, 1], 3);
+        let path = [(0, 0), (0, 1), (1, 1), (2, 1), (2, 2)];
+        let steps = instructions(&grid, &path);
+        let codes: Vec<_> = steps.iter().map(|s| (s.code, s.heading, s.cells)).collect();
+        assert_eq!(
+            codes,
+            vec![
+                (DEPART, 1, 1),
+                (TURN_RIGHT, 0, 2),
+                (TURN_LEFT, 1, 1),
+                (ARRIVE, 1, 0)
+            ]
+        );
+        assert!(steps[1].junction && !steps[2].junction);
+        assert_eq!(
+            describe(&steps[1]),
+            "Gira a la derecha en el cruce hacia el sur y avanza 2 casillas"
+        );
+        assert!(instructions(&grid, &[(0, 0)]).is_empty());
+    }
+}
@@ -0,0 +1,496 @@
+use wasm_bindgen::prelude::*;
+
+use crate::bytes::{Reader, Writer};
+use crate::cost::SlopeModel;
+use crate::policy;
+use crate::queue::OpenList;
+
+/// Opciones de la búsqueda con costes (`PathFinder::search_with`).
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct SearchOptions {
+    pub(crate) noise: f32,
+    pub(crate) seed: u32,
+    pub(crate) rng_seed: bool,
+    pub(crate) no_reverse: bool,
+    pub(crate) heading: Option<u8>,
+    pub(crate) slope: Option<SlopeModel>,
+    pub(crate) region: Option<Vec<u8>>,
+    pub(crate) max_clearance: bool,
+    pub(crate) min_clearance: u32,
+    pub(crate) min_turns: bool,
+    pub(crate) rotation_cost: f32,
+    pub(crate) break_cost: f32,
+    pub(crate) goal_radius: u32,
+    pub(crate) open_list: OpenList,
+    pub(crate) annotate: bool,
+    pub(crate) max_cost: Option<f32>,
+    pub(crate) window: Option<Window>,
+    pub(crate) must_visit: Option<MustVisit>,
+    pub(crate) transitions: Option<Transitions>,
+    pub(crate) capacity: u32,
+}
+
+/// Rectángulo del grid: filas `x..x + height` y columnas `y..y + width`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Window {
+    pub x: usize,
+    pub y: usize,
+    pub height: usize,
+    pub width: usize,
+}
+
+impl Window {
+    pub fn contains(&self, (x, y): (usize, usize)) -> bool {
+        (self.x..self.x.saturating_add(self.height)).contains(&x)
+            && (self.y..self.y.saturating_add(self.width)).contains(&y)
+    }
+}
+
+/// Etiqueta de terreno que la ruta tiene que pisar al menos una vez, sobre
+/// una capa de terreno con una entrada por celda.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct MustVisit {
+    pub terrain: Vec<u8>,
+    pub label: u8,
+}
+
+impl MustVisit {
+    /// Si la celda `idx` tiene la etiqueta.
+    pub fn hits(&self, idx: usize) -> bool {
+        self.terrain.get(idx) == Some(&self.label)
+    }
+}
+
+/// Máximo de etiquetas de una matriz de transiciones: las del terreno son
+/// bytes, así que no hay más.
+const MAX_LABELS: usize = 256;
+
+/// Pasos prohibidos entre etiquetas de terreno: `forbidden[a * labels + b]`
+/// indica que no se puede entrar en una celda `b` directamente desde una
+/// `a`. Las etiquetas `labels` o mayores no tienen restricciones.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Transitions {
+    pub terrain: Vec<u8>,
+    pub labels: usize,
+    pub forbidden: Vec<u8>,
+}
+
+impl Transitions {
+    /// Si se puede pasar de la celda `from` a la vecina `to`.
+    pub fn allows(&self, from: usize, to: usize) -> bool {
+        let label = |idx: usize| self.terrain.get(idx).map(|&l| usize::from(l));
+        match (label(from), label(to)) {
+            (Some(a), Some(b)) if a < self.labels && b < self.labels => self
+                .forbidden
+                .get(a * self.labels + b)
+                .is_none_or(|&f| f == 0),
+            _ => true,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl SearchOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Perturba el coste de cada paso con ruido en `[0, amplitude)` derivado de
+    /// `seed`. Cada NPC con su propia semilla toma una ruta distinta, con un
+    /// coste como mucho `1 + amplitude` veces el óptimo.
+    pub fn set_noise(&mut self, amplitude: f32, seed: u32) {
+        self.noise = amplitude;
+        self.seed = seed;
+        self.rng_seed = false;
+    }
+
+    /// Como `set_noise` pero con una semilla nueva en cada búsqueda, sacada
+    /// del generador del `PathFinder` (`PathFinder::set_rng_state`), para
+    /// que la aleatoriedad siga la semilla de la simulación del juego. Vale
+    /// para todo lo del `PathFinder` que recibe opciones (búsquedas, mapas,
+    /// isócronas, asignaciones, comparativas...): cada llamada saca una
+    /// semilla. El registro de `record` guarda la semilla usada.
+    pub fn set_noise_from_rng(&mut self, amplitude: f32) {
+        self.noise = amplitude;
+        self.rng_seed = true;
+    }
+
+    /// Prohíbe los giros de 180° inmediatos (agentes tipo vehículo o serpiente
+    /// que no pueden girar sobre sí mismos). `heading` es la dirección en la
+    /// que ya avanza el agente al salir (índice de 0 a 3: abajo, derecha,
+    /// arriba, izquierda); sin ella el primer paso es libre.
+    pub fn set_no_reverse(&mut self, enabled: bool, heading: Option<u8>) {
+        self.no_reverse = enabled;
+        self.heading = heading.filter(|&d| d < 4);
+    }
+
+    /// Coste energético sobre la capa de elevación (`PathFinder::set_elevation`):
+    /// cada paso cuesta `1 + uphill * dh` al subir y `1 - downhill * |dh|` al
+    /// bajar (con un mínimo de 0.1); los desniveles mayores que `max_slope`
+    /// son intransitables. Un factor negativo o que no es un número, o una
+    /// `max_slope` negativa, es una anomalía (ver `set_policy`) y en modo
+    /// tolerante se ignora.
+    pub fn set_slope_model(
+        &mut self,
+        uphill: f32,
+        downhill: f32,
+        max_slope: f32,
+    ) -> Result<(), JsError> {
+        let slope = SlopeModel {
+            uphill,
+            downhill,
+            max_slope,
+        };
+        if !slope.is_valid() {
+            policy::anomaly("modelo de pendiente con factores no válidos")?;
+            return Ok(());
+        }
+        self.slope = Some(slope);
+        Ok(())
+    }
+
+    /// Restringe la búsqueda a las celdas con valor distinto de 0 en `mask`
+    /// (una entrada por celda); el resto nunca se expande.
+    pub fn set_region_mask(&mut self, mask: Vec<u8>) {
+        self.region = Some(mask);
+    }
+
+    pub fn clear_region_mask(&mut self) {
+        self.region = None;
+    }
+
+    /// Objetivo de ruta más segura: maximiza la menor distancia a una pared
+    /// a lo largo del camino, sin contar inicio y destino (el borde del grid
+    /// cuenta como pared) y, entre las rutas con esa holgura, devuelve la de
+    /// menor coste.
+    pub fn set_max_clearance(&mut self, enabled: bool) {
+        self.max_clearance = enabled;
+    }
+
+    /// Exige que la ruta no pase por pasos de menos de `width` celdas de
+    /// ancho (ver `PathFinder::convoy_path`). Con 0 no hay restricción.
+    pub fn set_min_width(&mut self, width: u32) {
+        self.min_clearance = clearance_for_width(width);
+    }
+
+    /// Orientación inicial del agente (índice de 0 a 3: abajo, derecha,
+    /// arriba, izquierda). La usan `set_no_reverse`, el coste de rotación y
+    /// `PathFinder::path_facing`.
+    pub fn set_facing(&mut self, heading: Option<u8>) {
+        self.heading = heading.filter(|&d| d < 4);
+    }
+
+    /// Suma `cost` por cada giro de 90° (el doble en media vuelta), incluido
+    /// el giro desde la orientación inicial si se ha fijado.
+    pub fn set_rotation_cost(&mut self, cost: f32) {
+        self.rotation_cost = cost;
+    }
+
+    /// Permite derribar las paredes con puntos de vida
+    /// (`PathFinder::set_wall_hit_points`) pagando `cost_per_hp` por cada
+    /// punto. Con 0 las paredes vuelven a ser infranqueables.
+    pub fn set_break_cost(&mut self, cost_per_hp: f32) {
+        self.break_cost = cost_per_hp;
+    }
+
+    /// Si el destino está bloqueado o no se alcanza, busca en su lugar la
+    /// celda alcanzable más cercana a `radius` celdas o menos (distancia
+    /// euclídea) y lo indica en `PathResult::relaxed_goal`. Con 0 no hay
+    /// relajación.
+    pub fn set_goal_relaxation(&mut self, radius: u32) {
+        self.goal_radius = radius;
+    }
+
+    /// Estructura de la frontera (ver `OpenList`). No cambia el resultado,
+    /// solo el tiempo, y no se guarda con `record`.
+    pub fn set_open_list(&mut self, open_list: OpenList) {
+        self.open_list = open_list;
+    }
+
+    /// Abandona la búsqueda en cuanto ninguna ruta puede costar `max_cost` o
+    /// menos (coste hecho más la estimación de lo que falta), para consultas
+    /// del tipo "¿está la tienda a 20 casillas o menos?": sin ruta y con
+    /// `PathResult::truncated` si se pasó. `None` quita la cota. No se
+    /// aplica con `set_min_turns`.
+    pub fn set_max_cost(&mut self, max_cost: Option<f32>) {
+        self.max_cost = max_cost;
+    }
+
+    /// Limita la búsqueda a la ventana de `height` x `width` celdas con la
+    /// esquina en `(x, y)`, sin copiar el grid: un mundo por trozos puede
+    /// planificar dentro de un trozo sobre un único grid maestro (ver
+    /// `PathFinder::search_between`). Las coordenadas siguen siendo las del
+    /// grid completo.
+    pub fn set_window(&mut self, x: usize, y: usize, height: usize, width: usize) {
+        self.window = Some(Window {
+            x,
+            y,
+            height,
+            width,
+        });
+    }
+
+    pub fn clear_window(&mut self) {
+        self.window = None;
+    }
+
+    /// Exige que la ruta pase por al menos una celda con etiqueta `label` en
+    /// `terrain` (una entrada por celda), p. ej. "tiene que cruzar un
+    /// puente". La búsqueda recorre dos copias del grid, antes y después de
+    /// pisar la etiqueta, y solo acepta el destino en la segunda. Las
+    /// opciones que dependen de la dirección (`no_reverse`, giros) no se
+    /// aplican con esta restricción.
+    pub fn set_must_visit(&mut self, terrain: Vec<u8>, label: u8) {
+        self.must_visit = Some(MustVisit { terrain, label });
+    }
+
+    pub fn clear_must_visit(&mut self) {
+        self.must_visit = None;
+    }
+
+    /// Prohíbe pasos entre etiquetas de `terrain` (una entrada por celda),
+    /// p. ej. de agua profunda a acantilado: `matrix` tiene `labels * labels`
+    /// entradas y un valor distinto de 0 en `matrix[a * labels + b]` impide
+    /// entrar en una celda `b` directamente desde una `a`. Se aplica al
+    /// generar los vecinos, así que sigue valiendo ir de `a` a `b` pasando
+    /// por otra etiqueta. Más de 256 etiquetas o una matriz de otro tamaño es
+    /// una anomalía (ver `set_policy`) y en modo tolerante se ignora.
+    pub fn set_transition_matrix(
+        &mut self,
+        terrain: Vec<u8>,
+        labels: usize,
+        matrix: Vec<u8>,
+    ) -> Result<(), JsError> {
+        if labels > MAX_LABELS || labels.checked_mul(labels) != Some(matrix.len()) {
+            policy::anomaly("la matriz de transiciones no tiene labels * labels entradas")?;
+            return Ok(());
+        }
+        self.transitions = Some(Transitions {
+            terrain,
+            labels,
+            forbidden: matrix,
+        });
+        Ok(())
+    }
+
+    pub fn clear_transition_matrix(&mut self) {
+        self.transitions = None;
+    }
+
+    /// Bits de capacidad que necesita el agente al cambiar de planta en
+    /// `PathFinder::search_layers` (p. ej. 1 = silla de ruedas): solo usa los
+    /// ascensores que los cubren (`PathFinder::add_elevator`) y, si hay
+    /// alguno, ninguna escalera ni paso normal. Con 0 sirve todo.
+    pub fn set_required_capacity(&mut self, flags: u32) {
+        self.capacity = flags;
+    }
+
+    /// Objetivo de mínimos giros: minimiza primero el número de cambios de
+    /// dirección y después el coste, lo que da tramos rectos largos (puzzles
+    /// sobre grid, ruteo de circuitos). El coste devuelto no incluye los giros.
+    pub fn set_min_turns(&mut self, enabled: bool) {
+        self.min_turns = enabled;
+    }
+
+    /// Anota cada expansión (padre, `g`, prioridad y desempate) para
+    /// `PathFinder::explain` y `PathFinder::to_dot`. Apagado por defecto: sin
+    /// él la búsqueda no guarda nada de eso. No cambia la ruta.
+    pub fn set_annotate(&mut self, enabled: bool) {
+        self.annotate = enabled;
+    }
+}
+
+/// Holgura mínima que garantiza un paso de `width` celdas. Un pasillo de
+/// ancho `w` da holgura `ceil(w / 2)` en su centro, así que la holgura `c`
+/// asegura un ancho de `2c - 1`.
+pub(crate) fn clearance_for_width(width: u32) -> u32 {
+    if width == 0 { 0 } else { width / 2 + 1 }
+}
+
+/// Ancho garantizado por una holgura `c` (inversa de `clearance_for_width`).
+pub(crate) fn width_for_clearance(clearance: u32) -> u32 {
+    (2 * clearance).saturating_sub(1)
+}
+
+impl SearchOptions {
+    pub(crate) fn write_to(&self, w: &mut Writer) {
+        w.f32(self.noise);
+        w.u32(self.seed);
+        w.u8(u8::from(self.no_reverse));
+        w.u8(self.heading.unwrap_or(u8::MAX));
+        match self.slope {
+            Some(slope) => {
+                w.u8(1);
+                w.f32(slope.uphill);
+                w.f32(slope.downhill);
+                w.f32(slope.max_slope);
+            }
+            None => w.u8(0),
+        }
+        match &self.region {
+            Some(mask) => {
+                w.u8(1);
+                w.u32(mask.len() as u32);
+                w.bytes(mask);
+            }
+            None => w.u8(0),
+        }
+        w.u8(u8::from(self.max_clearance));
+        w.u32(self.min_clearance);
+        w.u8(u8::from(self.min_turns));
+        w.f32(self.rotation_cost);
+        w.f32(self.break_cost);
+        w.u32(self.goal_radius);
+        match self.max_cost {
+            Some(max) => {
+                w.u8(1);
+                w.f32(max);
+            }
+            None => w.u8(0),
+        }
+        match self.window {
+            Some(window) => {
+                w.u8(1);
+                let Window {
+                    x,
+                    y,
+                    height,
+                    width,
+                } = window;
+                [x, y, height, width].iter().for_each(|&v| w.u32(v as u32));
+            }
+            None => w.u8(0),
+        }
+        match &self.must_visit {
+            Some(visit) => {
+                w.u8(1);
+                w.u32(visit.terrain.len() as u32);
+                w.bytes(&visit.terrain);
+                w.u8(visit.label);
+            }
+            None => w.u8(0),
+        }
+        match &self.transitions {
+            Some(t) => {
+                w.u8(1);
+                w.u32(t.terrain.len() as u32);
+                w.bytes(&t.terrain);
+                w.u32(t.labels as u32);
+                w.bytes(&t.forbidden);
+            }
+            None => w.u8(0),
+        }
+        w.u32(self.capacity);
+    }
+
+    pub(crate) fn read_from(r: &mut Reader) -> Option<Self> {
+        let noise = r.f32()?;
+        let seed = r.u32()?;
+        let no_reverse = r.u8()? != 0;
+        let heading = Some(r.u8()?).filter(|&d| d < 4);
+        let slope = match r.u8()? {
+            0 => None,
+            _ => Some(SlopeModel {
+                uphill: r.f32()?,
+                downhill: r.f32()?,
+                max_slope: r.f32()?,
+            })
+            .filter(SlopeModel::is_valid)
+            .map(Some)?,
+        };
+        let region = match r.u8()? {
+            0 => None,
+            _ => {
+                let len = r.u32()? as usize;
+                Some(r.bytes(len)?.to_vec())
+            }
+        };
+        let max_clearance = r.u8()? != 0;
+        let min_clearance = r.u32()?;
+        let min_turns = r.u8()? != 0;
+        let rotation_cost = r.f32()?;
+        let break_cost = r.f32()?;
+        let goal_radius = r.u32()?;
+        let max_cost = match r.u8()? {
+            0 => None,
+            _ => Some(r.f32()?),
+        };
+        let window = match r.u8()? {
+            0 => None,
+            _ => Some(Window {
+                x: r.u32()? as usize,
+                y: r.u32()? as usize,
+                height: r.u32()? as usize,
+                width: r.u32()? as usize,
+            }),
+        };
+        let must_visit = match r.u8()? {
+            0 => None,
+            _ => {
+                let len = r.u32()? as usize;
+                let terrain = r.bytes(len)?.to_vec();
+                let label = r.u8()?;
+                Some(MustVisit { terrain, label })
+            }
+        };
+        let transitions = match r.u8()? {
+            0 => None,
+            _ => {
+                let len = r.u32()? as usize;
+                let terrain = r.bytes(len)?.to_vec();
+                let labels = r.u32()? as usize;
+                if labels > MAX_LABELS {
+                    return None;
+                }
+                let forbidden = r.bytes(labels.checked_mul(labels)?)?.to_vec();
+                Some(Transitions {
+                    terrain,
+                    labels,
+                    forbidden,
+                })
+            }
+        };
+        let capacity = r.u32()?;
+        Some(Self {
+            noise,
+            seed,
+            rng_seed: false,
+            no_reverse,
+            heading,
+            slope,
+            region,
+            max_clearance,
+            min_clearance,
+            min_turns,
+            rotation_cost,
+            break_cost,
+            goal_radius,
+            open_list: OpenList::default(),
+            annotate: false,
+            max_cost,
+            window,
+            must_visit,
+            transitions,
+            capacity,
+        })
+    }
+
+    /// Si la celda está dentro de la ventana de búsqueda.
+    pub(crate) fn in_window(&self, cell: (usize, usize)) -> bool {
+        self.window.is_none_or(|window| window.contains(cell))
+    }
+
+    /// Si la matriz de transiciones deja pasar de la celda `from` a `to`.
+    pub(crate) fn allows_step(&self, from: usize, to: usize) -> bool {
+        self.transitions.as_ref().is_none_or(|t| t.allows(from, to))
+    }
+
+    /// Si la celda `idx` está dentro de la región de búsqueda.
+    pub(crate) fn in_region(&self, idx: usize) -> bool {
+        self.region
+            .as_ref()
+            .is_none_or(|mask| mask.get(idx).is_some_and(|&m| m != 0))
+    }
+}
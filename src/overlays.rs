@@ -0,0 +1,85 @@
+/// Capa de coste con nombre que solo cuenta dentro de sus ventanas de
+/// tiempo `[inicio, fin)`. Sin ventanas está siempre activa.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Overlay {
+    pub name: String,
+    pub costs: Vec<f32>,
+    pub windows: Vec<(f32, f32)>,
+}
+
+impl Overlay {
+    pub fn active_at(&self, time: f32) -> bool {
+        self.windows.is_empty()
+            || self
+                .windows
+                .iter()
+                .any(|&(start, end)| start <= time && time < end)
+    }
+}
+
+/// Capas de coste programadas y hora actual con la que se eligen las
+/// activas. La unidad de tiempo la pone quien llama (p. ej. horas del día).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Overlays {
+    pub items: Vec<Overlay>,
+    pub time: f32,
+}
+
+impl Overlays {
+    /// Añade la capa o reemplaza la que tenga el mismo nombre.
+    pub fn insert(&mut self, overlay: Overlay) {
+        match self.items.iter_mut().find(|o| o.name == overlay.name) {
+            Some(slot) => *slot = overlay,
+            None => self.items.push(overlay),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.items.len();
+        self.items.retain(|o| o.name != name);
+        self.items.len() != before
+    }
+
+    pub fn active(&self) -> impl Iterator<Item = &Overlay> {
+        self.items.iter().filter(|o| o.active_at(self.time))
+    }
+
+    /// Cambia la hora y devuelve si cambió el conjunto de capas activas.
+    pub fn set_time(&mut self, time: f32) -> bool {
+        let changed = self
+            .items
+            .iter()
+            .any(|o| o.active_at(time) != o.active_at(self.time));
+        self.time = time;
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: la hora decide qué capas están activas
+    #[test]
+    fn schedule_switches_overlays() {
+        let mut overlays = Overlays::default();
+        overlays.insert(Overlay {
+            name: "mercado".to_string(),
+            costs: vec![5.0],
+            windows: vec![(9.0, 17.0)],
+        });
+        overlays.insert(Overlay {
+            name: "barro".to_string(),
+            costs: vec![1.0],
+            windows: vec![],
+        });
+        let names = |o: &Overlays| o.active().map(|o| o.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&overlays), vec!["barro"]);
+        assert!(overlays.set_time(9.0));
+        assert_eq!(names(&overlays), vec!["mercado", "barro"]);
+        assert!(!overlays.set_time(16.5));
+        assert!(overlays.set_time(17.0));
+        assert!(overlays.remove("barro") && !overlays.remove("barro"));
+        assert_eq!(names(&overlays), Vec::<String>::new());
+    }
+}
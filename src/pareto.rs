@@ -0,0 +1,235 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::SearchOptions;
+use crate::cost::StepCost;
+use crate::grid::Grid;
+
+/// Frente de Pareto de rutas entre longitud y peligro
+/// (`PathFinder::pareto_paths`), ordenado de la más corta a la más segura.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct ParetoFront {
+    routes: Vec<Route>,
+}
+
+#[derive(Clone, Debug)]
+struct Route {
+    cells: Vec<(usize, usize)>,
+    length: f64,
+    danger: f64,
+}
+
+#[wasm_bindgen]
+impl ParetoFront {
+    /// Número de rutas no dominadas.
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// Ruta `i` como `[x0, y0, x1, y1, ...]` (vacía si no existe).
+    pub fn path(&self, i: usize) -> Vec<usize> {
+        self.routes.get(i).map_or_else(Vec::new, |r| {
+            r.cells.iter().flat_map(|&(x, y)| [x, y]).collect()
+        })
+    }
+
+    /// Coste de la ruta `i` con las opciones de la búsqueda (NaN si no existe).
+    pub fn length(&self, i: usize) -> f64 {
+        self.routes.get(i).map_or(f64::NAN, |r| r.length)
+    }
+
+    /// Peligro acumulado de la ruta `i`: suma de la capa de peligro de cada
+    /// celda en la que entra (NaN si no existe).
+    pub fn danger(&self, i: usize) -> f64 {
+        self.routes.get(i).map_or(f64::NAN, |r| r.danger)
+    }
+}
+
+/// Etiqueta de la búsqueda multiobjetivo: una celda y la etiqueta de la que
+/// viene, como índice en el arena de etiquetas.
+struct Label {
+    cell: (usize, usize),
+    parent: Option<usize>,
+}
+
+/// Entrada del heap con los costes acumulados de una etiqueta, en orden
+/// lexicográfico (longitud, peligro).
+struct Open {
+    length: f64,
+    danger: f64,
+    label: usize,
+}
+
+impl PartialEq for Open {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Open {}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Open {
+    // Invertido para que `BinaryHeap` saque primero la menor etiqueta.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .length
+            .total_cmp(&self.length)
+            .then_with(|| other.danger.total_cmp(&self.danger))
+            .then_with(|| other.label.cmp(&self.label))
+    }
+}
+
+fn dominated(costs: &[(f64, f64)], length: f64, danger: f64) -> bool {
+    costs.iter().any(|&(l, d)| l <= length && d <= danger)
+}
+
+/// Búsqueda biobjetivo por etiquetas (Martins): se extraen las etiquetas en
+/// orden lexicográfico, así que cada una que no esté dominada por otra ya
+/// fijada en su celda es de Pareto. Se detiene al reunir `limit` rutas.
+///
+/// `danger` tiene un valor por celda; los valores negativos cuentan como 0.
+pub(crate) fn pareto_search(
+    grid: &Grid,
+    start: (usize, usize),
+    goal: (usize, usize),
+    opts: &SearchOptions,
+    danger: &[f32],
+    limit: usize,
+) -> ParetoFront {
+    let mut costs = StepCost::new(grid, opts);
+    if limit == 0 || !costs.allowed(start) || !costs.allowed(goal) {
+        return ParetoFront::default();
+    }
+    costs.prepare(start, goal);
+
+    let danger_at = |cell| f64::from(danger[grid.idx(cell)].max(0.0));
+    let mut labels = vec![Label {
+        cell: start,
+        parent: None,
+    }];
+    let mut fixed: Vec<Vec<(f64, f64)>> = vec![Vec::new(); grid.cells.len()];
+    let mut open = BinaryHeap::from([Open {
+        length: 0.0,
+        danger: 0.0,
+        label: 0,
+    }]);
+    let mut front = ParetoFront::default();
+
+    while let Some(Open {
+        length,
+        danger,
+        label,
+    }) = open.pop()
+    {
+        let cell = labels[label].cell;
+        let at = grid.idx(cell);
+        if dominated(&fixed[at], length, danger) {
+            continue;
+        }
+        fixed[at].push((length, danger));
+
+        if cell == goal {
+            front.routes.push(Route {
+                cells: trace(&labels, label),
+                length,
+                danger,
+            });
+            if front.routes.len() == limit {
+                break;
+            }
+            continue;
+        }
+
+        let goal_costs = &fixed[grid.idx(goal)];
+        for next in grid.neighbors(cell) {
+            if !costs.allowed(next) {
+                continue;
+            }
+            let Some(step) = costs.step(cell, next) else {
+                continue;
+            };
+            let (nl, nd) = (length + step, danger + danger_at(next));
+            if dominated(&fixed[grid.idx(next)], nl, nd) || dominated(goal_costs, nl, nd) {
+                continue;
+            }
+            labels.push(Label {
+                cell: next,
+                parent: Some(label),
+            });
+            open.push(Open {
+                length: nl,
+                danger: nd,
+                label: labels.len() - 1,
+            });
+        }
+    }
+
+    front
+}
+
+fn trace(labels: &[Label], mut label: usize) -> Vec<(usize, usize)> {
+    let mut cells = vec![labels[label].cell];
+    while let Some(p) = labels[label].parent {
+        label = p;
+        cells.push(labels[label].cell);
+    }
+    cells.reverse();
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: un pasillo corto y peligroso frente a un rodeo seguro
+    #[test]
+    fn pareto_keeps_both_trade_offs() {
+        // La fila central es directa pero peligrosa; la superior rodea.
+        #[rustfmt::skip]
+        let cells = vec![
+            1, 1, 1, 1, 1,
+            1, 0, 0, 0, 1,
+            1, 1, 1, 1, 1,
+        ];
+        let grid = Grid::new(cells, 5, 3);
+        let mut danger = vec![0.0; 15];
+        for y in 1..4 {
+            danger[2 * 5 + y] = 5.0;
+        }
+        let opts = SearchOptions::default();
+        let front = pareto_search(&grid, (2, 0), (2, 4), &opts, &danger, 8);
+        assert_eq!(front.len(), 2);
+        assert_eq!((front.length(0), front.danger(0)), (4.0, 15.0));
+        assert_eq!((front.length(1), front.danger(1)), (8.0, 0.0));
+    }
+
+    /// Test: sin peligro el frente se reduce a la ruta más corta
+    #[test]
+    fn pareto_without_danger_is_single_route() {
+        let grid = Grid::square(vec![1; 16], 4);
+        let front = pareto_search(
+            &grid,
+            (0, 0),
+            (3, 3),
+            &SearchOptions::default(),
+            &[0.0; 16],
+            8,
+        );
+        assert_eq!(front.len(), 1);
+        assert_eq!(front.length(0), 6.0);
+        assert_eq!(front.path(0).len(), 14);
+    }
+}
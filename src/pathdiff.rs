@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+/// Comparación entre una ruta previa y la actual (`PathFinder::path_diff`).
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathDiff {
+    prefix_len: usize,
+    segment: Vec<(usize, usize)>,
+    rejoin: Option<(usize, usize)>,
+}
+
+#[wasm_bindgen]
+impl PathDiff {
+    /// Número de celdas iniciales que comparten ambas rutas.
+    pub fn prefix_len(&self) -> usize {
+        self.prefix_len
+    }
+
+    /// Tramo de la ruta nueva entre el fin del prefijo común y el punto de
+    /// reencuentro (excluido), como `[x0, y0, x1, y1, ...]`. Si no hay
+    /// reencuentro llega hasta el final de la ruta nueva.
+    pub fn segment(&self) -> Vec<usize> {
+        self.segment.iter().flat_map(|&(x, y)| [x, y]).collect()
+    }
+
+    /// Índice en la ruta previa donde la nueva se reincorpora.
+    pub fn rejoin_previous(&self) -> Option<usize> {
+        self.rejoin.map(|(prev, _)| prev)
+    }
+
+    /// Índice en la ruta nueva del mismo punto de reencuentro.
+    pub fn rejoin_current(&self) -> Option<usize> {
+        self.rejoin.map(|(_, cur)| cur)
+    }
+}
+
+/// Compara `previous` con `current`: prefijo común, tramo divergente y la
+/// primera celda de `current` tras el prefijo que vuelve a estar en `previous`
+/// (también tras el prefijo).
+pub(crate) fn diff_paths(previous: &[(usize, usize)], current: &[(usize, usize)]) -> PathDiff {
+    let prefix_len = previous
+        .iter()
+        .zip(current)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let later: HashMap<(usize, usize), usize> = previous
+        .iter()
+        .enumerate()
+        .skip(prefix_len)
+        .map(|(i, &cell)| (cell, i))
+        .collect();
+
+    let rejoin = current
+        .iter()
+        .enumerate()
+        .skip(prefix_len)
+        .find_map(|(j, cell)| later.get(cell).map(|&i| (i, j)));
+
+    let end = rejoin.map_or(current.len(), |(_, j)| j);
+    PathDiff {
+        prefix_len,
+        segment: current[prefix_len.min(end)..end].to_vec(),
+        rejoin,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: desvío que vuelve a la ruta original
+    #[test]
+    fn diff_detour_rejoins() {
+        let prev = [(0, 0), (0, 1), (0, 2), (0, 3), (0, 4)];
+        let cur = [(0, 0), (0, 1), (1, 1), (1, 2), (1, 3), (0, 3), (0, 4)];
+        let d = diff_paths(&prev, &cur);
+        assert_eq!(d.prefix_len(), 2);
+        assert_eq!(d.segment(), vec![1, 1, 1, 2, 1, 3]);
+        assert_eq!(
+            (d.rejoin_previous(), d.rejoin_current()),
+            (Some(3), Some(5))
+        );
+    }
+
+    /// Test: rutas idénticas no tienen tramo divergente
+    #[test]
+    fn diff_identical_paths() {
+        let prev = [(0, 0), (1, 0)];
+        let d = diff_paths(&prev, &prev);
+        assert_eq!(d.prefix_len(), 2);
+        assert!(d.segment().is_empty());
+        assert_eq!(d.rejoin_previous(), None);
+    }
+}
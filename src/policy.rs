@@ -0,0 +1,113 @@
+use std::cell::Cell;
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+
+/// Qué hacer ante datos anómalos (destino bloqueado, grid con una longitud
+/// que no cuadra, pesos fuera de rango).
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Policy {
+    /// El método falla con una excepción JS con la descripción del problema,
+    /// para bibliotecas que prefieren fallar pronto.
+    Strict,
+    /// Sigue con la alternativa documentada en cada método (rellenar o
+    /// recortar, ajustar al rango, devolver `false`), para juegos que
+    /// prefieren no romperse.
+    #[default]
+    Lenient,
+}
+
+thread_local! {
+    // wasm tiene un solo hilo; en nativo cada hilo (y cada test) lleva la suya.
+    static POLICY: Cell<Policy> = Cell::new(Policy::default());
+}
+
+/// Fija la política de anomalías para todas las instancias. Por defecto es
+/// `Lenient`.
+#[wasm_bindgen]
+pub fn set_policy(policy: Policy) {
+    POLICY.with(|p| p.set(policy));
+}
+
+#[wasm_bindgen]
+pub fn policy() -> Policy {
+    POLICY.with(Cell::get)
+}
+
+/// Anomalía en modo estricto, con su descripción. Los métodos públicos la
+/// devuelven con `?` como `Err(JsError)`: wasm-bindgen suelta el préstamo
+/// del objeto antes de lanzar la excepción en JS, cosa que no pasa si se
+/// lanza desde dentro del método, y el objeto queda como estaba.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Anomaly(pub String);
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Anomaly {}
+
+/// Informa de una anomalía: en modo estricto es un error con `message`; en
+/// modo tolerante es `Ok` y quien llama sigue con su alternativa. Hay que
+/// comprobarlo antes de cambiar nada, para no dejar el estado a medias.
+pub(crate) fn anomaly(message: &str) -> Result<(), Anomaly> {
+    match policy() {
+        Policy::Strict => Err(Anomaly(message.to_string())),
+        Policy::Lenient => Ok(()),
+    }
+}
+
+/// `value` si está en `[0, 1]`; si no, anomalía y en modo tolerante el
+/// valor ajustado al rango (0 si no es un número).
+pub(crate) fn unit(value: f32, what: &str) -> Result<f32, Anomaly> {
+    if (0.0..=1.0).contains(&value) {
+        return Ok(value);
+    }
+    anomaly(&format!("{what} debe estar entre 0 y 1"))?;
+    Ok(if value.is_nan() {
+        0.0
+    } else {
+        value.clamp(0.0, 1.0)
+    })
+}
+
+/// Celdas de un grid de `len` celdas: si sobran o faltan es una anomalía y
+/// en modo tolerante se recortan o se completan con paredes.
+pub(crate) fn fit_cells(mut cells: Vec<u8>, len: usize) -> Result<Vec<u8>, Anomaly> {
+    if cells.len() != len {
+        anomaly(&format!(
+            "el grid tiene {} celdas y se esperaban {len}",
+            cells.len()
+        ))?;
+        cells.resize(len, 0);
+    }
+    Ok(cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: en modo tolerante se ajustan longitudes y rangos
+    #[test]
+    fn lenient_fallbacks() {
+        assert_eq!(policy(), Policy::Lenient);
+        assert_eq!(fit_cells(vec![1; 3], 4), Ok(vec![1, 1, 1, 0]));
+        assert_eq!(fit_cells(vec![1; 5], 4), Ok(vec![1; 4]));
+        assert_eq!(unit(1.5, "decay"), Ok(1.0));
+        assert_eq!(unit(f32::NAN, "decay"), Ok(0.0));
+    }
+
+    /// Test: en modo estricto una anomalía es un error
+    #[test]
+    fn strict_rejects_anomalies() {
+        set_policy(Policy::Strict);
+        assert_eq!(unit(0.5, "decay"), Ok(0.5));
+        let err = fit_cells(vec![1; 3], 4).unwrap_err();
+        assert_eq!(err.to_string(), "el grid tiene 3 celdas y se esperaban 4");
+        set_policy(Policy::Lenient);
+    }
+}
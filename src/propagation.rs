@@ -0,0 +1,63 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::grid::Grid;
+use crate::queue::MinEntry;
+
+/// Intensidad por celda de lo que emiten `emitters` (`(celda, intensidad)`),
+/// como un ruido o un olor: al entrar en una celda libre la intensidad se
+/// multiplica por `open` y al atravesar una pared por `wall` (ambos entre 0
+/// y 1; con `wall` 0 las paredes lo paran del todo). Cada celda se queda con
+/// el camino que le llega más fuerte, de cualquier emisor, y lo que baja de
+/// `threshold` ya no se propaga y vale 0.
+pub(crate) fn propagate(
+    grid: &Grid,
+    emitters: &[(usize, f32)],
+    open: f32,
+    wall: f32,
+    threshold: f32,
+) -> Vec<f32> {
+    let (open, wall) = (open.clamp(0.0, 1.0), wall.clamp(0.0, 1.0));
+    let mut intensity = vec![0.0f32; grid.cells.len()];
+    let mut frontier = BinaryHeap::new();
+    for &(idx, strength) in emitters {
+        if idx < intensity.len() && strength >= threshold && strength > intensity[idx] {
+            intensity[idx] = strength;
+            frontier.push(Reverse(MinEntry(f64::from(strength), idx)));
+        }
+    }
+    // Al revés que en Dijkstra, sale antes la intensidad mayor.
+    while let Some(Reverse(MinEntry(level, idx))) = frontier.pop() {
+        let level = level as f32;
+        if level < intensity[idx] {
+            continue;
+        }
+        for next in grid.neighbors(grid.coords(idx)) {
+            let factor = if grid.walkable(next) { open } else { wall };
+            let (n, heard) = (grid.idx(next), level * factor);
+            if heard >= threshold && heard > intensity[n] {
+                intensity[n] = heard;
+                frontier.push(Reverse(MinEntry(f64::from(heard), n)));
+            }
+        }
+    }
+    intensity
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: el sonido se apaga con la distancia, la pared lo amortigua más
+    /// y cada celda oye el emisor más fuerte
+    #[test]
+    fn sound_attenuates_through_walls() {
+        let grid = Grid::new(vec![1, 1, 0, 1, 1], 5, 1);
+        let heard = propagate(&grid, &[(0, 1.0)], 0.5, 0.25, 0.01);
+        assert_eq!(heard, vec![1.0, 0.5, 0.125, 0.0625, 0.03125]);
+        let heard = propagate(&grid, &[(0, 1.0)], 0.5, 0.0, 0.01);
+        assert_eq!(heard, vec![1.0, 0.5, 0.0, 0.0, 0.0]);
+        let heard = propagate(&grid, &[(0, 1.0), (4, 0.8)], 0.5, 0.25, 0.1);
+        assert_eq!(heard, vec![1.0, 0.5, 0.125, 0.4, 0.8]);
+    }
+}
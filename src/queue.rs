@@ -0,0 +1,325 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+
+use wasm_bindgen::prelude::*;
+
+/// Estructura de la frontera de las búsquedas con costes
+/// (`SearchOptions::set_open_list`). Todas sacan los estados en el mismo
+/// orden, así que la ruta y las expansiones no cambian; solo el tiempo.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OpenList {
+    /// Montículo binario: bueno en general.
+    #[default]
+    BinaryHeap,
+    /// Cola de cubetas, una por valor entero de prioridad: mucho más rápida
+    /// con costes unitarios o enteros pequeños. Con prioridades no enteras
+    /// o muy grandes pasa sola a montículo binario.
+    Buckets,
+    /// Montículo de emparejamiento (pairing heap): inserciones baratas,
+    /// útil cuando se reabren muchos estados.
+    PairingHeap,
+}
+
+/// Elemento de la frontera: sale antes el mayor según `Ord` (como en
+/// `BinaryHeap`) y `key` es su prioridad numérica, de menor a mayor en el
+/// mismo sentido, para repartirlo en cubetas.
+pub(crate) trait Keyed: Ord {
+    fn key(&self) -> f64;
+}
+
+/// Elemento de un montículo de mínimos por coste real: en `BinaryHeap` sale
+/// antes el de menor coste (comparado con `total_cmp`) y, con empate, el de
+/// menor índice.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct MinEntry(pub f64, pub usize);
+
+impl Eq for MinEntry {}
+
+impl PartialOrd for MinEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MinEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.total_cmp(&self.0).then(other.1.cmp(&self.1))
+    }
+}
+
+/// Frontera con la estructura elegida.
+pub(crate) enum Frontier<T> {
+    Heap(BinaryHeap<T>),
+    Buckets(Buckets<T>),
+    Pairing(PairingHeap<T>),
+}
+
+impl<T: Keyed> Frontier<T> {
+    pub fn new(kind: OpenList) -> Self {
+        match kind {
+            OpenList::BinaryHeap => Self::Heap(BinaryHeap::new()),
+            OpenList::Buckets => Self::Buckets(Buckets::default()),
+            OpenList::PairingHeap => Self::Pairing(PairingHeap::default()),
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        match self {
+            Self::Heap(heap) => heap.push(item),
+            Self::Buckets(buckets) => {
+                if let Err(item) = buckets.push(item) {
+                    let mut heap: BinaryHeap<T> = buckets.drain().collect();
+                    heap.push(item);
+                    *self = Self::Heap(heap);
+                }
+            }
+            Self::Pairing(pairing) => pairing.push(item),
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        match self {
+            Self::Heap(heap) => heap.pop(),
+            Self::Buckets(buckets) => buckets.pop(),
+            Self::Pairing(pairing) => pairing.pop(),
+        }
+    }
+
+    pub fn peek(&mut self) -> Option<&T> {
+        match self {
+            Self::Heap(heap) => heap.peek(),
+            Self::Buckets(buckets) => buckets.peek(),
+            Self::Pairing(pairing) => pairing.peek(),
+        }
+    }
+
+    /// Todo lo que queda, de menor a mayor (como `BinaryHeap::into_sorted_vec`).
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        if let Self::Heap(heap) = self {
+            return heap.into_sorted_vec();
+        }
+        let mut items: Vec<T> = std::iter::from_fn(|| self.pop()).collect();
+        items.reverse();
+        items
+    }
+}
+
+/// Cubetas más allá de la actual a partir de las que se abandona la cola.
+const MAX_BUCKETS: usize = 1 << 16;
+
+/// Cola de cubetas para prioridades enteras no negativas. Dentro de cada
+/// cubeta se sale por orden de llegada, que coincide con el orden de
+/// `Ord` si los empates se deshacen por llegada, como en las búsquedas.
+pub(crate) struct Buckets<T> {
+    buckets: VecDeque<VecDeque<T>>,
+    /// Prioridad de la primera cubeta.
+    base: usize,
+    len: usize,
+}
+
+impl<T> Default for Buckets<T> {
+    fn default() -> Self {
+        Self {
+            buckets: VecDeque::new(),
+            base: 0,
+            len: 0,
+        }
+    }
+}
+
+impl<T: Keyed> Buckets<T> {
+    /// Devuelve el elemento si su prioridad no cabe en una cubeta.
+    fn push(&mut self, item: T) -> Result<(), T> {
+        let key = item.key();
+        if !(key >= 0.0 && key.fract() == 0.0 && key < usize::MAX as f64) {
+            return Err(item);
+        }
+        let key = key as usize;
+        if self.len == 0 {
+            self.buckets.clear();
+            self.base = key;
+        }
+        if key < self.base {
+            if self.base - key > MAX_BUCKETS {
+                return Err(item);
+            }
+            for _ in key..self.base {
+                self.buckets.push_front(VecDeque::new());
+            }
+            self.base = key;
+        }
+        let slot = key - self.base;
+        if slot > MAX_BUCKETS {
+            return Err(item);
+        }
+        if slot >= self.buckets.len() {
+            self.buckets.resize_with(slot + 1, VecDeque::new);
+        }
+        self.buckets[slot].push_back(item);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Quita las cubetas vacías del principio.
+    fn settle(&mut self) {
+        while self.buckets.front().is_some_and(VecDeque::is_empty) {
+            self.buckets.pop_front();
+            self.base += 1;
+        }
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.settle();
+        let item = self.buckets.front_mut()?.pop_front()?;
+        self.len -= 1;
+        Some(item)
+    }
+
+    fn peek(&mut self) -> Option<&T> {
+        self.settle();
+        self.buckets.front()?.front()
+    }
+
+    fn drain(&mut self) -> impl Iterator<Item = T> + '_ {
+        self.len = 0;
+        self.buckets.drain(..).flatten()
+    }
+}
+
+/// Montículo de emparejamiento sobre un vector de nodos.
+pub(crate) struct PairingHeap<T> {
+    nodes: Vec<Node<T>>,
+    free: Vec<usize>,
+    root: Option<usize>,
+}
+
+struct Node<T> {
+    item: Option<T>,
+    child: Option<usize>,
+    sibling: Option<usize>,
+}
+
+impl<T> Default for PairingHeap<T> {
+    fn default() -> Self {
+        Self {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: None,
+        }
+    }
+}
+
+impl<T: Ord> PairingHeap<T> {
+    fn push(&mut self, item: T) {
+        let node = Node {
+            item: Some(item),
+            child: None,
+            sibling: None,
+        };
+        let idx = match self.free.pop() {
+            Some(idx) => {
+                self.nodes[idx] = node;
+                idx
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        };
+        self.root = Some(match self.root {
+            Some(root) => self.meld(root, idx),
+            None => idx,
+        });
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.nodes[self.root?].item.as_ref()
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        let root = self.root?;
+        let item = self.nodes[root].item.take();
+        self.free.push(root);
+
+        // Dos pasadas: se emparejan los hijos de izquierda a derecha y luego
+        // se funden las parejas de derecha a izquierda.
+        let mut pairs = Vec::new();
+        let mut next = self.nodes[root].child.take();
+        while let Some(a) = next {
+            let b = self.nodes[a].sibling.take();
+            next = b.and_then(|b| self.nodes[b].sibling.take());
+            pairs.push(match b {
+                Some(b) => self.meld(a, b),
+                None => a,
+            });
+        }
+        self.root = pairs.into_iter().rev().reduce(|acc, p| self.meld(p, acc));
+        item
+    }
+
+    /// Une dos raíces y devuelve la nueva.
+    fn meld(&mut self, a: usize, b: usize) -> usize {
+        let (top, below) = if self.nodes[a].item >= self.nodes[b].item {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.nodes[below].sibling = self.nodes[top].child;
+        self.nodes[top].child = Some(below);
+        top
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cmp::Reverse;
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Item(Reverse<(u32, u32)>);
+
+    impl PartialOrd for Item {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Item {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.0.cmp(&other.0)
+        }
+    }
+
+    impl Keyed for Item {
+        fn key(&self) -> f64 {
+            f64::from((self.0).0.0) / 2.0
+        }
+    }
+
+    fn drain(kind: OpenList, keys: &[u32]) -> Vec<(u32, u32)> {
+        let mut frontier = Frontier::new(kind);
+        let mut out = Vec::new();
+        for (order, &key) in keys.iter().enumerate() {
+            frontier.push(Item(Reverse((key, order as u32))));
+            if order % 3 == 2 {
+                out.extend(frontier.pop().map(|Item(Reverse(k))| k));
+            }
+        }
+        out.extend(std::iter::from_fn(|| frontier.pop()).map(|Item(Reverse(k))| k));
+        out
+    }
+
+    /// Test: las tres colas sacan lo mismo en el mismo orden, también cuando
+    /// las cubetas tienen que pasar a montículo
+    #[test]
+    fn backends_agree() {
+        let even = [8, 2, 6, 2, 4, 10, 0, 6, 2, 12, 4, 4];
+        let odd = [8, 2, 7, 2, 4, 10, 0, 6, 3, 12, 4, 4];
+        for keys in [&even, &odd] {
+            let reference = drain(OpenList::BinaryHeap, keys);
+            assert_eq!(drain(OpenList::Buckets, keys), reference);
+            assert_eq!(drain(OpenList::PairingHeap, keys), reference);
+        }
+    }
+}
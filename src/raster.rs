@@ -0,0 +1,177 @@
+use crate::grid::Grid;
+
+/// Punto en coordenadas continuas de celda: la celda `(x, y)` ocupa
+/// `[x, x + 1) × [y, y + 1)`, con `x` la fila e `y` la columna.
+type Point = (f32, f32);
+
+fn dist_to_segment(p: Point, a: Point, b: Point) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len2 = dx * dx + dy * dy;
+    let t = if len2 > 0.0 {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len2).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let (cx, cy) = (a.0 + t * dx - p.0, a.1 + t * dy - p.1);
+    (cx * cx + cy * cy).sqrt()
+}
+
+/// Si el segmento `a`-`b` corta el cuadrado de la celda `(x, y)`.
+fn crosses_cell(a: Point, b: Point, cell: (usize, usize)) -> bool {
+    clip(a, b, cell).is_some()
+}
+
+/// Tramo `[t0, t1]` del segmento `a + t (b - a)`, con `t` entre 0 y 1, que
+/// queda dentro del cuadrado de la celda `(x, y)` (recorte de Liang-Barsky).
+pub(crate) fn clip(a: Point, b: Point, (x, y): (usize, usize)) -> Option<(f32, f32)> {
+    let (mut t0, mut t1) = (0.0f32, 1.0f32);
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let sides = [
+        (-dx, a.0 - x as f32),
+        (dx, x as f32 + 1.0 - a.0),
+        (-dy, a.1 - y as f32),
+        (dy, y as f32 + 1.0 - a.1),
+    ];
+    for (p, q) in sides {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else if p < 0.0 {
+            t0 = t0.max(q / p);
+        } else {
+            t1 = t1.min(q / p);
+        }
+    }
+    (t0 <= t1).then_some((t0, t1))
+}
+
+/// Distancia del segmento `a`-`b` al cuadrado de la celda `(x, y)`.
+fn segment_to_cell(a: Point, b: Point, (x, y): (usize, usize)) -> f32 {
+    if crosses_cell(a, b, (x, y)) {
+        return 0.0;
+    }
+    let (x0, y0, x1, y1) = (x as f32, y as f32, x as f32 + 1.0, y as f32 + 1.0);
+    let to_cell = |(px, py): Point| {
+        let (cx, cy) = (px.clamp(x0, x1), py.clamp(y0, y1));
+        ((px - cx).powi(2) + (py - cy).powi(2)).sqrt()
+    };
+    let corners = [(x0, y0), (x0, y1), (x1, y0), (x1, y1)];
+    let from_corners = corners.iter().map(|&c| dist_to_segment(c, a, b));
+    from_corners.fold(to_cell(a).min(to_cell(b)), f32::min)
+}
+
+/// Marca como pared toda celda que el segmento `a`-`b` toca (supercover) o
+/// que queda a menos de `thickness / 2` de él. Devuelve cuántas celdas
+/// pasaron de libres a bloqueadas.
+pub(crate) fn block_segment(grid: &mut Grid, a: Point, b: Point, thickness: f32) -> usize {
+    let coords = [a.0, a.1, b.0, b.1, thickness];
+    if coords.iter().any(|v| !v.is_finite()) {
+        return 0;
+    }
+    let half = thickness.max(0.0) / 2.0;
+    let rows = span(a.0.min(b.0) - half, a.0.max(b.0) + half, grid.height);
+    let cols = span(a.1.min(b.1) - half, a.1.max(b.1) + half, grid.width);
+    let mut blocked = 0;
+    for x in rows {
+        for y in cols.clone() {
+            let idx = grid.idx((x, y));
+            if segment_to_cell(a, b, (x, y)) <= half && grid.cells[idx] == 1 {
+                grid.cells[idx] = 0;
+                blocked += 1;
+            }
+        }
+    }
+    blocked
+}
+
+/// Rellena el polígono `points` (cerrado implícitamente) por líneas de
+/// barrido con la regla par-impar: se bloquea cada celda cuyo centro queda
+/// dentro. Devuelve cuántas celdas pasaron de libres a bloqueadas.
+pub(crate) fn block_polygon(grid: &mut Grid, points: &[Point]) -> usize {
+    if points.len() < 3 || points.iter().any(|p| !p.0.is_finite() || !p.1.is_finite()) {
+        return 0;
+    }
+    let mut blocked = 0;
+    for x in 0..grid.height {
+        let row = x as f32 + 0.5;
+        let mut crossings: Vec<f32> = (0..points.len())
+            .filter_map(|i| {
+                let (a, b) = (points[i], points[(i + 1) % points.len()]);
+                // Semiabierto para no contar dos veces un vértice compartido.
+                ((a.0 <= row) != (b.0 <= row))
+                    .then(|| a.1 + (row - a.0) / (b.0 - a.0) * (b.1 - a.1))
+            })
+            .collect();
+        crossings.sort_by(f32::total_cmp);
+        for pair in crossings.chunks_exact(2) {
+            for y in span(pair[0] - 0.5, pair[1] - 0.5, grid.width) {
+                let center = y as f32 + 0.5;
+                let idx = grid.idx((x, y));
+                if center >= pair[0] && center < pair[1] && grid.cells[idx] == 1 {
+                    grid.cells[idx] = 0;
+                    blocked += 1;
+                }
+            }
+        }
+    }
+    blocked
+}
+
+/// Índices de celda que cubren el intervalo continuo `[lo, hi]`, recortados
+/// a `0..len`.
+pub(crate) fn span(lo: f32, hi: f32, len: usize) -> std::ops::Range<usize> {
+    let start = lo.floor().max(0.0) as usize;
+    let end = (hi.floor() + 1.0).clamp(0.0, len as f32) as usize;
+    start.min(end)..end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn walls(grid: &Grid) -> Vec<usize> {
+        (0..grid.cells.len())
+            .filter(|&i| grid.cells[i] == 0)
+            .collect()
+    }
+
+    /// Test: una diagonal marca todas las celdas que atraviesa y el grosor la ensancha
+    #[test]
+    fn segment_supercover_and_thickness() {
+        let mut grid = Grid::square(vec![1; 25], 5);
+        assert_eq!(block_segment(&mut grid, (0.5, 0.5), (2.5, 4.5), 0.0), 7);
+        assert_eq!(walls(&grid), vec![0, 1, 6, 7, 8, 13, 14]);
+
+        let mut grid = Grid::square(vec![1; 25], 5);
+        block_segment(&mut grid, (2.5, 0.5), (2.5, 4.5), 2.0);
+        assert_eq!(walls(&grid), (5..20).collect::<Vec<_>>());
+        assert_eq!(block_segment(&mut grid, (2.5, 0.5), (2.5, 4.5), 2.0), 0);
+    }
+
+    /// Test: un triángulo y un polígono cóncavo se rellenan por los centros
+    #[test]
+    fn polygon_scanline_fill() {
+        let mut grid = Grid::square(vec![1; 25], 5);
+        let square = [(1.0, 1.0), (1.0, 4.0), (4.0, 4.0), (4.0, 1.0)];
+        assert_eq!(block_polygon(&mut grid, &square), 9);
+        assert_eq!(walls(&grid), vec![6, 7, 8, 11, 12, 13, 16, 17, 18]);
+
+        // Forma de U: la muesca central queda libre.
+        let mut grid = Grid::square(vec![1; 25], 5);
+        let u = [
+            (0.0, 0.0),
+            (3.0, 0.0),
+            (3.0, 5.0),
+            (0.0, 5.0),
+            (0.0, 3.0),
+            (2.0, 3.0),
+            (2.0, 2.0),
+            (0.0, 2.0),
+        ];
+        assert_eq!(block_polygon(&mut grid, &u), 13);
+        assert_eq!(grid.cells[2], 1);
+        assert_eq!(grid.cells[7], 1);
+        assert_eq!(block_polygon(&mut grid, &u[..2]), 0);
+    }
+}
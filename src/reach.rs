@@ -0,0 +1,201 @@
+use std::collections::VecDeque;
+
+use crate::grid::Grid;
+
+/// Celda sin camino desde el origen.
+const NONE: usize = usize::MAX;
+
+/// Región alcanzable desde una celda, mantenida como árbol de expansión
+/// (cada celda alcanzable apunta a un vecino más cerca del origen) para
+/// que abrir o cerrar una celda solo toque la parte afectada: abrir inunda
+/// lo que pasa a ser alcanzable y cerrar solo reengancha el subárbol que
+/// colgaba de ella.
+#[derive(Clone, Debug)]
+pub(crate) struct Reach {
+    source: (usize, usize),
+    parent: Vec<usize>,
+    /// Revisión de las celdas del grid (ver `PathFinder::touched`) a la que
+    /// corresponde la región.
+    revision: u32,
+    /// Estado de cada celda la última vez que se entregaron los cambios.
+    reported: Vec<bool>,
+    flipped: Vec<usize>,
+}
+
+impl Reach {
+    pub fn new(grid: &Grid, source: (usize, usize), revision: u32) -> Self {
+        let len = grid.cells.len();
+        let mut reach = Self {
+            source,
+            parent: vec![NONE; len],
+            revision,
+            reported: vec![false; len],
+            flipped: vec![],
+        };
+        if grid.walkable(source) {
+            let idx = grid.idx(source);
+            reach.attach(idx, idx);
+            reach.flood(grid, VecDeque::from([idx]));
+        }
+        reach
+    }
+
+    pub fn reachable(&self, idx: usize) -> bool {
+        self.parent.get(idx).is_some_and(|&p| p != NONE)
+    }
+
+    /// Se pone al día con `grid` en la revisión `revision`: si cambió por
+    /// otras vías que `update` se vuelve a inundar la región entera, y las
+    /// celdas que cambiaron respecto a lo ya entregado quedan pendientes para
+    /// `take_changes`. Con la misma revisión no mira las celdas.
+    pub fn sync(&mut self, grid: &Grid, revision: u32) {
+        if revision == self.revision && grid.cells.len() == self.parent.len() {
+            return;
+        }
+        let old = std::mem::replace(self, Self::new(grid, self.source, revision));
+        if old.reported.len() == self.reported.len() {
+            self.flipped = (0..self.parent.len())
+                .filter(|&i| self.reachable(i) != old.reported[i])
+                .collect();
+            self.reported = old.reported;
+        }
+    }
+
+    /// Ajusta la región tras cambiar solo la celda `idx` de `grid`, cambio
+    /// que lo llevó a la revisión `revision`. Si ya iba atrasada no hace nada
+    /// y lo rehará `sync`.
+    pub fn update(&mut self, grid: &Grid, idx: usize, revision: u32) {
+        if self.revision.wrapping_add(1) != revision {
+            return;
+        }
+        self.revision = revision;
+        let open = grid.cells[idx] == 1;
+        if open == self.reachable(idx) {
+            return;
+        }
+        let source = grid.idx(self.source);
+        if open {
+            let parent = if idx == source {
+                Some(idx)
+            } else {
+                self.reachable_neighbor(grid, idx)
+            };
+            if let Some(parent) = parent {
+                self.attach(idx, parent);
+                self.flood(grid, VecDeque::from([idx]));
+            }
+            return;
+        }
+        let orphans = self.subtree(grid, idx);
+        for &i in &orphans {
+            self.parent[i] = NONE;
+            self.flipped.push(i);
+        }
+        if idx == source {
+            return;
+        }
+        let mut queue = VecDeque::new();
+        for &i in orphans[1..].iter().filter(|&&i| grid.cells[i] == 1) {
+            if let Some(next) = self.reachable_neighbor(grid, i) {
+                self.attach(i, next);
+                queue.push_back(i);
+            }
+        }
+        self.flood(grid, queue);
+    }
+
+    /// Celdas cuya alcanzabilidad cambió desde la llamada anterior, con su
+    /// estado actual. La primera vez son todas las alcanzables.
+    pub fn take_changes(&mut self) -> Vec<(usize, bool)> {
+        let mut flipped = std::mem::take(&mut self.flipped);
+        flipped.sort_unstable();
+        flipped.dedup();
+        flipped
+            .into_iter()
+            .filter_map(|i| {
+                let now = self.reachable(i);
+                (now != self.reported[i]).then(|| {
+                    self.reported[i] = now;
+                    (i, now)
+                })
+            })
+            .collect()
+    }
+
+    fn attach(&mut self, idx: usize, parent: usize) {
+        self.parent[idx] = parent;
+        self.flipped.push(idx);
+    }
+
+    fn reachable_neighbor(&self, grid: &Grid, idx: usize) -> Option<usize> {
+        grid.neighbors(grid.coords(idx))
+            .map(|c| grid.idx(c))
+            .find(|&n| self.reachable(n))
+    }
+
+    /// `idx` y todas las celdas que llegan al origen a través de ella.
+    fn subtree(&self, grid: &Grid, idx: usize) -> Vec<usize> {
+        let mut nodes = vec![idx];
+        let mut i = 0;
+        while let Some(&node) = nodes.get(i) {
+            i += 1;
+            nodes.extend(
+                grid.neighbors(grid.coords(node))
+                    .map(|c| grid.idx(c))
+                    .filter(|&n| self.parent[n] == node),
+            );
+        }
+        nodes
+    }
+
+    /// Extiende la región desde `queue` por las celdas transitables que aún
+    /// no eran alcanzables.
+    fn flood(&mut self, grid: &Grid, mut queue: VecDeque<usize>) {
+        while let Some(idx) = queue.pop_front() {
+            for next in grid.neighbors(grid.coords(idx)).map(|c| grid.idx(c)) {
+                if grid.cells[next] == 1 && !self.reachable(next) {
+                    self.attach(next, idx);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn full(grid: &Grid, source: (usize, usize)) -> Vec<bool> {
+        let fresh = Reach::new(grid, source, 0);
+        (0..grid.cells.len()).map(|i| fresh.reachable(i)).collect()
+    }
+
+    /// Test: tras cada cambio coincide con recalcular desde cero
+    #[test]
+    fn incremental_matches_full_flood() {
+        let mut grid = Grid::square(vec![1; 25], 5);
+        let mut reach = Reach::new(&grid, (0, 0), 0);
+        assert_eq!(reach.take_changes().len(), 25);
+        // Muro en la columna 2 con hueco en la fila 4, luego se cierra y se reabre.
+        let edits = [(0, 0), (1, 0), (2, 0), (3, 0), (4, 0), (4, 1), (2, 1)];
+        for (revision, (x, value)) in (1..).zip(edits) {
+            grid.cells[x * 5 + 2] = value;
+            reach.update(&grid, x * 5 + 2, revision);
+            let now: Vec<bool> = (0..25).map(|i| reach.reachable(i)).collect();
+            assert_eq!(now, full(&grid, (0, 0)));
+        }
+        // Solo se informa del cambio neto: los muros que quedaron.
+        let changes = reach.take_changes();
+        assert_eq!(changes, vec![(2, false), (7, false), (17, false)]);
+
+        // Un cambio por otra vía se ve al sincronizar con la revisión nueva,
+        // y se entrega como los demás.
+        grid.cells[0] = 0;
+        reach.sync(&grid, 7);
+        assert!(reach.reachable(1));
+        reach.sync(&grid, 8);
+        assert!((0..25).all(|i| !reach.reachable(i)));
+        assert_eq!(reach.take_changes().len(), 22);
+    }
+}
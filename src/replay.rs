@@ -0,0 +1,106 @@
+use crate::SearchOptions;
+use crate::bytes::{Reader, Writer};
+use crate::grid::Grid;
+
+const MAGIC: &[u8; 4] = b"PFRL";
+const VERSION: u8 = 13;
+
+/// Registro de una búsqueda: el grid, las opciones (o `None` para el BFS del
+/// constructor) y cada celda expandida en orden.
+#[derive(Clone, Debug)]
+pub(crate) struct SearchLog {
+    pub grid: Grid,
+    pub options: Option<SearchOptions>,
+    pub expanded: Vec<usize>,
+}
+
+pub(crate) fn encode(log: &SearchLog) -> Vec<u8> {
+    let mut w = Writer::new();
+    w.bytes(MAGIC);
+    w.u8(VERSION);
+    log.grid.write_to(&mut w);
+    match &log.options {
+        Some(opts) => {
+            w.u8(1);
+            opts.write_to(&mut w);
+        }
+        None => w.u8(0),
+    }
+    w.u32(log.expanded.len() as u32);
+    log.expanded.iter().for_each(|&idx| w.u32(idx as u32));
+    w.finish()
+}
+
+pub(crate) fn decode(bytes: &[u8]) -> Option<SearchLog> {
+    let mut r = Reader::new(bytes);
+    if r.bytes(4)? != MAGIC || r.u8()? != VERSION {
+        return None;
+    }
+    let grid = Grid::read_from(&mut r)?;
+    let options = match r.u8()? {
+        0 => None,
+        _ => Some(SearchOptions::read_from(&mut r)?),
+    };
+    let count = r.u32()? as usize;
+    let expanded = (0..count)
+        .map(|_| r.u32().map(|idx| idx as usize))
+        .collect::<Option<Vec<_>>>()?;
+    r.is_empty().then_some(SearchLog {
+        grid,
+        options,
+        expanded,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::Transitions;
+
+    /// Test: el registro sobrevive a la ida y vuelta por bytes
+    #[test]
+    fn log_roundtrip() {
+        let mut options = SearchOptions::default();
+        options.set_noise(0.25, 7);
+        options.set_region_mask(vec![1, 1, 0, 1]);
+        let log = SearchLog {
+            grid: Grid::square(vec![1, 1, 0, 1], 2),
+            options: Some(options),
+            expanded: vec![0, 1, 3],
+        };
+        let back = decode(&encode(&log)).unwrap();
+        assert_eq!(back.grid, log.grid);
+        assert_eq!(back.expanded, log.expanded);
+        let opts = back.options.unwrap();
+        assert_eq!((opts.noise, opts.seed), (0.25, 7));
+        assert_eq!(opts.region, Some(vec![1, 1, 0, 1]));
+    }
+
+    /// Test: un registro truncado o ajeno se rechaza
+    #[test]
+    fn log_rejects_garbage() {
+        let log = SearchLog {
+            grid: Grid::square(vec![1], 1),
+            options: None,
+            expanded: vec![0],
+        };
+        let bytes = encode(&log);
+        assert!(decode(&bytes[..bytes.len() - 1]).is_none());
+        assert!(decode(b"nope").is_none());
+
+        // Más etiquetas de transición de las que caben en un byte.
+        let options = SearchOptions {
+            transitions: Some(Transitions {
+                terrain: vec![0],
+                labels: 257,
+                forbidden: vec![0; 257 * 257],
+            }),
+            ..SearchOptions::default()
+        };
+        let log = SearchLog {
+            options: Some(options),
+            ..log
+        };
+        assert!(decode(&encode(&log)).is_none());
+    }
+}
@@ -0,0 +1,150 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::SearchOptions;
+use crate::result::PathResult;
+
+/// Petición de ruta en espera (`PathFinder::enqueue`).
+#[derive(Clone, Debug)]
+pub(crate) struct Request {
+    pub ticket: u32,
+    pub priority: f64,
+    pub start: (usize, usize),
+    pub goal: (usize, usize),
+    pub options: SearchOptions,
+}
+
+/// Sale antes la de mayor prioridad y, entre iguales, la más antigua.
+impl Ord for Request {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .total_cmp(&other.priority)
+            .then_with(|| other.ticket.cmp(&self.ticket))
+    }
+}
+
+impl PartialOrd for Request {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Request {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Request {}
+
+/// Cola de peticiones de ruta pendientes, por prioridad.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RequestQueue {
+    pending: BinaryHeap<Request>,
+    next: u32,
+}
+
+impl RequestQueue {
+    /// Encola la petición y devuelve su número, que nunca es 0.
+    pub fn push(
+        &mut self,
+        priority: f64,
+        start: (usize, usize),
+        goal: (usize, usize),
+        options: SearchOptions,
+    ) -> u32 {
+        self.next = self.next.wrapping_add(1).max(1);
+        self.pending.push(Request {
+            ticket: self.next,
+            priority,
+            start,
+            goal,
+            options,
+        });
+        self.next
+    }
+
+    pub fn pop(&mut self) -> Option<Request> {
+        self.pending.pop()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Quita la petición `ticket`. Devuelve si estaba pendiente.
+    pub fn cancel(&mut self, ticket: u32) -> bool {
+        let before = self.pending.len();
+        self.pending.retain(|r| r.ticket != ticket);
+        self.pending.len() != before
+    }
+}
+
+/// Peticiones atendidas por una llamada a `PathFinder::process`, en el orden
+/// en que se resolvieron.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct ProcessedRequests {
+    tickets: Vec<u32>,
+    results: Vec<PathResult>,
+    remaining: usize,
+}
+
+#[wasm_bindgen]
+impl ProcessedRequests {
+    pub fn len(&self) -> usize {
+        self.tickets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tickets.is_empty()
+    }
+
+    /// Números de petición (los de `enqueue`) de los resultados.
+    pub fn tickets(&self) -> Vec<u32> {
+        self.tickets.clone()
+    }
+
+    /// Resultado de la petición `i`-ésima atendida.
+    pub fn result(&self, i: usize) -> Option<PathResult> {
+        self.results.get(i).cloned()
+    }
+
+    /// Peticiones que quedaron para el siguiente `process`.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+}
+
+impl ProcessedRequests {
+    pub(crate) fn push(&mut self, ticket: u32, result: PathResult) {
+        self.tickets.push(ticket);
+        self.results.push(result);
+    }
+
+    pub(crate) fn set_remaining(&mut self, remaining: usize) {
+        self.remaining = remaining;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: sale primero la de más prioridad y, a igualdad, la más antigua
+    #[test]
+    fn requests_by_priority() {
+        let mut queue = RequestQueue::default();
+        let options = SearchOptions::default();
+        let a = queue.push(1.0, (0, 0), (1, 1), options.clone());
+        let b = queue.push(5.0, (0, 0), (2, 2), options.clone());
+        let c = queue.push(1.0, (0, 0), (3, 3), options.clone());
+        let d = queue.push(0.0, (0, 0), (4, 4), options);
+        assert!(queue.cancel(d));
+        assert!(!queue.cancel(d));
+        let order: Vec<u32> = std::iter::from_fn(|| queue.pop().map(|r| r.ticket)).collect();
+        assert_eq!(order, vec![b, a, c]);
+    }
+}
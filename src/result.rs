@@ -0,0 +1,141 @@
+use wasm_bindgen::prelude::*;
+
+/// Cómo terminó la última búsqueda de un `PathFinder`, aparte de la ruta.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Status {
+    /// Se detuvo por un límite antes de agotar la búsqueda.
+    pub truncated: bool,
+    /// La interrumpió quien la lanzó.
+    pub cancelled: bool,
+    /// Si hay ruta, es de coste mínimo con las opciones usadas.
+    pub optimal: bool,
+    /// Cota de lo lejos que está el coste del óptimo, como cociente
+    /// `coste / óptimo` (1 si es óptima, infinito si no se sabe).
+    pub suboptimality: f64,
+    /// Destino sustituto cuando el pedido no se alcanzaba
+    /// (`SearchOptions::set_goal_relaxation`).
+    pub relaxed_goal: Option<(usize, usize)>,
+    /// Algoritmo elegido por `PathFinder::search_auto` (vacío en el resto).
+    pub algorithm: &'static str,
+    /// `PathFinder::reanchor` no pudo aprovechar la ruta desde la nueva
+    /// posición del agente.
+    pub needs_replan: bool,
+}
+
+impl Default for Status {
+    fn default() -> Self {
+        Self {
+            truncated: false,
+            cancelled: false,
+            optimal: true,
+            suboptimality: 1.0,
+            relaxed_goal: None,
+            algorithm: "",
+            needs_replan: false,
+        }
+    }
+}
+
+/// Resultado completo de una búsqueda (`PathFinder::result`): distingue "no
+/// hay ruta" de "se rindió" y de "ruta parcial".
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathResult {
+    status: Status,
+    cost: f64,
+    path: Vec<(usize, usize)>,
+    /// Coste de cada paso de la ruta, que hace de longitud del tramo.
+    steps: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl PathResult {
+    pub fn found(&self) -> bool {
+        !self.path.is_empty()
+    }
+
+    pub fn truncated(&self) -> bool {
+        self.status.truncated
+    }
+
+    pub fn cancelled(&self) -> bool {
+        self.status.cancelled
+    }
+
+    pub fn optimal(&self) -> bool {
+        self.status.optimal
+    }
+
+    /// Cuántas veces más cara que la óptima puede ser la ruta (1 si es
+    /// óptima). Sirve para decidir si repetir con un método exacto.
+    pub fn suboptimality(&self) -> f64 {
+        self.status.suboptimality
+    }
+
+    /// Celda `[x, y]` a la que se ha llevado la ruta en lugar del destino
+    /// pedido, o vacío si no hubo sustitución.
+    pub fn relaxed_goal(&self) -> Vec<usize> {
+        self.status
+            .relaxed_goal
+            .map_or_else(Vec::new, |(x, y)| vec![x, y])
+    }
+
+    /// Algoritmo que eligió `PathFinder::search_auto` ("bfs", "astar",
+    /// "01bfs" o "rectangles"), o vacío si la búsqueda no fue automática.
+    pub fn algorithm(&self) -> String {
+        self.status.algorithm.to_string()
+    }
+
+    /// La ruta ya no sirve desde donde está el agente (ver
+    /// `PathFinder::reanchor`) y hay que buscar otra.
+    pub fn needs_replan(&self) -> bool {
+        self.status.needs_replan
+    }
+
+    pub fn cost(&self) -> f64 {
+        self.cost
+    }
+
+    /// Ruta como `[x0, y0, x1, y1, ...]`.
+    pub fn path(&self) -> Vec<usize> {
+        self.path.iter().flat_map(|&(x, y)| [x, y]).collect()
+    }
+
+    /// Posición `[x, y]` tras recorrer `distance` a lo largo de la ruta,
+    /// interpolada dentro del paso en curso; cada paso mide lo que cuesta, así
+    /// que el agente avanza más despacio por las celdas caras. Antes del
+    /// inicio da la primera celda, pasado el final la última y sin ruta nada.
+    pub fn position_at(&self, distance: f64) -> Vec<f64> {
+        let Some(&(x, y)) = self.path.first() else {
+            return vec![];
+        };
+        let mut left = distance.max(0.0);
+        let mut at = (x as f64, y as f64);
+        for (w, &len) in self.path.windows(2).zip(&self.steps) {
+            let next = (w[1].0 as f64, w[1].1 as f64);
+            if left < len {
+                let t = left / len;
+                return vec![at.0 + (next.0 - at.0) * t, at.1 + (next.1 - at.1) * t];
+            }
+            left -= len;
+            at = next;
+        }
+        vec![at.0, at.1]
+    }
+}
+
+impl PathResult {
+    pub(crate) fn new(
+        status: Status,
+        cost: f64,
+        path: Vec<(usize, usize)>,
+        steps: Vec<f64>,
+    ) -> Self {
+        Self {
+            status,
+            cost,
+            path,
+            steps,
+        }
+    }
+}
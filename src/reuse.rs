@@ -0,0 +1,29 @@
+/// Penalización por reutilizar rutas: cada ruta devuelta suma `penalty` a
+/// sus celdas y antes de cada consulta el calor acumulado se multiplica por
+/// `decay`, así una corriente de agentes se reparte por pasillos paralelos.
+#[derive(Clone, Debug)]
+pub(crate) struct RouteReuse {
+    pub penalty: f32,
+    pub decay: f32,
+    pub heat: Vec<f32>,
+}
+
+impl RouteReuse {
+    pub fn new(penalty: f32, decay: f32, cells: usize) -> Self {
+        Self {
+            penalty: penalty.max(0.0),
+            decay: decay.clamp(0.0, 1.0),
+            heat: vec![0.0; cells],
+        }
+    }
+
+    pub fn decay(&mut self) {
+        self.heat.iter_mut().for_each(|h| *h *= self.decay);
+    }
+
+    pub fn add_path(&mut self, cells: impl IntoIterator<Item = usize>) {
+        for idx in cells {
+            self.heat[idx] += self.penalty;
+        }
+    }
+}
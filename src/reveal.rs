@@ -0,0 +1,78 @@
+use wasm_bindgen::prelude::*;
+
+use crate::distance;
+use crate::grid::Grid;
+
+/// Orden en que se descubre el mapa desde un origen
+/// (`PathFinder::reveal_order`): las celdas alcanzables de menor a mayor
+/// distancia a pie, para animar la revelación por trozos sin rehacer el BFS
+/// en JS. Las celdas a las que no se llega no aparecen.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RevealOrder {
+    cells: Vec<(usize, usize)>,
+    distances: Vec<u32>,
+}
+
+#[wasm_bindgen]
+impl RevealOrder {
+    /// Celdas en el orden.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Hasta `count` celdas desde la posición `offset` del orden, como
+    /// `[x0, y0, d0, x1, y1, d1, ...]` con su distancia; vacío al pasarse
+    /// del final.
+    pub fn chunk(&self, offset: usize, count: usize) -> Vec<usize> {
+        let end = offset.saturating_add(count).min(self.cells.len());
+        (offset.min(end)..end)
+            .flat_map(|i| {
+                let (x, y) = self.cells[i];
+                [x, y, self.distances[i] as usize]
+            })
+            .collect()
+    }
+
+    /// Posición del orden donde empieza cada distancia (0, 1, 2, ...): el
+    /// anillo `d` son las celdas entre `rings()[d]` y `rings()[d + 1]`.
+    pub fn rings(&self) -> Vec<usize> {
+        let starts = self.distances.iter().enumerate();
+        starts
+            .filter(|&(i, &d)| i == 0 || d != self.distances[i - 1])
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// Celdas alcanzables desde `origin` en orden de BFS; sin nada si `origin`
+/// no es transitable.
+pub(crate) fn order(grid: &Grid, origin: (usize, usize)) -> RevealOrder {
+    let (dist, order) = distance::bfs_distances(grid, &[origin], u32::MAX);
+    RevealOrder {
+        distances: order.iter().map(|&idx| dist[idx]).collect(),
+        cells: order.into_iter().map(|idx| grid.coords(idx)).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: el orden va por anillos de distancia y se lee por trozos
+    #[test]
+    fn reveal_in_rings() {
+        let grid = Grid::new(vec![1, 1, 1, 1, 0, 1, 1, 1, 1], 3, 3);
+        let reveal = order(&grid, (0, 0));
+        assert_eq!(reveal.len(), 8);
+        assert_eq!(reveal.rings(), vec![0, 1, 3, 5, 7]);
+        assert_eq!(reveal.chunk(0, 2), vec![0, 0, 0, 1, 0, 1]);
+        assert_eq!(reveal.chunk(7, 5), vec![2, 2, 4]);
+        assert!(reveal.chunk(9, 5).is_empty());
+        assert!(order(&grid, (1, 1)).is_empty());
+    }
+}
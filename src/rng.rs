@@ -0,0 +1,42 @@
+/// Mezclador SplitMix64: determinista y sin estado, suficiente para ruido de
+/// costes y generación procedural.
+pub(crate) fn splitmix64(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Valor uniforme en `[0, 1)` derivado de `seed` y `key`.
+pub(crate) fn unit(seed: u64, key: u64) -> f64 {
+    (splitmix64(seed ^ splitmix64(key)) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Generador pseudoaleatorio con estado (SplitMix64), reproducible a partir
+/// de la semilla.
+#[derive(Clone, Debug)]
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Estado actual; `Rng::new(state)` continúa la misma secuencia.
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let z = self.state;
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        splitmix64(z)
+    }
+
+    /// Entero uniforme en `0..n` (`n > 0`).
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
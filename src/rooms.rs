@@ -0,0 +1,405 @@
+use std::collections::VecDeque;
+
+use wasm_bindgen::prelude::*;
+
+use crate::distance;
+use crate::grid::{DIRS, Grid};
+
+/// Tipo de zona de `Rooms::kind`.
+pub(crate) const ROOM: u8 = 0;
+pub(crate) const CORRIDOR: u8 = 1;
+pub(crate) const DOOR: u8 = 2;
+
+/// Vecinos de una celda en 8-vecindad (los bordes de una sala se cuentan
+/// en diagonal para que sus esquinas entren en ella).
+const AROUND: [(isize, isize); 8] = [
+    (1, 0),
+    (0, 1),
+    (-1, 0),
+    (0, -1),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+];
+
+/// Reparto del espacio transitable en salas y pasillos
+/// (`PathFinder::detect_rooms`). Las zonas se numeran desde 0: primero las
+/// salas y después pasillos y puertas, cada grupo en el orden en que aparece
+/// su primera celda al recorrer el grid por filas, así que el mismo mapa da
+/// siempre los mismos números y se les puede poner nombre ("la armería").
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct Rooms {
+    ids: Vec<i32>,
+    kinds: Vec<u8>,
+    names: Vec<String>,
+    width: usize,
+}
+
+#[wasm_bindgen]
+impl Rooms {
+    /// Zona de cada celda (`idx = x * width + y`); -1 en las paredes.
+    pub fn ids(&self) -> Vec<i32> {
+        self.ids.clone()
+    }
+
+    /// Zona de la celda `(x, y)`; -1 en paredes y fuera del grid.
+    pub fn id_at(&self, x: usize, y: usize) -> i32 {
+        if y >= self.width {
+            return -1;
+        }
+        self.ids.get(x * self.width + y).copied().unwrap_or(-1)
+    }
+
+    /// Número de zonas.
+    pub fn count(&self) -> usize {
+        self.kinds.len()
+    }
+
+    /// 0 si la zona `id` es una sala, 1 si es un pasillo y 2 si es una
+    /// puerta.
+    pub fn kind(&self, id: usize) -> Option<u8> {
+        self.kinds.get(id).copied()
+    }
+
+    /// Rectángulo que ocupa la zona `id` como `[x_min, y_min, x_max, y_max]`
+    /// (incluidos); vacío si no existe.
+    pub fn bounds(&self, id: usize) -> Vec<usize> {
+        let cells = self
+            .ids
+            .iter()
+            .enumerate()
+            .filter(|&(_, &z)| z == id as i32)
+            .map(|(idx, _)| (idx / self.width, idx % self.width));
+        cells.fold(Vec::new(), |b, (x, y)| match b[..] {
+            [x0, y0, x1, y1] => vec![x0.min(x), y0.min(y), x1.max(x), y1.max(y)],
+            _ => vec![x, y, x, y],
+        })
+    }
+
+    /// Contorno de la zona `id` como polígono `[x0, y0, x1, y1, ...]` sobre
+    /// las esquinas de las celdas (la celda `(x, y)` va de la esquina
+    /// `(x, y)` a la `(x + 1, y + 1)`), en sentido horario desde la esquina
+    /// superior izquierda y sin vértices en mitad de un lado. Solo el borde
+    /// exterior: un pilar dentro de una sala no abre un agujero. Vacío si no
+    /// existe.
+    pub fn polygon(&self, id: usize) -> Vec<usize> {
+        outline(&self.ids, self.width, id as i32)
+            .into_iter()
+            .flat_map(|(x, y)| [x, y])
+            .collect()
+    }
+
+    /// Nombre de la zona `id`: el puesto con `set_name` o, si no, "sala N",
+    /// "pasillo N" o "puerta N" con su número dentro de su tipo.
+    pub fn name(&self, id: usize) -> Option<String> {
+        self.names.get(id).cloned()
+    }
+
+    pub fn set_name(&mut self, id: usize, name: String) -> bool {
+        match self.names.get_mut(id) {
+            Some(slot) => {
+                *slot = name;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Salas y pasillos de `grid`. El núcleo de una sala es una componente
+/// conexa de celdas con holgura `min_clearance` o más y al menos
+/// `min_cells` celdas; la sala se extiende desde él `min_clearance - 1`
+/// celdas (en 8-vecindad) para cubrir sus bordes y esquinas, sin entrar en
+/// estrechamientos (celdas con paredes a ambos lados). Lo transitable que
+/// queda son pasillos, una zona por componente. Cada celda de `doors` es una
+/// zona de tipo puerta que separa lo que hay a sus lados.
+pub(crate) fn detect(grid: &Grid, doors: &[usize], min_clearance: u32, min_cells: usize) -> Rooms {
+    let clearance = distance::clearance_map(grid);
+    let mut free: Vec<bool> = grid.cells.iter().map(|&c| c == 1).collect();
+    for &door in doors {
+        if let Some(f) = free.get_mut(door) {
+            *f = false;
+        }
+    }
+    let min_clearance = min_clearance.max(1);
+    let core = |idx: usize| free[idx] && clearance[idx] >= min_clearance;
+
+    let mut ids = vec![-1i32; grid.cells.len()];
+    let mut kinds = Vec::new();
+    let mut queue = VecDeque::new();
+    let mut seen = vec![false; grid.cells.len()];
+    for idx in 0..grid.cells.len() {
+        if !core(idx) || seen[idx] {
+            continue;
+        }
+        let members = flood(grid, idx, core, &mut seen);
+        if members.len() < min_cells {
+            continue;
+        }
+        let id = kinds.len() as i32;
+        kinds.push(ROOM);
+        for &m in &members {
+            ids[m] = id;
+            queue.push_back((m, 0));
+        }
+    }
+
+    // Los bordes de las salas, a la vez desde todos los núcleos.
+    while let Some((idx, depth)) = queue.pop_front() {
+        if depth + 1 >= min_clearance {
+            continue;
+        }
+        for n in around(grid, idx) {
+            if free[n] && ids[n] == -1 && !pinched(grid, n) {
+                ids[n] = ids[idx];
+                queue.push_back((n, depth + 1));
+            }
+        }
+    }
+
+    let mut unassigned = vec![false; grid.cells.len()];
+    for idx in 0..grid.cells.len() {
+        if grid.cells[idx] != 1 || ids[idx] != -1 {
+            continue;
+        }
+        let id = kinds.len() as i32;
+        if !free[idx] {
+            kinds.push(DOOR);
+            ids[idx] = id;
+            continue;
+        }
+        kinds.push(CORRIDOR);
+        // Los núcleos demasiado pequeños ya están en `seen` y son pasillo.
+        for m in flood(grid, idx, |n| free[n] && ids[n] == -1, &mut unassigned) {
+            ids[m] = id;
+        }
+    }
+
+    let mut counts = [0; 3];
+    let names = kinds
+        .iter()
+        .map(|&kind| {
+            let n = counts[usize::from(kind)];
+            counts[usize::from(kind)] += 1;
+            match kind {
+                ROOM => format!("sala {n}"),
+                CORRIDOR => format!("pasillo {n}"),
+                _ => format!("puerta {n}"),
+            }
+        })
+        .collect();
+    Rooms {
+        ids,
+        kinds,
+        names,
+        width: grid.width,
+    }
+}
+
+/// Sitios donde poner puertas: cada celda de pasillo pegada (en 4-vecindad)
+/// a una sala y con paredes a ambos lados, como `(celda, sala, pasillo)` en
+/// orden de celda. Un pasillo que entra a una sala por una boca ancha no
+/// lleva puerta.
+pub(crate) fn suggest_doors(grid: &Grid, rooms: &Rooms) -> Vec<(usize, usize, usize)> {
+    let zone = |idx: usize| usize::try_from(rooms.ids[idx]).ok();
+    (0..grid.cells.len())
+        .filter_map(|idx| {
+            let corridor = zone(idx).filter(|&z| rooms.kinds[z] == CORRIDOR)?;
+            if !pinched(grid, idx) {
+                return None;
+            }
+            let room = step(grid, idx, &DIRS)
+                .filter_map(zone)
+                .find(|&z| rooms.kinds[z] == ROOM)?;
+            Some((idx, room, corridor))
+        })
+        .collect()
+}
+
+/// Borde exterior de las celdas con zona `id` (ver `Rooms::polygon`). Se
+/// recorre con el interior a la derecha; donde dos celdas de la zona solo
+/// se tocan por una esquina se gira a la izquierda para no dejar fuera a
+/// ninguna.
+fn outline(ids: &[i32], width: usize, id: i32) -> Vec<(usize, usize)> {
+    let Some(first) = ids.iter().position(|&z| z == id) else {
+        return Vec::new();
+    };
+    let inside = |x: isize, y: isize| {
+        let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) else {
+            return false;
+        };
+        y < width && ids.get(x * width + y) == Some(&id)
+    };
+    // Derecha, abajo, izquierda y arriba: cada una gira 90° a la derecha.
+    const HEADINGS: [(isize, isize); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+    // Hay lado de la esquina `(x, y)` hacia `h` si la celda a su derecha es
+    // de la zona y la de su izquierda no.
+    let side = |x: isize, y: isize, h: usize| match h {
+        0 => inside(x, y) && !inside(x - 1, y),
+        1 => inside(x, y - 1) && !inside(x, y),
+        2 => inside(x - 1, y - 1) && !inside(x, y - 1),
+        _ => inside(x - 1, y) && !inside(x - 1, y - 1),
+    };
+    let start = ((first / width) as isize, (first % width) as isize);
+    let (mut x, mut y, mut h) = (start.0, start.1, 0);
+    let mut corners = vec![(start.0 as usize, start.1 as usize)];
+    loop {
+        x += HEADINGS[h].0;
+        y += HEADINGS[h].1;
+        if (x, y) == start {
+            return corners;
+        }
+        let Some(next) = [h + 3, h, h + 1]
+            .map(|t| t % 4)
+            .into_iter()
+            .find(|&t| side(x, y, t))
+        else {
+            return corners;
+        };
+        if next != h {
+            corners.push((x as usize, y as usize));
+            h = next;
+        }
+    }
+}
+
+/// Celdas del grid alrededor de `idx` en la vecindad `dirs`.
+fn step<'a>(
+    grid: &'a Grid,
+    idx: usize,
+    dirs: &'a [(isize, isize)],
+) -> impl Iterator<Item = usize> + 'a {
+    let (x, y) = grid.coords(idx);
+    dirs.iter().filter_map(move |&(dx, dy)| {
+        let cell = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+        grid.in_bounds(cell).then(|| grid.idx(cell))
+    })
+}
+
+fn around(grid: &Grid, idx: usize) -> impl Iterator<Item = usize> + '_ {
+    step(grid, idx, &AROUND)
+}
+
+/// Si `idx` tiene pared (o el borde) arriba y abajo, o a izquierda y
+/// derecha.
+pub(crate) fn pinched(grid: &Grid, idx: usize) -> bool {
+    let blocked = |d| {
+        step(grid, idx, &[d])
+            .next()
+            .is_none_or(|n| grid.cells[n] != 1)
+    };
+    (blocked(AROUND[0]) && blocked(AROUND[2])) || (blocked(AROUND[1]) && blocked(AROUND[3]))
+}
+
+/// Componente (4-vecindad) de `start` por las celdas que cumplen `inside`,
+/// marcándolas en `seen`; las ya marcadas no entran.
+fn flood(
+    grid: &Grid,
+    start: usize,
+    inside: impl Fn(usize) -> bool,
+    seen: &mut [bool],
+) -> Vec<usize> {
+    let mut members = vec![start];
+    seen[start] = true;
+    let mut i = 0;
+    while let Some(&idx) = members.get(i) {
+        i += 1;
+        for n in step(grid, idx, &DIRS) {
+            if !seen[n] && inside(n) {
+                seen[n] = true;
+                members.push(n);
+            }
+        }
+    }
+    members
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: dos salas unidas por un pasillo, con una puerta sugerida en cada
+    /// boca; una puerta en el pasillo lo parte en dos
+    #[test]
+    fn rooms_and_corridors() {
+        #[rustfmt::skip]
+        let cells = vec![
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 1, 1, 1, 0, 0, 0, 1, 1, 1, 0,
+            0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0,
+            0, 1, 1, 1, 0, 0, 0, 1, 1, 1, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ];
+        let grid = Grid::new(cells, 11, 5);
+        let rooms = detect(&grid, &[], 2, 1);
+        assert_eq!(rooms.count(), 3);
+        assert_eq!((rooms.kind(0), rooms.kind(2)), (Some(ROOM), Some(CORRIDOR)));
+        assert_eq!(rooms.bounds(0), vec![1, 1, 3, 3]);
+        assert_eq!(rooms.bounds(2), vec![2, 4, 2, 6]);
+        assert_eq!((rooms.id_at(1, 9), rooms.id_at(0, 0)), (1, -1));
+        assert_eq!(rooms.name(2).as_deref(), Some("pasillo 0"));
+
+        let doors = suggest_doors(&grid, &rooms);
+        assert_eq!(doors, vec![(2 * 11 + 4, 0, 2), (2 * 11 + 6, 1, 2)]);
+
+        let split = detect(&grid, &[2 * 11 + 5], 2, 1);
+        assert_eq!(split.count(), 5);
+        assert_eq!(split.kind(3), Some(DOOR));
+        assert_eq!(split.bounds(4), vec![2, 6, 2, 6]);
+    }
+
+    /// Test: los contornos siguen la forma de la zona, no su rectángulo
+    #[test]
+    fn zone_polygons() {
+        #[rustfmt::skip]
+        let cells = vec![
+            1, 1, 1, 0, 0,
+            1, 1, 1, 0, 0,
+            1, 1, 1, 1, 1,
+            0, 0, 0, 0, 1,
+        ];
+        let grid = Grid::new(cells, 5, 4);
+        let rooms = detect(&grid, &[], 2, 1);
+        assert_eq!(rooms.count(), 2);
+        assert_eq!(rooms.polygon(0), vec![0, 0, 0, 3, 3, 3, 3, 0]);
+        assert_eq!(rooms.polygon(1), vec![2, 3, 2, 5, 4, 5, 4, 4, 3, 4, 3, 3]);
+        assert!(rooms.polygon(2).is_empty());
+
+        // Dos celdas que solo se tocan en diagonal quedan dentro del mismo
+        // contorno.
+        let diagonal = [0, -1, -1, 0];
+        let corners = outline(&diagonal, 2, 0);
+        assert_eq!(
+            corners,
+            vec![
+                (0, 0),
+                (0, 1),
+                (1, 1),
+                (1, 2),
+                (2, 2),
+                (2, 1),
+                (1, 1),
+                (1, 0)
+            ]
+        );
+    }
+
+    /// Test: un núcleo demasiado pequeño para ser sala queda en un único
+    /// pasillo con lo que lo rodea, no partido en pasillos de una celda
+    #[test]
+    fn small_cores_join_their_corridor() {
+        #[rustfmt::skip]
+        let cells = vec![
+            1, 1, 1, 1, 1,
+            1, 1, 1, 1, 1,
+            1, 1, 1, 1, 1,
+        ];
+        let grid = Grid::new(cells, 5, 3);
+        let rooms = detect(&grid, &[], 2, 10);
+        assert_eq!(rooms.count(), 1);
+        assert_eq!(rooms.kind(0), Some(CORRIDOR));
+        assert!(rooms.ids().iter().all(|&z| z == 0));
+    }
+}
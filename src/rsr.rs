@@ -0,0 +1,232 @@
+use crate::grid::Grid;
+use crate::search::{self, Outcome};
+
+/// Rectángulo de celdas libres: filas `x..x + h`, columnas `y..y + w`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub h: usize,
+    pub w: usize,
+}
+
+impl Rect {
+    fn on_perimeter(&self, (x, y): (usize, usize)) -> bool {
+        x == self.x || x == self.x + self.h - 1 || y == self.y || y == self.y + self.w - 1
+    }
+}
+
+/// Reducción de simetrías rectangulares (RSR): el espacio libre se divide en
+/// rectángulos y dentro de cada uno solo se expande el perímetro, con saltos
+/// en línea recta de un lado al opuesto. En 4-vecindad con coste unitario da
+/// rutas óptimas y evita miles de expansiones idénticas en campo abierto.
+///
+/// Guarda las celdas con las que se calculó para detectar si siguen valiendo.
+#[derive(Clone, Debug)]
+pub(crate) struct Rectangles {
+    cells: Vec<u8>,
+    pub rects: Vec<Rect>,
+    owner: Vec<usize>,
+}
+
+impl Rectangles {
+    /// Descomposición voraz: desde cada celda libre sin cubrir, en orden de
+    /// índice, crece primero a lo ancho y después a lo alto.
+    pub fn decompose(grid: &Grid) -> Self {
+        let free = |idx: usize, owner: &[usize]| grid.cells[idx] == 1 && owner[idx] == usize::MAX;
+        let mut owner = vec![usize::MAX; grid.cells.len()];
+        let mut rects = Vec::new();
+        for idx in 0..grid.cells.len() {
+            if !free(idx, &owner) {
+                continue;
+            }
+            let (x, y) = grid.coords(idx);
+            let w = (y..grid.width)
+                .take_while(|&c| free(grid.idx((x, c)), &owner))
+                .count();
+            let h = (x..grid.height)
+                .take_while(|&r| (y..y + w).all(|c| free(grid.idx((r, c)), &owner)))
+                .count();
+            for r in x..x + h {
+                for c in y..y + w {
+                    owner[grid.idx((r, c))] = rects.len();
+                }
+            }
+            rects.push(Rect { x, y, h, w });
+        }
+        Self {
+            cells: grid.cells.clone(),
+            rects,
+            owner,
+        }
+    }
+
+    pub fn valid_for(&self, grid: &Grid) -> bool {
+        self.cells == grid.cells
+    }
+
+    fn rect_of(&self, grid: &Grid, cell: (usize, usize)) -> Rect {
+        self.rects[self.owner[grid.idx(cell)]]
+    }
+
+    /// Búsqueda de coste unitario entre `start` y `goal` sobre el grafo
+    /// reducido. La ruta devuelta incluye todas las celdas intermedias.
+    pub fn search(
+        &self,
+        grid: &Grid,
+        start: (usize, usize),
+        goal: (usize, usize),
+    ) -> Outcome<(usize, usize)> {
+        if !grid.walkable(start) || !grid.walkable(goal) {
+            return Outcome::none();
+        }
+        let goal_rect = self.rect_of(grid, goal);
+        let outcome = search::astar(
+            start,
+            |&cell| cell == goal,
+            |&cell| self.successors(grid, cell, start, goal, goal_rect),
+            |&(x, y)| (x.abs_diff(goal.0) + y.abs_diff(goal.1)) as f64,
+        );
+        Outcome {
+            result: outcome.result.map(|mut found| {
+                found.states = unfold(&found.states);
+                found.g = (0..found.states.len()).map(|i| i as f64).collect();
+                found
+            }),
+            ..outcome
+        }
+    }
+
+    fn successors(
+        &self,
+        grid: &Grid,
+        cell: (usize, usize),
+        start: (usize, usize),
+        goal: (usize, usize),
+        goal_rect: Rect,
+    ) -> Vec<((usize, usize), f64)> {
+        let rect = self.rect_of(grid, cell);
+        let mut next = Vec::new();
+        let mut jump = |to: (usize, usize)| {
+            if to != cell {
+                let cost = to.0.abs_diff(cell.0) + to.1.abs_diff(cell.1);
+                next.push((to, cost as f64));
+            }
+        };
+
+        if rect == goal_rect && (cell.0 == goal.0 || cell.1 == goal.1) {
+            jump(goal);
+        }
+        if cell == start && !rect.on_perimeter(cell) {
+            if rect == goal_rect {
+                jump((cell.0, goal.1));
+                jump((goal.0, cell.1));
+            }
+            // Desde el interior solo se sale en línea recta hasta el borde.
+            jump((rect.x, cell.1));
+            jump((rect.x + rect.h - 1, cell.1));
+            jump((cell.0, rect.y));
+            jump((cell.0, rect.y + rect.w - 1));
+            return next;
+        }
+
+        // Saltos al lado opuesto del rectángulo.
+        if cell.0 == rect.x {
+            jump((rect.x + rect.h - 1, cell.1));
+        }
+        if cell.0 == rect.x + rect.h - 1 {
+            jump((rect.x, cell.1));
+        }
+        if cell.1 == rect.y {
+            jump((cell.0, rect.y + rect.w - 1));
+        }
+        if cell.1 == rect.y + rect.w - 1 {
+            jump((cell.0, rect.y));
+        }
+        for n in grid.neighbors(cell).filter(|&n| grid.walkable(n)) {
+            if self.rect_of(grid, n).on_perimeter(n) {
+                next.push((n, 1.0));
+            }
+        }
+        next
+    }
+}
+
+/// Despliega en celdas los saltos de la ruta reducida, que siempre van en
+/// línea recta por una fila o una columna.
+fn unfold(states: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut cells = states[..1].to_vec();
+    for w in states.windows(2) {
+        let (mut cur, to) = (w[0], w[1]);
+        while cur != to {
+            cur = (step_toward(cur.0, to.0), step_toward(cur.1, to.1));
+            cells.push(cur);
+        }
+    }
+    cells
+}
+
+fn step_toward(from: usize, to: usize) -> usize {
+    match from.cmp(&to) {
+        std::cmp::Ordering::Less => from + 1,
+        std::cmp::Ordering::Greater => from - 1,
+        std::cmp::Ordering::Equal => from,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distance::{UNREACHED, bfs_distances};
+    use crate::rng::Rng;
+
+    /// Test: en campo abierto hay un solo rectángulo y se expande mucho menos
+    #[test]
+    fn open_field_is_one_rectangle() {
+        let grid = Grid::square(vec![1; 400], 20);
+        let rsr = Rectangles::decompose(&grid);
+        assert_eq!(
+            rsr.rects,
+            vec![Rect {
+                x: 0,
+                y: 0,
+                h: 20,
+                w: 20
+            }]
+        );
+        let outcome = rsr.search(&grid, (0, 0), (19, 19));
+        let found = outcome.result.unwrap();
+        assert_eq!(found.cost, 38.0);
+        assert_eq!(found.states.len(), 39);
+        assert!(outcome.expanded.len() < 80);
+    }
+
+    /// Test: mismo coste que el BFS en grids aleatorios, con rutas contiguas
+    #[test]
+    fn matches_bfs_on_random_grids() {
+        let mut rng = Rng::new(7);
+        for round in 0..60 {
+            let density = if round % 2 == 0 { 14 } else { 19 };
+            let cells: Vec<u8> = (0..144)
+                .map(|_| u8::from(rng.below(20) < density))
+                .collect();
+            let grid = Grid::square(cells, 12);
+            let rsr = Rectangles::decompose(&grid);
+            let start = (rng.below(12), rng.below(12));
+            let goal = (rng.below(12), rng.below(12));
+            let (dist, _) = bfs_distances(&grid, &[start], u32::MAX);
+            let found = rsr.search(&grid, start, goal).result;
+            if !grid.walkable(start) || dist[grid.idx(goal)] == UNREACHED {
+                assert!(found.is_none());
+                continue;
+            }
+            let found = found.unwrap();
+            assert_eq!(found.cost, f64::from(dist[grid.idx(goal)]));
+            assert_eq!(found.states.len(), found.cost as usize + 1);
+            for w in found.states.windows(2) {
+                assert!(grid.walkable(w[1]));
+                assert_eq!(w[0].0.abs_diff(w[1].0) + w[0].1.abs_diff(w[1].1), 1);
+            }
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use crate::SearchOptions;
+use crate::distance::{UNREACHED, bfs_distances};
+use crate::grid::Grid;
+use crate::search::{self, Outcome};
+
+/// Celdas a `distance` pasos o menos de alguna amenaza, medidos con un BFS
+/// multifuente por celdas libres. Las amenazas sobre paredes no cuentan.
+pub(crate) fn danger_zone(grid: &Grid, threats: &[(usize, usize)], distance: u32) -> Vec<bool> {
+    let (dist, _) = bfs_distances(grid, threats, distance);
+    dist.into_iter().map(|d| d != UNREACHED).collect()
+}
+
+/// `grid_search` que nunca entra en la zona de peligro de `threats`. Si no
+/// hay ruta devuelve también el diagnóstico: las celdas peligrosas por las
+/// que pasaría la ruta sin la restricción (vacío si tampoco la hay).
+pub(crate) fn safe_search(
+    grid: &Grid,
+    start: (usize, usize),
+    goal: (usize, usize),
+    opts: &SearchOptions,
+    threats: &[(usize, usize)],
+    distance: u32,
+) -> (Outcome<(usize, usize)>, Vec<usize>) {
+    let danger = danger_zone(grid, threats, distance);
+    let mut safe = opts.clone();
+    safe.region = Some(
+        (0..grid.cells.len())
+            .map(|i| u8::from(!danger[i] && opts.in_region(i)))
+            .collect(),
+    );
+    let outcome = search::grid_search(grid, start, goal, &safe);
+    if outcome.result.is_some() {
+        return (outcome, vec![]);
+    }
+    let blocking = search::grid_search(grid, start, goal, opts)
+        .result
+        .map(|found| {
+            let cells = found.states.into_iter().map(|c| grid.idx(c));
+            cells.filter(|&i| danger[i]).collect()
+        })
+        .unwrap_or_default();
+    (outcome, blocking)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: rodea la zona de peligro y, si no puede, dice qué celdas la cortan
+    #[test]
+    fn keeps_away_from_threats() {
+        let grid = Grid::square(vec![1; 25], 5);
+        let opts = SearchOptions::default();
+        let (outcome, blocking) = safe_search(&grid, (0, 0), (0, 4), &opts, &[(0, 2)], 1);
+        let found = outcome.result.unwrap();
+        assert!(blocking.is_empty());
+        assert!(
+            found
+                .states
+                .iter()
+                .all(|&(x, y)| x.abs_diff(0) + y.abs_diff(2) > 1)
+        );
+        assert_eq!(found.cost, 8.0);
+
+        let (outcome, blocking) = safe_search(&grid, (0, 0), (0, 4), &opts, &[(2, 2)], 2);
+        assert!(outcome.result.is_none());
+        assert_eq!(blocking, vec![2]);
+    }
+}
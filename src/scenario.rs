@@ -0,0 +1,286 @@
+use crate::SearchOptions;
+use crate::bytes::{Reader, Writer};
+use crate::clock::SimClock;
+use crate::congestion::Congestion;
+use crate::fog::Fog;
+use crate::grid::Grid;
+use crate::hazard::Hazard;
+use crate::locks::Locks;
+use crate::overlays::{Overlay, Overlays};
+use crate::reuse::RouteReuse;
+use crate::spacetime::MovingObstacle;
+
+const MAGIC: &[u8; 4] = b"PFSC";
+/// Sube también cuando cambia el formato de `SearchOptions::write_to`.
+const VERSION: u8 = 8;
+
+type Query = ((usize, usize), (usize, usize));
+
+/// Todo lo que define un escenario de `PathFinder`: grid con sus capas,
+/// opciones de la última búsqueda, consultas de inicio y destino y las
+/// capas superpuestas (niebla, puertas y llaves, obstáculos móviles,
+/// penalizaciones dinámicas y capas de coste programadas) con el reloj de
+/// la simulación.
+#[derive(Clone, Debug)]
+pub(crate) struct Scenario {
+    pub grid: Grid,
+    pub options: Option<SearchOptions>,
+    pub queries: Vec<Query>,
+    pub fog: Option<Fog>,
+    pub locks: Locks,
+    pub moving: Vec<MovingObstacle>,
+    pub reuse: Option<RouteReuse>,
+    pub hazard: Option<Hazard>,
+    pub congestion: Option<Congestion>,
+    pub overlays: Overlays,
+    pub sim_clock: Option<SimClock>,
+}
+
+pub(crate) fn encode(s: &Scenario) -> Vec<u8> {
+    let mut w = Writer::new();
+    w.bytes(MAGIC);
+    w.u8(VERSION);
+    s.grid.write_to(&mut w);
+    write_option(&mut w, s.options.as_ref(), |w, opts| opts.write_to(w));
+    w.u32(s.queries.len() as u32);
+    for &((sx, sy), (gx, gy)) in &s.queries {
+        [sx, sy, gx, gy].iter().for_each(|&v| w.u32(v as u32));
+    }
+    write_option(&mut w, s.fog.as_ref(), |w, fog| {
+        fog.explored.iter().for_each(|&seen| w.u8(u8::from(seen)));
+    });
+    for labels in [&s.locks.doors, &s.locks.keys] {
+        let mut sorted: Vec<_> = labels.iter().collect();
+        sorted.sort();
+        w.u32(sorted.len() as u32);
+        for (&idx, &label) in sorted {
+            w.u32(idx as u32);
+            w.u8(label);
+        }
+    }
+    w.u32(s.moving.len() as u32);
+    for obstacle in &s.moving {
+        w.f32(obstacle.speed);
+        w.u32(obstacle.path.len() as u32);
+        for &(x, y) in &obstacle.path {
+            w.u32(x as u32);
+            w.u32(y as u32);
+        }
+    }
+    write_option(&mut w, s.reuse.as_ref(), |w, reuse| {
+        w.f32(reuse.penalty);
+        w.f32(reuse.decay);
+        reuse.heat.iter().for_each(|&v| w.f32(v));
+    });
+    write_option(&mut w, s.hazard.as_ref(), |w, hazard| {
+        w.f32(hazard.spread);
+        w.f32(hazard.decay);
+        w.f32(hazard.weight);
+        hazard.values.iter().for_each(|&v| w.f32(v));
+    });
+    write_option(&mut w, s.congestion.as_ref(), |w, congestion| {
+        w.f32(congestion.weight);
+        w.f32(congestion.decay);
+        congestion.load.iter().for_each(|&v| w.f32(v));
+    });
+    w.f32(s.overlays.time);
+    w.u32(s.overlays.items.len() as u32);
+    for overlay in &s.overlays.items {
+        w.u32(overlay.name.len() as u32);
+        w.bytes(overlay.name.as_bytes());
+        overlay.costs.iter().for_each(|&v| w.f32(v));
+        w.u32(overlay.windows.len() as u32);
+        for &(start, end) in &overlay.windows {
+            w.f32(start);
+            w.f32(end);
+        }
+    }
+    write_option(&mut w, s.sim_clock.as_ref(), |w, clock| {
+        w.u32(clock.tick);
+        w.f32(clock.step);
+        w.f32(clock.origin);
+    });
+    w.finish()
+}
+
+pub(crate) fn decode(bytes: &[u8]) -> Option<Scenario> {
+    let mut r = Reader::new(bytes);
+    if r.bytes(4)? != MAGIC || r.u8()? != VERSION {
+        return None;
+    }
+    let grid = Grid::read_from(&mut r)?;
+    let len = grid.cells.len();
+    let in_grid = |(x, y): (usize, usize)| grid.in_bounds((x, y)).then_some((x, y));
+    let options = read_option(&mut r, SearchOptions::read_from)?;
+    let queries = (0..r.u32()?)
+        .map(|_| {
+            let start = (r.u32()? as usize, r.u32()? as usize);
+            let goal = (r.u32()? as usize, r.u32()? as usize);
+            Some((in_grid(start)?, in_grid(goal)?))
+        })
+        .collect::<Option<_>>()?;
+    let fog = read_option(&mut r, |r| {
+        let explored = r.bytes(len)?.iter().map(|&b| b != 0).collect();
+        Some(Fog { explored })
+    })?;
+    let mut locks = Locks::default();
+    for labels in [&mut locks.doors, &mut locks.keys] {
+        for _ in 0..r.u32()? {
+            let idx = r.u32()? as usize;
+            let label = r.u8()?;
+            if idx >= len || label >= 32 {
+                return None;
+            }
+            labels.insert(idx, label);
+        }
+    }
+    let moving = (0..r.u32()?)
+        .map(|_| {
+            let speed = r.f32()?;
+            let path: Vec<_> = (0..r.u32()?)
+                .map(|_| in_grid((r.u32()? as usize, r.u32()? as usize)))
+                .collect::<Option<_>>()?;
+            MovingObstacle::new(path, speed)
+        })
+        .collect::<Option<_>>()?;
+    let reuse = read_option(&mut r, |r| {
+        Some(RouteReuse {
+            penalty: r.f32()?,
+            decay: r.f32()?,
+            heat: read_values(r, len)?,
+        })
+    })?;
+    let hazard = read_option(&mut r, |r| {
+        Some(Hazard {
+            spread: r.f32()?,
+            decay: r.f32()?,
+            weight: r.f32()?,
+            values: read_values(r, len)?,
+        })
+    })?;
+    let congestion = read_option(&mut r, |r| {
+        Some(Congestion {
+            weight: r.f32()?,
+            decay: r.f32()?,
+            load: read_values(r, len)?,
+        })
+    })?;
+    let time = r.f32()?;
+    let items = (0..r.u32()?)
+        .map(|_| {
+            let name_len = r.u32()? as usize;
+            let name = String::from_utf8(r.bytes(name_len)?.to_vec()).ok()?;
+            let costs = read_values(&mut r, len)
+                .filter(|c| c.iter().all(|v| v.is_finite() && *v >= 0.0))?;
+            let windows = (0..r.u32()?)
+                .map(|_| Some((r.f32()?, r.f32()?)))
+                .collect::<Option<_>>()?;
+            Some(Overlay {
+                name,
+                costs,
+                windows,
+            })
+        })
+        .collect::<Option<_>>()?;
+    let sim_clock = read_option(&mut r, |r| {
+        Some(SimClock {
+            tick: r.u32()?,
+            step: r.f32()?,
+            origin: r.f32()?,
+        })
+        .filter(SimClock::is_valid)
+    })?;
+    r.is_empty().then_some(Scenario {
+        grid,
+        options,
+        queries,
+        fog,
+        locks,
+        moving,
+        reuse,
+        hazard,
+        congestion,
+        overlays: Overlays { items, time },
+        sim_clock,
+    })
+}
+
+fn write_option<T>(w: &mut Writer, value: Option<&T>, write: impl FnOnce(&mut Writer, &T)) {
+    match value {
+        Some(value) => {
+            w.u8(1);
+            write(w, value);
+        }
+        None => w.u8(0),
+    }
+}
+
+/// `Some(None)` si no estaba, `None` si los datos no son válidos.
+fn read_option<T>(
+    r: &mut Reader,
+    read: impl FnOnce(&mut Reader) -> Option<T>,
+) -> Option<Option<T>> {
+    match r.u8()? {
+        0 => Some(None),
+        _ => read(r).map(Some),
+    }
+}
+
+fn read_values(r: &mut Reader, len: usize) -> Option<Vec<f32>> {
+    (0..len).map(|_| r.f32()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: el escenario completo sobrevive a la ida y vuelta y se rechaza
+    /// si está truncado
+    #[test]
+    fn scenario_roundtrip() {
+        let mut locks = Locks::default();
+        locks.doors.insert(1, 3);
+        locks.keys.insert(2, 3);
+        let mut options = SearchOptions::default();
+        options.set_rotation_cost(0.5);
+        let scenario = Scenario {
+            grid: Grid::square(vec![1, 1, 1, 0], 2),
+            options: Some(options),
+            queries: vec![((0, 0), (1, 0))],
+            fog: Some(Fog::new(4)),
+            locks,
+            moving: vec![MovingObstacle {
+                path: vec![(0, 1), (1, 1)],
+                speed: 0.5,
+            }],
+            reuse: None,
+            hazard: Some(Hazard::new(0.5, 0.1, 2.0, 4)),
+            congestion: None,
+            overlays: Overlays {
+                items: vec![Overlay {
+                    name: "mercado".to_string(),
+                    costs: vec![1.0, 2.0, 3.0, 4.0],
+                    windows: vec![(9.0, 17.0)],
+                }],
+                time: 10.0,
+            },
+            sim_clock: Some(SimClock {
+                tick: 3,
+                step: 0.5,
+                origin: 8.5,
+            }),
+        };
+        let bytes = encode(&scenario);
+        let back = decode(&bytes).unwrap();
+        assert_eq!(back.grid, scenario.grid);
+        assert_eq!(back.options.unwrap().rotation_cost, 0.5);
+        assert_eq!(back.queries, scenario.queries);
+        assert_eq!(back.locks.doors.get(&1), Some(&3));
+        assert_eq!(back.moving[0].path, vec![(0, 1), (1, 1)]);
+        assert_eq!(back.hazard.unwrap().weight, 2.0);
+        assert!(back.reuse.is_none() && back.fog.is_some());
+        assert_eq!(back.overlays, scenario.overlays);
+        assert_eq!(back.sim_clock, scenario.sim_clock);
+        assert!(decode(&bytes[..bytes.len() - 1]).is_none());
+    }
+}
@@ -0,0 +1,117 @@
+use wasm_bindgen::prelude::*;
+
+use crate::distance;
+use crate::grid::{self, Grid};
+
+/// Puntuación de una ruta cualquiera (`PathFinder::score_route`) por
+/// criterios, para comparar la dibujada por un usuario con la sugerida.
+/// `total` combina los criterios con los pesos pedidos: menor es mejor.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RouteScore {
+    length: f64,
+    danger: f64,
+    turns: f64,
+    clearance: f64,
+    total: f64,
+}
+
+#[wasm_bindgen]
+impl RouteScore {
+    /// Pasos de la ruta.
+    pub fn length(&self) -> f64 {
+        self.length
+    }
+
+    /// Peligro acumulado: suma de la capa de peligro de cada celda en la que
+    /// entra (0 sin capa de peligro).
+    pub fn danger(&self) -> f64 {
+        self.danger
+    }
+
+    /// Cambios de dirección.
+    pub fn turns(&self) -> f64 {
+        self.turns
+    }
+
+    /// Menor distancia a una pared a lo largo de la ruta, sin contar los
+    /// extremos salvo que no haya celdas interiores.
+    pub fn clearance(&self) -> f64 {
+        self.clearance
+    }
+
+    /// `w_longitud * length + w_peligro * danger + w_giros * turns -
+    /// w_holgura * clearance`.
+    pub fn total(&self) -> f64 {
+        self.total
+    }
+}
+
+/// Puntúa `path`, que debe ser continua y transitable (ver `verify`), con
+/// `weights` = `[longitud, peligro, giros, holgura]`. `danger` es la capa de
+/// peligro por celda, si la hay.
+pub(crate) fn score(
+    grid: &Grid,
+    path: &[(usize, usize)],
+    danger: Option<&[f32]>,
+    weights: [f64; 4],
+) -> RouteScore {
+    let length = path.len().saturating_sub(1) as f64;
+    let danger = danger.map_or(0.0, |values| {
+        path.iter()
+            .skip(1)
+            .map(|&c| f64::from(values[grid.idx(c)]))
+            .sum()
+    });
+    let headings: Vec<_> = path
+        .windows(2)
+        .filter_map(|w| grid::direction(w[0], w[1]))
+        .collect();
+    let turns = headings.windows(2).filter(|h| h[0] != h[1]).count() as f64;
+    let map = distance::clearance_map(grid);
+    let interior = if path.len() > 2 {
+        &path[1..path.len() - 1]
+    } else {
+        path
+    };
+    let clearance = interior
+        .iter()
+        .map(|&c| map[grid.idx(c)])
+        .min()
+        .map_or(0.0, f64::from);
+    let [w_length, w_danger, w_turns, w_clearance] = weights;
+    RouteScore {
+        length,
+        danger,
+        turns,
+        clearance,
+        total: w_length * length + w_danger * danger + w_turns * turns - w_clearance * clearance,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: la ruta junto a la pared gira menos; la del centro tiene más
+    /// holgura y gana si se valora la holgura
+    #[test]
+    fn score_by_criteria() {
+        let mut cells = vec![1; 25];
+        cells[3] = 0;
+        let grid = Grid::square(cells, 5);
+        let edge = [(1, 1), (1, 2), (1, 3), (2, 3), (3, 3)];
+        let middle = [(1, 1), (2, 1), (2, 2), (3, 2), (3, 3)];
+        let danger = vec![0.5; 25];
+        let a = score(&grid, &edge, Some(&danger), [1.0, 1.0, 1.0, 0.0]);
+        let b = score(&grid, &middle, Some(&danger), [1.0, 1.0, 1.0, 0.0]);
+        assert_eq!((a.length(), a.danger(), a.turns()), (4.0, 2.0, 1.0));
+        assert_eq!((b.turns(), a.clearance(), b.clearance()), (3.0, 1.0, 2.0));
+        assert_eq!((a.total(), b.total()), (7.0, 9.0));
+
+        // Valorando la holgura cambia la ganadora.
+        let a = score(&grid, &edge, Some(&danger), [1.0, 1.0, 1.0, 4.0]);
+        let b = score(&grid, &middle, Some(&danger), [1.0, 1.0, 1.0, 4.0]);
+        assert_eq!((a.total(), b.total()), (3.0, 1.0));
+    }
+}
@@ -0,0 +1,1471 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+
+use crate::SearchOptions;
+use crate::adjacency::Adjacency;
+use crate::cost::StepCost;
+use crate::distance::dijkstra;
+use crate::grid::{Grid, opposite};
+use crate::options::MustVisit;
+use crate::queue::{Frontier, Keyed, OpenList};
+
+/// Resultado de una búsqueda con costes: estados de inicio a fin, el `g`
+/// con que la búsqueda llegó a cada uno (el último es `cost`) y coste total.
+#[derive(Clone, Debug)]
+pub(crate) struct Found<S> {
+    pub states: Vec<S>,
+    pub g: Vec<f64>,
+    pub cost: f64,
+}
+
+/// Traza de una búsqueda: el resultado (si lo hay) y los estados expandidos
+/// en orden, incluido el destino. `steps` acompaña a `expanded` con los
+/// datos de cada expansión, o está vacío si la búsqueda no los anota.
+/// `open` es lo que quedaba en la frontera al terminar, en orden de salida;
+/// solo se rellena con la feature `debug`. `pruned` indica que una cota de
+/// coste dejó estados fuera.
+#[derive(Clone, Debug)]
+pub(crate) struct Outcome<S> {
+    pub result: Option<Found<S>>,
+    pub expanded: Vec<S>,
+    pub steps: Vec<Step<S>>,
+    pub open: Vec<(S, Step<S>)>,
+    pub pruned: bool,
+}
+
+/// Cómo salió un estado de la frontera: desde qué estado se llegó (ninguno
+/// en el inicio), con qué `g` y con qué prioridad, su número de llegada a
+/// la frontera (el desempate) y si otro estado vigente empataba con él.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Step<S> {
+    pub parent: Option<S>,
+    pub g: f64,
+    pub priority: f64,
+    pub order: usize,
+    pub tied: bool,
+}
+
+impl<S> Outcome<S> {
+    /// Búsqueda que no llegó a empezar.
+    pub fn none() -> Self {
+        Self {
+            result: None,
+            expanded: vec![],
+            steps: vec![],
+            open: vec![],
+            pruned: false,
+        }
+    }
+
+    /// La misma traza con cada estado convertido por `f`.
+    pub fn map<T>(self, f: impl Fn(S) -> T) -> Outcome<T> {
+        Outcome {
+            result: self.result.map(|found| Found {
+                states: found.states.into_iter().map(&f).collect(),
+                g: found.g,
+                cost: found.cost,
+            }),
+            expanded: self.expanded.into_iter().map(&f).collect(),
+            steps: self.steps.into_iter().map(|step| step.map(&f)).collect(),
+            open: self
+                .open
+                .into_iter()
+                .map(|(state, step)| (f(state), step.map(&f)))
+                .collect(),
+            pruned: self.pruned,
+        }
+    }
+}
+
+impl<S> Step<S> {
+    pub fn map<T>(self, f: impl Fn(S) -> T) -> Step<T> {
+        Step {
+            parent: self.parent.map(f),
+            g: self.g,
+            priority: self.priority,
+            order: self.order,
+            tied: self.tied,
+        }
+    }
+}
+
+struct Entry<S> {
+    f: f64,
+    order: usize,
+    g: f64,
+    state: S,
+}
+
+impl<S> PartialEq for Entry<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<S> Eq for Entry<S> {}
+
+impl<S> PartialOrd for Entry<S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S> Ord for Entry<S> {
+    // Invertido para que `BinaryHeap` saque primero el menor `f`; a igual `f`
+    // gana el que entró antes, lo que hace la búsqueda determinista.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .f
+            .total_cmp(&self.f)
+            .then_with(|| other.order.cmp(&self.order))
+    }
+}
+
+impl<S> Keyed for Entry<S> {
+    fn key(&self) -> f64 {
+        self.f
+    }
+}
+
+/// A* genérico sobre cualquier espacio de estados. Con `heuristic` = 0 es Dijkstra.
+pub(crate) fn astar<S, I>(
+    start: S,
+    is_goal: impl Fn(&S) -> bool,
+    successors: impl FnMut(&S) -> I,
+    heuristic: impl Fn(&S) -> f64,
+) -> Outcome<S>
+where
+    S: Copy + Eq + Hash,
+    I: IntoIterator<Item = (S, f64)>,
+{
+    best_first(
+        start,
+        is_goal,
+        successors,
+        |s, g| g + heuristic(s),
+        f64::INFINITY,
+        f64::INFINITY,
+        OpenList::default(),
+        false,
+    )
+}
+
+/// Búsqueda primero-el-mejor: expande siempre el estado abierto de menor
+/// `priority(estado, g)`. Un estado se reabre si se le encuentra un `g` menor.
+/// Los sucesores con prioridad mayor que `limit` se descartan sin entrar en
+/// la frontera (`Outcome::pruned`). `incumbent` es el coste de una solución
+/// ya conocida: además de descartar igual, si sale un estado que no es
+/// destino con prioridad igual o mayor la búsqueda termina sin resultado,
+/// porque no puede mejorarla. Con infinito no hay cota; solo tienen sentido
+/// si la prioridad no supera el coste real hasta el destino (como en A*).
+/// `open_list` elige la estructura de la frontera. Solo con `annotate` se
+/// rellena `Outcome::steps`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn best_first<S, I>(
+    start: S,
+    is_goal: impl Fn(&S) -> bool,
+    mut successors: impl FnMut(&S) -> I,
+    mut priority: impl FnMut(&S, f64) -> f64,
+    limit: f64,
+    incumbent: f64,
+    open_list: OpenList,
+    annotate: bool,
+) -> Outcome<S>
+where
+    S: Copy + Eq + Hash,
+    I: IntoIterator<Item = (S, f64)>,
+{
+    let mut open = Frontier::new(open_list);
+    let mut g: HashMap<S, f64> = HashMap::new();
+    let mut parent: HashMap<S, S> = HashMap::new();
+    let mut order = 0;
+    let mut expanded = Vec::new();
+    let mut steps = Vec::new();
+    let mut pruned = false;
+
+    g.insert(start, 0.0);
+    open.push(Entry {
+        f: priority(&start, 0.0),
+        order,
+        g: 0.0,
+        state: start,
+    });
+
+    while let Some(Entry {
+        f,
+        order: arrival,
+        g: cost,
+        state,
+    }) = open.pop()
+    {
+        if cost > g[&state] {
+            continue;
+        }
+        if incumbent.is_finite() && f >= incumbent && !is_goal(&state) {
+            break;
+        }
+        expanded.push(state);
+        if annotate {
+            // Las entradas viejas de la cima ya no cuentan para el empate.
+            while open.peek().is_some_and(|e| e.g > g[&e.state]) {
+                open.pop();
+            }
+            steps.push(Step {
+                parent: parent.get(&state).copied(),
+                g: cost,
+                priority: f,
+                order: arrival,
+                tied: open.peek().is_some_and(|e| e.f == f),
+            });
+        }
+        if is_goal(&state) {
+            let states = build_states(&parent, state);
+            return Outcome {
+                result: Some(Found {
+                    g: states.iter().map(|s| g[s]).collect(),
+                    states,
+                    cost,
+                }),
+                expanded,
+                steps,
+                open: frontier(open, &g, &parent),
+                pruned,
+            };
+        }
+
+        for (next, step) in successors(&state) {
+            let ng = cost + step;
+            if g.get(&next).is_none_or(|&old| ng < old) {
+                let f = priority(&next, ng);
+                if f > limit.min(incumbent) {
+                    pruned = true;
+                    continue;
+                }
+                g.insert(next, ng);
+                parent.insert(next, state);
+                order += 1;
+                open.push(Entry {
+                    f,
+                    order,
+                    g: ng,
+                    state: next,
+                });
+            }
+        }
+    }
+
+    Outcome {
+        result: None,
+        expanded,
+        steps,
+        open: vec![],
+        pruned,
+    }
+}
+
+/// Entradas vigentes de la frontera en el orden en que saldrían, con la
+/// feature `debug`; sin ella, vacío para no pagar el coste.
+fn frontier<S: Copy + Eq + Hash>(
+    open: Frontier<Entry<S>>,
+    g: &HashMap<S, f64>,
+    parent: &HashMap<S, S>,
+) -> Vec<(S, Step<S>)> {
+    if !cfg!(feature = "debug") {
+        return vec![];
+    }
+    let mut live = open.into_sorted_vec();
+    live.retain(|e| e.g <= g[&e.state]);
+    live.iter()
+        .rev()
+        .map(|e| {
+            let step = Step {
+                parent: parent.get(&e.state).copied(),
+                g: e.g,
+                priority: e.f,
+                order: e.order,
+                tied: false,
+            };
+            (e.state, step)
+        })
+        .collect()
+}
+
+fn build_states<S: Copy + Eq + Hash>(parent: &HashMap<S, S>, mut end: S) -> Vec<S> {
+    let mut states = vec![end];
+    while let Some(&p) = parent.get(&end) {
+        end = p;
+        states.push(end);
+    }
+    states.reverse();
+    states
+}
+
+/// Dirección de llegada para los estados sin dirección (inicio, o cuando
+/// ninguna opción la necesita).
+const NO_DIR: usize = 4;
+
+/// Búsqueda sobre el grid aplicando las opciones configuradas.
+///
+/// El estado es `(celda, dirección de llegada)`; la dirección solo se
+/// distingue cuando una opción depende de ella, y si no vale siempre `NO_DIR`.
+pub(crate) fn grid_search(
+    grid: &Grid,
+    start: (usize, usize),
+    goal: (usize, usize),
+    opts: &SearchOptions,
+) -> Outcome<(usize, usize)> {
+    grid_search_in(grid, None, start, goal, opts)
+}
+
+/// `grid_search` recorriendo los vecinos precalculados de `adjacency`, si
+/// está al día con `grid`.
+pub(crate) fn grid_search_in(
+    grid: &Grid,
+    adjacency: Option<&Adjacency>,
+    start: (usize, usize),
+    goal: (usize, usize),
+    opts: &SearchOptions,
+) -> Outcome<(usize, usize)> {
+    let Some(mut space) = GridSpace::new(grid, start, goal, opts) else {
+        return Outcome::none();
+    };
+    space.use_adjacency(adjacency);
+    let h_scale = space.costs.min_step();
+    let priority =
+        |(x, y): (usize, usize), g| g + h_scale * (x.abs_diff(goal.0) + y.abs_diff(goal.1)) as f64;
+    space.search(start, goal, priority, space.limit(), f64::INFINITY)
+}
+
+/// Como `grid_search`, pero ordenando la frontera con `priority(celda, g)`
+/// en lugar de `g + h` y sin la cota `max_cost`, que solo vale para A*.
+pub(crate) fn grid_search_by(
+    grid: &Grid,
+    start: (usize, usize),
+    goal: (usize, usize),
+    opts: &SearchOptions,
+    priority: impl FnMut((usize, usize), f64) -> f64,
+) -> Outcome<(usize, usize)> {
+    match GridSpace::new(grid, start, goal, opts) {
+        Some(space) => space.search(start, goal, priority, f64::INFINITY, f64::INFINITY),
+        None => Outcome::none(),
+    }
+}
+
+/// `grid_search` arrancada con una ruta anterior de `start` a `goal` como
+/// solución de partida: los estados que no pueden mejorarla no entran en la
+/// frontera y, si aun así no se encuentra nada mejor, se devuelve ella. Una
+/// ruta que ya no es válida con el grid u opciones actuales se ignora.
+pub(crate) fn warm_search(
+    grid: &Grid,
+    start: (usize, usize),
+    goal: (usize, usize),
+    opts: &SearchOptions,
+    incumbent: &[(usize, usize)],
+) -> Outcome<(usize, usize)> {
+    let Some(space) = GridSpace::new(grid, start, goal, opts) else {
+        return Outcome::none();
+    };
+    let h_scale = space.costs.min_step();
+    let priority =
+        |(x, y): (usize, usize), g| g + h_scale * (x.abs_diff(goal.0) + y.abs_diff(goal.1)) as f64;
+    let labelled = opts.must_visit.as_ref().is_none_or(|visit| {
+        incumbent
+            .iter()
+            .any(|&c| grid.in_bounds(c) && visit.hits(grid.idx(c)))
+    });
+    // Con `min_turns` la clave no es un número, así que no hay cota.
+    let valid = incumbent.first() == Some(&start)
+        && incumbent.last() == Some(&goal)
+        && labelled
+        && !opts.min_turns;
+    let Some(walked) = space.walk(incumbent).filter(|_| valid) else {
+        return space.search(start, goal, priority, space.limit(), f64::INFINITY);
+    };
+    let bound = walked[walked.len() - 1];
+    let mut outcome = space.search(start, goal, priority, space.limit(), bound);
+    if outcome.result.is_none() && bound <= space.limit() {
+        outcome.result = Some(Found {
+            states: incumbent.to_vec(),
+            g: walked,
+            cost: bound,
+        });
+    }
+    outcome
+}
+
+/// Dijkstra sin montículo para cuando entrar en cada celda solo puede
+/// costar dos valores (p. ej. normal y penalizada): una cola FIFO por valor,
+/// cada una ordenada por construcción, y se saca del frente menor. Con pesos
+/// 0 y 1 es la BFS 0-1. Devuelve `None` si las opciones o el grid dan más
+/// de dos costes distintos o dependen de la dirección; entonces hay que
+/// usar `grid_search`.
+pub(crate) fn two_cost_search(
+    grid: &Grid,
+    start: Cell,
+    goal: Cell,
+    opts: &SearchOptions,
+) -> Option<Outcome<Cell>> {
+    if !cost_by_cell(opts) {
+        return None;
+    }
+    let mut costs = StepCost::new(grid, opts);
+    if !costs.allowed(start) || !costs.allowed(goal) {
+        return Some(Outcome::none());
+    }
+    let (levels, entry) = entry_levels(grid, opts)?;
+    costs.prepare(start, goal);
+
+    let mut dist = vec![f64::INFINITY; grid.cells.len()];
+    let mut parent: HashMap<usize, usize> = HashMap::new();
+    let mut queues = [VecDeque::new(), VecDeque::new()];
+    let mut expanded = Vec::new();
+    let (source, target) = (grid.idx(start), grid.idx(goal));
+    dist[source] = 0.0;
+    queues[0].push_back((0.0, source));
+    loop {
+        let front = |q: &VecDeque<(f64, usize)>| q.front().map_or(f64::INFINITY, |e| e.0);
+        let next = usize::from(front(&queues[1]) < front(&queues[0]));
+        let Some((d, idx)) = queues[next].pop_front() else {
+            break;
+        };
+        if d > dist[idx] {
+            continue;
+        }
+        expanded.push(grid.coords(idx));
+        if idx == target {
+            let mut states = vec![idx];
+            while let Some(&p) = parent.get(states.last()?) {
+                states.push(p);
+            }
+            states.reverse();
+            return Some(Outcome {
+                result: Some(Found {
+                    g: states.iter().map(|&i| dist[i]).collect(),
+                    states: states.into_iter().map(|i| grid.coords(i)).collect(),
+                    cost: d,
+                }),
+                expanded,
+                ..Outcome::none()
+            });
+        }
+        for n in grid
+            .neighbors(grid.coords(idx))
+            .filter(|&n| costs.allowed(n))
+        {
+            let n = grid.idx(n);
+            let nd = d + levels[entry(n)];
+            if nd < dist[n] {
+                dist[n] = nd;
+                parent.insert(n, idx);
+                queues[entry(n)].push_back((nd, n));
+            }
+        }
+    }
+    Some(Outcome {
+        expanded,
+        ..Outcome::none()
+    })
+}
+
+/// Si `two_cost_search` se puede usar con estas opciones en este grid.
+pub(crate) fn has_two_costs(grid: &Grid, opts: &SearchOptions) -> bool {
+    cost_by_cell(opts) && entry_levels(grid, opts).is_some()
+}
+
+/// Si el coste de un paso solo depende de la celda de llegada: sin
+/// pendiente, ruido, etiqueta obligatoria, transiciones prohibidas ni
+/// opciones que dependan de la dirección.
+fn cost_by_cell(opts: &SearchOptions) -> bool {
+    let directed = opts.no_reverse || opts.min_turns || opts.rotation_cost > 0.0;
+    let labelled = opts.must_visit.is_some() || opts.transitions.is_some();
+    !directed && !labelled && opts.noise <= 0.0 && opts.slope.is_none()
+}
+
+/// Costes distintos de entrar en una celda (como mucho dos) y el índice del
+/// de cada celda, sacados del índice de la penalización del grid (ver
+/// `Grid::set_penalty`), o `None` si hay más o se pueden derribar paredes,
+/// que cuestan según su vida.
+fn entry_levels<'a>(
+    grid: &'a Grid,
+    opts: &SearchOptions,
+) -> Option<(Vec<f64>, impl Fn(usize) -> usize + 'a)> {
+    if opts.break_cost > 0.0 && grid.hit_points.is_some() {
+        return None;
+    }
+    let (values, entry) = match (&grid.penalty, grid.penalty_levels()) {
+        (None, _) => (&[0.0][..], &[][..]),
+        (Some(_), Some(levels)) => levels,
+        (Some(_), None) => return None,
+    };
+    let levels = values.iter().map(|&p| 1.0 + f64::from(p)).collect();
+    Some((levels, move |idx: usize| {
+        entry.get(idx).map_or(0, |&e| usize::from(e))
+    }))
+}
+
+/// Solo el coste óptimo de `grid_search`, sin guardar padres ni reconstruir
+/// la ruta. `None` si no hay ruta.
+pub(crate) fn grid_cost(
+    grid: &Grid,
+    start: (usize, usize),
+    goal: (usize, usize),
+    opts: &SearchOptions,
+) -> Option<f64> {
+    if opts.must_visit.is_some() || opts.min_turns {
+        return grid_search(grid, start, goal, opts).result.map(|f| f.cost);
+    }
+    let space = GridSpace::new(grid, start, goal, opts)?;
+    let h_scale = space.costs.min_step();
+    let mut open = BinaryHeap::new();
+    let mut best: HashMap<Directed, f64> = HashMap::new();
+    let initial = (start, space.initial);
+    best.insert(initial, 0.0);
+    open.push(Entry {
+        f: 0.0,
+        order: 0,
+        g: 0.0,
+        state: initial,
+    });
+    let mut order = 0;
+
+    while let Some(Entry { g, state, .. }) = open.pop() {
+        if g > best[&state] {
+            continue;
+        }
+        if state.0 == goal {
+            return Some(g);
+        }
+        for (next, step) in space.successors(state) {
+            let ng = g + step;
+            if best.get(&next).is_none_or(|&old| ng < old) {
+                best.insert(next, ng);
+                order += 1;
+                let ((x, y), _) = next;
+                let h = h_scale * (x.abs_diff(goal.0) + y.abs_diff(goal.1)) as f64;
+                if ng + h > space.limit() {
+                    continue;
+                }
+                open.push(Entry {
+                    f: ng + h,
+                    order,
+                    g: ng,
+                    state: next,
+                });
+            }
+        }
+    }
+    None
+}
+
+/// Coste óptimo de `grid_search` de `start` a cada celda de `goals` (`None`
+/// si no hay ruta), con un solo Dijkstra por los mismos estados y pasos que
+/// la búsqueda. Con opciones que dependen del destino (holgura, etiqueta
+/// obligatoria) o con `min_turns` se hace una búsqueda por destino.
+pub(crate) fn goal_costs(
+    grid: &Grid,
+    start: (usize, usize),
+    goals: &[(usize, usize)],
+    opts: &SearchOptions,
+) -> Vec<Option<f64>> {
+    if opts.must_visit.is_some() || opts.min_turns || opts.max_clearance || opts.min_clearance > 0 {
+        return goals
+            .iter()
+            .map(|&goal| grid_cost(grid, start, goal, opts))
+            .collect();
+    }
+    let mut found = vec![None; goals.len()];
+    let Some(space) = GridSpace::new(grid, start, start, opts) else {
+        return found;
+    };
+    let mut pending: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    for (i, &goal) in goals.iter().enumerate() {
+        if grid.in_bounds(goal) && space.costs.allowed(goal) {
+            pending.entry(goal).or_default().push(i);
+        }
+    }
+    let initial = (start, space.initial);
+    let mut best: HashMap<Directed, f64> = HashMap::from([(initial, 0.0)]);
+    let mut open = BinaryHeap::from([Entry {
+        f: 0.0,
+        order: 0,
+        g: 0.0,
+        state: initial,
+    }]);
+    let mut order = 0;
+    while !pending.is_empty()
+        && let Some(Entry { g, state, .. }) = open.pop()
+    {
+        if g > best[&state] {
+            continue;
+        }
+        for i in pending.remove(&state.0).unwrap_or_default() {
+            found[i] = Some(g);
+        }
+        for (next, step) in space.successors(state) {
+            let ng = g + step;
+            if ng <= space.limit() && best.get(&next).is_none_or(|&old| ng < old) {
+                best.insert(next, ng);
+                order += 1;
+                open.push(Entry {
+                    f: ng,
+                    order,
+                    g: ng,
+                    state: next,
+                });
+            }
+        }
+    }
+    found
+}
+
+/// `grid_search` encadenada por `stops` en orden (inicio, paradas, destino):
+/// une los tramos óptimos y devuelve también el coste de cada uno. Las
+/// expansiones de todos los tramos se acumulan. Si algún tramo no tiene ruta
+/// no hay resultado.
+pub(crate) fn via_search(
+    grid: &Grid,
+    stops: &[(usize, usize)],
+    opts: &SearchOptions,
+) -> (Outcome<(usize, usize)>, Vec<f64>) {
+    let mut expanded = Vec::new();
+    let mut segments = Vec::new();
+    let mut states: Vec<_> = stops.first().into_iter().copied().collect();
+    let mut g = vec![0.0; states.len()];
+    for leg in stops.windows(2) {
+        let outcome = grid_search(grid, leg[0], leg[1], opts);
+        expanded.extend(outcome.expanded);
+        let Some(found) = outcome.result else {
+            let result = None;
+            return (
+                Outcome {
+                    result,
+                    expanded,
+                    ..Outcome::none()
+                },
+                vec![],
+            );
+        };
+        let before: f64 = segments.iter().sum();
+        states.extend(&found.states[1..]);
+        g.extend(found.g[1..].iter().map(|leg| before + leg));
+        segments.push(found.cost);
+    }
+    let cost = segments.iter().sum();
+    let result = Some(Found { states, g, cost });
+    (
+        Outcome {
+            result,
+            expanded,
+            ..Outcome::none()
+        },
+        segments,
+    )
+}
+
+/// Celda `(x, y)` del grid.
+type Cell = (usize, usize);
+
+/// `grid_search` que, si no llega a `goal` y las opciones fijan un radio de
+/// relajación, prueba con las celdas alcanzables a esa distancia euclídea o
+/// menos del destino, de la más cercana a la más lejana. Devuelve también
+/// el destino sustituto usado, si lo hay. Las expansiones se acumulan.
+pub(crate) fn relaxed_search(
+    grid: &Grid,
+    adjacency: Option<&Adjacency>,
+    start: (usize, usize),
+    goal: (usize, usize),
+    opts: &SearchOptions,
+) -> (Outcome<Cell>, Option<Cell>) {
+    let mut outcome = grid_search_in(grid, adjacency, start, goal, opts);
+    let radius = opts.goal_radius as usize;
+    if outcome.result.is_some() || radius == 0 || !grid.in_bounds(start) {
+        return (outcome, None);
+    }
+    let costs = StepCost::new(grid, opts);
+    let reach = dijkstra(grid, &costs, grid.idx(start), false);
+    let dist2 = |(x, y): (usize, usize)| x.abs_diff(goal.0).pow(2) + y.abs_diff(goal.1).pow(2);
+    let mut near: Vec<(usize, usize)> = (goal.0.saturating_sub(radius)..=goal.0 + radius)
+        .flat_map(|x| (goal.1.saturating_sub(radius)..=goal.1 + radius).map(move |y| (x, y)))
+        .filter(|&cell| cell != goal && grid.in_bounds(cell) && dist2(cell) <= radius * radius)
+        .filter(|&cell| reach[grid.idx(cell)].is_finite())
+        .collect();
+    near.sort_by_key(|&cell| dist2(cell));
+    for cell in near {
+        let retry = grid_search_in(grid, adjacency, start, cell, opts);
+        outcome.expanded.extend(retry.expanded);
+        if retry.result.is_some() {
+            outcome.result = retry.result;
+            return (outcome, Some(cell));
+        }
+    }
+    (outcome, None)
+}
+
+/// Estado de `grid_search`: celda y dirección de llegada.
+type Directed = ((usize, usize), usize);
+
+/// Espacio de estados `(celda, dirección de llegada)` de `grid_search` con
+/// las opciones ya resueltas.
+struct GridSpace<'a> {
+    grid: &'a Grid,
+    opts: &'a SearchOptions,
+    costs: StepCost<'a>,
+    adjacency: Option<&'a Adjacency>,
+    initial: usize,
+    directed: bool,
+    rotation: f64,
+}
+
+impl<'a> GridSpace<'a> {
+    /// `None` si el inicio o el destino no están permitidos.
+    fn new(
+        grid: &'a Grid,
+        start: (usize, usize),
+        goal: (usize, usize),
+        opts: &'a SearchOptions,
+    ) -> Option<Self> {
+        let mut costs = StepCost::new(grid, opts);
+        if !costs.allowed(start) || !costs.allowed(goal) {
+            return None;
+        }
+        costs.prepare(start, goal);
+
+        let directed = opts.no_reverse || opts.min_turns || opts.rotation_cost > 0.0;
+        let initial = match opts.heading {
+            Some(d) if directed => usize::from(d),
+            _ => NO_DIR,
+        };
+        Some(Self {
+            grid,
+            opts,
+            costs,
+            adjacency: None,
+            initial,
+            directed,
+            rotation: f64::from(opts.rotation_cost.max(0.0)),
+        })
+    }
+
+    /// Recorre los vecinos con `adjacency` si vale para el grid; con paredes
+    /// derribables no, porque también hay que pasar por ellas.
+    fn use_adjacency(&mut self, adjacency: Option<&'a Adjacency>) {
+        self.adjacency = adjacency.filter(|a| a.fits(self.grid) && self.opts.break_cost <= 0.0);
+    }
+
+    fn successors(&self, (cell, dir): Directed) -> Vec<(Directed, f64)> {
+        match self.adjacency {
+            Some(adjacency) => self.expand(
+                (cell, dir),
+                adjacency
+                    .steps(self.grid.idx(cell))
+                    .map(|(d, next)| (d, self.grid.coords(next))),
+            ),
+            None => self.expand((cell, dir), self.grid.steps(cell)),
+        }
+    }
+
+    /// Sucesores de `(cell, dir)` entre los pasos `(dirección, vecino)`.
+    fn expand(
+        &self,
+        (cell, dir): Directed,
+        steps: impl Iterator<Item = (usize, (usize, usize))>,
+    ) -> Vec<(Directed, f64)> {
+        steps
+            .filter(|&(d, next)| {
+                self.costs.allowed(next)
+                    && !(self.opts.no_reverse && dir != NO_DIR && d == opposite(dir))
+                    && self
+                        .opts
+                        .allows_step(self.grid.idx(cell), self.grid.idx(next))
+            })
+            .filter_map(|(d, next)| {
+                let state = (next, if self.directed { d } else { NO_DIR });
+                let quarters = if dir == NO_DIR {
+                    0
+                } else {
+                    quarter_turns(dir, d)
+                };
+                let extra = self.rotation * quarters as f64;
+                Some((state, self.costs.step(cell, next)? + extra))
+            })
+            .collect()
+    }
+
+    /// Cota de `SearchOptions::set_max_cost` para la prioridad de A* (con
+    /// `min_turns` no se aplica: lo primero son los giros, no el coste).
+    fn limit(&self) -> f64 {
+        match self.opts.max_cost {
+            Some(max) if !self.opts.min_turns => f64::from(max),
+            _ => f64::INFINITY,
+        }
+    }
+
+    /// Búsqueda en este espacio con `priority(celda, g)` y las cotas de
+    /// `best_first` (que `min_turns` no usa).
+    fn search(
+        &self,
+        start: (usize, usize),
+        goal: (usize, usize),
+        mut priority: impl FnMut((usize, usize), f64) -> f64,
+        limit: f64,
+        incumbent: f64,
+    ) -> Outcome<(usize, usize)> {
+        if let Some(visit) = &self.opts.must_visit {
+            return self.search_labelled(visit, start, goal, priority, limit, incumbent);
+        }
+        if self.opts.min_turns {
+            return self.search_turns(start, goal, priority);
+        }
+        best_first(
+            (start, self.initial),
+            |&(cell, _)| cell == goal,
+            |&state| self.successors(state),
+            |&(cell, _), g| priority(cell, g),
+            limit,
+            incumbent,
+            self.opts.open_list,
+            self.opts.annotate,
+        )
+        .map(|(cell, _)| cell)
+    }
+
+    /// `search` con `min_turns`: la clave de cada estado es el par
+    /// `(giros, prioridad)` comparado en orden, así que un giro de menos
+    /// gana siempre, cuesten lo que cuesten los pasos, y el coste solo
+    /// desempata. La frontera es siempre un montículo binario.
+    fn search_turns(
+        &self,
+        start: (usize, usize),
+        goal: (usize, usize),
+        mut priority: impl FnMut((usize, usize), f64) -> f64,
+    ) -> Outcome<(usize, usize)> {
+        let initial = (start, self.initial);
+        let mut open = BinaryHeap::new();
+        let mut best: HashMap<Directed, (u32, f64)> = HashMap::from([(initial, (0, 0.0))]);
+        let mut parent: HashMap<Directed, Directed> = HashMap::new();
+        let mut expanded = Vec::new();
+        let mut steps = Vec::new();
+        let mut order = 0;
+        open.push(TurnEntry {
+            turns: 0,
+            f: priority(start, 0.0),
+            order,
+            g: 0.0,
+            state: initial,
+        });
+
+        while let Some(entry) = open.pop() {
+            let state = entry.state;
+            if best[&state] != (entry.turns, entry.g) {
+                continue;
+            }
+            expanded.push(state);
+            if self.opts.annotate {
+                steps.push(Step {
+                    parent: parent.get(&state).copied(),
+                    g: entry.g,
+                    priority: entry.f,
+                    order: entry.order,
+                    tied: false,
+                });
+            }
+            if state.0 == goal {
+                let states = build_states(&parent, state);
+                let result = Found {
+                    g: states.iter().map(|s| best[s].1).collect(),
+                    states,
+                    cost: entry.g,
+                };
+                return Outcome {
+                    result: Some(result),
+                    expanded,
+                    steps,
+                    ..Outcome::none()
+                }
+                .map(|(cell, _)| cell);
+            }
+            for (next, step) in self.successors(state) {
+                let turned = state.1 != NO_DIR && state.1 != next.1;
+                let key = (entry.turns + u32::from(turned), entry.g + step);
+                if best.get(&next).is_none_or(|&old| fewer(key, old)) {
+                    best.insert(next, key);
+                    parent.insert(next, state);
+                    order += 1;
+                    open.push(TurnEntry {
+                        turns: key.0,
+                        f: priority(next.0, key.1),
+                        order,
+                        g: key.1,
+                        state: next,
+                    });
+                }
+            }
+        }
+        Outcome {
+            expanded,
+            steps,
+            ..Outcome::none()
+        }
+        .map(|(cell, _)| cell)
+    }
+
+    /// `search` con `SearchOptions::set_must_visit`: producto del grid por
+    /// dos capas, antes y después de pisar una celda con la etiqueta. Los
+    /// pasos son los de siempre sin dirección de llegada; se pasa a la
+    /// segunda capa al entrar en la etiqueta y solo ahí vale el destino.
+    fn search_labelled(
+        &self,
+        visit: &MustVisit,
+        start: (usize, usize),
+        goal: (usize, usize),
+        mut priority: impl FnMut((usize, usize), f64) -> f64,
+        limit: f64,
+        incumbent: f64,
+    ) -> Outcome<(usize, usize)> {
+        let hits = |cell| visit.hits(self.grid.idx(cell));
+        best_first(
+            (start, hits(start)),
+            |&(cell, touched)| touched && cell == goal,
+            |&(cell, touched)| {
+                self.successors((cell, NO_DIR))
+                    .into_iter()
+                    .map(|((next, _), step)| ((next, touched || hits(next)), step))
+                    .collect::<Vec<_>>()
+            },
+            |&(cell, _), g| priority(cell, g),
+            limit,
+            incumbent,
+            self.opts.open_list,
+            self.opts.annotate,
+        )
+        .map(|(cell, _)| cell)
+    }
+
+    /// Coste acumulado hasta cada celda de `path` en este espacio, o `None`
+    /// si algún paso no está permitido.
+    fn walk(&self, path: &[(usize, usize)]) -> Option<Vec<f64>> {
+        let mut state = (*path.first()?, self.initial);
+        let mut costs = vec![0.0];
+        for &next in &path[1..] {
+            let step;
+            (state, step) = self
+                .successors(state)
+                .into_iter()
+                .find(|&((cell, _), _)| cell == next)?;
+            costs.push(costs[costs.len() - 1] + step);
+        }
+        Some(costs)
+    }
+}
+
+/// Si la clave `(giros, coste)` de `a` es mejor que la de `b`: menos giros
+/// o, con los mismos, menos coste.
+fn fewer(a: (u32, f64), b: (u32, f64)) -> bool {
+    a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)).is_lt()
+}
+
+/// Elemento de la frontera de `min_turns` (ver `GridSpace::search_turns`).
+struct TurnEntry {
+    turns: u32,
+    f: f64,
+    order: usize,
+    g: f64,
+    state: Directed,
+}
+
+impl PartialEq for TurnEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for TurnEntry {}
+
+impl PartialOrd for TurnEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TurnEntry {
+    // Invertido como `Entry`: sale antes el de menos giros, después el de
+    // menor `f` y después el que entró antes.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .turns
+            .cmp(&self.turns)
+            .then_with(|| other.f.total_cmp(&self.f))
+            .then_with(|| other.order.cmp(&self.order))
+    }
+}
+
+/// Giros de 90° necesarios para pasar de mirar hacia `from` a mirar hacia
+/// `to` (índices de `DIRS`).
+fn quarter_turns(from: usize, to: usize) -> usize {
+    match (to + 4 - from) % 4 {
+        2 => 2,
+        0 => 0,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open(n: usize) -> Grid {
+        Grid::square(vec![1; n * n], n)
+    }
+
+    /// Test: sin ruido el coste es la distancia Manhattan
+    #[test]
+    fn search_cost_without_noise() {
+        let found = grid_search(&open(4), (0, 0), (3, 3), &SearchOptions::default())
+            .result
+            .unwrap();
+        assert_eq!(found.cost, 6.0);
+        assert_eq!(found.states.len(), 7);
+    }
+
+    /// Test: semillas distintas dan rutas distintas pero de igual longitud
+    #[test]
+    fn noise_varies_routes() {
+        let grid = open(8);
+        let routes: Vec<_> = (0..6)
+            .map(|seed| {
+                let mut opts = SearchOptions::default();
+                opts.set_noise(0.2, seed);
+                grid_search(&grid, (0, 0), (7, 7), &opts)
+                    .result
+                    .unwrap()
+                    .states
+            })
+            .collect();
+        assert!(routes.iter().all(|r| r.len() == 15));
+        assert!(routes.iter().any(|r| r != &routes[0]));
+    }
+
+    /// Test: sin giros de 180° el agente rodea en vez de volver sobre sus pasos
+    #[test]
+    fn no_reverse_forbids_u_turns() {
+        let grid = open(3);
+        let mut opts = SearchOptions::default();
+        opts.set_no_reverse(true, None);
+        let found = grid_search(&grid, (0, 0), (0, 2), &opts).result.unwrap();
+        for w in found.states.windows(3) {
+            assert_ne!(w[0], w[2], "giro de 180° en {:?}", w[1]);
+        }
+    }
+
+    /// Test: con rumbo inicial el agente no puede salir hacia atrás
+    #[test]
+    fn no_reverse_respects_heading() {
+        // Pasillo de ancho 1 en la fila 0: mirando a la derecha no se puede
+        // volver a la izquierda.
+        let grid = Grid::square(vec![1, 1, 1, 0, 0, 0, 0, 0, 0], 3);
+        let mut opts = SearchOptions::default();
+        opts.set_no_reverse(true, Some(1));
+        assert!(grid_search(&grid, (0, 1), (0, 2), &opts).result.is_some());
+        assert!(grid_search(&grid, (0, 1), (0, 0), &opts).result.is_none());
+
+        // En campo abierto hay que rodear en vez de ir directo.
+        let found = grid_search(&open(3), (0, 1), (0, 0), &opts).result.unwrap();
+        assert_eq!(found.states, vec![(0, 1), (1, 1), (1, 0), (0, 0)]);
+    }
+
+    /// Test: con modelo de pendiente la ruta rodea la colina
+    #[test]
+    fn slope_model_avoids_hill() {
+        let mut grid = open(3);
+        grid.elevation = Some(vec![0.0, 0.0, 0.0, 0.0, 5.0, 0.0, 0.0, 0.0, 0.0]);
+        let mut opts = SearchOptions::default();
+        opts.set_slope_model(1.0, 0.5, 10.0).unwrap();
+        let found = grid_search(&grid, (1, 0), (1, 2), &opts).result.unwrap();
+        assert!(!found.states.contains(&(1, 1)));
+        assert_eq!(found.cost, 4.0);
+    }
+
+    /// Test: los pasos nunca cuestan 0 o menos, y un modelo con factores
+    /// negativos se ignora
+    #[test]
+    fn slope_steps_stay_positive() {
+        let mut grid = open(2);
+        grid.elevation = Some(vec![0.0, -50.0, f32::NAN, 0.0]);
+        let mut opts = SearchOptions::default();
+        opts.set_slope_model(0.0, 1.0, 100.0).unwrap();
+        let costs = StepCost::new(&grid, &opts);
+        assert_eq!(costs.step((0, 0), (0, 1)), Some(0.1));
+        assert_eq!(costs.step((0, 0), (1, 0)), None);
+
+        opts.set_slope_model(-1.0, 0.0, 100.0).unwrap();
+        opts.set_slope_model(0.0, f32::NAN, 100.0).unwrap();
+        opts.set_slope_model(0.0, 0.0, -1.0).unwrap();
+        assert_eq!(opts.slope.map(|s| s.downhill), Some(1.0));
+    }
+
+    /// Test: una pendiente por encima del umbral es intransitable
+    #[test]
+    fn slope_model_threshold() {
+        let mut grid = Grid::square(vec![1, 1, 0, 0], 2);
+        grid.elevation = Some(vec![0.0, 3.0, 0.0, 0.0]);
+        let mut opts = SearchOptions::default();
+        opts.set_slope_model(1.0, 0.0, 2.0).unwrap();
+        assert!(grid_search(&grid, (0, 0), (0, 1), &opts).result.is_none());
+        opts.set_slope_model(1.0, 0.0, 3.0).unwrap();
+        assert_eq!(
+            grid_search(&grid, (0, 0), (0, 1), &opts)
+                .result
+                .unwrap()
+                .cost,
+            4.0
+        );
+    }
+
+    /// Test: la etiqueta obligatoria desvía la ruta hasta pisarla y sin
+    /// ninguna celda alcanzable con ella no hay ruta
+    #[test]
+    fn must_visit_label_detours() {
+        let mut opts = SearchOptions::default();
+        opts.set_must_visit(vec![0, 0, 0, 0, 0, 0, 0, 2, 0], 2);
+        let found = grid_search(&open(3), (0, 0), (0, 2), &opts).result.unwrap();
+        assert!(found.states.contains(&(2, 1)));
+        assert_eq!(found.cost, 6.0);
+        assert_eq!(grid_cost(&open(3), (0, 0), (0, 2), &opts), Some(6.0));
+
+        opts.set_must_visit(vec![2, 0, 0, 0, 0, 0, 0, 0, 0], 2);
+        let found = grid_search(&open(3), (0, 0), (0, 2), &opts).result.unwrap();
+        assert_eq!(found.cost, 2.0);
+
+        let walled = Grid::square(vec![1, 1, 1, 0, 0, 0, 1, 1, 1], 3);
+        opts.set_must_visit(vec![0, 0, 0, 0, 0, 0, 0, 2, 0], 2);
+        assert!(grid_search(&walled, (0, 0), (0, 2), &opts).result.is_none());
+    }
+
+    /// Test: la matriz prohíbe el paso directo entre dos etiquetas pero no
+    /// rodear por una tercera
+    #[test]
+    fn forbidden_transition_detours() {
+        let mut opts = SearchOptions::default();
+        // Fila superior: agua profunda (1) y acantilado (2); abajo, orilla (0).
+        let terrain = vec![1, 2, 2, 0, 0, 0, 0, 0, 0];
+        opts.set_transition_matrix(terrain, 3, vec![0, 0, 0, 0, 0, 1, 0, 0, 0])
+            .unwrap();
+        let found = grid_search(&open(3), (0, 0), (0, 1), &opts).result.unwrap();
+        assert_eq!(found.states, vec![(0, 0), (1, 0), (1, 1), (0, 1)]);
+
+        // Hacia atrás sí se puede, y sin orilla no hay ruta.
+        let back = grid_search(&open(3), (0, 1), (0, 0), &opts).result.unwrap();
+        assert_eq!(back.cost, 1.0);
+        let strip = Grid::square(vec![1, 1, 1, 0, 0, 0, 0, 0, 0], 3);
+        assert!(grid_search(&strip, (0, 0), (0, 2), &opts).result.is_none());
+        assert_eq!(grid_cost(&strip, (0, 0), (0, 2), &opts), None);
+
+        // Un número de etiquetas cuyo cuadrado se desborda se ignora.
+        let mut huge = SearchOptions::default();
+        huge.set_transition_matrix(vec![0; 9], usize::MAX, vec![0])
+            .unwrap();
+        assert!(huge.transitions.is_none());
+    }
+
+    /// Test: la máscara de región obliga a rodear y nunca se sale de ella
+    #[test]
+    fn region_mask_restricts_search() {
+        let mut opts = SearchOptions::default();
+        opts.set_region_mask(vec![1, 1, 1, 0, 0, 1, 0, 0, 1]);
+        let found = grid_search(&open(3), (0, 0), (2, 2), &opts).result.unwrap();
+        assert_eq!(found.states, vec![(0, 0), (0, 1), (0, 2), (1, 2), (2, 2)]);
+
+        opts.set_region_mask(vec![1, 1, 0, 1, 0, 0, 0, 0, 1]);
+        assert!(
+            grid_search(&open(3), (0, 0), (2, 2), &opts)
+                .result
+                .is_none()
+        );
+    }
+
+    /// Test: una prioridad voraz (solo distancia al destino) también llega
+    #[test]
+    fn custom_priority_greedy() {
+        let grid = Grid::square(vec![1, 1, 1, 1, 0, 1, 1, 1, 1], 3);
+        let goal = (2, 2);
+        let greedy = |(x, y): (usize, usize), _| (x.abs_diff(goal.0) + y.abs_diff(goal.1)) as f64;
+        let found = grid_search_by(&grid, (0, 0), goal, &SearchOptions::default(), greedy);
+        assert_eq!(found.result.unwrap().states.len(), 5);
+        assert!(found.expanded.len() <= 5);
+    }
+
+    /// Test: el objetivo de máxima holgura rodea el pilar con margen
+    #[test]
+    fn max_clearance_keeps_away_from_walls() {
+        let mut cells = vec![1; 121];
+        cells[5 * 11 + 5] = 0;
+        let grid = Grid::square(cells, 11);
+        let map = crate::distance::clearance_map(&grid);
+        let bottleneck = |states: &[(usize, usize)]| {
+            states[1..states.len() - 1]
+                .iter()
+                .map(|&c| map[grid.idx(c)])
+                .min()
+        };
+
+        let plain = grid_search(&grid, (5, 2), (5, 8), &SearchOptions::default());
+        assert_eq!(bottleneck(&plain.result.unwrap().states), Some(1));
+
+        let mut opts = SearchOptions::default();
+        opts.set_max_clearance(true);
+        let safe = grid_search(&grid, (5, 2), (5, 8), &opts).result.unwrap();
+        assert_eq!(bottleneck(&safe.states), Some(3));
+    }
+
+    /// Test: la holgura máxima se mide con los pasos que permite la
+    /// búsqueda: un acantilado en medio deja solo el paso del borde
+    #[test]
+    fn max_clearance_respects_slope() {
+        let mut grid = open(9);
+        let mut elevation = vec![0.0; 81];
+        for y in 1..9 {
+            elevation[4 * 9 + y] = 100.0;
+        }
+        grid.elevation = Some(elevation);
+        let mut opts = SearchOptions::default();
+        opts.set_max_clearance(true);
+        opts.set_slope_model(0.0, 0.0, 1.0).unwrap();
+        let found = grid_search(&grid, (2, 4), (6, 4), &opts).result.unwrap();
+        assert!(found.states.contains(&(4, 0)));
+        let best = StepCost::new(&grid, &opts).max_bottleneck((2, 4), (6, 4));
+        assert_eq!(best, 1);
+    }
+
+    /// Test: la misma semilla reproduce la misma ruta
+    #[test]
+    fn noise_is_deterministic() {
+        let mut opts = SearchOptions::default();
+        opts.set_noise(0.3, 42);
+        let a = grid_search(&open(6), (0, 0), (5, 5), &opts).result.unwrap();
+        let b = grid_search(&open(6), (0, 0), (5, 5), &opts).result.unwrap();
+        assert_eq!(a.states, b.states);
+    }
+
+    /// Test: con `min_turns` la ruta da un solo giro en lugar de escalonarse
+    #[test]
+    fn min_turns_prefers_straight_segments() {
+        let mut opts = SearchOptions::default();
+        opts.set_min_turns(true);
+        let found = grid_search(&open(5), (0, 0), (4, 4), &opts).result.unwrap();
+        assert_eq!(found.cost, 8.0);
+        let turns = found
+            .states
+            .windows(3)
+            .filter(|w| {
+                let a = (
+                    w[1].0 as isize - w[0].0 as isize,
+                    w[1].1 as isize - w[0].1 as isize,
+                );
+                let b = (
+                    w[2].0 as isize - w[1].0 as isize,
+                    w[2].1 as isize - w[1].1 as isize,
+                );
+                a != b
+            })
+            .count();
+        assert_eq!(turns, 1);
+    }
+
+    /// Test: un giro de menos compensa aunque la ruta sea más larga
+    #[test]
+    fn min_turns_trades_length_for_turns() {
+        // La ruta más corta (8 pasos) zigzaguea; la de menos giros da un
+        // rodeo de 10 por la fila superior.
+        #[rustfmt::skip]
+        let cells = vec![
+            1, 1, 1, 1, 1,
+            1, 0, 0, 0, 1,
+            1, 1, 0, 1, 1,
+            0, 1, 1, 1, 0,
+            1, 0, 0, 1, 1,
+        ];
+        let grid = Grid::square(cells, 5);
+        let plain = grid_search(&grid, (0, 0), (4, 4), &SearchOptions::default());
+        assert_eq!(plain.result.unwrap().cost, 8.0);
+        let mut opts = SearchOptions::default();
+        opts.set_min_turns(true);
+        let found = grid_search(&grid, (0, 0), (4, 4), &opts).result.unwrap();
+        assert_eq!(found.cost, 10.0);
+        assert_eq!(found.states[4], (0, 4));
+    }
+
+    /// Test: un giro de menos gana aunque sus celdas cuesten mucho más que
+    /// todo el resto del grid
+    #[test]
+    fn min_turns_holds_with_large_costs() {
+        let mut grid = open(3);
+        grid.set_penalty(Some(vec![
+            0.0, 1000.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1000.0, 0.0,
+        ]));
+        let mut opts = SearchOptions::default();
+        opts.set_min_turns(true);
+        let found = grid_search(&grid, (0, 0), (2, 2), &opts).result.unwrap();
+        assert_eq!((found.states.len(), found.cost), (5, 1004.0));
+        assert_eq!(grid_cost(&grid, (0, 0), (2, 2), &opts), Some(1004.0));
+    }
+
+    /// Test: el coste de rotación cuenta el giro inicial y los de 180° doble
+    #[test]
+    fn rotation_cost_from_initial_facing() {
+        let mut opts = SearchOptions::default();
+        opts.set_rotation_cost(0.5);
+        opts.set_facing(Some(2));
+        // Mirando hacia arriba, ir hacia abajo exige media vuelta.
+        let found = grid_search(&open(3), (0, 1), (2, 1), &opts).result.unwrap();
+        assert_eq!(found.cost, 2.0 + 2.0 * 0.5);
+        assert_eq!(found.states, vec![(0, 1), (1, 1), (2, 1)]);
+    }
+
+    /// Test: el coste sin ruta coincide con el de la búsqueda completa
+    #[test]
+    fn grid_cost_matches_search() {
+        let grid = Grid::square(vec![1, 1, 1, 0, 0, 1, 1, 1, 1], 3);
+        let mut opts = SearchOptions::default();
+        for min_turns in [false, true] {
+            opts.set_min_turns(min_turns);
+            let full = grid_search(&grid, (2, 0), (0, 0), &opts).result.unwrap();
+            assert_eq!(grid_cost(&grid, (2, 0), (0, 0), &opts), Some(full.cost));
+        }
+        assert_eq!(grid_cost(&grid, (0, 0), (1, 0), &opts), None);
+    }
+
+    /// Test: los costes a varios destinos de una pasada coinciden con los de
+    /// buscar cada uno, también con opciones de dirección y transiciones
+    #[test]
+    fn goal_costs_match_searches() {
+        let grid = Grid::square(vec![1, 1, 1, 1, 0, 1, 1, 1, 1], 3);
+        let goals = [(0, 2), (2, 2), (2, 0), (1, 1), (0, 0), (2, 2)];
+        let mut turning = SearchOptions::default();
+        turning.set_rotation_cost(0.5);
+        turning.set_no_reverse(true, Some(2));
+        let mut labelled = SearchOptions::default();
+        labelled
+            .set_transition_matrix(vec![0, 1, 0, 0, 0, 0, 0, 0, 0], 2, vec![0, 1, 0, 0])
+            .unwrap();
+        let mut capped = SearchOptions::default();
+        capped.set_max_cost(Some(3.0));
+        for opts in [SearchOptions::default(), turning, labelled, capped] {
+            let expected: Vec<_> = goals
+                .iter()
+                .map(|&goal| grid_cost(&grid, (0, 0), goal, &opts))
+                .collect();
+            assert_eq!(goal_costs(&grid, (0, 0), &goals, &opts), expected);
+        }
+    }
+
+    /// Test: partir de la ruta óptima la devuelve con el mismo coste
+    #[test]
+    fn warm_search_keeps_incumbent_cost() {
+        let grid = open(5);
+        let mut opts = SearchOptions::default();
+        for min_turns in [false, true] {
+            opts.set_min_turns(min_turns);
+            let cold = grid_search(&grid, (0, 0), (4, 4), &opts).result.unwrap();
+            let warm = warm_search(&grid, (0, 0), (4, 4), &opts, &cold.states);
+            assert_eq!(warm.result.unwrap().cost, cold.cost);
+        }
+        let detour = [(0, 0), (0, 1), (1, 1), (1, 0), (2, 0), (3, 0), (4, 0)];
+        let warm = warm_search(&grid, (0, 0), (4, 0), &opts, &detour);
+        assert_eq!(warm.result.unwrap().states.len(), 5);
+    }
+
+    /// Test: la estructura de la frontera no cambia ruta ni expansiones
+    #[test]
+    fn open_lists_expand_in_same_order() {
+        let cells = (0..100).map(|i| u8::from(i % 7 != 3)).collect();
+        let grid = Grid::square(cells, 10);
+        let mut opts = SearchOptions::default();
+        opts.set_rotation_cost(1.0);
+        let reference = grid_search(&grid, (0, 0), (9, 9), &opts);
+        for open_list in [OpenList::Buckets, OpenList::PairingHeap] {
+            opts.set_open_list(open_list);
+            let outcome = grid_search(&grid, (0, 0), (9, 9), &opts);
+            assert_eq!(outcome.expanded, reference.expanded);
+            assert_eq!(outcome.result.unwrap().states.len(), 19);
+        }
+    }
+
+    /// Test: una entrada vieja en la cima no cuenta como empate, y sin
+    /// anotar no se guardan los pasos
+    #[test]
+    fn stale_top_is_not_a_tie() {
+        // 0 -> X(1) y 0 -> Y(2) cuestan 3; por Z(3) Y baja a 2 y su entrada
+        // vieja (f = 3) queda en la cima al expandir X.
+        let edges = |s: &u8| match s {
+            0 => vec![(1, 3.0), (2, 3.0), (3, 1.0)],
+            3 => vec![(2, 1.0)],
+            _ => vec![],
+        };
+        let run = |annotate| {
+            let open_list = OpenList::default();
+            let inf = f64::INFINITY;
+            best_first(
+                0,
+                |&s| s == 1,
+                edges,
+                |_, g| g,
+                inf,
+                inf,
+                open_list,
+                annotate,
+            )
+        };
+        let outcome = run(true);
+        assert_eq!(outcome.expanded, vec![0, 3, 2, 1]);
+        assert!(!outcome.steps[3].tied);
+        assert!(run(false).steps.is_empty());
+    }
+
+    /// Test: con dos costes la búsqueda por colas iguala a A* y con más se
+    /// niega
+    #[test]
+    fn two_cost_search_matches_astar() {
+        let mut grid = open(6);
+        grid.set_penalty(Some(
+            (0..36).map(|i| if i % 6 < 3 { 4.0 } else { 0.0 }).collect(),
+        ));
+        let opts = SearchOptions::default();
+        for goal in [(5, 0), (5, 5), (0, 4)] {
+            let fast = two_cost_search(&grid, (0, 0), goal, &opts).unwrap();
+            let reference = grid_search(&grid, (0, 0), goal, &opts);
+            let (fast, reference) = (fast.result.unwrap(), reference.result.unwrap());
+            assert_eq!(
+                (fast.cost, fast.states.len()),
+                (reference.cost, reference.states.len())
+            );
+        }
+        assert!(has_two_costs(&grid, &opts));
+        assert_eq!(grid.penalty_levels().unwrap().0, &[4.0, 0.0]);
+
+        // El índice se rehace al cambiar la penalización.
+        let mut penalty = grid.penalty.clone().unwrap();
+        penalty[7] = 1.0;
+        grid.set_penalty(Some(penalty));
+        assert!(grid.penalty_levels().is_none() && !has_two_costs(&grid, &opts));
+        assert!(two_cost_search(&grid, (0, 0), (5, 5), &opts).is_none());
+    }
+
+    /// Test: la ruta por paradas pasa por todas en orden y suma sus tramos
+    #[test]
+    fn via_search_visits_stops_in_order() {
+        let grid = open(4);
+        let stops = [(0, 0), (0, 3), (3, 0), (3, 3)];
+        let (outcome, segments) = via_search(&grid, &stops, &SearchOptions::default());
+        let found = outcome.result.unwrap();
+        assert_eq!(segments, vec![3.0, 6.0, 3.0]);
+        assert_eq!(found.cost, 12.0);
+        assert_eq!(found.states.len(), 13);
+        assert_eq!(found.states[3], (0, 3));
+        assert_eq!(found.states[9], (3, 0));
+
+        let walled = Grid::square(vec![1, 0, 1, 0, 1, 1, 1, 1, 1], 3);
+        let (outcome, segments) = via_search(&walled, &[(0, 0), (2, 2)], &SearchOptions::default());
+        assert!(outcome.result.is_none() && segments.is_empty());
+    }
+}
@@ -0,0 +1,307 @@
+use wasm_bindgen::prelude::*;
+
+use crate::artifacts::{Artifacts, SECTION_COMPONENTS};
+use crate::grid::Grid;
+use crate::policy::{self, Anomaly};
+
+/// Marca de componente para celdas bloqueadas.
+const NO_COMPONENT: u32 = u32::MAX;
+
+/// Sesión de consultas sobre un grid fijo: guarda el grid, las componentes
+/// conexas precalculadas y los búferes de trabajo, de modo que `query` no
+/// reserva memoria. Pensada para miles de consultas sobre el mismo mapa.
+#[wasm_bindgen]
+pub struct Session {
+    grid: Grid,
+    component: Vec<u32>,
+    stamp: Vec<u32>,
+    parent: Vec<u32>,
+    queue: Vec<u32>,
+    path: Vec<u32>,
+    generation: u32,
+}
+
+#[wasm_bindgen]
+impl Session {
+    /// Sesión sobre un grid de `size` x `size`; con otra longitud se aplica
+    /// la política de anomalías como en `PathFinder::new`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(grid: Vec<u8>, size: usize) -> Result<Session, JsError> {
+        let cells = policy::fit_cells(grid, size * size)?;
+        Ok(Self::from_grid(Grid::square(cells, size)))
+    }
+
+    /// Crea la sesión reutilizando el preprocesado guardado con
+    /// `save_preprocessing`, sin recalcularlo. Falla si los artefactos son de
+    /// otro mapa o están dañados.
+    pub fn with_preprocessing(
+        grid: Vec<u8>,
+        size: usize,
+        artifacts: &[u8],
+    ) -> Result<Session, JsError> {
+        let grid = policy::fit_cells(grid, size * size)?;
+        Self::from_artifacts(Grid::square(grid, size), artifacts).map_err(JsError::new)
+    }
+
+    /// Serializa el preprocesado de la sesión para calcularlo en un paso de
+    /// build y distribuirlo junto al mapa.
+    pub fn save_preprocessing(&self) -> Vec<u8> {
+        Artifacts {
+            fingerprint: self.grid.fingerprint(),
+            sections: vec![(SECTION_COMPONENTS, self.component.clone())],
+        }
+        .encode()
+    }
+
+    /// BFS de `(sx, sy)` a `(gx, gy)`. Devuelve si hay camino; la ruta queda
+    /// disponible en `path()` hasta la siguiente consulta.
+    pub fn query(&mut self, sx: usize, sy: usize, gx: usize, gy: usize) -> bool {
+        self.path.clear();
+        let (start, goal) = ((sx, sy), (gx, gy));
+        if !self.grid.walkable(start) || !self.grid.walkable(goal) {
+            return false;
+        }
+        let (s, g) = (self.grid.idx(start), self.grid.idx(goal));
+        if self.component[s] != self.component[g] {
+            return false;
+        }
+
+        self.next_generation();
+        let generation = self.generation;
+        self.queue.clear();
+        self.queue.push(s as u32);
+        self.stamp[s] = generation;
+        self.parent[s] = s as u32;
+
+        let mut head = 0;
+        while head < self.queue.len() {
+            let cur = self.queue[head] as usize;
+            head += 1;
+            if cur == g {
+                break;
+            }
+            let cell = self.grid.coords(cur);
+            for next in self.grid.neighbors(cell) {
+                let idx = self.grid.idx(next);
+                if self.stamp[idx] != generation && self.grid.cells[idx] == 1 {
+                    self.stamp[idx] = generation;
+                    self.parent[idx] = cur as u32;
+                    self.queue.push(idx as u32);
+                }
+            }
+        }
+
+        let mut cur = g;
+        self.path.push(cur as u32);
+        while cur != s {
+            cur = self.parent[cur] as usize;
+            self.path.push(cur as u32);
+        }
+        self.path.reverse();
+        true
+    }
+
+    /// Reserva de una vez los búferes para grids de hasta `size * size`
+    /// celdas, para que la memoria wasm crezca al arrancar y no en mitad de
+    /// la partida al cargar un mapa mayor con `load_grid`. Falla si no caben
+    /// en memoria.
+    pub fn reserve_for(&mut self, size: usize) -> Result<(), JsError> {
+        self.reserve(size)?;
+        Ok(())
+    }
+
+    /// Sustituye el mapa reutilizando los búferes ya reservados. Falla, sin
+    /// tocar el mapa actual, si el nuevo no cabe en memoria.
+    pub fn load_grid(&mut self, grid: &[u8], size: usize) -> Result<(), JsError> {
+        let n = self.reserve(size)?;
+        self.grid.cells.clear();
+        self.grid
+            .cells
+            .extend_from_slice(&grid[..grid.len().min(n)]);
+        self.grid.cells.resize(n, 0);
+        self.grid.width = size;
+        self.grid.height = size;
+        self.grid.elevation = None;
+        self.grid.set_penalty(None);
+
+        let n = self.grid.cells.len();
+        self.stamp.clear();
+        self.stamp.resize(n, 0);
+        self.parent.clear();
+        self.parent.resize(n, 0);
+        self.generation = 0;
+        self.path.clear();
+        fill_components(&self.grid, &mut self.component, &mut self.queue);
+        Ok(())
+    }
+
+    /// Ruta de la última consulta como `[x0, y0, x1, y1, ...]`.
+    pub fn path(&self) -> Vec<usize> {
+        self.path
+            .iter()
+            .flat_map(|&idx| {
+                let (x, y) = self.grid.coords(idx as usize);
+                [x, y]
+            })
+            .collect()
+    }
+
+    /// Número de pasos de la última ruta (0 si no hubo camino).
+    pub fn path_len(&self) -> usize {
+        self.path.len().saturating_sub(1)
+    }
+}
+
+impl Session {
+    pub(crate) fn from_grid(grid: Grid) -> Self {
+        let component = components(&grid);
+        Self::from_grid_with(grid, component)
+    }
+
+    fn from_grid_with(grid: Grid, component: Vec<u32>) -> Self {
+        let n = grid.cells.len();
+        Self {
+            grid,
+            component,
+            stamp: vec![0; n],
+            parent: vec![0; n],
+            queue: Vec::with_capacity(n),
+            path: Vec::with_capacity(n),
+            generation: 0,
+        }
+    }
+
+    pub(crate) fn from_artifacts(grid: Grid, bytes: &[u8]) -> Result<Self, &'static str> {
+        let artifacts = Artifacts::decode(bytes).ok_or("artefactos de preprocesado inválidos")?;
+        if artifacts.fingerprint != grid.fingerprint() {
+            return Err("los artefactos corresponden a otro mapa");
+        }
+        let component = artifacts
+            .section(SECTION_COMPONENTS)
+            .filter(|c| c.len() == grid.cells.len())
+            .ok_or("faltan las componentes conexas")?
+            .to_vec();
+
+        Ok(Self::from_grid_with(grid, component))
+    }
+
+    /// Asegura capacidad para `size * size` celdas en todos los búferes y
+    /// devuelve ese número; si no cabe, error sin tocar el mapa.
+    fn reserve(&mut self, size: usize) -> Result<usize, Anomaly> {
+        let too_big = || Anomaly("el grid es demasiado grande".to_string());
+        let n = size.checked_mul(size).ok_or_else(too_big)?;
+        for buf in [
+            &mut self.component,
+            &mut self.stamp,
+            &mut self.parent,
+            &mut self.queue,
+            &mut self.path,
+        ] {
+            buf.try_reserve(n.saturating_sub(buf.len()))
+                .map_err(|_| too_big())?;
+        }
+        self.grid
+            .cells
+            .try_reserve(n.saturating_sub(self.grid.cells.len()))
+            .map_err(|_| too_big())?;
+        Ok(n)
+    }
+
+    fn next_generation(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+        if self.generation == 0 {
+            self.stamp.fill(0);
+            self.generation = 1;
+        }
+    }
+}
+
+/// Etiqueta cada celda transitable con el id de su componente conexa.
+pub(crate) fn components(grid: &Grid) -> Vec<u32> {
+    let mut component = Vec::new();
+    fill_components(grid, &mut component, &mut Vec::new());
+    component
+}
+
+/// Como `components`, pero escribiendo en búferes existentes.
+fn fill_components(grid: &Grid, component: &mut Vec<u32>, stack: &mut Vec<u32>) {
+    component.clear();
+    component.resize(grid.cells.len(), NO_COMPONENT);
+    stack.clear();
+    let mut next_id = 0;
+
+    for start in 0..grid.cells.len() {
+        if grid.cells[start] != 1 || component[start] != NO_COMPONENT {
+            continue;
+        }
+        component[start] = next_id;
+        stack.push(start as u32);
+        while let Some(cur) = stack.pop() {
+            for next in grid.neighbors(grid.coords(cur as usize)) {
+                let idx = grid.idx(next);
+                if grid.cells[idx] == 1 && component[idx] == NO_COMPONENT {
+                    component[idx] = next_id;
+                    stack.push(idx as u32);
+                }
+            }
+        }
+        next_id += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: consultas repetidas reutilizan los búferes sin mezclar resultados
+    #[test]
+    fn session_repeated_queries() {
+        let mut s = Session::new(vec![1, 1, 0, 0, 1, 1, 0, 1, 1], 3).unwrap();
+        assert!(s.query(0, 0, 2, 2));
+        assert_eq!(s.path_len(), 4);
+        assert!(s.query(2, 2, 0, 1));
+        assert_eq!(s.path().first(), Some(&2));
+        assert_eq!(s.path_len(), 3);
+        let capacity = s.queue.capacity();
+        for _ in 0..100 {
+            s.query(0, 0, 2, 2);
+        }
+        assert_eq!(s.queue.capacity(), capacity);
+    }
+
+    /// Test: con capacidad reservada, cargar un mapa mayor no realoca
+    #[test]
+    fn session_reserve_then_load() {
+        let mut s = Session::new(vec![1; 4], 2).unwrap();
+        s.reserve_for(8).unwrap();
+        let capacity = (s.stamp.capacity(), s.queue.capacity());
+        s.load_grid(&[1; 64], 8).unwrap();
+        assert!(s.query(0, 0, 7, 7));
+        assert_eq!(s.path_len(), 14);
+        assert_eq!((s.stamp.capacity(), s.queue.capacity()), capacity);
+
+        // Un tamaño que desborda o no cabe en memoria falla sin tocar el mapa.
+        let too_big = Err(Anomaly("el grid es demasiado grande".to_string()));
+        assert_eq!(s.reserve(usize::MAX), too_big);
+        assert_eq!(s.reserve(1 << 31), too_big);
+        assert!(s.query(0, 0, 7, 7) && s.grid.cells.len() == 64);
+    }
+
+    /// Test: el preprocesado guardado se recarga y solo vale para su mapa
+    #[test]
+    fn session_preprocessing_roundtrip() {
+        let cells = vec![1, 0, 0, 1, 1, 1, 0, 0, 1];
+        let saved = Session::new(cells.clone(), 3).unwrap().save_preprocessing();
+        let mut s = Session::from_artifacts(Grid::square(cells, 3), &saved).unwrap();
+        assert!(s.query(0, 0, 2, 2));
+        assert!(Session::from_artifacts(Grid::square(vec![1; 9], 3), &saved).is_err());
+    }
+
+    /// Test: componentes distintas se descartan sin buscar
+    #[test]
+    fn session_disconnected() {
+        let mut s = Session::new(vec![1, 0, 0, 1], 2).unwrap();
+        assert!(!s.query(0, 0, 1, 1));
+        assert!(s.path().is_empty());
+    }
+}
@@ -0,0 +1,87 @@
+/// Interpola la ruta con una spline Catmull-Rom uniforme.
+///
+/// Devuelve `samples_per_segment` muestras por tramo más el punto final,
+/// aplanadas como `[x0, y0, x1, y1, ...]`. Si una muestra cae en una celda
+/// no transitable se sustituye por la interpolación lineal del tramo.
+pub(crate) fn catmull_rom(
+    path: &[(usize, usize)],
+    samples_per_segment: usize,
+    walkable: impl Fn(usize, usize) -> bool,
+) -> Vec<f32> {
+    let Some(&last) = path.last() else {
+        return vec![];
+    };
+
+    let samples = samples_per_segment.max(1);
+    let pts: Vec<(f32, f32)> = path.iter().map(|&(x, y)| (x as f32, y as f32)).collect();
+    let mut out = Vec::with_capacity((pts.len() - 1) * samples * 2 + 2);
+
+    for i in 0..pts.len() - 1 {
+        let p0 = pts[i.saturating_sub(1)];
+        let p1 = pts[i];
+        let p2 = pts[i + 1];
+        let p3 = pts[(i + 2).min(pts.len() - 1)];
+
+        for k in 0..samples {
+            let t = k as f32 / samples as f32;
+            let (mut x, mut y) = (
+                spline(p0.0, p1.0, p2.0, p3.0, t),
+                spline(p0.1, p1.1, p2.1, p3.1, t),
+            );
+
+            if !is_free(x, y, &walkable) {
+                x = p1.0 + (p2.0 - p1.0) * t;
+                y = p1.1 + (p2.1 - p1.1) * t;
+            }
+            out.push(x);
+            out.push(y);
+        }
+    }
+
+    out.push(last.0 as f32);
+    out.push(last.1 as f32);
+    out
+}
+
+fn spline(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+fn is_free(x: f32, y: f32, walkable: &impl Fn(usize, usize) -> bool) -> bool {
+    let (rx, ry) = (x.round(), y.round());
+    rx >= 0.0 && ry >= 0.0 && walkable(rx as usize, ry as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: la spline pasa por todos los waypoints
+    #[test]
+    fn spline_passes_through_waypoints() {
+        let path = vec![(0, 0), (0, 1), (1, 1), (2, 1)];
+        let out = catmull_rom(&path, 4, |_, _| true);
+        assert_eq!(out.len(), 3 * 4 * 2 + 2);
+        for (i, &(x, y)) in path.iter().enumerate() {
+            assert_eq!(out[i * 8], x as f32);
+            assert_eq!(out[i * 8 + 1], y as f32);
+        }
+    }
+
+    /// Test: las muestras nunca caen dentro de un obstáculo
+    #[test]
+    fn spline_avoids_obstacles() {
+        let path = vec![(0, 0), (0, 1), (1, 1), (1, 2)];
+        let blocked = |x: usize, y: usize| (x, y) == (1, 0) || (x, y) == (0, 2);
+        let out = catmull_rom(&path, 8, |x, y| x < 2 && y < 3 && !blocked(x, y));
+        for p in out.chunks(2) {
+            let (x, y) = (p[0].round() as usize, p[1].round() as usize);
+            assert!(!blocked(x, y), "muestra en obstáculo: {p:?}");
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::grid::Grid;
+
+/// Obstáculo que recorre `path` avanzando `speed` celdas por paso de tiempo
+/// y se queda en la última celda al terminar.
+#[derive(Clone, Debug)]
+pub(crate) struct MovingObstacle {
+    pub path: Vec<(usize, usize)>,
+    pub speed: f32,
+}
+
+impl MovingObstacle {
+    /// `None` si `path` está vacía o `speed` no es un número positivo.
+    pub fn new(path: Vec<(usize, usize)>, speed: f32) -> Option<Self> {
+        (!path.is_empty() && speed.is_finite() && speed > 0.0).then_some(Self { path, speed })
+    }
+
+    pub fn position(&self, t: usize) -> (usize, usize) {
+        let step = (t as f32 * self.speed).floor() as usize;
+        self.path[step.min(self.path.len() - 1)]
+    }
+
+    /// Primer instante a partir del cual el obstáculo ya no se mueve.
+    fn settle_time(&self) -> usize {
+        if self.speed <= 0.0 {
+            return 0;
+        }
+        ((self.path.len() - 1) as f32 / self.speed).ceil() as usize
+    }
+}
+
+/// BFS en espacio-tiempo: en cada paso el agente se mueve a un vecino o espera.
+/// Evita ocupar la misma celda que un obstáculo en el mismo instante y los
+/// cruces frontales (intercambio de celdas entre `t` y `t + 1`).
+///
+/// Devuelve `(x, y, t)` por paso; una espera aparece como la misma celda
+/// con `t` consecutivos. Vacío si no hay ruta.
+pub(crate) fn timed_search(
+    grid: &Grid,
+    start: (usize, usize),
+    goal: (usize, usize),
+    obstacles: &[MovingObstacle],
+) -> Vec<(usize, usize, usize)> {
+    let occupied = |cell: (usize, usize), t: usize| obstacles.iter().any(|o| o.position(t) == cell);
+    let swapped = |from: (usize, usize), to: (usize, usize), t: usize| {
+        obstacles
+            .iter()
+            .any(|o| o.position(t) == to && o.position(t + 1) == from)
+    };
+
+    if !grid.walkable(start) || !grid.walkable(goal) || occupied(start, 0) {
+        return vec![];
+    }
+
+    // Pasado `settle` el mundo es estático, así que el estado (celda, t) se
+    // puede colapsar en (celda, settle) sin perder rutas.
+    let settle = obstacles
+        .iter()
+        .map(MovingObstacle::settle_time)
+        .max()
+        .unwrap_or(0);
+    let key = |cell: (usize, usize), t: usize| (grid.idx(cell), t.min(settle.saturating_add(1)));
+
+    let mut queue = VecDeque::new();
+    let mut visited = HashSet::new();
+    let mut parent: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+
+    queue.push_back((start, 0));
+    visited.insert(key(start, 0));
+
+    while let Some((cell, t)) = queue.pop_front() {
+        if cell == goal {
+            return build_timed_path(grid, &parent, key(cell, t), t);
+        }
+
+        let stay = std::iter::once(cell);
+        for next in stay.chain(grid.neighbors(cell)) {
+            let nt = t + 1;
+            let k = key(next, nt);
+            if !grid.walkable(next) || visited.contains(&k) {
+                continue;
+            }
+            if occupied(next, nt) || swapped(cell, next, t) {
+                continue;
+            }
+            visited.insert(k);
+            parent.insert(k, key(cell, t));
+            queue.push_back((next, nt));
+        }
+    }
+
+    vec![]
+}
+
+fn build_timed_path(
+    grid: &Grid,
+    parent: &HashMap<(usize, usize), (usize, usize)>,
+    mut end: (usize, usize),
+    mut t: usize,
+) -> Vec<(usize, usize, usize)> {
+    let (x, y) = grid.coords(end.0);
+    let mut path = vec![(x, y, t)];
+    while let Some(&p) = parent.get(&end) {
+        end = p;
+        t -= 1;
+        let (x, y) = grid.coords(end.0);
+        path.push((x, y, t));
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open(n: usize) -> Grid {
+        Grid::square(vec![1; n * n], n)
+    }
+
+    /// Test: sin obstáculos móviles coincide con la ruta más corta
+    #[test]
+    fn timed_without_obstacles() {
+        let path = timed_search(&open(3), (0, 0), (2, 2), &[]);
+        assert_eq!(path.len(), 5);
+        assert_eq!(path.last(), Some(&(2, 2, 4)));
+    }
+
+    /// Test: el agente espera a que el obstáculo despeje el pasillo
+    #[test]
+    fn timed_waits_for_obstacle() {
+        // Pasillo en la fila 0 cruzado por un obstáculo que sube y baja.
+        let grid = Grid::square(vec![1, 1, 1, 0, 1, 0, 0, 1, 0], 3);
+        let obstacle = MovingObstacle {
+            path: vec![(1, 1), (0, 1), (1, 1), (2, 1)],
+            speed: 1.0,
+        };
+        let path = timed_search(&grid, (0, 0), (0, 2), std::slice::from_ref(&obstacle));
+        assert!(!path.is_empty());
+        for &(x, y, t) in &path {
+            assert_ne!(obstacle.position(t), (x, y));
+        }
+        assert!(
+            path.windows(2)
+                .any(|w| (w[0].0, w[0].1) == (w[1].0, w[1].1))
+        );
+    }
+
+    /// Test: un obstáculo que queda fijo en el destino impide llegar
+    #[test]
+    fn timed_goal_permanently_blocked() {
+        let obstacle = MovingObstacle {
+            path: vec![(1, 0), (2, 0), (2, 1), (2, 2)],
+            speed: 1.0,
+        };
+        assert!(timed_search(&open(3), (0, 0), (2, 2), &[obstacle]).is_empty());
+    }
+
+    /// Test: la velocidad tiene que ser un número positivo, y una muy lenta
+    /// no desborda el instante en que el mundo se queda quieto
+    #[test]
+    fn obstacle_speed_is_validated() {
+        for speed in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            assert!(MovingObstacle::new(vec![(0, 1)], speed).is_none());
+        }
+        assert!(MovingObstacle::new(vec![], 1.0).is_none());
+
+        let slow = MovingObstacle::new(vec![(1, 1), (1, 2)], 1e-30).unwrap();
+        assert_eq!(slow.settle_time(), usize::MAX);
+        assert_eq!(timed_search(&open(3), (0, 0), (0, 2), &[slow]).len(), 3);
+    }
+}
@@ -0,0 +1,121 @@
+use wasm_bindgen::prelude::*;
+
+use crate::distance::{self, UNREACHED};
+use crate::grid::Grid;
+use crate::rng::Rng;
+
+/// Restricciones de `PathFinder::select_spawns`.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct SpawnConstraints {
+    pub(crate) min_separation: u32,
+    pub(crate) start: Option<((usize, usize), u32)>,
+    pub(crate) min_clearance: u32,
+    pub(crate) seed: u32,
+}
+
+#[wasm_bindgen]
+impl SpawnConstraints {
+    /// Sin restricciones y con semilla 0.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pasos a pie que separan como mínimo a dos puntos de aparición (los de
+    /// zonas sin conexión siempre están bastante separados).
+    pub fn set_min_separation(&mut self, steps: u32) {
+        self.min_separation = steps;
+    }
+
+    /// Los puntos deben estar a `min_distance` pasos a pie o más de
+    /// `(x, y)`, y poder llegar hasta allí (si `(x, y)` es pared o está fuera
+    /// del grid, ninguno cumple).
+    pub fn set_start(&mut self, x: usize, y: usize, min_distance: u32) {
+        self.start = Some(((x, y), min_distance));
+    }
+
+    pub fn clear_start(&mut self) {
+        self.start = None;
+    }
+
+    /// Holgura mínima de cada punto, en pasos hasta la pared o el borde más
+    /// cercano: 2 deja fuera las celdas pegadas a una pared.
+    pub fn set_min_clearance(&mut self, clearance: u32) {
+        self.min_clearance = clearance;
+    }
+
+    /// Semilla del orden en que se prueban las celdas; la misma semilla da
+    /// los mismos puntos.
+    pub fn set_seed(&mut self, seed: u32) {
+        self.seed = seed;
+    }
+}
+
+/// Hasta `count` celdas que cumplen `constraints`. Se prueban las celdas
+/// candidatas en un orden aleatorio de la semilla y se acepta cada una que
+/// esté a la separación pedida de las ya elegidas, así que puede devolver
+/// menos de `count` aunque otra elección cupiera.
+pub(crate) fn select(grid: &Grid, count: usize, constraints: &SpawnConstraints) -> Vec<usize> {
+    let clearance = distance::clearance_map(grid);
+    let from_start = constraints
+        .start
+        .map(|(cell, _)| distance::bfs_distances(grid, &[cell], u32::MAX).0);
+    let mut candidates: Vec<usize> = (0..grid.cells.len())
+        .filter(|&idx| grid.cells[idx] == 1 && clearance[idx] >= constraints.min_clearance)
+        .filter(|&idx| match (&from_start, constraints.start) {
+            (Some(dist), Some((_, min))) => dist[idx] != UNREACHED && dist[idx] >= min,
+            _ => true,
+        })
+        .collect();
+    let mut rng = Rng::new(u64::from(constraints.seed));
+    for i in (1..candidates.len()).rev() {
+        candidates.swap(i, rng.below(i + 1));
+    }
+
+    // Celdas demasiado cerca (a pie) de algún punto ya elegido.
+    let mut crowded = vec![false; grid.cells.len()];
+    let mut chosen = Vec::new();
+    for idx in candidates {
+        if chosen.len() == count {
+            break;
+        }
+        if crowded[idx] {
+            continue;
+        }
+        chosen.push(idx);
+        if let Some(depth) = constraints.min_separation.checked_sub(1) {
+            let near = distance::bfs_distances(grid, &[grid.coords(idx)], depth).1;
+            near.into_iter().for_each(|n| crowded[n] = true);
+        }
+    }
+    chosen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: los puntos respetan separación, distancia al inicio y holgura, y
+    /// la semilla los fija
+    #[test]
+    fn spawns_respect_constraints() {
+        let grid = Grid::square(vec![1; 49], 7);
+        let mut constraints = SpawnConstraints::new();
+        constraints.set_min_separation(4);
+        constraints.set_start(0, 0, 3);
+        constraints.set_min_clearance(2);
+        let spawns = select(&grid, 10, &constraints);
+        assert!(spawns.len() >= 2);
+        for (i, &a) in spawns.iter().enumerate() {
+            let (x, y) = grid.coords(a);
+            assert!(x + y >= 3 && (1..=5).contains(&x) && (1..=5).contains(&y));
+            for &b in &spawns[i + 1..] {
+                let (bx, by) = grid.coords(b);
+                assert!(x.abs_diff(bx) + y.abs_diff(by) >= 4);
+            }
+        }
+        assert_eq!(spawns, select(&grid, 10, &constraints));
+        assert_eq!(select(&grid, 1, &constraints).len(), 1);
+    }
+}
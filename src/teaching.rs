@@ -0,0 +1,133 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::Grid;
+use crate::search::Step;
+
+/// Anotaciones de cada expansión de la última búsqueda con costes
+/// (`PathFinder::explain`), para interfaces educativas paso a paso. La
+/// entrada `i` corresponde a la `i`-ésima celda expandida; `h` se obtiene
+/// como `f - g`.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExpansionLog {
+    cells: Vec<(usize, usize)>,
+    parents: Vec<Option<(usize, usize)>>,
+    g: Vec<f64>,
+    f: Vec<f64>,
+    order: Vec<u32>,
+    tied: Vec<bool>,
+}
+
+#[wasm_bindgen]
+impl ExpansionLog {
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Celdas expandidas en orden, como `[x0, y0, x1, y1, ...]`.
+    pub fn cells(&self) -> Vec<usize> {
+        self.cells.iter().flat_map(|&(x, y)| [x, y]).collect()
+    }
+
+    /// Celda desde la que se llegó a cada una, con el mismo formato; el
+    /// inicio aparece como su propio padre.
+    pub fn parents(&self) -> Vec<usize> {
+        self.parents
+            .iter()
+            .zip(&self.cells)
+            .flat_map(|(p, &cell)| {
+                let (x, y) = p.unwrap_or(cell);
+                [x, y]
+            })
+            .collect()
+    }
+
+    pub fn g(&self) -> Vec<f64> {
+        self.g.clone()
+    }
+
+    pub fn h(&self) -> Vec<f64> {
+        self.g.iter().zip(&self.f).map(|(g, f)| f - g).collect()
+    }
+
+    pub fn f(&self) -> Vec<f64> {
+        self.f.clone()
+    }
+
+    /// Número de llegada a la frontera de cada expansión: a igual `f` sale
+    /// antes el menor.
+    pub fn arrival_order(&self) -> Vec<u32> {
+        self.order.clone()
+    }
+
+    /// 1 si al expandirla había otra celda con la misma `f` y decidió el
+    /// orden de llegada.
+    pub fn tie_broken(&self) -> Vec<u8> {
+        self.tied.iter().map(|&t| u8::from(t)).collect()
+    }
+
+    /// Explicación en texto de por qué se eligió la expansión `i`; vacía si
+    /// `i` está fuera de rango.
+    pub fn reason(&self, i: usize) -> String {
+        let Some(&(x, y)) = self.cells.get(i) else {
+            return String::new();
+        };
+        let (g, f) = (self.g[i], self.f[i]);
+        let mut text = format!(
+            "({x}, {y}): f = g + h = {g} + {} = {f}, la menor de la frontera",
+            f - g
+        );
+        if self.tied[i] {
+            text += &format!("; empate resuelto por orden de llegada ({})", self.order[i]);
+        }
+        text
+    }
+}
+
+impl ExpansionLog {
+    /// Anotaciones a partir de las expansiones (índices de celda) y sus
+    /// datos; sin datos el registro queda vacío.
+    pub(crate) fn new(grid: &Grid, expanded: &[usize], steps: &[Step<usize>]) -> Self {
+        let mut log = Self::default();
+        for (&idx, step) in expanded.iter().zip(steps) {
+            log.cells.push(grid.coords(idx));
+            log.parents.push(step.parent.map(|p| grid.coords(p)));
+            log.g.push(step.g);
+            log.f.push(step.priority);
+            log.order.push(step.order as u32);
+            log.tied.push(step.tied);
+        }
+        log
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{PathFinder, SearchOptions};
+
+    /// Test: en campo abierto A* anota g, h y los empates de f
+    #[test]
+    fn explains_each_expansion() {
+        let mut pf = PathFinder::new(vec![1; 4], 2).unwrap();
+        assert!(pf.explain().is_empty());
+        let mut opts = SearchOptions::new();
+        pf.search_with(&opts).unwrap();
+        assert!(pf.explain().is_empty());
+        opts.set_annotate(true);
+        pf.search_with(&opts).unwrap();
+        let log = pf.explain();
+        assert_eq!(log.len(), 4);
+        assert_eq!(log.cells()[..2], [0, 0]);
+        assert_eq!(log.parents()[..4], [0, 0, 0, 0]);
+        assert_eq!((log.g()[0], log.h()[0], log.f()[0]), (0.0, 2.0, 2.0));
+        assert!(log.f().iter().all(|&f| f == 2.0));
+        // Tras el inicio, (1, 0) y (0, 1) empatan y gana la que llegó antes.
+        assert_eq!(log.tie_broken()[1], 1);
+        assert!(log.reason(1).contains("empate"));
+        assert!(log.reason(9).is_empty());
+    }
+}
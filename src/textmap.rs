@@ -0,0 +1,113 @@
+use crate::grid::Grid;
+
+/// Carácter de los que tienen alguna celda de la ruta, en cualquiera de
+/// los dos formatos. Tapa el resto de celdas del carácter, así que la ruta
+/// se ve como una línea de puntos entre los muros.
+const PATH_GLYPH: char = '•';
+
+/// Marcas por celda al dibujar el grid como texto: las paredes con tinta y
+/// las celdas de `path` aparte.
+fn marks(grid: &Grid, path: &[(usize, usize)]) -> (Vec<bool>, Vec<bool>) {
+    let ink = grid.cells.iter().map(|&c| c != 1).collect();
+    let mut route = vec![false; grid.cells.len()];
+    for &cell in path.iter().filter(|&&c| grid.in_bounds(c)) {
+        route[grid.idx(cell)] = true;
+    }
+    (ink, route)
+}
+
+/// Marca de `(x, y)` en `marks`; fuera del grid no hay.
+fn marked(grid: &Grid, marks: &[bool], x: usize, y: usize) -> bool {
+    grid.in_bounds((x, y)) && marks[grid.idx((x, y))]
+}
+
+/// Grid y ruta con medios bloques Unicode: cada carácter cubre dos filas de
+/// una columna y los de la ruta son `PATH_GLYPH`. Las líneas van separadas
+/// por `\n`.
+pub(crate) fn half_blocks(grid: &Grid, path: &[(usize, usize)]) -> String {
+    let (ink, route) = marks(grid, path);
+    let lines: Vec<String> = (0..grid.height.div_ceil(2))
+        .map(|row| {
+            (0..grid.width)
+                .map(|y| {
+                    if (0..2).any(|dx| marked(grid, &route, 2 * row + dx, y)) {
+                        return PATH_GLYPH;
+                    }
+                    let top = marked(grid, &ink, 2 * row, y);
+                    let bottom = marked(grid, &ink, 2 * row + 1, y);
+                    match (top, bottom) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    lines.join("\n")
+}
+
+/// Bit del punto Braille de la fila `dx` (0 a 3) y columna `dy` (0 o 1)
+/// dentro de un carácter.
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// Grid y ruta en Braille Unicode: cada carácter cubre cuatro filas y dos
+/// columnas, así que un mapa de 2000 x 2000 ocupa 500 líneas de 1000
+/// caracteres. Los de la ruta son `PATH_GLYPH`. Las líneas van separadas
+/// por `\n`.
+pub(crate) fn braille(grid: &Grid, path: &[(usize, usize)]) -> String {
+    let (ink, route) = marks(grid, path);
+    let lines: Vec<String> = (0..grid.height.div_ceil(4))
+        .map(|row| {
+            (0..grid.width.div_ceil(2))
+                .map(|col| {
+                    let mut bits = 0;
+                    let mut on_path = false;
+                    for (dx, dots) in BRAILLE_DOTS.iter().enumerate() {
+                        for (dy, &dot) in dots.iter().enumerate() {
+                            let (x, y) = (4 * row + dx, 2 * col + dy);
+                            on_path |= marked(grid, &route, x, y);
+                            if marked(grid, &ink, x, y) {
+                                bits |= dot;
+                            }
+                        }
+                    }
+                    if on_path {
+                        return PATH_GLYPH;
+                    }
+                    char::from_u32(0x2800 + bits).unwrap_or(' ')
+                })
+                .collect()
+        })
+        .collect();
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: paredes y ruta se ven en medios bloques y en Braille, la ruta
+    /// con su propio carácter
+    #[test]
+    fn text_renderings() {
+        let grid = Grid::new(vec![0, 1, 1, 0, 0, 1], 2, 3);
+        assert_eq!(half_blocks(&grid, &[]), "▀▄\n▀ ");
+        assert_eq!(half_blocks(&grid, &[(0, 1)]), "▀•\n▀ ");
+        assert_eq!(braille(&grid, &[]), "\u{2815}");
+        assert_eq!(braille(&grid, &[(2, 1)]), "•");
+
+        // Una pared y un tramo de ruta con la misma forma no se confunden.
+        let column = Grid::new(vec![1, 0, 1, 1, 0, 1], 3, 2);
+        let walls = half_blocks(&column, &[]);
+        let route = half_blocks(&Grid::square(vec![1; 9], 3), &[(0, 1), (1, 1)]);
+        assert_eq!(walls, " █ ");
+        assert_eq!(route.lines().next(), Some(" • "));
+        assert!(!walls.contains(PATH_GLYPH) && !route.contains('█'));
+        assert_eq!(
+            braille(&Grid::square(vec![1; 25], 5), &[]).lines().count(),
+            2
+        );
+    }
+}
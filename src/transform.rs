@@ -0,0 +1,345 @@
+use wasm_bindgen::prelude::*;
+
+use crate::grid::Grid;
+
+/// Cuándo queda bloqueada una celda gruesa al reducir la resolución.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DownsamplePolicy {
+    /// Bloqueada si lo está alguna de sus celdas finas (conservador).
+    BlockedIfAny,
+    /// Bloqueada si lo están más de la mitad de sus celdas finas.
+    BlockedIfMajority,
+}
+
+/// Grid de `width` x `height` cuya celda `(x, y)` se toma de
+/// `source((x, y))` en `grid`, junto con sus capas. Las celdas sin origen
+/// quedan bloqueadas y a 0 en las capas.
+fn remap(
+    grid: &Grid,
+    width: usize,
+    height: usize,
+    source: impl Fn((usize, usize)) -> Option<(usize, usize)>,
+) -> Grid {
+    let sources: Vec<Option<usize>> = (0..width * height)
+        .map(|idx| source((idx / width, idx % width)).map(|cell| grid.idx(cell)))
+        .collect();
+    let pick = |layer: &Vec<f32>| {
+        sources
+            .iter()
+            .map(|s| s.map_or(0.0, |i| layer[i]))
+            .collect()
+    };
+    let mut out = Grid::new(
+        sources
+            .iter()
+            .map(|s| s.map_or(0, |i| grid.cells[i]))
+            .collect(),
+        width,
+        height,
+    );
+    out.elevation = grid.elevation.as_ref().map(pick);
+    out.set_penalty(grid.penalty.as_ref().map(pick));
+    out.hit_points = grid.hit_points.as_ref().map(pick);
+    out
+}
+
+/// Operación entre los obstáculos de dos grids del mismo tamaño (en ambos
+/// cualquier valor distinto de 1 es obstáculo).
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GridOp {
+    /// Obstáculo si lo es en cualquiera (paredes estáticas ∪ unidades).
+    Union,
+    /// Obstáculo solo si lo es en los dos.
+    Intersection,
+    /// Obstáculos del primero que no lo son en el segundo (abrir huecos).
+    Difference,
+}
+
+/// Combina los obstáculos de `grid` con los de `other` (una entrada por
+/// celda) conservando las capas de `grid`. `None` si el tamaño no coincide.
+pub(crate) fn combine(grid: &Grid, other: &[u8], op: GridOp) -> Option<Grid> {
+    if other.len() != grid.cells.len() {
+        return None;
+    }
+    let mut out = grid.clone();
+    for (cell, &o) in out.cells.iter_mut().zip(other) {
+        let (a, b) = (*cell != 1, o != 1);
+        let blocked = match op {
+            GridOp::Union => a || b,
+            GridOp::Intersection => a && b,
+            GridOp::Difference => a && !b,
+        };
+        *cell = u8::from(!blocked);
+    }
+    Some(out)
+}
+
+/// Gira el grid `quarter_turns` veces 90° en sentido horario.
+pub(crate) fn rotate(grid: &Grid, quarter_turns: u32) -> Grid {
+    (0..quarter_turns % 4).fold(grid.clone(), |g, _| {
+        let h = g.height;
+        remap(&g, g.height, g.width, |(x, y)| Some((h - 1 - y, x)))
+    })
+}
+
+/// Refleja las filas (de arriba abajo) con `vertical` o las columnas.
+pub(crate) fn mirror(grid: &Grid, vertical: bool) -> Grid {
+    let (w, h) = (grid.width, grid.height);
+    remap(grid, w, h, |(x, y)| {
+        Some(if vertical {
+            (h - 1 - x, y)
+        } else {
+            (x, w - 1 - y)
+        })
+    })
+}
+
+/// Ventana de `height` x `width` celdas con esquina en `(x, y)`, o `None` si
+/// se sale del grid o está vacía.
+pub(crate) fn crop(
+    grid: &Grid,
+    (x, y): (usize, usize),
+    height: usize,
+    width: usize,
+) -> Option<Grid> {
+    let fits = width > 0
+        && height > 0
+        && x.checked_add(height).is_some_and(|end| end <= grid.height)
+        && y.checked_add(width).is_some_and(|end| end <= grid.width);
+    fits.then(|| remap(grid, width, height, |(r, c)| Some((x + r, y + c))))
+}
+
+/// Desplaza el contenido `dx` filas y `dy` columnas; lo que entra por los
+/// bordes queda bloqueado.
+pub(crate) fn translate(grid: &Grid, dx: isize, dy: isize) -> Grid {
+    remap(grid, grid.width, grid.height, |(x, y)| {
+        let cell = (x.checked_add_signed(-dx)?, y.checked_add_signed(-dy)?);
+        grid.in_bounds(cell).then_some(cell)
+    })
+}
+
+/// Engorda los obstáculos `n` celdas (elemento cuadrado): queda bloqueada
+/// toda celda con una pared a distancia de Chebyshev `n` o menos. El borde
+/// del grid no cuenta como pared.
+pub(crate) fn dilate(grid: &Grid, n: usize) -> Grid {
+    let mut out = grid.clone();
+    out.cells = spread(grid, n, 0);
+    out
+}
+
+/// Adelgaza los obstáculos `n` celdas: una pared se libera si hay una celda
+/// libre a distancia de Chebyshev `n` o menos.
+pub(crate) fn erode(grid: &Grid, n: usize) -> Grid {
+    let mut out = grid.clone();
+    out.cells = spread(grid, n, 1);
+    out
+}
+
+/// Margen de seguridad: bloquea toda celda cuyo centro esté a distancia
+/// euclídea `radius` o menos del centro de una pared, como se hace con el
+/// radio de un robot antes de planificar. El borde del grid no cuenta como
+/// pared. El radio se limita a la diagonal del grid, que ya lo cubre todo;
+/// `None` si no es finito.
+pub(crate) fn inflate(grid: &Grid, radius: f32) -> Option<Grid> {
+    if !radius.is_finite() {
+        return None;
+    }
+    let diagonal = (grid.width as f32).hypot(grid.height as f32);
+    let radius = radius.clamp(0.0, diagonal);
+    let reach = radius.floor() as isize;
+    let offsets: Vec<(isize, isize)> = (-reach..=reach)
+        .flat_map(|dx| (-reach..=reach).map(move |dy| (dx, dy)))
+        .filter(|&(dx, dy)| ((dx * dx + dy * dy) as f32) <= radius * radius)
+        .collect();
+    let mut out = grid.clone();
+    for idx in (0..grid.cells.len()).filter(|&i| grid.cells[i] != 1) {
+        let (x, y) = grid.coords(idx);
+        for &(dx, dy) in &offsets {
+            let (Some(nx), Some(ny)) = (x.checked_add_signed(dx), y.checked_add_signed(dy)) else {
+                continue;
+            };
+            if grid.in_bounds((nx, ny)) {
+                out.cells[grid.idx((nx, ny))] = 0;
+            }
+        }
+    }
+    Some(out)
+}
+
+/// Grid de resolución `factor` veces menor: cada celda gruesa cubre un
+/// bloque de `factor` x `factor` celdas finas (menos en los bordes). Las
+/// capas no se transfieren. `None` si `factor` es 0.
+pub(crate) fn downsample(grid: &Grid, factor: usize, policy: DownsamplePolicy) -> Option<Grid> {
+    if factor == 0 {
+        return None;
+    }
+    let (width, height) = (grid.width.div_ceil(factor), grid.height.div_ceil(factor));
+    let cells = (0..width * height)
+        .map(|idx| {
+            let block = block_cells(grid, factor, (idx / width, idx % width));
+            let walls = block.iter().filter(|&&i| grid.cells[i] != 1).count();
+            let blocked = match policy {
+                DownsamplePolicy::BlockedIfAny => walls > 0,
+                DownsamplePolicy::BlockedIfMajority => walls * 2 > block.len(),
+            };
+            u8::from(!blocked)
+        })
+        .collect();
+    Some(Grid::new(cells, width, height))
+}
+
+/// Índices de las celdas finas cubiertas por la celda gruesa `(cx, cy)`.
+fn block_cells(grid: &Grid, factor: usize, (cx, cy): (usize, usize)) -> Vec<usize> {
+    let rows = cx * factor..((cx + 1) * factor).min(grid.height);
+    rows.flat_map(|x| {
+        let cols = cy * factor..((cy + 1) * factor).min(grid.width);
+        cols.map(move |y| grid.idx((x, y)))
+    })
+    .collect()
+}
+
+/// Traduce una ruta sobre el grid reducido a puntos de paso en `grid` (el
+/// fino): por cada celda gruesa, la celda transitable de su bloque más
+/// cercana al centro. Se omiten las celdas gruesas fuera del grid o sin
+/// ninguna celda fina libre.
+pub(crate) fn upsample_path(
+    grid: &Grid,
+    factor: usize,
+    coarse: &[(usize, usize)],
+) -> Vec<(usize, usize)> {
+    if factor == 0 {
+        return Vec::new();
+    }
+    coarse
+        .iter()
+        .filter_map(|&(cx, cy)| {
+            let block = block_cells(grid, factor, (cx, cy));
+            let center = (cx * factor + factor / 2, cy * factor + factor / 2);
+            block
+                .into_iter()
+                .map(|i| grid.coords(i))
+                .filter(|&cell| grid.walkable(cell))
+                .min_by_key(|&(x, y)| x.abs_diff(center.0) + y.abs_diff(center.1))
+        })
+        .collect()
+}
+
+/// Extiende el valor `value` (0 o 1) a las celdas a distancia de Chebyshev
+/// `n` o menos, en dos pasadas separables (filas y columnas).
+fn spread(grid: &Grid, n: usize, value: u8) -> Vec<u8> {
+    let (w, h) = (grid.width, grid.height);
+    let is = |v: u8| if value == 1 { v == 1 } else { v != 1 };
+    let mut rows = vec![false; w * h];
+    for x in 0..h {
+        for y in 0..w {
+            let cols = y.saturating_sub(n)..(y + n + 1).min(w);
+            rows[x * w + y] = cols.into_iter().any(|c| is(grid.cells[x * w + c]));
+        }
+    }
+    let mut cells = grid.cells.clone();
+    for x in 0..h {
+        for y in 0..w {
+            let near = (x.saturating_sub(n)..(x + n + 1).min(h)).any(|r| rows[r * w + y]);
+            if near {
+                cells[x * w + y] = value;
+            }
+        }
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Grid {
+        // 2 filas x 3 columnas.
+        Grid::new(vec![1, 0, 1, 1, 1, 0], 3, 2)
+    }
+
+    /// Test: girar cambia las dimensiones y cuatro giros dejan el grid igual
+    #[test]
+    fn rotate_and_mirror() {
+        let g = rotate(&sample(), 1);
+        assert_eq!((g.width, g.height), (2, 3));
+        assert_eq!(g.cells, vec![1, 1, 1, 0, 0, 1]);
+        assert_eq!(rotate(&sample(), 4), sample());
+        assert_eq!(mirror(&sample(), false).cells, vec![1, 0, 1, 0, 1, 1]);
+        assert_eq!(mirror(&sample(), true).cells, vec![1, 1, 0, 1, 0, 1]);
+    }
+
+    /// Test: unión, intersección y diferencia de obstáculos
+    #[test]
+    fn combine_obstacles() {
+        let other = [0, 0, 1, 1, 1, 1];
+        let cells = |op| combine(&sample(), &other, op).unwrap().cells;
+        assert_eq!(cells(GridOp::Union), vec![0, 0, 1, 1, 1, 0]);
+        assert_eq!(cells(GridOp::Intersection), vec![1, 0, 1, 1, 1, 1]);
+        assert_eq!(cells(GridOp::Difference), vec![1, 1, 1, 1, 1, 0]);
+        assert!(combine(&sample(), &[1; 4], GridOp::Union).is_none());
+    }
+
+    /// Test: recorte y desplazamiento rellenan con paredes lo que falta
+    #[test]
+    fn crop_and_translate() {
+        let g = crop(&sample(), (0, 1), 2, 2).unwrap();
+        assert_eq!(g.cells, vec![0, 1, 1, 0]);
+        assert!(crop(&sample(), (1, 1), 2, 2).is_none());
+        assert!(crop(&sample(), (usize::MAX, 0), 2, 2).is_none());
+        assert!(crop(&sample(), (0, usize::MAX), 1, 2).is_none());
+        assert_eq!(translate(&sample(), 0, 1).cells, vec![0, 1, 0, 0, 1, 1]);
+    }
+
+    /// Test: dilatar y erosionar (cierre) devuelve la pared aislada original
+    #[test]
+    fn dilate_and_erode() {
+        let mut cells = vec![1; 25];
+        cells[12] = 0;
+        let grid = Grid::square(cells, 5);
+        let fat = dilate(&grid, 1);
+        assert_eq!(fat.cells.iter().filter(|&&c| c == 0).count(), 9);
+        assert_eq!(erode(&fat, 1), grid);
+        assert_eq!(erode(&grid, 1).cells, vec![1; 25]);
+        assert_eq!(dilate(&grid, 0), grid);
+    }
+
+    /// Test: reducir con cada política y volver a coordenadas finas
+    #[test]
+    fn downsample_and_map_back() {
+        #[rustfmt::skip]
+        let cells = vec![
+            1, 1, 0, 0, 1,
+            1, 0, 0, 1, 1,
+            1, 1, 1, 1, 1,
+        ];
+        let grid = Grid::new(cells, 5, 3);
+        let any = downsample(&grid, 2, DownsamplePolicy::BlockedIfAny).unwrap();
+        assert_eq!((any.width, any.height), (3, 2));
+        assert_eq!(any.cells, vec![0, 0, 1, 1, 1, 1]);
+        let majority = downsample(&grid, 2, DownsamplePolicy::BlockedIfMajority).unwrap();
+        assert_eq!(majority.cells, vec![1, 0, 1, 1, 1, 1]);
+        assert!(downsample(&grid, 0, DownsamplePolicy::BlockedIfAny).is_none());
+
+        let fine = upsample_path(&grid, 2, &[(0, 0), (0, 1), (1, 2)]);
+        assert_eq!(fine, vec![(0, 1), (1, 3), (2, 4)]);
+    }
+
+    /// Test: el inflado usa un disco, no un cuadrado, y el radio se acota
+    #[test]
+    fn inflate_uses_a_disk() {
+        let mut cells = vec![1; 49];
+        cells[24] = 0;
+        let grid = Grid::square(cells, 7);
+        let walls = |g: &Grid| g.cells.iter().filter(|&&c| c == 0).count();
+        let inflated = |radius| walls(&inflate(&grid, radius).unwrap());
+        assert_eq!(inflated(1.0), 5);
+        assert_eq!(inflated(1.5), 9);
+        assert_eq!(inflated(2.0), 13);
+        assert_eq!(inflate(&grid, 0.0), Some(grid.clone()));
+        // Un radio enorme se queda en la diagonal: todo bloqueado.
+        assert_eq!(inflated(1e30), 49);
+        assert!(inflate(&grid, f32::INFINITY).is_none() && inflate(&grid, f32::NAN).is_none());
+    }
+}
@@ -0,0 +1,58 @@
+use crate::grid::Grid;
+
+/// Tiempo acumulado al llegar a cada punto de `path`: cada paso mide la
+/// distancia euclídea entre centros, recorrida la mitad a la velocidad del
+/// terreno de la celda de salida y la otra mitad a la de llegada
+/// (`speeds[terrain[idx]]`). Un terreno sin velocidad positiva o una celda
+/// fuera del grid hacen infinito el tiempo desde ese punto.
+pub(crate) fn cumulative_times(
+    grid: &Grid,
+    path: &[(usize, usize)],
+    terrain: &[u8],
+    speeds: &[f32],
+) -> Vec<f32> {
+    let pace = |cell: (usize, usize)| {
+        let speed = grid
+            .in_bounds(cell)
+            .then(|| speeds.get(usize::from(terrain[grid.idx(cell)])))
+            .flatten()
+            .copied()
+            .filter(|&s| s > 0.0);
+        speed.map_or(f32::INFINITY, |s| 1.0 / s)
+    };
+    let mut elapsed = 0.0;
+    let mut times = Vec::with_capacity(path.len());
+    for (i, &cell) in path.iter().enumerate() {
+        if i > 0 {
+            let prev = path[i - 1];
+            let (dx, dy) = (
+                prev.0.abs_diff(cell.0) as f32,
+                prev.1.abs_diff(cell.1) as f32,
+            );
+            elapsed += dx.hypot(dy) * 0.5 * (pace(prev) + pace(cell));
+        }
+        times.push(elapsed);
+    }
+    times
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: cada paso promedia el ritmo de sus dos terrenos
+    #[test]
+    fn times_follow_terrain_speeds() {
+        let grid = Grid::square(vec![1; 4], 2);
+        let terrain = [0, 1, 0, 2];
+        let path = [(0, 0), (0, 1), (1, 1)];
+        assert_eq!(
+            cumulative_times(&grid, &path, &terrain, &[1.0, 0.5]),
+            vec![0.0, 1.5, f32::INFINITY]
+        );
+        assert_eq!(
+            cumulative_times(&grid, &path, &terrain, &[1.0, 0.5, 2.0]),
+            vec![0.0, 1.5, 2.75]
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use wasm_bindgen::prelude::*;
+
+use crate::distance;
+use crate::grid::Grid;
+
+/// Veredicto de `PathFinder::verify_path_against` sobre una ruta enviada
+/// por un cliente.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathVerdict {
+    /// Ruta continua, transitable y de longitud mínima.
+    Valid,
+    /// El cliente tiene otro mapa (su `grid_hash` no coincide).
+    GridMismatch,
+    /// Ruta vacía.
+    Empty,
+    /// Pasa por una celda fuera del grid o bloqueada.
+    Blocked,
+    /// Dos celdas seguidas no son vecinas (salto o teletransporte).
+    Discontinuous,
+    /// Es válida pero más larga que la ruta más corta entre sus extremos.
+    NotShortest,
+}
+
+/// Comprueba `path` sobre `grid` paso a paso y compara su longitud con la
+/// distancia BFS entre su primera y su última celda.
+pub(crate) fn verify(grid: &Grid, path: &[(usize, usize)]) -> PathVerdict {
+    let (Some(&start), Some(&goal)) = (path.first(), path.last()) else {
+        return PathVerdict::Empty;
+    };
+    if !path.iter().all(|&cell| grid.walkable(cell)) {
+        return PathVerdict::Blocked;
+    }
+    if path
+        .windows(2)
+        .any(|w| w[0].0.abs_diff(w[1].0) + w[0].1.abs_diff(w[1].1) != 1)
+    {
+        return PathVerdict::Discontinuous;
+    }
+    let steps = path.len() - 1;
+    let (dist, _) = distance::bfs_distances(grid, &[start], steps as u32);
+    if (dist[grid.idx(goal)] as usize) < steps {
+        PathVerdict::NotShortest
+    } else {
+        PathVerdict::Valid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: cada tipo de trampa tiene su veredicto
+    #[test]
+    fn verdicts() {
+        let grid = Grid::square(vec![1, 1, 1, 1, 0, 1, 1, 1, 1], 3);
+        assert_eq!(
+            verify(&grid, &[(0, 0), (0, 1), (0, 2), (1, 2)]),
+            PathVerdict::Valid
+        );
+        assert_eq!(verify(&grid, &[(0, 0)]), PathVerdict::Valid);
+        assert_eq!(verify(&grid, &[]), PathVerdict::Empty);
+        assert_eq!(verify(&grid, &[(0, 1), (1, 1)]), PathVerdict::Blocked);
+        assert_eq!(verify(&grid, &[(0, 0), (0, 2)]), PathVerdict::Discontinuous);
+        let detour = [(0, 0), (0, 1), (0, 0), (1, 0)];
+        assert_eq!(verify(&grid, &detour), PathVerdict::NotShortest);
+    }
+}
@@ -0,0 +1,52 @@
+use crate::grid::Grid;
+
+/// Celda transitable más cercana a la posición de mundo `(wx, wy)`, con
+/// celdas de `cell_size` unidades: `wx` avanza por filas (`x`) y `wy` por
+/// columnas (`y`). Si la celda bajo el punto está bloqueada o fuera del grid
+/// se elige la transitable con el centro más próximo; `None` si no hay
+/// ninguna o `cell_size` no es positivo.
+pub(crate) fn snap(grid: &Grid, wx: f32, wy: f32, cell_size: f32) -> Option<(usize, usize)> {
+    if cell_size <= 0.0 || ![cell_size, wx, wy].iter().all(|v| v.is_finite()) {
+        return None;
+    }
+    let (fx, fy) = (wx / cell_size, wy / cell_size);
+    let under = (fx.floor(), fy.floor());
+    if under.0 >= 0.0 && under.1 >= 0.0 {
+        let cell = (under.0 as usize, under.1 as usize);
+        if grid.walkable(cell) {
+            return Some(cell);
+        }
+    }
+
+    let dist = |(x, y): (usize, usize)| {
+        let (dx, dy) = (x as f32 + 0.5 - fx, y as f32 + 0.5 - fy);
+        dx * dx + dy * dy
+    };
+    (0..grid.cells.len())
+        .map(|idx| grid.coords(idx))
+        .filter(|&cell| grid.walkable(cell))
+        .min_by(|&a, &b| dist(a).total_cmp(&dist(b)))
+}
+
+/// Centros de las celdas de `path` en coordenadas de mundo, como
+/// `[wx0, wy0, wx1, wy1, ...]`.
+pub(crate) fn to_world(path: &[(usize, usize)], cell_size: f32) -> Vec<f32> {
+    path.iter()
+        .flat_map(|&(x, y)| [(x as f32 + 0.5) * cell_size, (y as f32 + 0.5) * cell_size])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: un punto sobre una pared se ajusta a la celda libre más cercana
+    #[test]
+    fn snap_moves_off_walls() {
+        let grid = Grid::square(vec![1, 0, 1, 1, 0, 1, 1, 1, 1], 3);
+        assert_eq!(snap(&grid, 5.0, 25.0, 10.0), Some((0, 2)));
+        assert_eq!(snap(&grid, 5.0, 17.0, 10.0), Some((0, 2)));
+        assert_eq!(snap(&grid, -4.0, -4.0, 10.0), Some((0, 0)));
+        assert_eq!(snap(&grid, 5.0, 5.0, 0.0), None);
+    }
+}